@@ -0,0 +1,298 @@
+//! # Typed Account Loading
+//!
+//! Each pattern's `try_from_slice` only checks `data.len() >= SIZE`. Any
+//! account owned by the program that is at least that long parses, so a
+//! `UserDeposit` passed where a `Treasury` is expected turns its bytes into
+//! a garbage treasury.
+//!
+//! [`load_typed`] checks owner, exact length and (if the type has one) the
+//! leading type tag before parsing:
+//!
+//! ```ignore
+//! impl AccountData for Treasury {
+//!     const LEN: usize = TREASURY_SIZE;
+//!     fn try_from_slice(data: &[u8]) -> Result<Self, ProgramError> {
+//!         Treasury::try_from_slice(data)
+//!     }
+//! }
+//!
+//! let treasury: Treasury = load_typed(treasury_acc, program_id)?;
+//! ```
+//!
+//! Layouts that carry a schema version byte pass it to
+//! [`check_schema_version`] so data written by a newer program version is
+//! rejected instead of misread. Closing such an account overwrites the
+//! version byte with [`CLOSED_ACCOUNT_TAG`]; the same check then rejects the
+//! leftover data, and [`check_not_closed`] stops it being initialized again.
+//! [`check_uninitialized`] stops a live account being initialized again.
+//!
+//! A layout whose validity depends on the owning program (a stored PDA bump,
+//! say) overrides [`AccountData::try_from_owned_slice`], which `load_typed`
+//! calls with the program id.
+//!
+//! `close_*` instructions wipe the data with [`zero_account_data`], or with
+//! [`close_account_data`] when the layout has a version byte to tag, before
+//! moving the lamports out.
+//!
+//! ## Anchor Comparison
+//! `Account<'info, T>` checks the owner and the 8-byte discriminator. Layouts
+//! here have no discriminator yet, so the exact length stands in for it;
+//! a type that adds a tag byte sets [`AccountData::TAG`].
+
+use pinocchio::{error::ProgramError, AccountView, Address, ProgramResult};
+
+use crate::error::CommonError;
+
+/// A fixed-size account layout owned by a pattern program.
+pub trait AccountData: Sized {
+    /// Exact length of the serialized account data.
+    const LEN: usize;
+
+    /// Value of the first data byte identifying this type, if the layout
+    /// begins with one.
+    const TAG: Option<u8> = None;
+
+    /// Parse the account data; called only after length and tag matched.
+    fn try_from_slice(data: &[u8]) -> Result<Self, ProgramError>;
+
+    /// Parse the data of an account owned by `program_id`.
+    ///
+    /// Defaults to [`AccountData::try_from_slice`]. Override it to reject
+    /// data that is only invalid for a given program, such as a stored PDA
+    /// bump that is not the canonical one.
+    fn try_from_owned_slice(data: &[u8], _program_id: &Address) -> Result<Self, ProgramError> {
+        Self::try_from_slice(data)
+    }
+}
+
+/// Load `account` as a `T`, checking owner, length and tag together.
+///
+/// Fails with `IllegalOwner` if `program_id` does not own the account and
+/// `CommonError::AccountTypeMismatch` if the length or tag do not match `T`.
+pub fn load_typed<T: AccountData>(
+    account: &AccountView,
+    program_id: &Address,
+) -> Result<T, ProgramError> {
+    if !account.owned_by(program_id) {
+        return Err(ProgramError::IllegalOwner);
+    }
+
+    let data = account.try_borrow()?;
+    check_type::<T>(&data)?;
+    T::try_from_owned_slice(&data, program_id)
+}
+
+/// Version byte value marking a closed account.
+///
+/// No schema version will reach it, so it cannot be mistaken for live data.
+pub const CLOSED_ACCOUNT_TAG: u8 = 0xFF;
+
+/// Rejects account data whose stored layout version is newer than `supported`.
+///
+/// Older versions are accepted: a version bump may only add meaning to bytes
+/// that older data leaves zero. Fails with `CommonError::AccountClosed` for
+/// [`CLOSED_ACCOUNT_TAG`] and `CommonError::UnsupportedVersion` otherwise.
+pub fn check_schema_version(found: u8, supported: u8) -> Result<(), ProgramError> {
+    check_not_closed(found)?;
+    if found > supported {
+        return Err(CommonError::UnsupportedVersion.into());
+    }
+    Ok(())
+}
+
+/// Rejects an account whose version byte is [`CLOSED_ACCOUNT_TAG`].
+///
+/// Initializers call this on the raw version byte before writing, so a
+/// closed-but-not-reclaimed account cannot be revived over its old data.
+/// Fails with `CommonError::AccountClosed`.
+pub fn check_not_closed(version: u8) -> Result<(), ProgramError> {
+    if version == CLOSED_ACCOUNT_TAG {
+        return Err(CommonError::AccountClosed.into());
+    }
+    Ok(())
+}
+
+/// Rejects account data that has already been written.
+///
+/// Initializers call this before writing, the check Anchor's `init` makes by
+/// requiring a fresh account: freshly allocated data is all zero, so any
+/// non-zero byte means a previous initialization would be overwritten.
+/// Fails with `AccountAlreadyInitialized`.
+pub fn check_uninitialized(data: &[u8]) -> Result<(), ProgramError> {
+    if data.iter().any(|byte| *byte != 0) {
+        return Err(ProgramError::AccountAlreadyInitialized);
+    }
+    Ok(())
+}
+
+/// Overwrites all of `account`'s data with zeros.
+///
+/// The mutable borrow ends before this returns, so the caller can move the
+/// account's lamports or borrow it again straight after.
+pub fn zero_account_data(account: &AccountView) -> ProgramResult {
+    account.try_borrow_mut()?.fill(0);
+    Ok(())
+}
+
+/// Zeros `account`'s data, then writes [`CLOSED_ACCOUNT_TAG`] at `version_offset`.
+///
+/// A wiped versioned account would otherwise read as freshly allocated and
+/// pass [`check_uninitialized`]; the tag keeps [`check_not_closed`] rejecting
+/// it until the runtime reclaims the account. Fails with `AccountDataTooSmall`
+/// if the data does not reach `version_offset`, leaving it unchanged.
+pub fn close_account_data(account: &AccountView, version_offset: usize) -> ProgramResult {
+    let mut data = account.try_borrow_mut()?;
+    if version_offset >= data.len() {
+        return Err(ProgramError::AccountDataTooSmall);
+    }
+    data.fill(0);
+    data[version_offset] = CLOSED_ACCOUNT_TAG;
+    Ok(())
+}
+
+fn check_type<T: AccountData>(data: &[u8]) -> Result<(), ProgramError> {
+    if data.len() != T::LEN {
+        return Err(CommonError::AccountTypeMismatch.into());
+    }
+    if let Some(tag) = T::TAG {
+        if data.first() != Some(&tag) {
+            return Err(CommonError::AccountTypeMismatch.into());
+        }
+    }
+    Ok(())
+}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::MockAccountView;
+
+    const PROGRAM_ID: Address = Address::new_from_array([9u8; 32]);
+    const ADDRESS: Address = Address::new_from_array([1u8; 32]);
+
+    struct Counter(u64);
+
+    impl AccountData for Counter {
+        const LEN: usize = 8;
+        fn try_from_slice(data: &[u8]) -> Result<Self, ProgramError> {
+            Ok(Counter(u64::from_le_bytes(data[..8].try_into().unwrap())))
+        }
+    }
+
+    struct Tagged;
+
+    impl AccountData for Tagged {
+        const LEN: usize = 2;
+        const TAG: Option<u8> = Some(7);
+        fn try_from_slice(_data: &[u8]) -> Result<Self, ProgramError> {
+            Ok(Tagged)
+        }
+    }
+
+    #[test]
+    fn test_load_typed_exact_length() {
+        let mut account = MockAccountView::new(ADDRESS, PROGRAM_ID, 0, &42u64.to_le_bytes());
+        let counter: Counter = load_typed(&account.view(), &PROGRAM_ID).unwrap();
+        assert_eq!(counter.0, 42);
+    }
+
+    #[test]
+    fn test_load_typed_rejects_foreign_but_large_enough_data() {
+        let mut longer = MockAccountView::new(ADDRESS, PROGRAM_ID, 0, &[0u8; 16]);
+        let result = load_typed::<Counter>(&longer.view(), &PROGRAM_ID);
+        assert!(matches!(result, Err(e) if e == CommonError::AccountTypeMismatch.into()));
+
+        let mut foreign = MockAccountView::new(ADDRESS, Address::default(), 0, &[0u8; 8]);
+        let result = load_typed::<Counter>(&foreign.view(), &PROGRAM_ID);
+        assert!(matches!(result, Err(ProgramError::IllegalOwner)));
+    }
+
+    #[test]
+    fn test_check_schema_version() {
+        assert!(check_schema_version(0, 0).is_ok());
+        assert!(check_schema_version(1, 2).is_ok());
+        assert_eq!(check_schema_version(3, 2), Err(CommonError::UnsupportedVersion.into()));
+    }
+
+    #[test]
+    fn test_closed_tag_rejected() {
+        let closed = Err(CommonError::AccountClosed.into());
+        assert_eq!(check_schema_version(CLOSED_ACCOUNT_TAG, 0), closed);
+        assert_eq!(check_not_closed(CLOSED_ACCOUNT_TAG), closed);
+        assert!(check_not_closed(0).is_ok());
+    }
+
+    #[test]
+    fn test_check_uninitialized() {
+        assert!(check_uninitialized(&[0u8; 8]).is_ok());
+        assert!(check_uninitialized(&[]).is_ok());
+        let mut written = [0u8; 8];
+        written[7] = 1;
+        assert_eq!(check_uninitialized(&written), Err(ProgramError::AccountAlreadyInitialized));
+    }
+
+    #[test]
+    fn test_zero_account_data() {
+        let mut account = MockAccountView::new(ADDRESS, PROGRAM_ID, 0, &[0xAB; 16]).writable();
+        zero_account_data(&account.view()).unwrap();
+        assert_eq!(account.data(), &[0u8; 16]);
+
+        // The borrow was released: the account can be borrowed again
+        assert!(account.view().try_borrow_mut().is_ok());
+    }
+
+    #[test]
+    fn test_close_account_data_keeps_closed_tag() {
+        let mut account = MockAccountView::new(ADDRESS, PROGRAM_ID, 0, &[0xAB; 16]).writable();
+        close_account_data(&account.view(), 15).unwrap();
+
+        let mut expected = [0u8; 16];
+        expected[15] = CLOSED_ACCOUNT_TAG;
+        assert_eq!(account.data(), &expected);
+        assert_eq!(check_not_closed(account.data()[15]), Err(CommonError::AccountClosed.into()));
+
+        let result = close_account_data(&account.view(), 16);
+        assert_eq!(result, Err(ProgramError::AccountDataTooSmall));
+        assert_eq!(account.data(), &expected);
+    }
+
+    /// Valid only for the program whose first address byte it stores.
+    struct Stamped;
+
+    impl AccountData for Stamped {
+        const LEN: usize = 1;
+        fn try_from_slice(_data: &[u8]) -> Result<Self, ProgramError> {
+            Ok(Stamped)
+        }
+        fn try_from_owned_slice(data: &[u8], program_id: &Address) -> Result<Self, ProgramError> {
+            if data[0] != program_id.as_ref()[0] {
+                return Err(ProgramError::InvalidAccountData);
+            }
+            Self::try_from_slice(data)
+        }
+    }
+
+    #[test]
+    fn test_load_typed_passes_program_id() {
+        let mut stamped = MockAccountView::new(ADDRESS, PROGRAM_ID, 0, &[9]);
+        assert!(load_typed::<Stamped>(&stamped.view(), &PROGRAM_ID).is_ok());
+
+        let mut other = MockAccountView::new(ADDRESS, PROGRAM_ID, 0, &[8]);
+        let result = load_typed::<Stamped>(&other.view(), &PROGRAM_ID);
+        assert!(matches!(result, Err(ProgramError::InvalidAccountData)));
+    }
+
+    #[test]
+    fn test_load_typed_checks_tag() {
+        let mut tagged = MockAccountView::new(ADDRESS, PROGRAM_ID, 0, &[7, 0]);
+        assert!(load_typed::<Tagged>(&tagged.view(), &PROGRAM_ID).is_ok());
+
+        let mut other = MockAccountView::new(ADDRESS, PROGRAM_ID, 0, &[8, 0]);
+        let result = load_typed::<Tagged>(&other.view(), &PROGRAM_ID);
+        assert!(matches!(result, Err(e) if e == CommonError::AccountTypeMismatch.into()));
+    }
+}
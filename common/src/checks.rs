@@ -0,0 +1,442 @@
+//! # Declarative Security Checks
+//!
+//! Secure Pinocchio handlers tend to open with a long run of `if` blocks, one
+//! per constraint Anchor would express in `#[derive(Accounts)]`.
+//! [`SecurityChecks`] lets a handler list those constraints as a chain and get
+//! back the first failure:
+//!
+//! ```ignore
+//! SecurityChecks::new(program_id)
+//!     .signer(withdrawer)
+//!     .owned(user_deposit_acc)
+//!     .pda(&[USER_DEPOSIT_SEED, treasury, owner], user_deposit_acc, user_deposit.bump)
+//!     .has_one(&user_deposit.owner, withdrawer)
+//!     .or(SecureError::Unauthorized)
+//!     .run()?;
+//! ```
+//!
+//! Checks run in the order they are listed. Once one fails, the remaining
+//! checks are skipped and [`SecurityChecks::run`] returns that failure.
+//!
+//! ## Anchor Comparison
+//! | Anchor | SecurityChecks |
+//! |--------|----------------|
+//! | `Signer<'info>` | `.signer(&acc)` |
+//! | `Account<'info, T>` (owner check) | `.owned(&acc)` |
+//! | `seeds = [...], bump = acc.bump` | `.pda(seeds, &acc, acc.bump)` |
+//! | `seeds = [...] @ E1, bump = acc.bump @ E2` | `.pda_address(seeds, &acc).or(E1).bump(acc.bump).or(E2)` |
+//! | `has_one = field` | `.has_one(&stored, &acc)` |
+//! | `Account<'info, T>` (never a sysvar or program) | `.not_sysvar_or_program(&acc)` |
+//! | `@ MyError::Variant` | `.or(MyError::Variant)` |
+
+use pinocchio::{error::ProgramError, AccountView, Address, ProgramResult};
+
+use crate::{error::CommonError, pda::find_program_address};
+
+/// Sysvar addresses that must never stand in for a program data account.
+pub const SYSVAR_IDS: [Address; 8] = [
+    // SysvarC1ock11111111111111111111111111111111
+    Address::new_from_array([
+        0x06, 0xa7, 0xd5, 0x17, 0x18, 0xc7, 0x74, 0xc9, 0x28, 0x56, 0x63, 0x98, 0x69, 0x1d, 0x5e,
+        0xb6, 0x8b, 0x5e, 0xb8, 0xa3, 0x9b, 0x4b, 0x6d, 0x5c, 0x73, 0x55, 0x5b, 0x21, 0x00, 0x00,
+        0x00, 0x00,
+    ]),
+    // SysvarRent111111111111111111111111111111111
+    Address::new_from_array([
+        0x06, 0xa7, 0xd5, 0x17, 0x19, 0x2c, 0x5c, 0x51, 0x21, 0x8c, 0xc9, 0x4c, 0x3d, 0x4a, 0xf1,
+        0x7f, 0x58, 0xda, 0xee, 0x08, 0x9b, 0xa1, 0xfd, 0x44, 0xe3, 0xdb, 0xd9, 0x8a, 0x00, 0x00,
+        0x00, 0x00,
+    ]),
+    // SysvarEpochSchedu1e111111111111111111111111
+    Address::new_from_array([
+        0x06, 0xa7, 0xd5, 0x17, 0x18, 0xdc, 0x3f, 0xee, 0x02, 0xd3, 0xe4, 0x7f, 0x01, 0x00, 0xf8,
+        0xb0, 0x54, 0xf7, 0x94, 0x2e, 0x60, 0x59, 0x1e, 0x3f, 0x50, 0x87, 0x19, 0xa8, 0x05, 0x00,
+        0x00, 0x00,
+    ]),
+    // SysvarEpochRewards1111111111111111111111111
+    Address::new_from_array([
+        0x06, 0xa7, 0xd5, 0x17, 0x18, 0xdc, 0x3f, 0xee, 0x02, 0xa5, 0x58, 0xbf, 0x83, 0xce, 0x66,
+        0xe1, 0x44, 0x42, 0x2a, 0x1c, 0x34, 0x95, 0x0b, 0x27, 0xc1, 0x86, 0x9b, 0x5a, 0x9c, 0x00,
+        0x00, 0x00,
+    ]),
+    // SysvarS1otHashes111111111111111111111111111
+    Address::new_from_array([
+        0x06, 0xa7, 0xd5, 0x17, 0x19, 0x2f, 0x0a, 0xaf, 0xc6, 0xf2, 0x65, 0xe3, 0xfb, 0x77, 0xcc,
+        0x7a, 0xda, 0x82, 0xc5, 0x29, 0xd0, 0xbe, 0x3b, 0x13, 0x6e, 0x2d, 0x00, 0x55, 0x20, 0x00,
+        0x00, 0x00,
+    ]),
+    // SysvarStakeHistory1111111111111111111111111
+    Address::new_from_array([
+        0x06, 0xa7, 0xd5, 0x17, 0x19, 0x35, 0x84, 0xd0, 0xfe, 0xed, 0x9b, 0xb3, 0x43, 0x1d, 0x13,
+        0x20, 0x6b, 0xe5, 0x44, 0x28, 0x1b, 0x57, 0xb8, 0x56, 0x6c, 0xc5, 0x37, 0x5f, 0xf4, 0x00,
+        0x00, 0x00,
+    ]),
+    // Sysvar1nstructions1111111111111111111111111
+    Address::new_from_array([
+        0x06, 0xa7, 0xd5, 0x17, 0x18, 0x7b, 0xd1, 0x66, 0x35, 0xda, 0xd4, 0x04, 0x55, 0xfd, 0xc2,
+        0xc0, 0xc1, 0x24, 0xc6, 0x8f, 0x21, 0x56, 0x75, 0xa5, 0xdb, 0xba, 0xcb, 0x5f, 0x08, 0x00,
+        0x00, 0x00,
+    ]),
+    // SysvarRecentB1ockHashes11111111111111111111
+    Address::new_from_array([
+        0x06, 0xa7, 0xd5, 0x17, 0x19, 0x2c, 0x56, 0x8e, 0xe0, 0x8a, 0x84, 0x5f, 0x73, 0xd2, 0x97,
+        0x88, 0xcf, 0x03, 0x5c, 0x31, 0x45, 0xb2, 0x1a, 0xb3, 0x44, 0xd8, 0x06, 0x2e, 0xa9, 0x40,
+        0x00, 0x00,
+    ]),
+];
+
+/// Reject `account` if it is the program itself or one of [`SYSVAR_IDS`].
+///
+/// A sysvar or the program account passed where a data account is expected
+/// must fail before anything parses or writes it. Fails with
+/// `InvalidAccountData`.
+pub fn assert_not_sysvar_or_program(account: &AccountView, program_id: &Address) -> ProgramResult {
+    let address = account.address();
+    if address == program_id || SYSVAR_IDS.contains(address) {
+        return Err(ProgramError::InvalidAccountData);
+    }
+    Ok(())
+}
+
+/// Most trailing accounts a batch instruction processes in one call.
+///
+/// Keeps a batch well inside the compute budget, so it can never run out of
+/// compute halfway through and leave some accounts updated and others not.
+pub const MAX_BATCH: usize = 16;
+
+/// Reject a batch of `len` accounts above [`MAX_BATCH`].
+///
+/// Call before touching any account so an oversized batch changes nothing.
+/// Fails with `CommonError::BatchTooLarge`.
+pub fn check_batch_len(len: usize) -> ProgramResult {
+    if len > MAX_BATCH {
+        return Err(CommonError::BatchTooLarge.into());
+    }
+    Ok(())
+}
+
+/// Ordered list of account constraints, evaluated until the first failure.
+#[must_use = "checks do nothing until `run()` is called"]
+pub struct SecurityChecks<'a> {
+    program_id: &'a Address,
+    result: ProgramResult,
+    /// Whether the most recent step is the one that failed, so `or` only
+    /// replaces that step's error.
+    last_failed: bool,
+    /// Canonical bump found by the latest `pda_address`, for `bump`.
+    canonical_bump: Option<u8>,
+}
+
+impl<'a> SecurityChecks<'a> {
+    /// Start a chain of checks for an instruction of `program_id`.
+    pub fn new(program_id: &'a Address) -> Self {
+        Self { program_id, result: Ok(()), last_failed: false, canonical_bump: None }
+    }
+
+    /// Require `account` to have signed the transaction.
+    ///
+    /// Fails with `MissingRequiredSignature`.
+    pub fn signer(self, account: &AccountView) -> Self {
+        self.step(|_| {
+            if !account.is_signer() {
+                return Err(ProgramError::MissingRequiredSignature);
+            }
+            Ok(())
+        })
+    }
+
+    /// Require `account` to be owned by the program.
+    ///
+    /// Fails with `IllegalOwner`.
+    pub fn owned(self, account: &AccountView) -> Self {
+        self.step(|program_id| {
+            if !account.owned_by(program_id) {
+                return Err(ProgramError::IllegalOwner);
+            }
+            Ok(())
+        })
+    }
+
+    /// Require `account` to be the PDA for `seeds`, with `stored_bump` equal
+    /// to the canonical bump.
+    ///
+    /// Fails with `CommonError::InvalidPda` on an address mismatch and
+    /// `CommonError::InvalidBump` on a non-canonical stored bump. Returns
+    /// `Err(CommonError::PdaDerivationFailed)` when no bump produces an
+    /// off-curve address for `seeds`.
+    pub fn pda(self, seeds: &[&[u8]], account: &AccountView, stored_bump: u8) -> Self {
+        self.step(|program_id| {
+            let (expected, canonical_bump) = find_program_address(seeds, program_id)?;
+            if account.address() != &expected {
+                return Err(CommonError::InvalidPda.into());
+            }
+            if stored_bump != canonical_bump {
+                return Err(CommonError::InvalidBump.into());
+            }
+            Ok(())
+        })
+    }
+
+    /// Require `account` to be the PDA for `seeds`, keeping the canonical bump
+    /// for a following [`SecurityChecks::bump`].
+    ///
+    /// Splits [`SecurityChecks::pda`] in two so each half can take its own
+    /// `.or()`. Fails with `CommonError::InvalidPda` on an address mismatch.
+    /// Returns `Err(CommonError::PdaDerivationFailed)` when no bump produces
+    /// an off-curve address for `seeds`, unless a following `.or()` replaces
+    /// it like any other failure of this step.
+    pub fn pda_address(mut self, seeds: &[&[u8]], account: &AccountView) -> Self {
+        let mut canonical_bump = None;
+        self = self.step(|program_id| {
+            let (expected, bump) = find_program_address(seeds, program_id)?;
+            if account.address() != &expected {
+                return Err(CommonError::InvalidPda.into());
+            }
+            canonical_bump = Some(bump);
+            Ok(())
+        });
+        self.canonical_bump = canonical_bump;
+        self
+    }
+
+    /// Require `stored_bump` to be the canonical bump found by the preceding
+    /// [`SecurityChecks::pda_address`].
+    ///
+    /// Fails with `CommonError::InvalidBump`, including when no address check
+    /// came first.
+    pub fn bump(self, stored_bump: u8) -> Self {
+        let canonical_bump = self.canonical_bump;
+        self.step(|_| {
+            if canonical_bump != Some(stored_bump) {
+                return Err(CommonError::InvalidBump.into());
+            }
+            Ok(())
+        })
+    }
+
+    /// Require `account` to be neither the program nor a sysvar.
+    ///
+    /// Fails with `InvalidAccountData`; see [`assert_not_sysvar_or_program`].
+    pub fn not_sysvar_or_program(self, account: &AccountView) -> Self {
+        self.step(|program_id| assert_not_sysvar_or_program(account, program_id))
+    }
+
+    /// Require an address stored in account data to match `account`.
+    ///
+    /// Fails with `CommonError::HasOneMismatch`.
+    pub fn has_one(self, stored: &Address, account: &AccountView) -> Self {
+        self.step(|_| {
+            if stored != account.address() {
+                return Err(CommonError::HasOneMismatch.into());
+            }
+            Ok(())
+        })
+    }
+
+    /// Replace the error of the preceding check, if that check failed.
+    ///
+    /// Lets a program keep its own error codes, like Anchor's `@ Error`.
+    pub fn or(mut self, error: impl Into<ProgramError>) -> Self {
+        if self.last_failed {
+            self.result = Err(error.into());
+        }
+        self
+    }
+
+    /// Return the first failure, or `Ok(())` if every check passed.
+    pub fn run(self) -> ProgramResult {
+        self.result
+    }
+
+    fn step(mut self, check: impl FnOnce(&Address) -> ProgramResult) -> Self {
+        self.last_failed = false;
+        if self.result.is_ok() {
+            if let Err(e) = check(self.program_id) {
+                self.result = Err(e);
+                self.last_failed = true;
+            }
+        }
+        self
+    }
+}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{decode_address, MockAccountView};
+
+    const PROGRAM_ID: Address = Address::new_from_array([9u8; 32]);
+    const SEED: &[u8] = b"vault";
+    const AUTHORITY: Address = Address::new_from_array([1u8; 32]);
+
+    fn pda_account() -> (MockAccountView, u8) {
+        let (address, bump) =
+            find_program_address(&[SEED, AUTHORITY.as_ref()], &PROGRAM_ID).unwrap();
+        (MockAccountView::new(address, PROGRAM_ID, 0, &[]), bump)
+    }
+
+    fn authority(signed: bool) -> MockAccountView {
+        let account = MockAccountView::new(AUTHORITY, Address::default(), 0, &[]);
+        if signed {
+            account.signer()
+        } else {
+            account
+        }
+    }
+
+    #[test]
+    fn test_check_batch_len() {
+        assert!(check_batch_len(0).is_ok());
+        assert!(check_batch_len(MAX_BATCH).is_ok());
+        assert_eq!(check_batch_len(MAX_BATCH + 1), Err(CommonError::BatchTooLarge.into()));
+    }
+
+    #[test]
+    fn test_all_checks_pass() {
+        let (mut vault, bump) = pda_account();
+        let mut authority = authority(true);
+        let (vault, authority) = (vault.view(), authority.view());
+
+        let result = SecurityChecks::new(&PROGRAM_ID)
+            .signer(&authority)
+            .owned(&vault)
+            .pda(&[SEED, AUTHORITY.as_ref()], &vault, bump)
+            .has_one(&AUTHORITY, &authority)
+            .run();
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn test_signer_failure() {
+        let mut authority = authority(false);
+        let result = SecurityChecks::new(&PROGRAM_ID).signer(&authority.view()).run();
+        assert_eq!(result, Err(ProgramError::MissingRequiredSignature));
+    }
+
+    #[test]
+    fn test_owned_failure() {
+        let mut foreign = MockAccountView::new(AUTHORITY, Address::default(), 0, &[]);
+        let result = SecurityChecks::new(&PROGRAM_ID).owned(&foreign.view()).run();
+        assert_eq!(result, Err(ProgramError::IllegalOwner));
+    }
+
+    #[test]
+    fn test_pda_address_failure() {
+        let (_, bump) = pda_account();
+        let mut fake = MockAccountView::new(Address::new_from_array([7u8; 32]), PROGRAM_ID, 0, &[]);
+        let result = SecurityChecks::new(&PROGRAM_ID)
+            .pda(&[SEED, AUTHORITY.as_ref()], &fake.view(), bump)
+            .run();
+        assert_eq!(result, Err(CommonError::InvalidPda.into()));
+    }
+
+    #[test]
+    fn test_pda_bump_failure() {
+        let (mut vault, bump) = pda_account();
+        let result = SecurityChecks::new(&PROGRAM_ID)
+            .pda(&[SEED, AUTHORITY.as_ref()], &vault.view(), bump.wrapping_sub(1))
+            .run();
+        assert_eq!(result, Err(CommonError::InvalidBump.into()));
+    }
+
+    #[test]
+    fn test_split_pda_checks_map_each_failure() {
+        let (mut vault, bump) = pda_account();
+        let vault = vault.view();
+        let seeds: &[&[u8]] = &[SEED, AUTHORITY.as_ref()];
+        let run = |account: &AccountView, stored_bump: u8| {
+            SecurityChecks::new(&PROGRAM_ID)
+                .pda_address(seeds, account)
+                .or(ProgramError::Custom(1))
+                .bump(stored_bump)
+                .or(ProgramError::Custom(2))
+                .run()
+        };
+
+        assert_eq!(run(&vault, bump), Ok(()));
+        assert_eq!(run(&vault, bump.wrapping_sub(1)), Err(ProgramError::Custom(2)));
+        let mut fake = MockAccountView::new(Address::new_from_array([7u8; 32]), PROGRAM_ID, 0, &[]);
+        assert_eq!(run(&fake.view(), bump), Err(ProgramError::Custom(1)));
+
+        // A bump check with no address check before it never passes
+        let result = SecurityChecks::new(&PROGRAM_ID).bump(bump).run();
+        assert_eq!(result, Err(CommonError::InvalidBump.into()));
+    }
+
+    #[test]
+    fn test_has_one_failure() {
+        let mut authority = authority(true);
+        let other = Address::new_from_array([2u8; 32]);
+        let result = SecurityChecks::new(&PROGRAM_ID).has_one(&other, &authority.view()).run();
+        assert_eq!(result, Err(CommonError::HasOneMismatch.into()));
+    }
+
+    #[test]
+    fn test_first_failure_wins_and_or_overrides_only_it() {
+        let mut unsigned = authority(false);
+        let mut foreign = MockAccountView::new(AUTHORITY, Address::default(), 0, &[]);
+        let (unsigned, foreign) = (unsigned.view(), foreign.view());
+
+        // The owner check also fails, but the signer failure comes first
+        let result = SecurityChecks::new(&PROGRAM_ID).signer(&unsigned).owned(&foreign).run();
+        assert_eq!(result, Err(ProgramError::MissingRequiredSignature));
+
+        // `or` maps the failing step's error
+        let result =
+            SecurityChecks::new(&PROGRAM_ID).signer(&unsigned).or(ProgramError::Custom(42)).run();
+        assert_eq!(result, Err(ProgramError::Custom(42)));
+
+        // ...but not the error of an earlier step
+        let result = SecurityChecks::new(&PROGRAM_ID)
+            .signer(&unsigned)
+            .owned(&foreign)
+            .or(ProgramError::Custom(42))
+            .run();
+        assert_eq!(result, Err(ProgramError::MissingRequiredSignature));
+
+        // ...and does nothing when the step passed
+        let mut signed = authority(true);
+        let result = SecurityChecks::new(&PROGRAM_ID)
+            .signer(&signed.view())
+            .or(ProgramError::Custom(42))
+            .run();
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn test_sysvar_ids_match_documented_addresses() {
+        let documented = [
+            "SysvarC1ock11111111111111111111111111111111",
+            "SysvarRent111111111111111111111111111111111",
+            "SysvarEpochSchedu1e111111111111111111111111",
+            "SysvarEpochRewards1111111111111111111111111",
+            "SysvarS1otHashes111111111111111111111111111",
+            "SysvarStakeHistory1111111111111111111111111",
+            "Sysvar1nstructions1111111111111111111111111",
+            "SysvarRecentB1ockHashes11111111111111111111",
+        ];
+        for (id, encoded) in SYSVAR_IDS.iter().zip(documented) {
+            assert_eq!(Some(*id), decode_address(encoded), "{encoded}");
+        }
+    }
+
+    #[test]
+    fn test_not_sysvar_or_program() {
+        let (mut vault, _) = pda_account();
+        let mut program = MockAccountView::new(PROGRAM_ID, Address::default(), 0, &[]);
+        let mut clock = MockAccountView::new(SYSVAR_IDS[0], Address::default(), 0, &[]);
+        let (vault, program, clock) = (vault.view(), program.view(), clock.view());
+
+        assert!(assert_not_sysvar_or_program(&vault, &PROGRAM_ID).is_ok());
+        assert_eq!(
+            assert_not_sysvar_or_program(&program, &PROGRAM_ID),
+            Err(ProgramError::InvalidAccountData)
+        );
+        let result = SecurityChecks::new(&PROGRAM_ID).not_sysvar_or_program(&clock).run();
+        assert_eq!(result, Err(ProgramError::InvalidAccountData));
+    }
+}
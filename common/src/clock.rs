@@ -0,0 +1,176 @@
+//! # Clock Abstraction
+//!
+//! Time-based checks (cooldowns, timelocks, daily caps, staleness) need the
+//! current slot and timestamp, but the Clock sysvar only exists on-chain.
+//!
+//! Handlers take `&impl ClockSource` instead of reading the sysvar directly:
+//! - On-chain: pass a [`SyscallClock`] read once at the start of the instruction
+//! - In tests: pass a [`MockClock`] and move time forward explicitly
+//!
+//! ## Anchor Comparison
+//! ```ignore
+//! // Anchor: sysvar read inline, hard to unit test
+//! let now = Clock::get()?.unix_timestamp;
+//!
+//! // Pinocchio + ClockSource: time injected by the caller
+//! fn withdraw(clock: &impl ClockSource, ...) -> ProgramResult {
+//!     assert_timelock_elapsed(clock, vault.unlock_timestamp)?;
+//! }
+//! ```
+
+use pinocchio::{error::ProgramError, ProgramResult};
+
+#[cfg(target_os = "solana")]
+use pinocchio::sysvars::{clock::Clock, Sysvar};
+
+use crate::error::CommonError;
+
+// =============================================================================
+// CLOCK SOURCE
+// =============================================================================
+
+/// Source of the current slot and Unix timestamp.
+pub trait ClockSource {
+    /// Current slot.
+    fn slot(&self) -> u64;
+
+    /// Current Unix timestamp in seconds, as reported by the cluster.
+    fn unix_timestamp(&self) -> i64;
+}
+
+/// Clock backed by the Clock sysvar.
+///
+/// The sysvar is read once in [`SyscallClock::get`] and the values are cached,
+/// so a handler can consult the clock repeatedly without extra syscalls.
+#[derive(Clone, Copy, Debug)]
+pub struct SyscallClock {
+    slot: u64,
+    unix_timestamp: i64,
+}
+
+impl SyscallClock {
+    /// Read the Clock sysvar.
+    ///
+    /// Off-chain there is no sysvar, so this returns `UnsupportedSysvar`;
+    /// tests should use [`MockClock`] instead.
+    #[cfg(target_os = "solana")]
+    pub fn get() -> Result<Self, ProgramError> {
+        let clock = Clock::get()?;
+        Ok(Self { slot: clock.slot, unix_timestamp: clock.unix_timestamp })
+    }
+
+    /// Off-chain stub of [`SyscallClock::get`].
+    #[cfg(not(target_os = "solana"))]
+    pub fn get() -> Result<Self, ProgramError> {
+        Err(ProgramError::UnsupportedSysvar)
+    }
+}
+
+impl ClockSource for SyscallClock {
+    fn slot(&self) -> u64 {
+        self.slot
+    }
+
+    fn unix_timestamp(&self) -> i64 {
+        self.unix_timestamp
+    }
+}
+
+/// Clock with caller-controlled time, for tests.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct MockClock {
+    /// Slot returned by [`ClockSource::slot`].
+    pub slot: u64,
+    /// Timestamp returned by [`ClockSource::unix_timestamp`].
+    pub unix_timestamp: i64,
+}
+
+impl MockClock {
+    /// Create a clock frozen at the given slot and timestamp.
+    pub fn new(slot: u64, unix_timestamp: i64) -> Self {
+        Self { slot, unix_timestamp }
+    }
+
+    /// Move the clock forward by `slots` slots.
+    pub fn advance_slots(&mut self, slots: u64) {
+        self.slot = self.slot.saturating_add(slots);
+    }
+
+    /// Move the clock forward by `seconds` seconds.
+    pub fn advance_seconds(&mut self, seconds: i64) {
+        self.unix_timestamp = self.unix_timestamp.saturating_add(seconds);
+    }
+}
+
+impl ClockSource for MockClock {
+    fn slot(&self) -> u64 {
+        self.slot
+    }
+
+    fn unix_timestamp(&self) -> i64 {
+        self.unix_timestamp
+    }
+}
+
+// =============================================================================
+// TIME-BASED CHECKS
+// =============================================================================
+
+/// Rejects the operation until `unlock_timestamp` has been reached.
+///
+/// // SECURITY: The unlock time is compared against the cluster clock, never
+/// // against a timestamp supplied in instruction data.
+pub fn assert_timelock_elapsed(clock: &impl ClockSource, unlock_timestamp: i64) -> ProgramResult {
+    if clock.unix_timestamp() < unlock_timestamp {
+        return Err(CommonError::TimelockActive.into());
+    }
+
+    Ok(())
+}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const UNLOCK_AT: i64 = 1_700_000_000;
+
+    #[test]
+    fn test_timelock_rejects_before_unlock() {
+        let clock = MockClock::new(100, UNLOCK_AT - 1);
+        let result = assert_timelock_elapsed(&clock, UNLOCK_AT);
+        assert!(matches!(result, Err(ProgramError::Custom(0x2000))));
+    }
+
+    #[test]
+    fn test_timelock_allows_at_and_after_unlock() {
+        let mut clock = MockClock::new(100, UNLOCK_AT - 60);
+        assert!(assert_timelock_elapsed(&clock, UNLOCK_AT).is_err());
+
+        clock.advance_seconds(60);
+        assert!(assert_timelock_elapsed(&clock, UNLOCK_AT).is_ok());
+
+        clock.advance_seconds(1);
+        assert!(assert_timelock_elapsed(&clock, UNLOCK_AT).is_ok());
+    }
+
+    #[test]
+    fn test_mock_clock_advances() {
+        let mut clock = MockClock::new(10, 1_000);
+        clock.advance_slots(5);
+        clock.advance_seconds(30);
+        assert_eq!(clock.slot(), 15);
+        assert_eq!(clock.unix_timestamp(), 1_030);
+
+        clock.advance_slots(u64::MAX);
+        assert_eq!(clock.slot(), u64::MAX);
+    }
+
+    #[test]
+    fn test_syscall_clock_unavailable_off_chain() {
+        assert!(matches!(SyscallClock::get(), Err(ProgramError::UnsupportedSysvar)));
+    }
+}
@@ -0,0 +1,245 @@
+//! # Ed25519 Curve Check
+//!
+//! A PDA is, by construction, an address that is *not* a valid ed25519 point:
+//! `find_program_address` keeps decrementing the bump until the hash lands
+//! off the curve, so no private key can ever sign for it. Wallet keys are
+//! always on the curve.
+//!
+//! Comparing an account against a re-derived PDA already rules out wallet
+//! keys, but checking the curve directly gives a specific error when a wallet
+//! is passed where a program address is expected (and works even where the
+//! seeds are not known).
+//!
+//! - On-chain: the `sol_curve_validate_point` syscall
+//! - Off-chain: a reference decompression check over GF(2^255 - 19), so
+//!   tests classify addresses exactly as the runtime does
+//!
+//! ## Anchor Comparison
+//! ```ignore
+//! // Anchor / solana-program
+//! let is_wallet = pubkey.is_on_curve();
+//!
+//! // Pinocchio + this crate
+//! let is_wallet = is_on_curve(address);
+//! ```
+
+use pinocchio::Address;
+
+#[cfg(target_os = "solana")]
+use pinocchio::syscalls;
+
+/// Curve id for edwards25519 in `sol_curve_validate_point`.
+#[cfg(target_os = "solana")]
+const CURVE25519_EDWARDS: u64 = 0;
+
+/// Returns true if `address` decompresses to a valid ed25519 point.
+#[cfg(target_os = "solana")]
+pub fn is_on_curve(address: &Address) -> bool {
+    let mut result = 0u8;
+    let status = unsafe {
+        syscalls::sol_curve_validate_point(
+            CURVE25519_EDWARDS,
+            address as *const _ as *const u8,
+            &mut result as *mut u8,
+        )
+    };
+    status == 0
+}
+
+/// Returns true if `address` decompresses to a valid ed25519 point.
+///
+/// Off-chain reference implementation: `address` is read as a compressed
+/// point `(y, sign)` and is on the curve iff `x^2 = (y^2 - 1) / (d*y^2 + 1)`
+/// has a solution.
+#[cfg(not(target_os = "solana"))]
+pub fn is_on_curve(address: &Address) -> bool {
+    field::is_decompressible(address.as_array())
+}
+
+/// Minimal arithmetic modulo p = 2^255 - 19 on four little-endian u64 limbs.
+///
+/// Values are kept below 2^256 between operations and only reduced fully
+/// for comparison. Not constant-time; for off-chain tests only.
+#[cfg(not(target_os = "solana"))]
+mod field {
+    type Fe = [u64; 4];
+
+    const P: Fe = [0xffff_ffff_ffff_ffed, u64::MAX, u64::MAX, 0x7fff_ffff_ffff_ffff];
+
+    const ONE: Fe = [1, 0, 0, 0];
+
+    /// Edwards d = -121665 / 121666
+    const D: Fe = [
+        0x75eb_4dca_1359_78a3,
+        0x0070_0a4d_4141_d8ab,
+        0x8cc7_4079_7779_e898,
+        0x5203_6cee_2b6f_fe73,
+    ];
+
+    /// (p - 5) / 8 = 2^252 - 3
+    const P_MINUS_5_OVER_8: Fe = [0xffff_ffff_ffff_fffd, u64::MAX, u64::MAX, 0x0fff_ffff_ffff_ffff];
+
+    pub fn is_decompressible(bytes: &[u8; 32]) -> bool {
+        let mut y = [0u64; 4];
+        for (limb, chunk) in y.iter_mut().zip(bytes.chunks_exact(8)) {
+            *limb = u64::from_le_bytes(chunk.try_into().unwrap());
+        }
+        // Top bit is the sign of x, not part of y
+        y[3] &= 0x7fff_ffff_ffff_ffff;
+
+        let yy = mul(&y, &y);
+        let u = sub(&yy, &ONE);
+        let v = add(&mul(&D, &yy), &ONE);
+
+        // Candidate root r = u * v^3 * (u * v^7)^((p - 5) / 8)
+        let v3 = mul(&mul(&v, &v), &v);
+        let v7 = mul(&mul(&v3, &v3), &v);
+        let r = mul(&mul(&u, &v3), &pow(&mul(&u, &v7), &P_MINUS_5_OVER_8));
+
+        // u/v is a square iff v * r^2 is +u or -u
+        let check = canonical(&mul(&v, &mul(&r, &r)));
+        check == canonical(&u) || check == canonical(&sub(&[0; 4], &u))
+    }
+
+    fn add(a: &Fe, b: &Fe) -> Fe {
+        let mut out = [0u64; 4];
+        let mut carry = 0u128;
+        for i in 0..4 {
+            let sum = a[i] as u128 + b[i] as u128 + carry;
+            out[i] = sum as u64;
+            carry = sum >> 64;
+        }
+        // 2^256 = 38 (mod p)
+        if carry != 0 {
+            out = add(&out, &[38, 0, 0, 0]);
+        }
+        out
+    }
+
+    fn sub(a: &Fe, b: &Fe) -> Fe {
+        let mut out = [0u64; 4];
+        let mut borrow = false;
+        for i in 0..4 {
+            let (d1, b1) = a[i].overflowing_sub(b[i]);
+            let (d2, b2) = d1.overflowing_sub(borrow as u64);
+            out[i] = d2;
+            borrow = b1 || b2;
+        }
+        // Wrapped by 2^256, which is 38 too much (mod p)
+        if borrow {
+            out = sub(&out, &[38, 0, 0, 0]);
+        }
+        out
+    }
+
+    fn mul(a: &Fe, b: &Fe) -> Fe {
+        let mut wide = [0u64; 8];
+        for i in 0..4 {
+            let mut carry = 0u128;
+            for j in 0..4 {
+                let t = wide[i + j] as u128 + a[i] as u128 * b[j] as u128 + carry;
+                wide[i + j] = t as u64;
+                carry = t >> 64;
+            }
+            wide[i + 4] = carry as u64;
+        }
+
+        // Fold the high half back in: hi * 2^256 = hi * 38 (mod p)
+        let mut out = [0u64; 4];
+        let mut carry = 0u128;
+        for i in 0..4 {
+            let t = wide[i] as u128 + wide[i + 4] as u128 * 38 + carry;
+            out[i] = t as u64;
+            carry = t >> 64;
+        }
+        add(&out, &[(carry * 38) as u64, 0, 0, 0])
+    }
+
+    fn pow(base: &Fe, exp: &Fe) -> Fe {
+        let mut result = ONE;
+        for i in (0..256).rev() {
+            result = mul(&result, &result);
+            if (exp[i / 64] >> (i % 64)) & 1 == 1 {
+                result = mul(&result, base);
+            }
+        }
+        result
+    }
+
+    /// Fully reduce into [0, p).
+    fn canonical(a: &Fe) -> Fe {
+        let mut out = *a;
+        while !less_than(&out, &P) {
+            out = sub_no_wrap(&out, &P);
+        }
+        out
+    }
+
+    fn less_than(a: &Fe, b: &Fe) -> bool {
+        for i in (0..4).rev() {
+            if a[i] != b[i] {
+                return a[i] < b[i];
+            }
+        }
+        false
+    }
+
+    fn sub_no_wrap(a: &Fe, b: &Fe) -> Fe {
+        let mut out = [0u64; 4];
+        let mut borrow = false;
+        for i in 0..4 {
+            let (d1, b1) = a[i].overflowing_sub(b[i]);
+            let (d2, b2) = d1.overflowing_sub(borrow as u64);
+            out[i] = d2;
+            borrow = b1 || b2;
+        }
+        out
+    }
+}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn y(value: u8) -> Address {
+        let mut bytes = [0u8; 32];
+        bytes[0] = value;
+        Address::new_from_array(bytes)
+    }
+
+    #[test]
+    fn test_known_points_are_on_curve() {
+        // Identity (y = 1) and the ed25519 base point
+        assert!(is_on_curve(&y(1)));
+
+        let mut base = [0x66u8; 32];
+        base[0] = 0x58;
+        assert!(is_on_curve(&Address::new_from_array(base)));
+    }
+
+    #[test]
+    fn test_known_off_curve_values() {
+        // y = 2, 7, 8, 11 have no x on edwards25519
+        for value in [2, 7, 8, 11] {
+            assert!(!is_on_curve(&y(value)), "y = {value}");
+        }
+        for value in [0, 3, 4, 5, 6, 9, 10] {
+            assert!(is_on_curve(&y(value)), "y = {value}");
+        }
+    }
+
+    #[test]
+    fn test_sign_bit_is_ignored_for_y() {
+        let mut bytes = [0u8; 32];
+        bytes[0] = 7;
+        bytes[31] = 0x80;
+        assert!(!is_on_curve(&Address::new_from_array(bytes)));
+
+        bytes[0] = 9;
+        assert!(is_on_curve(&Address::new_from_array(bytes)));
+    }
+}
@@ -0,0 +1,130 @@
+//! # Shared Error Codes
+//!
+//! Error codes that mean the same thing in every pattern program, so clients
+//! can match on one value instead of a per-pattern variant.
+//!
+//! | Code | Variant | Meaning |
+//! |------|---------|---------|
+//! | `0x2000` | `TimelockActive` | Operation attempted before its unlock time |
+//! | `0x2001` | `InsufficientBalance` | Withdrawal exceeds the recorded balance |
+//! | `0x2002` | `InvalidPda` | Account is not the PDA for the expected seeds |
+//! | `0x2003` | `InvalidBump` | Stored bump is not the canonical bump |
+//! | `0x2004` | `HasOneMismatch` | Stored address does not match the supplied account |
+//! | `0x2005` | `AccountTypeMismatch` | Account data length or type tag is not the expected type's |
+//! | `0x2006` | `UnsupportedVersion` | Account data was written by a newer schema version |
+//! | `0x2007` | `AccountClosed` | Account carries the closed tag and cannot be read or reinitialized |
+//! | `0x2008` | `BatchTooLarge` | Batch instruction was given more than `MAX_BATCH` accounts |
+//! | `0x2009` | `AccountNotWritable` | Account the instruction writes was passed read-only |
+//! | `0x200a` | `AddressMismatch` | Account is not the exact address the instruction expects |
+//! | `0x200b` | `PdaDerivationFailed` | No bump yields an off-curve address for the seeds |
+
+use pinocchio::error::ProgramError;
+
+/// Error codes for the shared helpers.
+///
+/// Uses the 0x2000 range so they never collide with per-pattern error enums.
+#[repr(u32)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CommonError {
+    /// Returned when an operation is attempted before its unlock time.
+    TimelockActive = 0x2000,
+
+    /// Returned when a withdrawal asks for more than the caller's recorded balance.
+    /// Every pattern's `withdraw` maps "not enough funds" to this code.
+    InsufficientBalance = 0x2001,
+
+    /// Returned by `SecurityChecks::pda` and `SecurityChecks::pda_address`
+    /// when the account address does not match the address derived from the
+    /// seeds.
+    InvalidPda = 0x2002,
+
+    /// Returned by `SecurityChecks::pda` and `SecurityChecks::bump` when the
+    /// stored bump is not the canonical bump for the seeds.
+    InvalidBump = 0x2003,
+
+    /// Returned by `SecurityChecks::has_one` when an address stored in account
+    /// data does not match the supplied account.
+    HasOneMismatch = 0x2004,
+
+    /// Returned by `load_typed` when account data is not exactly the
+    /// expected type's length or does not start with its type tag.
+    AccountTypeMismatch = 0x2005,
+
+    /// Returned by `check_schema_version` when account data carries a layout
+    /// version newer than the program understands.
+    UnsupportedVersion = 0x2006,
+
+    /// Returned by `check_schema_version` and `check_not_closed` when the
+    /// version byte holds `CLOSED_ACCOUNT_TAG`.
+    AccountClosed = 0x2007,
+
+    /// Returned by `check_batch_len` when a batch instruction is given more
+    /// than `MAX_BATCH` accounts.
+    BatchTooLarge = 0x2008,
+
+    /// Returned by `Role::writable` when the account was not passed as
+    /// writable.
+    AccountNotWritable = 0x2009,
+
+    /// Returned by `Role::address_is` when the account is not the expected
+    /// address.
+    AddressMismatch = 0x200a,
+
+    /// Returned by `find_program_address` when no bump in `0..=255` yields
+    /// an off-curve address for the seeds.
+    PdaDerivationFailed = 0x200b,
+}
+
+impl From<CommonError> for ProgramError {
+    fn from(e: CommonError) -> Self {
+        ProgramError::Custom(e as u32)
+    }
+}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_error_conversion() {
+        let err: ProgramError = CommonError::TimelockActive.into();
+        assert!(matches!(err, ProgramError::Custom(0x2000)));
+
+        let err: ProgramError = CommonError::InsufficientBalance.into();
+        assert!(matches!(err, ProgramError::Custom(0x2001)));
+
+        let err: ProgramError = CommonError::InvalidPda.into();
+        assert!(matches!(err, ProgramError::Custom(0x2002)));
+
+        let err: ProgramError = CommonError::InvalidBump.into();
+        assert!(matches!(err, ProgramError::Custom(0x2003)));
+
+        let err: ProgramError = CommonError::HasOneMismatch.into();
+        assert!(matches!(err, ProgramError::Custom(0x2004)));
+
+        let err: ProgramError = CommonError::AccountTypeMismatch.into();
+        assert!(matches!(err, ProgramError::Custom(0x2005)));
+
+        let err: ProgramError = CommonError::UnsupportedVersion.into();
+        assert!(matches!(err, ProgramError::Custom(0x2006)));
+
+        let err: ProgramError = CommonError::AccountClosed.into();
+        assert!(matches!(err, ProgramError::Custom(0x2007)));
+
+        let err: ProgramError = CommonError::BatchTooLarge.into();
+        assert!(matches!(err, ProgramError::Custom(0x2008)));
+
+        let err: ProgramError = CommonError::AccountNotWritable.into();
+        assert!(matches!(err, ProgramError::Custom(0x2009)));
+
+        let err: ProgramError = CommonError::AddressMismatch.into();
+        assert!(matches!(err, ProgramError::Custom(0x200a)));
+
+        let err: ProgramError = CommonError::PdaDerivationFailed.into();
+        assert!(matches!(err, ProgramError::Custom(0x200b)));
+    }
+}
@@ -0,0 +1,64 @@
+//! # Pinocchio Security Common
+//!
+//! Shared building blocks for the Pinocchio security pattern programs.
+//!
+//! Each pattern program stays self-contained for readability; this crate only
+//! holds plumbing that several patterns need and that would otherwise be
+//! duplicated (and drift) across programs.
+//!
+//! ## Modules
+//! - [`account`] - `load_typed` owner/length/tag check before parsing account data, `check_schema_version`, the closed-account tag, the `check_uninitialized` re-initialization guard and `zero_account_data`/`close_account_data` for wiping closed accounts
+//! - [`checks`] - `SecurityChecks` builder for declarative account validation, sysvar/program denylist, `MAX_BATCH` cap
+//! - [`clock`] - `ClockSource` abstraction so time-based checks are testable off-chain
+//! - [`curve`] - `is_on_curve` ed25519 check for telling PDAs from wallet keys
+//! - [`error`] - Error codes shared by every pattern (`CommonError`)
+//! - [`math`] - `SafeMath` checked `u64` arithmetic returning a caller-chosen error
+//! - [`pda`] - `find_program_address` with an off-chain stand-in for tests, `address_hex` for logging derived addresses
+//! - [`redact`] - `format_address`/`format_amount` for logs that hide values under a `redact` feature
+//! - [`rent`] - `rent_exempt_minimum` from the Rent sysvar, with cluster defaults off-chain
+//! - [`role`] - `Role` per-account signer/owner/writable/address assertions
+//! - [`system`] - `create_account_signed`, `transfer`, `allocate_signed` and `assign_signed` System Program CPIs for creating and funding PDA accounts
+//! - [`testing`] - `MockAccountView`, `ProgramTest`, `decode_address`, `assert_reinit_rejected` and `XorShift64` for off-chain tests (`test-utils` feature)
+
+#![allow(unexpected_cfgs)]
+
+pub mod account;
+pub mod checks;
+pub mod clock;
+pub mod curve;
+pub mod error;
+pub mod math;
+pub mod pda;
+pub mod redact;
+pub mod rent;
+pub mod role;
+pub mod system;
+#[cfg(any(test, feature = "test-utils"))]
+pub mod testing;
+
+pub use account::{
+    check_not_closed, check_schema_version, check_uninitialized, close_account_data, load_typed,
+    zero_account_data, AccountData, CLOSED_ACCOUNT_TAG,
+};
+pub use checks::{
+    assert_not_sysvar_or_program, check_batch_len, SecurityChecks, MAX_BATCH, SYSVAR_IDS,
+};
+pub use clock::{ClockSource, MockClock, SyscallClock};
+pub use curve::is_on_curve;
+pub use error::CommonError;
+pub use math::SafeMath;
+pub use pda::{address_hex, find_program_address};
+pub use redact::{
+    format_address, format_amount, ADDRESS_LOG_LEN, AMOUNT_LOG_LEN, REDACTED_ADDRESS_BYTES,
+    REDACTED_AMOUNT,
+};
+pub use rent::{minimum_balance, rent_exempt_minimum, rent_shortfall};
+pub use role::Role;
+pub use system::{
+    allocate_signed, assign_signed, create_account_signed, transfer, SYSTEM_PROGRAM_ID,
+};
+#[cfg(any(test, feature = "test-utils"))]
+pub use testing::{
+    account_hash, assert_reinit_rejected, decode_address, AccountMeta, MockAccountView,
+    ProgramTest, XorShift64,
+};
@@ -0,0 +1,81 @@
+//! # Checked Arithmetic with Domain Errors
+//!
+//! Every pattern spells checked arithmetic out in full:
+//!
+//! ```ignore
+//! let total = vault.total.checked_add(amount).ok_or(SecureError::ArithmeticOverflow)?;
+//! ```
+//!
+//! The `.ok_or(...)` tail is easy to copy with the wrong error (a subtraction
+//! reporting overflow). [`SafeMath`] puts the error next to the operation:
+//!
+//! ```ignore
+//! let total = vault.total.add_or(amount, SecureError::ArithmeticOverflow)?;
+//! let balance = user.balance.sub_or(amount, SecureError::ArithmeticUnderflow)?;
+//! ```
+//!
+//! ## Anchor Comparison
+//! Anchor programs write the same `checked_*` + `ok_or(error!(...))` chains;
+//! the framework adds nothing here, so the helper applies to both.
+
+/// Checked `u64` arithmetic that fails with a caller-chosen error.
+pub trait SafeMath: Sized {
+    /// `self + rhs`, or `err` on overflow.
+    fn add_or<E>(self, rhs: Self, err: E) -> Result<Self, E>;
+
+    /// `self - rhs`, or `err` on underflow.
+    fn sub_or<E>(self, rhs: Self, err: E) -> Result<Self, E>;
+
+    /// `self * rhs`, or `err` on overflow.
+    fn mul_or<E>(self, rhs: Self, err: E) -> Result<Self, E>;
+}
+
+impl SafeMath for u64 {
+    #[inline]
+    fn add_or<E>(self, rhs: Self, err: E) -> Result<Self, E> {
+        self.checked_add(rhs).ok_or(err)
+    }
+
+    #[inline]
+    fn sub_or<E>(self, rhs: Self, err: E) -> Result<Self, E> {
+        self.checked_sub(rhs).ok_or(err)
+    }
+
+    #[inline]
+    fn mul_or<E>(self, rhs: Self, err: E) -> Result<Self, E> {
+        self.checked_mul(rhs).ok_or(err)
+    }
+}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, PartialEq)]
+    enum TestError {
+        Overflow,
+        Underflow,
+    }
+
+    #[test]
+    fn test_add_or() {
+        assert_eq!(40u64.add_or(2, TestError::Overflow), Ok(42));
+        assert_eq!(u64::MAX.add_or(1, TestError::Overflow), Err(TestError::Overflow));
+    }
+
+    #[test]
+    fn test_sub_or() {
+        assert_eq!(44u64.sub_or(2, TestError::Underflow), Ok(42));
+        assert_eq!(0u64.sub_or(1, TestError::Underflow), Err(TestError::Underflow));
+    }
+
+    #[test]
+    fn test_mul_or() {
+        assert_eq!(21u64.mul_or(2, TestError::Overflow), Ok(42));
+        assert_eq!((1u64 << 32).mul_or(1 << 32, TestError::Overflow), Err(TestError::Overflow));
+    }
+}
@@ -0,0 +1,178 @@
+//! # PDA Derivation
+//!
+//! `find_program_address` for Pinocchio programs, shared so that every
+//! helper that re-derives a PDA (e.g. [`crate::checks::SecurityChecks::pda`])
+//! agrees with the program on the address and canonical bump.
+//!
+//! - On-chain: the `sol_try_find_program_address` syscall
+//! - Off-chain: a deterministic stand-in so unit tests run without the runtime
+//!
+//! Both return `CommonError::PdaDerivationFailed` instead of panicking when
+//! every bump lands on the curve, so the caller's instruction fails with an
+//! error code rather than an opaque abort.
+//!
+//! [`address_hex`] renders a derived address for `derive_address`
+//! instructions, which log it so clients can check their own derivation
+//! against the program's in a simulation.
+//!
+//! ## Anchor Comparison
+//! ```ignore
+//! // Anchor: derivation hidden behind the constraint
+//! #[account(seeds = [...], bump)]
+//!
+//! // Pinocchio: derive explicitly, then compare
+//! let (expected, canonical_bump) = find_program_address(&[...], program_id)?;
+//! ```
+
+use pinocchio::{error::ProgramError, Address};
+
+use crate::error::CommonError;
+
+#[cfg(target_os = "solana")]
+use pinocchio::syscalls;
+
+#[cfg(not(target_os = "solana"))]
+use crate::curve::is_on_curve;
+
+/// Find a valid program derived address and its canonical bump seed.
+///
+/// This wraps the `sol_try_find_program_address` syscall to derive PDAs
+/// on-chain. The syscall efficiently finds the canonical (highest valid)
+/// bump seed that produces an off-curve address.
+///
+/// Fails with `CommonError::PdaDerivationFailed` if no bump is viable.
+#[cfg(target_os = "solana")]
+#[inline]
+pub fn find_program_address(
+    seeds: &[&[u8]],
+    program_id: &Address,
+) -> Result<(Address, u8), ProgramError> {
+    let mut pda_bytes = core::mem::MaybeUninit::<[u8; 32]>::uninit();
+    let mut bump_seed = u8::MAX;
+
+    let result = unsafe {
+        syscalls::sol_try_find_program_address(
+            seeds as *const _ as *const u8,
+            seeds.len() as u64,
+            program_id as *const _ as *const u8,
+            pda_bytes.as_mut_ptr() as *mut u8,
+            &mut bump_seed as *mut u8,
+        )
+    };
+
+    if result == 0 {
+        Ok((Address::new_from_array(unsafe { pda_bytes.assume_init() }), bump_seed))
+    } else {
+        Err(CommonError::PdaDerivationFailed.into())
+    }
+}
+
+/// Test-only implementation of find_program_address.
+///
+/// This is a simplified implementation for unit tests that don't run on Solana.
+/// It produces deterministic addresses for testing serialization/deserialization,
+/// but does NOT perform actual cryptographic PDA derivation. Like the real
+/// derivation, it walks the bump down from 255 and returns the first candidate
+/// that is off the ed25519 curve.
+///
+/// Fails with `CommonError::PdaDerivationFailed` if no bump is viable.
+///
+/// **WARNING:** This implementation is NOT secure and should ONLY be used in tests.
+/// Real PDA derivation requires the Solana runtime syscall.
+#[cfg(not(target_os = "solana"))]
+#[inline]
+pub fn find_program_address(
+    seeds: &[&[u8]],
+    program_id: &Address,
+) -> Result<(Address, u8), ProgramError> {
+    search_bumps(seeds, program_id, |candidate| !is_on_curve(candidate))
+}
+
+/// Walks the bump down from 255 and returns the first candidate `viable`
+/// accepts. Split out so tests can simulate a search where no bump works.
+#[cfg(not(target_os = "solana"))]
+fn search_bumps(
+    seeds: &[&[u8]],
+    program_id: &Address,
+    viable: impl Fn(&Address) -> bool,
+) -> Result<(Address, u8), ProgramError> {
+    for bump in (0..=u8::MAX).rev() {
+        // For non-Solana targets (tests), we create a deterministic but
+        // not cryptographically correct address. This allows unit tests
+        // to run without the Solana runtime.
+        let mut hasher_input = Vec::new();
+        for seed in seeds {
+            hasher_input.extend_from_slice(seed);
+        }
+        hasher_input.push(bump);
+        hasher_input.extend_from_slice(program_id.as_ref());
+
+        // Simple XOR hash for testing - NOT cryptographically secure
+        let mut result = [0u8; 32];
+        for (i, byte) in hasher_input.iter().enumerate() {
+            result[i % 32] ^= byte;
+            result[(i + 7) % 32] = result[(i + 7) % 32].wrapping_add(*byte);
+        }
+
+        let candidate = Address::new_from_array(result);
+        if viable(&candidate) {
+            return Ok((candidate, bump));
+        }
+    }
+
+    Err(CommonError::PdaDerivationFailed.into())
+}
+
+/// Lowercase hex of `address`, for logging it in a fixed, parseable form.
+pub fn address_hex(address: &Address) -> [u8; 64] {
+    const DIGITS: &[u8; 16] = b"0123456789abcdef";
+
+    let mut hex = [0u8; 64];
+    for (i, byte) in address.as_ref().iter().enumerate() {
+        hex[2 * i] = DIGITS[(byte >> 4) as usize];
+        hex[2 * i + 1] = DIGITS[(byte & 0x0f) as usize];
+    }
+    hex
+}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_derivation_is_deterministic_and_off_curve() {
+        let program_id = Address::new_from_array([9u8; 32]);
+        let seeds: &[&[u8]] = &[b"treasury", &[1u8; 32]];
+
+        let (pda, bump) = find_program_address(seeds, &program_id).unwrap();
+        assert_eq!(find_program_address(seeds, &program_id), Ok((pda, bump)));
+        assert!(!is_on_curve(&pda));
+
+        let (other, _) = find_program_address(&[b"treasury", &[2u8; 32]], &program_id).unwrap();
+        assert_ne!(pda, other);
+    }
+
+    #[test]
+    fn test_exhausted_bump_search_returns_error() {
+        // Every candidate rejected, as if all 256 bumps landed on the curve
+        let program_id = Address::new_from_array([9u8; 32]);
+        let result = search_bumps(&[b"treasury"], &program_id, |_| false);
+        assert_eq!(result, Err(CommonError::PdaDerivationFailed.into()));
+    }
+
+    #[test]
+    fn test_address_hex() {
+        let mut bytes = [0u8; 32];
+        bytes[0] = 0xab;
+        bytes[31] = 0x09;
+        let hex = address_hex(&Address::new_from_array(bytes));
+
+        assert_eq!(&hex[..4], b"ab00");
+        assert_eq!(&hex[60..], b"0009");
+        assert!(hex[4..60].iter().all(|&c| c == b'0'));
+    }
+}
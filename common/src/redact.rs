@@ -0,0 +1,103 @@
+//! # Log Redaction
+//!
+//! Security logs are most useful with the addresses and amounts involved, but
+//! production logs are public and permanent. Programs that offer a `redact`
+//! feature format those values with these helpers instead of logging them
+//! raw:
+//!
+//! - [`format_address`] - full lowercase hex, or only the first
+//!   [`REDACTED_ADDRESS_BYTES`] bytes followed by `..`
+//! - [`format_amount`] - the decimal amount, or [`REDACTED_AMOUNT`]
+//!
+//! Both write into a caller-provided buffer and return a `&str`, which every
+//! `log!` backend accepts.
+//!
+//! ## Anchor Comparison
+//! Anchor's `msg!` has no redaction either; the same helpers work there.
+
+use pinocchio::Address;
+
+use crate::pda::address_hex;
+
+/// Leading address bytes kept in a redacted log line.
+pub const REDACTED_ADDRESS_BYTES: usize = 4;
+
+/// Logged in place of an amount when redacting.
+pub const REDACTED_AMOUNT: &str = "<redacted>";
+
+/// Buffer size that fits any [`format_address`] output.
+pub const ADDRESS_LOG_LEN: usize = 64;
+
+/// Buffer size that fits any [`format_amount`] output (`u64::MAX` has 20 digits).
+pub const AMOUNT_LOG_LEN: usize = 20;
+
+/// Formats `address` for a log line, shortened when `redact` is set.
+pub fn format_address<'a>(
+    address: &Address,
+    redact: bool,
+    buf: &'a mut [u8; ADDRESS_LOG_LEN],
+) -> &'a str {
+    *buf = address_hex(address);
+    let len = if redact {
+        let shown = 2 * REDACTED_ADDRESS_BYTES;
+        buf[shown..shown + 2].copy_from_slice(b"..");
+        shown + 2
+    } else {
+        ADDRESS_LOG_LEN
+    };
+    // Only ASCII hex digits and dots were written
+    core::str::from_utf8(&buf[..len]).unwrap_or_default()
+}
+
+/// Formats `amount` for a log line, hidden when `redact` is set.
+pub fn format_amount(amount: u64, redact: bool, buf: &mut [u8; AMOUNT_LOG_LEN]) -> &str {
+    if redact {
+        return REDACTED_AMOUNT;
+    }
+
+    let mut start = AMOUNT_LOG_LEN;
+    let mut rest = amount;
+    loop {
+        start -= 1;
+        buf[start] = b'0' + (rest % 10) as u8;
+        rest /= 10;
+        if rest == 0 {
+            break;
+        }
+    }
+    core::str::from_utf8(&buf[start..]).unwrap_or_default()
+}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_address() {
+        let mut bytes = [0u8; 32];
+        bytes[..4].copy_from_slice(&[0xde, 0xad, 0xbe, 0xef]);
+        bytes[31] = 0x01;
+        let address = Address::new_from_array(bytes);
+
+        let mut buf = [0u8; ADDRESS_LOG_LEN];
+        let full = format_address(&address, false, &mut buf);
+        assert_eq!(full.len(), 64);
+        assert!(full.starts_with("deadbeef00") && full.ends_with("01"));
+
+        let mut buf = [0u8; ADDRESS_LOG_LEN];
+        assert_eq!(format_address(&address, true, &mut buf), "deadbeef..");
+    }
+
+    #[test]
+    fn test_format_amount() {
+        let mut buf = [0u8; AMOUNT_LOG_LEN];
+        assert_eq!(format_amount(0, false, &mut buf), "0");
+        assert_eq!(format_amount(1_500, false, &mut buf), "1500");
+        assert_eq!(format_amount(u64::MAX, false, &mut buf), "18446744073709551615");
+        assert_eq!(format_amount(1_500, true, &mut buf), REDACTED_AMOUNT);
+    }
+}
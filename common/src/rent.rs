@@ -0,0 +1,111 @@
+//! # Rent Exemption
+//!
+//! An account whose lamports fall below the rent-exempt minimum for its data
+//! length can be reclaimed by the runtime. Handlers that create accounts need
+//! the minimum to top them up; handlers that debit lamports out of a program
+//! account need it to refuse withdrawals that would leave the account short.
+//!
+//! - On-chain: [`rent_exempt_minimum`] reads the Rent sysvar
+//! - Off-chain: it uses the default cluster values, so tests see the same
+//!   numbers `getMinimumBalanceForRentExemption` returns on a local validator
+//!
+//! ## Anchor Comparison
+//! ```ignore
+//! // Anchor / solana-program
+//! let minimum = Rent::get()?.minimum_balance(data_len);
+//!
+//! // Pinocchio + this crate
+//! let minimum = rent_exempt_minimum(data_len)?;
+//! ```
+
+use pinocchio::error::ProgramError;
+
+#[cfg(target_os = "solana")]
+use pinocchio::syscalls;
+
+/// Bytes of per-account metadata the runtime charges rent for on top of data.
+const ACCOUNT_STORAGE_OVERHEAD: u64 = 128;
+
+/// Default cluster rent values, used by the off-chain rent stub.
+#[cfg(not(target_os = "solana"))]
+const DEFAULT_LAMPORTS_PER_BYTE_YEAR: u64 = 3_480;
+#[cfg(not(target_os = "solana"))]
+const DEFAULT_EXEMPTION_THRESHOLD: f64 = 2.0;
+
+/// In-memory layout of the Rent sysvar as written by `sol_get_rent_sysvar`.
+#[cfg(target_os = "solana")]
+#[repr(C)]
+struct RentSysvar {
+    lamports_per_byte_year: u64,
+    exemption_threshold: f64,
+    // Part of the sysvar layout; not needed for the exemption calculation
+    #[allow(dead_code)]
+    burn_percent: u8,
+}
+
+/// Minimum balance for an account with `data_len` bytes to be rent-exempt.
+///
+/// Same formula as `Rent::minimum_balance` in the Solana SDK.
+pub fn minimum_balance(
+    lamports_per_byte_year: u64,
+    exemption_threshold: f64,
+    data_len: usize,
+) -> u64 {
+    let bytes = ACCOUNT_STORAGE_OVERHEAD.saturating_add(data_len as u64);
+    (bytes.saturating_mul(lamports_per_byte_year) as f64 * exemption_threshold) as u64
+}
+
+/// Reads the Rent sysvar and returns the rent-exempt minimum for `data_len` bytes.
+#[cfg(target_os = "solana")]
+pub fn rent_exempt_minimum(data_len: usize) -> Result<u64, ProgramError> {
+    let mut rent = core::mem::MaybeUninit::<RentSysvar>::uninit();
+
+    let result = unsafe { syscalls::sol_get_rent_sysvar(rent.as_mut_ptr() as *mut u8) };
+    if result != 0 {
+        return Err(ProgramError::UnsupportedSysvar);
+    }
+
+    let rent = unsafe { rent.assume_init() };
+    Ok(minimum_balance(rent.lamports_per_byte_year, rent.exemption_threshold, data_len))
+}
+
+/// Test-only implementation of rent_exempt_minimum.
+///
+/// There is no Rent sysvar off-chain, so this uses the default cluster values.
+#[cfg(not(target_os = "solana"))]
+pub fn rent_exempt_minimum(data_len: usize) -> Result<u64, ProgramError> {
+    Ok(minimum_balance(DEFAULT_LAMPORTS_PER_BYTE_YEAR, DEFAULT_EXEMPTION_THRESHOLD, data_len))
+}
+
+/// Lamports that must be added to `current_lamports` to reach `minimum`.
+///
+/// Zero when the account is already rent-exempt, e.g. pre-funded by the client.
+#[inline]
+pub fn rent_shortfall(current_lamports: u64, minimum: u64) -> u64 {
+    minimum.saturating_sub(current_lamports)
+}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rent_exempt_minimum_matches_cluster_default() {
+        // Same values `getMinimumBalanceForRentExemption` returns on a default cluster
+        assert_eq!(rent_exempt_minimum(0).unwrap(), 890_880);
+        assert_eq!(rent_exempt_minimum(73).unwrap(), 1_398_960);
+        assert_eq!(minimum_balance(3_480, 2.0, 0), 890_880);
+    }
+
+    #[test]
+    fn test_rent_shortfall() {
+        assert_eq!(rent_shortfall(0, 100), 100);
+        assert_eq!(rent_shortfall(40, 100), 60);
+        assert_eq!(rent_shortfall(100, 100), 0);
+        assert_eq!(rent_shortfall(150, 100), 0);
+    }
+}
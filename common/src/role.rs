@@ -0,0 +1,153 @@
+//! # Per-Account Role Assertions
+//!
+//! [`SecurityChecks`](crate::checks::SecurityChecks) lists an instruction's
+//! constraints across accounts. [`Role`] states what one account must be, in
+//! one expression next to where the account is named:
+//!
+//! ```ignore
+//! Role::of(caller).signer().check()?;
+//! Role::of(admin_config_acc).owned_by(program_id).writable().check()?;
+//! Role::of(vault_acc).address_is(&expected_vault).check()?;
+//! ```
+//!
+//! Assertions run in the order they are written; [`Role::check`] returns the
+//! first failure.
+//!
+//! ## Anchor Comparison
+//! | Anchor | Role |
+//! |--------|------|
+//! | `Signer<'info>` | `.signer()` |
+//! | `Account<'info, T>` (owner check) | `.owned_by(program_id)` |
+//! | `#[account(mut)]` | `.writable()` |
+//! | `#[account(address = expected)]` | `.address_is(&expected)` |
+
+use pinocchio::{error::ProgramError, AccountView, Address, ProgramResult};
+
+use crate::error::CommonError;
+
+/// Assertions about a single account, evaluated until the first failure.
+#[must_use = "role assertions do nothing until `check()` is called"]
+pub struct Role<'a> {
+    account: &'a AccountView,
+    result: ProgramResult,
+}
+
+impl<'a> Role<'a> {
+    /// Start asserting the role of `account`.
+    pub fn of(account: &'a AccountView) -> Self {
+        Self { account, result: Ok(()) }
+    }
+
+    /// Require the account to have signed the transaction.
+    ///
+    /// Fails with `MissingRequiredSignature`.
+    pub fn signer(self) -> Self {
+        let ok = self.account.is_signer();
+        self.step(ok, ProgramError::MissingRequiredSignature)
+    }
+
+    /// Require the account to be owned by `owner`.
+    ///
+    /// Fails with `IllegalOwner`.
+    pub fn owned_by(self, owner: &Address) -> Self {
+        let ok = self.account.owned_by(owner);
+        self.step(ok, ProgramError::IllegalOwner)
+    }
+
+    /// Require the account to be passed as writable.
+    ///
+    /// Fails with `CommonError::AccountNotWritable`.
+    pub fn writable(self) -> Self {
+        let ok = self.account.is_writable();
+        self.step(ok, CommonError::AccountNotWritable.into())
+    }
+
+    /// Require the account to be exactly `expected`.
+    ///
+    /// Fails with `CommonError::AddressMismatch`.
+    pub fn address_is(self, expected: &Address) -> Self {
+        let ok = self.account.address() == expected;
+        self.step(ok, CommonError::AddressMismatch.into())
+    }
+
+    /// Return the first failure, or `Ok(())` if every assertion held.
+    pub fn check(self) -> ProgramResult {
+        self.result
+    }
+
+    fn step(mut self, ok: bool, error: ProgramError) -> Self {
+        if self.result.is_ok() && !ok {
+            self.result = Err(error);
+        }
+        self
+    }
+}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::MockAccountView;
+
+    const PROGRAM_ID: Address = Address::new_from_array([9u8; 32]);
+    const ADDRESS: Address = Address::new_from_array([1u8; 32]);
+
+    fn account() -> MockAccountView {
+        MockAccountView::new(ADDRESS, PROGRAM_ID, 0, &[])
+    }
+
+    #[test]
+    fn test_all_roles_hold() {
+        let mut acc = account().signer().writable();
+        let result = Role::of(&acc.view())
+            .signer()
+            .owned_by(&PROGRAM_ID)
+            .writable()
+            .address_is(&ADDRESS)
+            .check();
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn test_signer_failure() {
+        let result = Role::of(&account().view()).signer().check();
+        assert_eq!(result, Err(ProgramError::MissingRequiredSignature));
+    }
+
+    #[test]
+    fn test_owned_by_failure() {
+        let result = Role::of(&account().view()).owned_by(&Address::default()).check();
+        assert_eq!(result, Err(ProgramError::IllegalOwner));
+    }
+
+    #[test]
+    fn test_writable_failure() {
+        let result = Role::of(&account().view()).writable().check();
+        assert_eq!(result, Err(CommonError::AccountNotWritable.into()));
+    }
+
+    #[test]
+    fn test_address_is_failure() {
+        let other = Address::new_from_array([2u8; 32]);
+        let result = Role::of(&account().view()).address_is(&other).check();
+        assert_eq!(result, Err(CommonError::AddressMismatch.into()));
+    }
+
+    #[test]
+    fn test_first_failure_wins() {
+        // Unsigned and read-only: the assertion written first is reported
+        let mut acc = account();
+        let acc = acc.view();
+        assert_eq!(
+            Role::of(&acc).writable().signer().check(),
+            Err(CommonError::AccountNotWritable.into())
+        );
+        assert_eq!(
+            Role::of(&acc).signer().writable().check(),
+            Err(ProgramError::MissingRequiredSignature)
+        );
+    }
+}
@@ -0,0 +1,269 @@
+//! # System Program CPI
+//!
+//! PDA accounts are created by the program that owns their seeds: it asks the
+//! System Program to `CreateAccount` and signs for the new address with the
+//! PDA seeds. [`create_account_signed`] builds that instruction in one place,
+//! so every caller funds the account to the rent-exempt minimum and hands it
+//! to the right owner. [`transfer`] moves lamports out of a wallet that
+//! signed the transaction, such as a depositor funding a PDA.
+//!
+//! `CreateAccount` fails for an address that already holds lamports, and
+//! anyone can send lamports to a PDA before it is created. [`allocate_signed`]
+//! and [`assign_signed`] finish creating such a pre-funded PDA, the same way
+//! Anchor's `init` does.
+//!
+//! The instruction is always sent to [`SYSTEM_PROGRAM_ID`], never to the
+//! address of a caller-supplied account, so a substituted "system program"
+//! cannot intercept the CPI.
+//!
+//! ## Anchor Comparison
+//! ```ignore
+//! // Anchor: `init` performs the CPI
+//! #[account(init, payer = authority, space = SIZE, seeds = [...], bump)]
+//!
+//! // Pinocchio + this crate
+//! create_account_signed(authority, treasury_acc, program_id, SIZE, &signer_seeds)?;
+//!
+//! // Anchor: system_program::transfer(cpi_ctx, amount)
+//! transfer(depositor, treasury_acc, amount)?;
+//!
+//! // Anchor: `init` on an address that already holds lamports
+//! transfer(authority, treasury_acc, shortfall)?;
+//! allocate_signed(treasury_acc, SIZE, &signer_seeds)?;
+//! assign_signed(treasury_acc, program_id, &signer_seeds)?;
+//! ```
+
+use pinocchio::{
+    cpi::{invoke, invoke_signed, Seed, Signer},
+    instruction::{InstructionAccount, InstructionView},
+    AccountView, Address, ProgramResult,
+};
+
+use crate::rent::rent_exempt_minimum;
+
+/// Address of the System Program.
+pub const SYSTEM_PROGRAM_ID: Address = Address::new_from_array([0u8; 32]);
+
+/// System Program instruction index for `CreateAccount`.
+const CREATE_ACCOUNT_DISCRIMINATOR: u32 = 0;
+
+/// System Program instruction index for `Assign`.
+const ASSIGN_DISCRIMINATOR: u32 = 1;
+
+/// System Program instruction index for `Transfer`.
+const TRANSFER_DISCRIMINATOR: u32 = 2;
+
+/// System Program instruction index for `Allocate`.
+const ALLOCATE_DISCRIMINATOR: u32 = 8;
+
+/// Length of `CreateAccount` instruction data:
+/// discriminator (4) + lamports (8) + space (8) + owner (32).
+pub const CREATE_ACCOUNT_DATA_LEN: usize = 52;
+
+/// Builds the `CreateAccount` instruction data.
+pub fn create_account_data(
+    lamports: u64,
+    space: u64,
+    owner: &Address,
+) -> [u8; CREATE_ACCOUNT_DATA_LEN] {
+    let mut data = [0u8; CREATE_ACCOUNT_DATA_LEN];
+    data[0..4].copy_from_slice(&CREATE_ACCOUNT_DISCRIMINATOR.to_le_bytes());
+    data[4..12].copy_from_slice(&lamports.to_le_bytes());
+    data[12..20].copy_from_slice(&space.to_le_bytes());
+    data[20..52].copy_from_slice(owner.as_ref());
+    data
+}
+
+/// Length of `Transfer` instruction data: discriminator (4) + lamports (8).
+pub const TRANSFER_DATA_LEN: usize = 12;
+
+/// Builds the `Transfer` instruction data.
+pub fn transfer_data(lamports: u64) -> [u8; TRANSFER_DATA_LEN] {
+    let mut data = [0u8; TRANSFER_DATA_LEN];
+    data[0..4].copy_from_slice(&TRANSFER_DISCRIMINATOR.to_le_bytes());
+    data[4..12].copy_from_slice(&lamports.to_le_bytes());
+    data
+}
+
+/// Length of `Allocate` instruction data: discriminator (4) + space (8).
+pub const ALLOCATE_DATA_LEN: usize = 12;
+
+/// Builds the `Allocate` instruction data.
+pub fn allocate_data(space: u64) -> [u8; ALLOCATE_DATA_LEN] {
+    let mut data = [0u8; ALLOCATE_DATA_LEN];
+    data[0..4].copy_from_slice(&ALLOCATE_DISCRIMINATOR.to_le_bytes());
+    data[4..12].copy_from_slice(&space.to_le_bytes());
+    data
+}
+
+/// Length of `Assign` instruction data: discriminator (4) + owner (32).
+pub const ASSIGN_DATA_LEN: usize = 36;
+
+/// Builds the `Assign` instruction data.
+pub fn assign_data(owner: &Address) -> [u8; ASSIGN_DATA_LEN] {
+    let mut data = [0u8; ASSIGN_DATA_LEN];
+    data[0..4].copy_from_slice(&ASSIGN_DISCRIMINATOR.to_le_bytes());
+    data[4..36].copy_from_slice(owner.as_ref());
+    data
+}
+
+/// Builds the `CreateAccount` account metas: funding payer, then the new
+/// account. Both must be writable signers.
+pub fn create_account_metas<'a>(
+    payer: &'a Address,
+    new_account: &'a Address,
+) -> [InstructionAccount<'a>; 2] {
+    [InstructionAccount::writable_signer(payer), InstructionAccount::writable_signer(new_account)]
+}
+
+/// Creates `new_account` with `space` bytes owned by `owner`, funded by
+/// `payer` with the rent-exempt minimum.
+///
+/// `bump_seeds` are the PDA seeds of `new_account`, bump included; the program
+/// signs for the new address with them. The System Program rejects the call
+/// if `new_account` already holds lamports or data.
+pub fn create_account_signed<const N: usize>(
+    payer: &AccountView,
+    new_account: &AccountView,
+    owner: &Address,
+    space: usize,
+    bump_seeds: &[Seed; N],
+) -> ProgramResult {
+    let lamports = rent_exempt_minimum(space)?;
+    let data = create_account_data(lamports, space as u64, owner);
+    let accounts = create_account_metas(payer.address(), new_account.address());
+
+    let instruction =
+        InstructionView { program_id: &SYSTEM_PROGRAM_ID, accounts: &accounts, data: &data };
+
+    let signer = Signer::from(bump_seeds);
+    invoke_signed::<2>(&instruction, &[payer, new_account], &[signer])
+}
+
+/// Moves `lamports` from `from` to `to` through the System Program.
+///
+/// `from` must be a writable signer owned by the System Program; the System
+/// Program rejects the call if it holds fewer than `lamports`.
+pub fn transfer(from: &AccountView, to: &AccountView, lamports: u64) -> ProgramResult {
+    let data = transfer_data(lamports);
+    let accounts = [
+        InstructionAccount::writable_signer(from.address()),
+        InstructionAccount::writable(to.address()),
+    ];
+
+    let instruction =
+        InstructionView { program_id: &SYSTEM_PROGRAM_ID, accounts: &accounts, data: &data };
+
+    invoke::<2>(&instruction, &[from, to])
+}
+
+/// Gives `account` `space` bytes of data, signing for it with `bump_seeds`.
+///
+/// `account` must still be owned by the System Program with no data, such as
+/// a pre-funded PDA that `CreateAccount` would reject.
+pub fn allocate_signed<const N: usize>(
+    account: &AccountView,
+    space: usize,
+    bump_seeds: &[Seed; N],
+) -> ProgramResult {
+    let data = allocate_data(space as u64);
+    let accounts = [InstructionAccount::writable_signer(account.address())];
+
+    let instruction =
+        InstructionView { program_id: &SYSTEM_PROGRAM_ID, accounts: &accounts, data: &data };
+
+    let signer = Signer::from(bump_seeds);
+    invoke_signed::<1>(&instruction, &[account], &[signer])
+}
+
+/// Hands `account` to `owner`, signing for it with `bump_seeds`.
+///
+/// Called after [`allocate_signed`]; once assigned, only `owner` can write
+/// the account's data.
+pub fn assign_signed<const N: usize>(
+    account: &AccountView,
+    owner: &Address,
+    bump_seeds: &[Seed; N],
+) -> ProgramResult {
+    let data = assign_data(owner);
+    let accounts = [InstructionAccount::writable_signer(account.address())];
+
+    let instruction =
+        InstructionView { program_id: &SYSTEM_PROGRAM_ID, accounts: &accounts, data: &data };
+
+    let signer = Signer::from(bump_seeds);
+    invoke_signed::<1>(&instruction, &[account], &[signer])
+}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::MockAccountView;
+
+    #[test]
+    fn test_create_account_data() {
+        let owner = Address::new_from_array([7u8; 32]);
+        let data = create_account_data(1_000, 82, &owner);
+
+        assert_eq!(&data[0..4], &0u32.to_le_bytes());
+        assert_eq!(&data[4..12], &1_000u64.to_le_bytes());
+        assert_eq!(&data[12..20], &82u64.to_le_bytes());
+        assert_eq!(&data[20..52], owner.as_ref());
+    }
+
+    #[test]
+    fn test_transfer_data() {
+        let data = transfer_data(1_500);
+
+        assert_eq!(&data[0..4], &2u32.to_le_bytes());
+        assert_eq!(&data[4..12], &1_500u64.to_le_bytes());
+    }
+
+    #[test]
+    fn test_allocate_and_assign_data() {
+        let data = allocate_data(82);
+        assert_eq!(&data[0..4], &8u32.to_le_bytes());
+        assert_eq!(&data[4..12], &82u64.to_le_bytes());
+
+        let owner = Address::new_from_array([7u8; 32]);
+        let data = assign_data(&owner);
+        assert_eq!(&data[0..4], &1u32.to_le_bytes());
+        assert_eq!(&data[4..36], owner.as_ref());
+    }
+
+    #[test]
+    fn test_create_account_metas() {
+        let payer = Address::new_from_array([1u8; 32]);
+        let new_account = Address::new_from_array([2u8; 32]);
+        let metas = create_account_metas(&payer, &new_account);
+
+        assert_eq!(metas[0].address, &payer);
+        assert!(metas[0].is_writable && metas[0].is_signer);
+        assert_eq!(metas[1].address, &new_account);
+        assert!(metas[1].is_writable && metas[1].is_signer);
+    }
+
+    #[test]
+    fn test_create_account_signed() {
+        let owner = Address::new_from_array([7u8; 32]);
+        let mut payer = MockAccountView::new(
+            Address::new_from_array([1u8; 32]),
+            SYSTEM_PROGRAM_ID,
+            10_000_000,
+            &[],
+        )
+        .signer()
+        .writable();
+        let mut new_account =
+            MockAccountView::new(Address::new_from_array([2u8; 32]), SYSTEM_PROGRAM_ID, 0, &[])
+                .writable();
+        let seeds = [Seed::from(b"seed"), Seed::from(&[255u8])];
+
+        let result = create_account_signed(&payer.view(), &new_account.view(), &owner, 82, &seeds);
+        assert_eq!(result, Ok(()));
+    }
+}
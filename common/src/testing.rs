@@ -0,0 +1,538 @@
+//! # Test Accounts
+//!
+//! `AccountView` is a thin pointer into the runtime's serialized input buffer,
+//! so handlers cannot be called off-chain without something that lays an
+//! account out the same way the runtime does.
+//!
+//! [`MockAccountView`] owns that memory: a `RuntimeAccount` header followed by
+//! the account data. Tests build one per account, pick the signer/writable
+//! flags the instruction should see, and pass `view()` to the handler.
+//!
+//! ```ignore
+//! let mut user_balance = MockAccountView::new(address, program_id, 0, &data);
+//! let mut owner = MockAccountView::new(owner_key, SYSTEM, 0, &[]).signer();
+//!
+//! // user_balance is left read-only, so a handler that mutates it must reject
+//! let result = withdraw(&[user_balance.view(), owner.view()], &amount);
+//! ```
+//!
+//! [`ProgramTest`] holds a set of mocks across several instructions and
+//! dispatches each one through a program's `process_instruction`, so a test
+//! can run a whole flow and check the state after every step:
+//!
+//! ```ignore
+//! let mut test = ProgramTest::new(ID, process_instruction);
+//! let treasury = test.add_account(treasury_pda, ID, 1_000_000, &[0; TREASURY_SIZE]);
+//! let authority = test.add_account(authority_key, SYSTEM, 0, &[]);
+//!
+//! test.process(&[AccountMeta::writable(treasury), AccountMeta::readonly(authority).signer()], &[0])?;
+//! assert_eq!(test.account(treasury).lamports(), 1_000_000);
+//! ```
+//!
+//! [`MockAccountView::untouchable`] makes every borrow of an account fail, so
+//! a test can show a handler rejected without reading it.
+//!
+//! [`account_hash`] fingerprints account data, so a test can snapshot an
+//! account and assert a no-op or dry run left it exactly as it was.
+//!
+//! [`assert_reinit_rejected`] runs an init-style instruction against an
+//! account that already holds data and checks it is refused untouched, so
+//! every program's re-initialization guard is tested the same way:
+//!
+//! ```ignore
+//! let mut config = MockAccountView::new(config_key, ID, 0, &initialized).writable();
+//! assert_reinit_rejected(&mut config, |config| {
+//!     initialize_config(&ID, &[config.view(), super_admin.view()], &[255])
+//! });
+//! ```
+//!
+//! [`decode_address`] turns a base58 program ID back into bytes, so a test can
+//! check a hardcoded `ID` array against the address it is documented as.
+//!
+//! [`XorShift64`] drives randomized tests from a fixed seed, so a failing
+//! sequence replays identically on every run.
+//!
+//! Only compiled for tests or with the `test-utils` feature.
+
+use core::mem::size_of;
+
+use pinocchio::{
+    account::RuntimeAccount, error::ProgramError, AccountView, Address, ProgramResult,
+};
+
+/// Borrow state of an account nobody has borrowed yet.
+const NOT_BORROWED: u8 = u8::MAX;
+
+/// Borrow state of an account whose data is mutably borrowed.
+const MUTABLY_BORROWED: u8 = 0;
+
+/// Spare bytes after the data, mirroring the runtime's realloc headroom.
+const MAX_PERMITTED_DATA_INCREASE: usize = 10 * 1024;
+
+/// An account laid out in memory the way the runtime hands it to a program.
+///
+/// Accounts start out read-only and unsigned, like an account passed without
+/// any `[writable]` / `[signer]` marker.
+#[derive(Clone)]
+pub struct MockAccountView {
+    // u64 words keep the header 8-byte aligned, as in the input buffer
+    buffer: Vec<u64>,
+}
+
+impl MockAccountView {
+    /// Create a read-only, unsigned account holding `data`.
+    pub fn new(address: Address, owner: Address, lamports: u64, data: &[u8]) -> Self {
+        let header = size_of::<RuntimeAccount>();
+        let len = header + data.len() + MAX_PERMITTED_DATA_INCREASE;
+        let mut account = Self { buffer: vec![0u64; len.div_ceil(8)] };
+
+        // SAFETY: the buffer is zeroed, aligned, and at least `header` bytes long
+        unsafe {
+            account.header_mut().write(RuntimeAccount {
+                borrow_state: NOT_BORROWED,
+                is_signer: 0,
+                is_writable: 0,
+                executable: 0,
+                resize_delta: 0,
+                address,
+                owner,
+                lamports,
+                data_len: data.len() as u64,
+            });
+        }
+        account.bytes_mut()[header..header + data.len()].copy_from_slice(data);
+
+        account
+    }
+
+    /// Mark the account as a signer of the instruction.
+    pub fn signer(mut self) -> Self {
+        // SAFETY: the header was written in `new`
+        unsafe { (*self.header_mut()).is_signer = 1 };
+        self
+    }
+
+    /// Mark the account as writable by the instruction.
+    pub fn writable(mut self) -> Self {
+        // SAFETY: the header was written in `new`
+        unsafe { (*self.header_mut()).is_writable = 1 };
+        self
+    }
+
+    /// Hold the account's data as if mutably borrowed elsewhere, so any
+    /// `try_borrow` / `try_borrow_mut` by the handler fails with
+    /// `AccountBorrowFailed`.
+    ///
+    /// Proves a handler rejected before reading the account at all.
+    pub fn untouchable(mut self) -> Self {
+        // SAFETY: the header was written in `new`
+        unsafe { (*self.header_mut()).borrow_state = MUTABLY_BORROWED };
+        self
+    }
+
+    fn set_flags(&mut self, is_signer: bool, is_writable: bool) {
+        // SAFETY: the header was written in `new`
+        unsafe {
+            (*self.header_mut()).is_signer = is_signer as u8;
+            (*self.header_mut()).is_writable = is_writable as u8;
+        }
+    }
+
+    /// View of the account to pass to a handler.
+    ///
+    /// The view points into this mock, so post-state written by the handler
+    /// can be read back with [`MockAccountView::data`] and
+    /// [`MockAccountView::lamports`].
+    pub fn view(&mut self) -> AccountView {
+        // SAFETY: the buffer holds a valid header followed by `data_len` bytes
+        unsafe { AccountView::new_unchecked(self.header_mut()) }
+    }
+
+    /// Current account data.
+    pub fn data(&self) -> &[u8] {
+        let header = size_of::<RuntimeAccount>();
+        &self.bytes()[header..header + self.header().data_len as usize]
+    }
+
+    /// Current lamport balance.
+    pub fn lamports(&self) -> u64 {
+        self.header().lamports
+    }
+
+    fn header(&self) -> &RuntimeAccount {
+        // SAFETY: the header was written in `new` and the buffer is aligned
+        unsafe { &*(self.buffer.as_ptr() as *const RuntimeAccount) }
+    }
+
+    fn header_mut(&mut self) -> *mut RuntimeAccount {
+        self.buffer.as_mut_ptr() as *mut RuntimeAccount
+    }
+
+    fn bytes(&self) -> &[u8] {
+        // SAFETY: reinterpreting initialized u64 words as bytes
+        unsafe {
+            core::slice::from_raw_parts(self.buffer.as_ptr() as *const u8, self.buffer.len() * 8)
+        }
+    }
+
+    fn bytes_mut(&mut self) -> &mut [u8] {
+        // SAFETY: reinterpreting initialized u64 words as bytes
+        unsafe {
+            core::slice::from_raw_parts_mut(
+                self.buffer.as_mut_ptr() as *mut u8,
+                self.buffer.len() * 8,
+            )
+        }
+    }
+}
+
+// =============================================================================
+// PROGRAM TEST
+// =============================================================================
+
+/// Signature of a program's `process_instruction` entrypoint.
+pub type ProcessInstruction = fn(&Address, &[AccountView], &[u8]) -> ProgramResult;
+
+/// One account of an instruction passed to [`ProgramTest::process`].
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct AccountMeta {
+    index: usize,
+    is_signer: bool,
+    is_writable: bool,
+}
+
+impl AccountMeta {
+    /// Pass the account at `index` read-only.
+    pub fn readonly(index: usize) -> Self {
+        Self { index, is_signer: false, is_writable: false }
+    }
+
+    /// Pass the account at `index` writable.
+    pub fn writable(index: usize) -> Self {
+        Self { index, is_signer: false, is_writable: true }
+    }
+
+    /// Also mark the account as a signer.
+    pub fn signer(mut self) -> Self {
+        self.is_signer = true;
+        self
+    }
+}
+
+/// In-memory accounts plus the program that runs instructions against them.
+///
+/// Like the runtime, a failed instruction leaves every account as it was
+/// before the instruction started. CPIs are not executed.
+pub struct ProgramTest {
+    program_id: Address,
+    process_instruction: ProcessInstruction,
+    accounts: Vec<MockAccountView>,
+}
+
+impl ProgramTest {
+    /// Create an empty test for `program_id`.
+    pub fn new(program_id: Address, process_instruction: ProcessInstruction) -> Self {
+        Self { program_id, process_instruction, accounts: Vec::new() }
+    }
+
+    /// Add an account and return the index instructions refer to it by.
+    pub fn add_account(
+        &mut self,
+        address: Address,
+        owner: Address,
+        lamports: u64,
+        data: &[u8],
+    ) -> usize {
+        self.accounts.push(MockAccountView::new(address, owner, lamports, data));
+        self.accounts.len() - 1
+    }
+
+    /// Current state of the account at `index`.
+    pub fn account(&self, index: usize) -> &MockAccountView {
+        &self.accounts[index]
+    }
+
+    /// Run one instruction with `accounts` in the given order.
+    ///
+    /// An account listed more than once is a signer or writable if any of
+    /// its entries is, as in the runtime.
+    pub fn process(&mut self, accounts: &[AccountMeta], instruction_data: &[u8]) -> ProgramResult {
+        for meta in accounts {
+            let same = accounts.iter().filter(|other| other.index == meta.index);
+            let is_signer = same.clone().any(|other| other.is_signer);
+            let is_writable = same.clone().any(|other| other.is_writable);
+            self.accounts[meta.index].set_flags(is_signer, is_writable);
+        }
+
+        let snapshot = self.accounts.clone();
+        let views: Vec<AccountView> =
+            accounts.iter().map(|meta| self.accounts[meta.index].view()).collect();
+
+        let result = (self.process_instruction)(&self.program_id, &views, instruction_data);
+        if result.is_err() {
+            self.accounts = snapshot;
+        }
+        result
+    }
+}
+
+// =============================================================================
+// RE-INITIALIZATION
+// =============================================================================
+
+/// Asserts that `init` refuses to initialize `account` a second time.
+///
+/// `account` must already hold initialized data. `init` runs the instruction
+/// with it; the result must be `AccountAlreadyInitialized` and the data must
+/// be unchanged.
+pub fn assert_reinit_rejected(
+    account: &mut MockAccountView,
+    init: impl FnOnce(&mut MockAccountView) -> ProgramResult,
+) {
+    let before = account.data().to_vec();
+    assert_eq!(init(account), Err(ProgramError::AccountAlreadyInitialized));
+    assert_eq!(account.data(), &before[..], "rejected re-initialization modified the account");
+}
+
+/// Bitcoin/Solana base58 alphabet.
+const BASE58_ALPHABET: &[u8; 58] = b"123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+
+/// Decode a base58 string into a 32-byte address.
+///
+/// Returns `None` for a non-alphabet character or a value that does not fit
+/// in exactly 32 bytes.
+pub fn decode_address(encoded: &str) -> Option<Address> {
+    let mut bytes = [0u8; 32];
+    for c in encoded.bytes() {
+        let mut carry = BASE58_ALPHABET.iter().position(|&a| a == c)? as u32;
+        // bytes is big-endian: multiply by 58 and add the digit
+        for byte in bytes.iter_mut().rev() {
+            carry += *byte as u32 * 58;
+            *byte = carry as u8;
+            carry >>= 8;
+        }
+        if carry != 0 {
+            return None;
+        }
+    }
+
+    // Each leading '1' encodes one leading zero byte; anything else is not
+    // the canonical encoding of a 32-byte value
+    let leading_ones = encoded.bytes().take_while(|&c| c == b'1').count();
+    let leading_zeros = bytes.iter().take_while(|&&b| b == 0).count();
+    if leading_ones != leading_zeros {
+        return None;
+    }
+
+    Some(Address::new_from_array(bytes))
+}
+
+/// FNV-1a 64-bit offset basis.
+const FNV_OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+
+/// FNV-1a 64-bit prime.
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+/// Deterministic 32-byte fingerprint of account data, for snapshot tests.
+///
+/// Four FNV-1a lanes, each seeded with its lane index, so equal data always
+/// hashes equal across runs and platforms. Not collision resistant; only
+/// compare states a test produced itself.
+///
+/// ```ignore
+/// let before = account_hash(vault.data());
+/// process_deposit(&ID, &accounts, &dry_run_data)?;
+/// assert_eq!(account_hash(vault.data()), before);
+/// ```
+pub fn account_hash(data: &[u8]) -> [u8; 32] {
+    let mut hash = [0u8; 32];
+    for (lane, out) in hash.chunks_exact_mut(8).enumerate() {
+        let mut state = FNV_OFFSET_BASIS;
+        for byte in core::iter::once(lane as u8).chain(data.iter().copied()) {
+            state ^= u64::from(byte);
+            state = state.wrapping_mul(FNV_PRIME);
+        }
+        out.copy_from_slice(&state.to_le_bytes());
+    }
+    hash
+}
+
+// =============================================================================
+// RANDOMIZED TESTS
+// =============================================================================
+
+/// Deterministic xorshift64 PRNG so failures are reproducible.
+///
+/// Not uniform enough for anything but picking test inputs.
+pub struct XorShift64(u64);
+
+impl XorShift64 {
+    /// Start the sequence at `seed`. Zero is a fixed point of xorshift, so it
+    /// is rejected.
+    pub fn new(seed: u64) -> Self {
+        assert_ne!(seed, 0, "xorshift64 never leaves a zero seed");
+        Self(seed)
+    }
+
+    /// Next value of the sequence.
+    pub fn next_u64(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    /// Next value reduced to `0..bound`.
+    pub fn below(&mut self, bound: u64) -> u64 {
+        self.next_u64() % bound
+    }
+}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_mock_account_flags_and_data() {
+        let address = Address::new_from_array([1u8; 32]);
+        let owner = Address::new_from_array([2u8; 32]);
+
+        let mut readonly = MockAccountView::new(address, owner, 500, &[7, 8, 9]);
+        let view = readonly.view();
+        assert!(!view.is_signer());
+        assert!(!view.is_writable());
+        assert_eq!(view.address(), &address);
+        assert!(view.owned_by(&owner));
+        assert_eq!(view.lamports(), 500);
+        assert_eq!(&*view.try_borrow().unwrap(), &[7, 8, 9]);
+
+        let mut writable = MockAccountView::new(address, owner, 0, &[0; 4]).writable().signer();
+        let view = writable.view();
+        assert!(view.is_signer());
+        assert!(view.is_writable());
+
+        view.try_borrow_mut().unwrap()[0] = 42;
+        view.set_lamports(10);
+        assert_eq!(writable.data(), &[42, 0, 0, 0]);
+        assert_eq!(writable.lamports(), 10);
+    }
+
+    #[test]
+    fn test_untouchable_account_refuses_borrows() {
+        let address = Address::new_from_array([1u8; 32]);
+        let mut account = MockAccountView::new(address, address, 0, &[7]).writable().untouchable();
+        let view = account.view();
+        assert!(matches!(view.try_borrow(), Err(ProgramError::AccountBorrowFailed)));
+        assert!(matches!(view.try_borrow_mut(), Err(ProgramError::AccountBorrowFailed)));
+        assert_eq!(account.data(), &[7]);
+    }
+
+    #[test]
+    fn test_xorshift_replays_from_seed() {
+        let mut a = XorShift64::new(0x5EED);
+        let mut b = XorShift64::new(0x5EED);
+        for _ in 0..100 {
+            let value = a.below(10);
+            assert!(value < 10);
+            assert_eq!(value, b.below(10));
+        }
+        assert_ne!(a.next_u64(), XorShift64::new(0x5EEE).next_u64());
+    }
+
+    #[test]
+    fn test_account_hash() {
+        let data = [3u8; 40];
+        assert_eq!(account_hash(&data), account_hash(&[3u8; 40]));
+
+        // Any single changed byte changes the hash
+        for i in 0..data.len() {
+            let mut changed = data;
+            changed[i] ^= 1;
+            assert_ne!(account_hash(&changed), account_hash(&data));
+        }
+
+        // Length is part of the state
+        assert_ne!(account_hash(&data[..39]), account_hash(&data));
+        assert_ne!(account_hash(&[]), account_hash(&[0]));
+    }
+
+    /// Counter program: adds the instruction byte to account 0's first data
+    /// byte, then fails if the result is odd.
+    fn counter_program(
+        _program_id: &Address,
+        accounts: &[AccountView],
+        instruction_data: &[u8],
+    ) -> ProgramResult {
+        let [counter, authority] = accounts else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+        if !authority.is_signer() {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+        let mut data = counter.try_borrow_mut()?;
+        data[0] += instruction_data[0];
+        if data[0] % 2 == 1 {
+            return Err(ProgramError::InvalidArgument);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_program_test_runs_and_rolls_back() {
+        let program_id = Address::new_from_array([9u8; 32]);
+        let mut test = ProgramTest::new(program_id, counter_program);
+        let counter = test.add_account(Address::new_from_array([1u8; 32]), program_id, 0, &[0]);
+        let authority = test.add_account(Address::new_from_array([2u8; 32]), program_id, 0, &[]);
+
+        let accounts = [AccountMeta::writable(counter), AccountMeta::readonly(authority).signer()];
+        assert_eq!(test.process(&accounts, &[2]), Ok(()));
+        assert_eq!(test.account(counter).data(), &[2]);
+
+        // A failed instruction leaves the state from before it
+        assert_eq!(test.process(&accounts, &[1]), Err(ProgramError::InvalidArgument));
+        assert_eq!(test.account(counter).data(), &[2]);
+
+        // Flags come from the metas of each instruction, not earlier ones
+        let unsigned = [AccountMeta::writable(counter), AccountMeta::readonly(authority)];
+        assert_eq!(test.process(&unsigned, &[2]), Err(ProgramError::MissingRequiredSignature));
+        assert_eq!(test.process(&accounts, &[2]), Ok(()));
+        assert_eq!(test.account(counter).data(), &[4]);
+    }
+
+    #[test]
+    fn test_assert_reinit_rejected() {
+        let mut account = MockAccountView::new(Address::default(), Address::default(), 0, &[1]);
+        assert_reinit_rejected(&mut account, |account| {
+            crate::account::check_uninitialized(&account.view().try_borrow()?)
+        });
+    }
+
+    #[test]
+    #[should_panic(expected = "modified the account")]
+    fn test_assert_reinit_rejected_catches_write() {
+        let mut account =
+            MockAccountView::new(Address::default(), Address::default(), 0, &[1]).writable();
+        assert_reinit_rejected(&mut account, |account| {
+            account.view().try_borrow_mut()?[0] = 2;
+            Err(ProgramError::AccountAlreadyInitialized)
+        });
+    }
+
+    #[test]
+    fn test_decode_address() {
+        // System program and SPL Token program IDs
+        assert_eq!(decode_address("11111111111111111111111111111111"), Some(Address::default()));
+        let token = decode_address("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA").unwrap();
+        assert_eq!(&token.as_ref()[..4], &[0x06, 0xdd, 0xf6, 0xe1]);
+        assert_eq!(&token.as_ref()[28..], &[0x7e, 0xff, 0x00, 0xa9]);
+
+        // Invalid character, too long, and a non-canonical leading '1'
+        assert_eq!(decode_address("0OIl"), None);
+        assert_eq!(decode_address("TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DAA"), None);
+        assert_eq!(decode_address("1TokenkegQfeZyiNwAJbNbGKPFXCWuBvf9Ss623VQ5DA"), None);
+    }
+}
@@ -20,8 +20,19 @@
 #![allow(unexpected_cfgs)]
 
 use pinocchio::{entrypoint, error::ProgramError, AccountView, Address, ProgramResult};
+
+// Logging shim: `log!` forwards to `solana_program_log` with the default `logs`
+// feature and compiles to nothing under `--no-default-features`.
+#[cfg(feature = "logs")]
 use solana_program_log::log;
 
+#[cfg(not(feature = "logs"))]
+macro_rules! log {
+    ($($arg:tt)*) => {{
+        let _ = core::format_args!($($arg)*);
+    }};
+}
+
 // =============================================================================
 // PROGRAM ID
 // =============================================================================
@@ -62,6 +73,7 @@ pub const UPDATE_BALANCE_DISCRIMINATOR: u8 = 1;
 ///
 /// This struct is identical to the Anchor version but uses manual serialization.
 /// In Pinocchio, there's no Anchor discriminator (8 bytes saved).
+#[derive(Debug, Clone, PartialEq)]
 pub struct UserAccount {
     /// The public key of the user who owns this account.
     /// This field SHOULD be checked on every modification, but the vulnerable
@@ -289,9 +301,6 @@ mod tests {
         account.serialize(&mut buffer).unwrap();
 
         let deserialized = UserAccount::try_from_slice(&buffer).unwrap();
-        assert_eq!(deserialized.authority, account.authority);
-        assert_eq!(deserialized.balance, account.balance);
-        assert_eq!(deserialized.is_initialized, account.is_initialized);
-        assert_eq!(deserialized.bump, account.bump);
+        assert_eq!(deserialized, account);
     }
 }
@@ -12,6 +12,7 @@
 //! - Signer validation using `Signer<'info>` type
 //! - Ownership validation using `has_one = authority` constraint
 //! - State validation using `constraint = user_account.is_initialized`
+//! - Overflow-safe ledger updates using `checked_add` / `checked_sub`
 //!
 //! ## Learning Objectives
 //! After studying this code, you should understand:
@@ -79,6 +80,18 @@ pub struct UserAccount {
     pub bump: u8,
 }
 
+/// Direction of a balance change applied by `update_balance`.
+///
+/// // SECURITY: Accepting a delta instead of an absolute value means the
+/// // caller can only move the balance by an amount the ledger can account for.
+#[derive(AnchorSerialize, AnchorDeserialize, Clone, Copy, PartialEq, Eq)]
+pub enum BalanceDirection {
+    /// Add `amount` to the balance.
+    Credit,
+    /// Subtract `amount` from the balance.
+    Debit,
+}
+
 // =============================================================================
 // ERROR CODES
 // =============================================================================
@@ -99,6 +112,16 @@ pub enum ErrorCode {
     /// // SECURITY: Prevents re-initialization attacks that could reset account state.
     #[msg("Account has already been initialized")]
     AlreadyInitialized,
+
+    /// Returned when a credit would overflow the balance.
+    /// // SECURITY: Prevents wrapping to a small value via `checked_add`.
+    #[msg("Arithmetic overflow")]
+    ArithmeticOverflow,
+
+    /// Returned when a debit exceeds the current balance.
+    /// // SECURITY: Prevents underflow wrapping to a huge value via `checked_sub`.
+    #[msg("Insufficient funds for debit")]
+    InsufficientFunds,
 }
 
 // =============================================================================
@@ -145,22 +168,28 @@ pub mod secure_missing_validation {
         Ok(())
     }
 
-    /// Updates the balance of a user account.
+    /// Credits or debits the balance of a user account.
     ///
     /// // SECURITY: This instruction is PROPERLY SECURED with these validations:
     /// // - `authority: Signer<'info>` - Ensures authority signed the transaction
     /// // - `has_one = authority` - Verifies user_account.authority matches signer
     /// // - `constraint = user_account.is_initialized` - Ensures account is valid
+    /// // - `checked_add` / `checked_sub` - Rejects overflow and underflow
     ///
     /// Compare with vulnerable version which lacks ALL of these checks!
     ///
     /// # Arguments
     /// * `ctx` - The context containing validated account references
-    /// * `new_balance` - The new balance to set
+    /// * `amount` - The amount to credit or debit
+    /// * `direction` - Whether to credit or debit `amount`
     ///
     /// # Returns
     /// * `Result<()>` - Success if authorized, error if validation fails
-    pub fn update_balance(ctx: Context<UpdateBalance>, new_balance: u64) -> Result<()> {
+    pub fn update_balance(
+        ctx: Context<UpdateBalance>,
+        amount: u64,
+        direction: BalanceDirection,
+    ) -> Result<()> {
         // SECURITY: At this point, Anchor has already validated:
         // 1. ctx.accounts.authority is a signer (must have signed transaction)
         // 2. ctx.accounts.user_account.authority == ctx.accounts.authority.key()
@@ -174,7 +203,15 @@ pub mod secure_missing_validation {
         // Store old balance for logging
         let old_balance = user_account.balance;
 
-        // SECURITY: Safe to set balance - all authorization checks passed
+        // SECURITY: Apply the delta with checked arithmetic - authorization already passed
+        let new_balance = match direction {
+            BalanceDirection::Credit => {
+                old_balance.checked_add(amount).ok_or(ErrorCode::ArithmeticOverflow)?
+            }
+            BalanceDirection::Debit => {
+                old_balance.checked_sub(amount).ok_or(ErrorCode::InsufficientFunds)?
+            }
+        };
         user_account.balance = new_balance;
 
         msg!("✓ SECURITY VERIFIED: Balance updated from {} to {}", old_balance, new_balance);
@@ -292,7 +292,7 @@ describe("Missing Validation Pattern", () => {
                 // 1. has_one = authority constraint requires user_account.authority == signer
                 // 2. Signer<'info> type requires the authority to actually sign
                 await secureProgram.methods
-                    .updateBalance(new BN(999999))
+                    .updateBalance(new BN(999999), { credit: {} })
                     .accounts({
                         userAccount: victimSecurePda, // Victim's account
                         authority: attackerKeypair.publicKey, // Attacker as authority
@@ -360,7 +360,7 @@ describe("Missing Validation Pattern", () => {
 
             const legitimateNewBalance = 500;
             await secureProgram.methods
-                .updateBalance(new BN(legitimateNewBalance))
+                .updateBalance(new BN(legitimateNewBalance), { credit: {} })
                 .accounts({
                     userAccount: victimSecurePda,
                     authority: victimKeypair.publicKey,
@@ -379,7 +379,7 @@ describe("Missing Validation Pattern", () => {
             let attackerUpdateFailed = false;
             try {
                 await secureProgram.methods
-                    .updateBalance(new BN(0)) // Try to drain balance
+                    .updateBalance(new BN(legitimateNewBalance), { debit: {} }) // Try to drain balance
                     .accounts({
                         userAccount: victimSecurePda,
                         authority: attackerKeypair.publicKey,
@@ -408,5 +408,118 @@ describe("Missing Validation Pattern", () => {
             console.log("  The has_one constraint ensures only the authority can modify.");
             console.log("  ----------------------------------------\n");
         });
+
+        it("✓ rejects credit that would overflow the balance", async () => {
+            // SECURITY: update_balance is a ledger operation using checked_add
+            console.log("\n");
+            console.log("  ----------------------------------------");
+            console.log("  TEST: Credit Overflow Rejected");
+            console.log("  ----------------------------------------");
+            console.log("");
+
+            await secureProgram.methods
+                .initialize()
+                .accounts({
+                    userAccount: victimSecurePda,
+                    authority: victimKeypair.publicKey,
+                    systemProgram: anchor.web3.SystemProgram.programId,
+                })
+                .signers([victimKeypair])
+                .rpc();
+
+            // Step 1: Credit up to u64::MAX (allowed)
+            const U64_MAX = new BN("18446744073709551615");
+            await secureProgram.methods
+                .updateBalance(U64_MAX, { credit: {} })
+                .accounts({
+                    userAccount: victimSecurePda,
+                    authority: victimKeypair.publicKey,
+                })
+                .signers([victimKeypair])
+                .rpc();
+            console.log("  Step 1: Balance credited to u64::MAX");
+
+            // Step 2: Credit 1 more (must overflow)
+            let overflowSucceeded = false;
+            let errorReceived = "";
+            try {
+                await secureProgram.methods
+                    .updateBalance(new BN(1), { credit: {} })
+                    .accounts({
+                        userAccount: victimSecurePda,
+                        authority: victimKeypair.publicKey,
+                    })
+                    .signers([victimKeypair])
+                    .rpc();
+                overflowSucceeded = true;
+            } catch (err: any) {
+                errorReceived = err.message || err.toString();
+                console.log("  Step 2: Overflowing credit rejected (as expected).");
+            }
+
+            const account = await secureProgram.account.userAccount.fetch(victimSecurePda);
+
+            expect(overflowSucceeded).to.be.false;
+            expect(errorReceived).to.include("ArithmeticOverflow");
+            expect(account.balance.toString()).to.equal(U64_MAX.toString());
+
+            console.log("  ✓ SECURITY: Balance did not wrap around");
+            console.log("  ----------------------------------------\n");
+        });
+
+        it("✓ rejects debit larger than the balance", async () => {
+            // SECURITY: update_balance is a ledger operation using checked_sub
+            console.log("\n");
+            console.log("  ----------------------------------------");
+            console.log("  TEST: Debit Underflow Rejected");
+            console.log("  ----------------------------------------");
+            console.log("");
+
+            await secureProgram.methods
+                .initialize()
+                .accounts({
+                    userAccount: victimSecurePda,
+                    authority: victimKeypair.publicKey,
+                    systemProgram: anchor.web3.SystemProgram.programId,
+                })
+                .signers([victimKeypair])
+                .rpc();
+
+            await secureProgram.methods
+                .updateBalance(new BN(100), { credit: {} })
+                .accounts({
+                    userAccount: victimSecurePda,
+                    authority: victimKeypair.publicKey,
+                })
+                .signers([victimKeypair])
+                .rpc();
+            console.log("  Step 1: Balance credited to 100");
+
+            let underflowSucceeded = false;
+            let errorReceived = "";
+            try {
+                await secureProgram.methods
+                    .updateBalance(new BN(101), { debit: {} })
+                    .accounts({
+                        userAccount: victimSecurePda,
+                        authority: victimKeypair.publicKey,
+                    })
+                    .signers([victimKeypair])
+                    .rpc();
+                underflowSucceeded = true;
+            } catch (err: any) {
+                errorReceived = err.message || err.toString();
+                console.log("  Step 2: Debit of 101 rejected (as expected).");
+            }
+
+            const account = await secureProgram.account.userAccount.fetch(victimSecurePda);
+
+            expect(underflowSucceeded).to.be.false;
+            expect(errorReceived).to.include("InsufficientFunds");
+            expect(account.balance.toNumber()).to.equal(100);
+
+            console.log("  ✓ SECURITY: Balance did not underflow");
+            console.log("  ----------------------------------------\n");
+        });
     });
 });
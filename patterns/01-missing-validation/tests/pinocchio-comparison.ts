@@ -32,9 +32,16 @@ const PINOCCHIO_SECURE_PROGRAM_ID = new PublicKey("ENZfh7vCvh9qvbKNQgWDvThLcmaR9
 const INITIALIZE_DISCRIMINATOR = 0;
 const UPDATE_BALANCE_DISCRIMINATOR = 1;
 
+// Secure update_balance direction byte
+const DIRECTION_CREDIT = 0;
+const DIRECTION_DEBIT = 1;
+
 // Account size: authority (32) + balance (8) + is_initialized (1) + bump (1) = 42 bytes
 const USER_ACCOUNT_SIZE = 42;
 
+// Secure layout appends total_credited (8) + total_debited (8) = 58 bytes
+const SECURE_USER_ACCOUNT_SIZE = 58;
+
 // PDA seed (same as Anchor programs for consistency)
 const USER_ACCOUNT_SEED = Buffer.from("user_account");
 
@@ -64,7 +71,7 @@ function buildInitializeInstructionData(bump: number): Buffer {
 }
 
 /**
- * Build instruction data for update_balance instruction.
+ * Build instruction data for the VULNERABLE program's update_balance instruction.
  * Format: [discriminator (1 byte)] [new_balance (8 bytes, little-endian)]
  */
 function buildUpdateBalanceInstructionData(newBalance: bigint): Buffer {
@@ -74,21 +81,39 @@ function buildUpdateBalanceInstructionData(newBalance: bigint): Buffer {
     return data;
 }
 
+/**
+ * Build instruction data for the SECURE program's update_balance instruction.
+ * Format: [discriminator (1 byte)] [amount (8 bytes, little-endian)] [direction (1 byte)]
+ */
+function buildSecureUpdateBalanceInstructionData(amount: bigint, direction: number): Buffer {
+    const data = Buffer.alloc(10);
+    data.writeUInt8(UPDATE_BALANCE_DISCRIMINATOR, 0);
+    data.writeBigUInt64LE(amount, 1);
+    data.writeUInt8(direction, 9);
+    return data;
+}
+
 /**
  * Decode user account data from raw bytes.
  * Layout: authority (32) | balance (8) | is_initialized (1) | bump (1)
+ *         [| total_credited (8) | total_debited (8)]  (secure program only)
  */
 function decodeUserAccount(data: Buffer): {
     authority: PublicKey;
     balance: bigint;
     isInitialized: boolean;
     bump: number;
+    totalCredited?: bigint;
+    totalDebited?: bigint;
 } {
+    const hasTotals = data.length >= SECURE_USER_ACCOUNT_SIZE;
     return {
         authority: new PublicKey(data.slice(0, 32)),
         balance: data.readBigUInt64LE(32),
         isInitialized: data[40] !== 0,
         bump: data[41],
+        totalCredited: hasTotals ? data.readBigUInt64LE(42) : undefined,
+        totalDebited: hasTotals ? data.readBigUInt64LE(50) : undefined,
     };
 }
 
@@ -308,7 +333,7 @@ describe("Pinocchio Framework Comparison", () => {
                 connection,
                 victimKeypair,
                 PINOCCHIO_SECURE_PROGRAM_ID,
-                USER_ACCOUNT_SIZE
+                SECURE_USER_ACCOUNT_SIZE
             );
 
             // Step 1: Victim initializes account
@@ -352,7 +377,7 @@ describe("Pinocchio Framework Comparison", () => {
                         { pubkey: userAccount.publicKey, isSigner: false, isWritable: true },
                         { pubkey: attackerKeypair.publicKey, isSigner: true, isWritable: false },
                     ],
-                    data: buildUpdateBalanceInstructionData(BigInt(999999)),
+                    data: buildSecureUpdateBalanceInstructionData(BigInt(999999), DIRECTION_CREDIT),
                 });
 
                 const updateTx = new Transaction().add(updateIx);
@@ -400,7 +425,7 @@ describe("Pinocchio Framework Comparison", () => {
                 connection,
                 victimKeypair,
                 PINOCCHIO_SECURE_PROGRAM_ID,
-                USER_ACCOUNT_SIZE
+                SECURE_USER_ACCOUNT_SIZE
             );
 
             // Initialize account
@@ -427,7 +452,7 @@ describe("Pinocchio Framework Comparison", () => {
                     { pubkey: userAccount.publicKey, isSigner: false, isWritable: true },
                     { pubkey: victimKeypair.publicKey, isSigner: true, isWritable: false },
                 ],
-                data: buildUpdateBalanceInstructionData(legitimateBalance),
+                data: buildSecureUpdateBalanceInstructionData(legitimateBalance, DIRECTION_CREDIT),
             });
 
             await sendAndConfirmTransaction(connection, new Transaction().add(updateIx), [victimKeypair]);
@@ -444,6 +469,266 @@ describe("Pinocchio Framework Comparison", () => {
             console.log("  Pinocchio secure program correctly validates and allows true authority.");
             console.log("  ----------------------------------------\n");
         });
+
+        it("(Pinocchio) initializes a fresh account - ZEROED DATA", async () => {
+            /**
+             * A freshly created account has all-zero data, so the
+             * re-initialization guard allows it through.
+             */
+            const userAccount = await createFundedAccount(
+                connection,
+                victimKeypair,
+                PINOCCHIO_SECURE_PROGRAM_ID,
+                SECURE_USER_ACCOUNT_SIZE
+            );
+
+            const [, bump] = deriveUserAccountPda(victimKeypair.publicKey, PINOCCHIO_SECURE_PROGRAM_ID);
+
+            const initIx = new TransactionInstruction({
+                programId: PINOCCHIO_SECURE_PROGRAM_ID,
+                keys: [
+                    { pubkey: userAccount.publicKey, isSigner: false, isWritable: true },
+                    { pubkey: victimKeypair.publicKey, isSigner: true, isWritable: false },
+                ],
+                data: buildInitializeInstructionData(bump),
+            });
+
+            await sendAndConfirmTransaction(connection, new Transaction().add(initIx), [victimKeypair]);
+
+            const accountInfo = await connection.getAccountInfo(userAccount.publicKey);
+            const accountData = decodeUserAccount(accountInfo!.data);
+
+            expect(accountData.authority.toString()).to.equal(victimKeypair.publicKey.toString());
+            expect(accountData.isInitialized).to.be.true;
+            expect(accountData.balance).to.equal(BigInt(0));
+
+            console.log("  VERIFIED: Fresh account initialized with is_initialized = true");
+        });
+
+        it("(Pinocchio) blocks re-initialization - AUTHORITY TAKEOVER PREVENTED", async () => {
+            /**
+             * Anchor's `init` constraint refuses to run on an existing account.
+             * Pinocchio must enforce this manually by checking the stored
+             * authority is still zeroed before writing.
+             */
+            console.log("\n");
+            console.log("  ----------------------------------------");
+            console.log("  PINOCCHIO: Re-initialization Blocked");
+            console.log("  ----------------------------------------");
+            console.log("");
+
+            const userAccount = await createFundedAccount(
+                connection,
+                victimKeypair,
+                PINOCCHIO_SECURE_PROGRAM_ID,
+                SECURE_USER_ACCOUNT_SIZE
+            );
+
+            // Step 1: Victim initializes account
+            const [, bump] = deriveUserAccountPda(victimKeypair.publicKey, PINOCCHIO_SECURE_PROGRAM_ID);
+
+            const initIx = new TransactionInstruction({
+                programId: PINOCCHIO_SECURE_PROGRAM_ID,
+                keys: [
+                    { pubkey: userAccount.publicKey, isSigner: false, isWritable: true },
+                    { pubkey: victimKeypair.publicKey, isSigner: true, isWritable: false },
+                ],
+                data: buildInitializeInstructionData(bump),
+            });
+
+            await sendAndConfirmTransaction(connection, new Transaction().add(initIx), [victimKeypair]);
+            console.log("  Step 1: Victim initialized account");
+
+            // Step 2: Attacker re-runs initialize to claim the account
+            console.log("  Step 2: Attacker attempts to re-initialize...");
+
+            let attackSucceeded = false;
+            let errorMessage = "";
+
+            try {
+                const reinitIx = new TransactionInstruction({
+                    programId: PINOCCHIO_SECURE_PROGRAM_ID,
+                    keys: [
+                        { pubkey: userAccount.publicKey, isSigner: false, isWritable: true },
+                        { pubkey: attackerKeypair.publicKey, isSigner: true, isWritable: false },
+                    ],
+                    data: buildInitializeInstructionData(bump),
+                });
+
+                await sendAndConfirmTransaction(connection, new Transaction().add(reinitIx), [
+                    attackerKeypair,
+                ]);
+                attackSucceeded = true;
+            } catch (err: any) {
+                errorMessage = err.message || err.toString();
+                console.log("  Transaction rejected (as expected).");
+            }
+
+            const accountInfo = await connection.getAccountInfo(userAccount.publicKey);
+            const accountData = decodeUserAccount(accountInfo!.data);
+
+            expect(attackSucceeded).to.be.false;
+            // AlreadyInitialized = 0x1002
+            expect(errorMessage).to.include("0x1002");
+            expect(accountData.authority.toString()).to.equal(victimKeypair.publicKey.toString());
+
+            console.log("");
+            console.log("  SECURITY VERIFIED: Authority unchanged after re-initialization attempt");
+            console.log("  ----------------------------------------\n");
+        });
+
+        it("(Pinocchio) rejects credit overflow - CHECKED ARITHMETIC", async () => {
+            const userAccount = await createFundedAccount(
+                connection,
+                victimKeypair,
+                PINOCCHIO_SECURE_PROGRAM_ID,
+                SECURE_USER_ACCOUNT_SIZE
+            );
+            const [, bump] = deriveUserAccountPda(victimKeypair.publicKey, PINOCCHIO_SECURE_PROGRAM_ID);
+            const keys = [
+                { pubkey: userAccount.publicKey, isSigner: false, isWritable: true },
+                { pubkey: victimKeypair.publicKey, isSigner: true, isWritable: false },
+            ];
+
+            const U64_MAX = BigInt("18446744073709551615");
+            const setupTx = new Transaction().add(
+                new TransactionInstruction({
+                    programId: PINOCCHIO_SECURE_PROGRAM_ID,
+                    keys,
+                    data: buildInitializeInstructionData(bump),
+                }),
+                new TransactionInstruction({
+                    programId: PINOCCHIO_SECURE_PROGRAM_ID,
+                    keys,
+                    data: buildSecureUpdateBalanceInstructionData(U64_MAX, DIRECTION_CREDIT),
+                })
+            );
+            await sendAndConfirmTransaction(connection, setupTx, [victimKeypair]);
+
+            let overflowSucceeded = false;
+            let errorMessage = "";
+            try {
+                const overflowIx = new TransactionInstruction({
+                    programId: PINOCCHIO_SECURE_PROGRAM_ID,
+                    keys,
+                    data: buildSecureUpdateBalanceInstructionData(BigInt(1), DIRECTION_CREDIT),
+                });
+                await sendAndConfirmTransaction(connection, new Transaction().add(overflowIx), [
+                    victimKeypair,
+                ]);
+                overflowSucceeded = true;
+            } catch (err: any) {
+                errorMessage = err.message || err.toString();
+            }
+
+            const accountInfo = await connection.getAccountInfo(userAccount.publicKey);
+            const accountData = decodeUserAccount(accountInfo!.data);
+
+            expect(overflowSucceeded).to.be.false;
+            // ArithmeticOverflow = 0x1003
+            expect(errorMessage).to.include("0x1003");
+            expect(accountData.balance).to.equal(U64_MAX);
+
+            console.log("  VERIFIED: Credit overflow rejected, balance stays at u64::MAX");
+        });
+
+        it("(Pinocchio) rejects debit underflow - CHECKED ARITHMETIC", async () => {
+            const userAccount = await createFundedAccount(
+                connection,
+                victimKeypair,
+                PINOCCHIO_SECURE_PROGRAM_ID,
+                SECURE_USER_ACCOUNT_SIZE
+            );
+            const [, bump] = deriveUserAccountPda(victimKeypair.publicKey, PINOCCHIO_SECURE_PROGRAM_ID);
+            const keys = [
+                { pubkey: userAccount.publicKey, isSigner: false, isWritable: true },
+                { pubkey: victimKeypair.publicKey, isSigner: true, isWritable: false },
+            ];
+
+            const setupTx = new Transaction().add(
+                new TransactionInstruction({
+                    programId: PINOCCHIO_SECURE_PROGRAM_ID,
+                    keys,
+                    data: buildInitializeInstructionData(bump),
+                }),
+                new TransactionInstruction({
+                    programId: PINOCCHIO_SECURE_PROGRAM_ID,
+                    keys,
+                    data: buildSecureUpdateBalanceInstructionData(BigInt(100), DIRECTION_CREDIT),
+                })
+            );
+            await sendAndConfirmTransaction(connection, setupTx, [victimKeypair]);
+
+            let underflowSucceeded = false;
+            let errorMessage = "";
+            try {
+                const underflowIx = new TransactionInstruction({
+                    programId: PINOCCHIO_SECURE_PROGRAM_ID,
+                    keys,
+                    data: buildSecureUpdateBalanceInstructionData(BigInt(101), DIRECTION_DEBIT),
+                });
+                await sendAndConfirmTransaction(connection, new Transaction().add(underflowIx), [
+                    victimKeypair,
+                ]);
+                underflowSucceeded = true;
+            } catch (err: any) {
+                errorMessage = err.message || err.toString();
+            }
+
+            const accountInfo = await connection.getAccountInfo(userAccount.publicKey);
+            const accountData = decodeUserAccount(accountInfo!.data);
+
+            expect(underflowSucceeded).to.be.false;
+            // InsufficientFunds = 0x1004
+            expect(errorMessage).to.include("0x1004");
+            expect(accountData.balance).to.equal(BigInt(100));
+            expect(accountData.totalCredited).to.equal(BigInt(100));
+            expect(accountData.totalDebited).to.equal(BigInt(0));
+
+            console.log("  VERIFIED: Debit underflow rejected, balance unchanged");
+        });
+
+        it("(Pinocchio) tracks cumulative credits and debits - AUDIT TRAIL", async () => {
+            const userAccount = await createFundedAccount(
+                connection,
+                victimKeypair,
+                PINOCCHIO_SECURE_PROGRAM_ID,
+                SECURE_USER_ACCOUNT_SIZE
+            );
+            const [, bump] = deriveUserAccountPda(victimKeypair.publicKey, PINOCCHIO_SECURE_PROGRAM_ID);
+            const keys = [
+                { pubkey: userAccount.publicKey, isSigner: false, isWritable: true },
+                { pubkey: victimKeypair.publicKey, isSigner: true, isWritable: false },
+            ];
+            const update = (amount: number, direction: number) =>
+                new TransactionInstruction({
+                    programId: PINOCCHIO_SECURE_PROGRAM_ID,
+                    keys,
+                    data: buildSecureUpdateBalanceInstructionData(BigInt(amount), direction),
+                });
+
+            const tx = new Transaction().add(
+                new TransactionInstruction({
+                    programId: PINOCCHIO_SECURE_PROGRAM_ID,
+                    keys,
+                    data: buildInitializeInstructionData(bump),
+                }),
+                update(100, DIRECTION_CREDIT),
+                update(30, DIRECTION_DEBIT),
+                update(50, DIRECTION_CREDIT),
+                update(20, DIRECTION_DEBIT)
+            );
+            await sendAndConfirmTransaction(connection, tx, [victimKeypair]);
+
+            const accountInfo = await connection.getAccountInfo(userAccount.publicKey);
+            const accountData = decodeUserAccount(accountInfo!.data);
+
+            expect(accountData.balance).to.equal(BigInt(100));
+            expect(accountData.totalCredited).to.equal(BigInt(150));
+            expect(accountData.totalDebited).to.equal(BigInt(50));
+
+            console.log("  VERIFIED: Ledger totals match the sequence of credits and debits");
+        });
     });
 
     // =============================================================================
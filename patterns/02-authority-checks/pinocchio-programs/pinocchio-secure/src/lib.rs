@@ -8,7 +8,15 @@
 //! - Manual is_signer() verification (Pinocchio equivalent of Anchor's `Signer<'info>`)
 //! - Manual super_admin comparison (Pinocchio equivalent of Anchor's `constraint`)
 //! - Manual is_admin() membership checks (Pinocchio equivalent of custom constraints)
+//! - Admin-or-manager authority via caller_is_authorized() for fee and pause changes
 //! - Manual owned_by() verification for account ownership
+//! - Manager PDA and bump re-derivation via load_manager() before any manager
+//!   account is trusted
+//!
+//! ## Features
+//! - `sorted_admins`: keep `admin_list[..admin_count]` in ascending address
+//!   order so `is_admin` is a binary search. Worth it only if `MAX_ADMINS`
+//!   grows well beyond its current value.
 //!
 //! ## Key Differences from Anchor
 //! - No declarative account constraints - all checks are explicit code
@@ -21,16 +29,28 @@
 #![allow(unexpected_cfgs)]
 
 use pinocchio::{entrypoint, error::ProgramError, AccountView, Address, ProgramResult};
+use pinocchio_security_common::{check_uninitialized, zero_account_data, Role, SecurityChecks};
+
+// Logging shim: `log!` forwards to `solana_program_log` with the default `logs`
+// feature and compiles to nothing under `--no-default-features`.
+#[cfg(feature = "logs")]
 use solana_program_log::log;
 
+#[cfg(not(feature = "logs"))]
+macro_rules! log {
+    ($($arg:tt)*) => {{
+        let _ = core::format_args!($($arg)*);
+    }};
+}
+
 // =============================================================================
 // PROGRAM ID
 // =============================================================================
 
 /// Program ID: 3P6BDR7EK5DV7gWyVLSceYRbnUkywjDupYugSQre7eyp
 pub const ID: Address = Address::new_from_array([
-    0xa7, 0xc1, 0xcd, 0x5c, 0xb7, 0xb3, 0xa2, 0x30, 0xc9, 0xac, 0x2b, 0xc8, 0x67, 0x94, 0xc2, 0x6c,
-    0x61, 0xd3, 0x8c, 0xc4, 0xd0, 0x6a, 0x60, 0xae, 0xb5, 0x83, 0x33, 0x0e, 0x93, 0x8f, 0x55, 0xc3,
+    0x23, 0x60, 0x01, 0x19, 0x4e, 0xa9, 0x1b, 0xdf, 0x81, 0xae, 0x2d, 0x97, 0x37, 0x57, 0xfd, 0x39,
+    0x25, 0x31, 0x5e, 0x40, 0x53, 0x9b, 0x47, 0x7d, 0xee, 0xf2, 0xb1, 0xf8, 0xe6, 0xda, 0xf0, 0x33,
 ]);
 
 // =============================================================================
@@ -40,6 +60,9 @@ pub const ID: Address = Address::new_from_array([
 /// Maximum number of administrators allowed in the admin_list.
 pub const MAX_ADMINS: usize = 3;
 
+/// Highest fee an admin may configure: 10000 basis points = 100%.
+pub const MAX_FEE_BASIS_POINTS: u16 = 10_000;
+
 /// AdminConfig account size (no Anchor discriminator):
 /// - super_admin (Address): 32 bytes
 /// - admin_list ([Address; 3]): 96 bytes
@@ -47,9 +70,10 @@ pub const MAX_ADMINS: usize = 3;
 /// - fee_basis_points (u16): 2 bytes
 /// - paused (bool): 1 byte
 /// - bump (u8): 1 byte
+/// - fee_recipient (Address): 32 bytes
 ///
-/// Total: 133 bytes
-pub const ADMIN_CONFIG_SIZE: usize = 32 + 96 + 1 + 2 + 1 + 1;
+/// Total: 165 bytes
+pub const ADMIN_CONFIG_SIZE: usize = 32 + 96 + 1 + 2 + 1 + 1 + 32;
 
 /// ManagerAccount size (no Anchor discriminator):
 /// - authority (Address): 32 bytes
@@ -58,9 +82,10 @@ pub const ADMIN_CONFIG_SIZE: usize = 32 + 96 + 1 + 2 + 1 + 1;
 /// - can_pause (bool): 1 byte
 /// - is_active (bool): 1 byte
 /// - bump (u8): 1 byte
+/// - max_fee_delta (u16): 2 bytes
 ///
-/// Total: 68 bytes
-pub const MANAGER_ACCOUNT_SIZE: usize = 32 + 32 + 1 + 1 + 1 + 1;
+/// Total: 70 bytes
+pub const MANAGER_ACCOUNT_SIZE: usize = 32 + 32 + 1 + 1 + 1 + 1 + 2;
 
 /// Seed for admin_config PDA
 pub const ADMIN_CONFIG_SEED: &[u8] = b"admin_config";
@@ -80,6 +105,10 @@ pub const UNPAUSE_PROTOCOL_DISCRIMINATOR: u8 = 4;
 pub const CREATE_MANAGER_DISCRIMINATOR: u8 = 5;
 pub const REMOVE_ADMIN_DISCRIMINATOR: u8 = 6;
 pub const DEACTIVATE_MANAGER_DISCRIMINATOR: u8 = 7;
+pub const MANAGER_UPDATE_FEE_DISCRIMINATOR: u8 = 8;
+pub const CONFIGURE_DISCRIMINATOR: u8 = 9;
+pub const SET_FEE_RECIPIENT_DISCRIMINATOR: u8 = 10;
+pub const CLOSE_MANAGER_DISCRIMINATOR: u8 = 11;
 
 // =============================================================================
 // CUSTOM ERRORS
@@ -106,6 +135,18 @@ pub enum SecureError {
     ManagerNotActive = 6,
     /// The admin to remove was not found in the admin_list.
     AdminNotFound = 7,
+    /// The supplied address is the zero/default address.
+    InvalidAddress = 8,
+    /// The manager tried to move the fee by more than its max_fee_delta.
+    FeeDeltaTooLarge = 9,
+    /// The requested fee exceeds MAX_FEE_BASIS_POINTS (100%).
+    FeeTooHigh = 10,
+    /// The same address appears more than once in a new admin list.
+    DuplicateAdmin = 11,
+    /// The fee destination is not the configured fee_recipient.
+    FeeRecipientMismatch = 12,
+    /// A validation-only account was passed writable.
+    UnexpectedWritableAccount = 13,
 }
 
 impl From<SecureError> for ProgramError {
@@ -118,6 +159,56 @@ impl From<SecureError> for ProgramError {
 // HELPER FUNCTIONS
 // =============================================================================
 
+/// Rejects the zero/default address for accounts that only contribute a pubkey.
+///
+/// # SECURITY: Dead Entry Prevention
+///
+/// A default address in admin_list or as a manager is an entry no one can
+/// sign for, and it matches the empty slots past admin_count. Anchor
+/// equivalent: `constraint = acc.key() != Pubkey::default() @ ErrorCode::InvalidAddress`
+pub fn assert_not_default_address(address: &Address) -> ProgramResult {
+    if address == &Address::default() {
+        log!("SECURITY REJECTION: Default (zero) address not allowed");
+        return Err(SecureError::InvalidAddress.into());
+    }
+
+    Ok(())
+}
+
+/// Rejects a writable account where the handler only reads it.
+///
+/// # SECURITY: Least Privilege
+///
+/// Handlers like create_manager read admin_config to authorize the caller and
+/// never write it. Insisting it arrives read-only means a later edit that
+/// starts writing it fails loudly instead of silently mutating the config.
+pub fn assert_read_only(account: &AccountView) -> ProgramResult {
+    if account.is_writable() {
+        log!("SECURITY REJECTION: Validation-only account passed as writable");
+        return Err(SecureError::UnexpectedWritableAccount.into());
+    }
+
+    Ok(())
+}
+
+/// Converts a stored count to a `usize` bound, rejecting counts above `max`.
+///
+/// # SECURITY: Corrupted Counts
+///
+/// `admin_count` is read straight from account data. A count past the end of
+/// admin_list would make `list[..count]` or `list[count - 1]` panic (or, after
+/// a careless refactor, index the wrong slot), so it is rejected with
+/// `InvalidAccountData` before it is used as an index.
+pub fn as_bounded_index(count: u8, max: usize) -> Result<usize, ProgramError> {
+    let count = usize::from(count);
+    if count > max {
+        log!("SECURITY REJECTION: Stored count {} exceeds maximum {}", count, max);
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    Ok(count)
+}
+
 /// Checks if a given address is in the admin_list.
 ///
 /// # SECURITY: Reusable Authority Validation
@@ -138,10 +229,164 @@ impl From<SecureError> for ProgramError {
 ///
 /// `true` if the key is found in admin_list[0..admin_count], `false` otherwise
 pub fn is_admin(admin_list: &[Address; MAX_ADMINS], admin_count: u8, key: &Address) -> bool {
+    find_admin(admin_list, admin_count, key).is_some()
+}
+
+/// Position of `key` in admin_list[0..admin_count], if present.
+///
+/// Binary search with `sorted_admins`, linear scan otherwise.
+fn find_admin(admin_list: &[Address; MAX_ADMINS], admin_count: u8, key: &Address) -> Option<usize> {
     // SECURITY: Only check valid entries (0..admin_count)
     // This prevents reading uninitialized array slots
-    let count = admin_count as usize;
-    admin_list.iter().take(count).any(|admin| admin.as_ref() == key.as_ref())
+    let admins = &admin_list[..(admin_count as usize).min(MAX_ADMINS)];
+
+    #[cfg(feature = "sorted_admins")]
+    {
+        admins.binary_search(key).ok()
+    }
+    #[cfg(not(feature = "sorted_admins"))]
+    {
+        admins.iter().position(|admin| admin == key)
+    }
+}
+
+/// A delegated permission a [`ManagerAccount`] can hold.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ManagerPermission {
+    /// `can_modify_fees`
+    ModifyFees,
+    /// `can_pause`
+    Pause,
+}
+
+/// Whether `key` may run an instruction open to admins and to managers
+/// holding `required_permission`.
+///
+/// # SECURITY: Admin-or-Manager Authority
+///
+/// Grants access if `key` is in admin_list, or if `manager_account` was
+/// created for `key`, is still active, and holds `required_permission`.
+/// A manager account for some other key never authorizes the caller.
+pub fn caller_is_authorized(
+    admin_config: &AdminConfig,
+    manager_account: Option<&ManagerAccount>,
+    key: &Address,
+    required_permission: ManagerPermission,
+) -> bool {
+    if is_admin(&admin_config.admin_list, admin_config.admin_count, key) {
+        return true;
+    }
+
+    manager_account.is_some_and(|manager| {
+        let permitted = match required_permission {
+            ManagerPermission::ModifyFees => manager.can_modify_fees,
+            ManagerPermission::Pause => manager.can_pause,
+        };
+        manager.manager == *key && manager.is_active && permitted
+    })
+}
+
+/// Reads a manager account after proving it is the manager PDA this program created.
+///
+/// # SECURITY: Manager Account Validation
+///
+/// Every instruction that reads or mutates a manager account goes through
+/// this helper, so none of them can skip a check:
+/// 1. The account is owned by this program (`IllegalOwner`)
+/// 2. Its data is exactly `MANAGER_ACCOUNT_SIZE` bytes (`InvalidAccountData`)
+/// 3. Its address is the PDA for `["manager", manager]` (`CommonError::InvalidPda`)
+/// 4. Its stored bump is the canonical bump (`CommonError::InvalidBump`)
+///
+/// Ownership alone is not enough: an account this program owns for some other
+/// purpose, or one at an address no manager was created for, would otherwise
+/// be read as a manager. Anchor equivalent:
+/// `seeds = [b"manager", manager_account.manager.as_ref()], bump = manager_account.bump`
+pub fn load_manager(
+    manager_acc: &AccountView,
+    program_id: &Address,
+) -> Result<ManagerAccount, ProgramError> {
+    if !manager_acc.owned_by(program_id) {
+        return Err(ProgramError::IllegalOwner);
+    }
+
+    if manager_acc.data_len() != MANAGER_ACCOUNT_SIZE {
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let account_data = manager_acc.try_borrow()?;
+    let manager_data = ManagerAccount::try_from_slice(&account_data)?;
+    drop(account_data);
+
+    SecurityChecks::new(program_id)
+        .pda(&[MANAGER_SEED, manager_data.manager.as_ref()], manager_acc, manager_data.bump)
+        .run()?;
+
+    Ok(manager_data)
+}
+
+/// Reads the optional manager account passed to an admin-or-manager instruction.
+fn load_manager_account(
+    program_id: &Address,
+    manager_account_acc: Option<&AccountView>,
+) -> Result<Option<ManagerAccount>, ProgramError> {
+    // SECURITY: Only manager PDAs this program wrote can grant permissions
+    manager_account_acc.map(|acc| load_manager(acc, program_id)).transpose()
+}
+
+/// Validates every field of a bulk `configure` call before any is applied.
+///
+/// # SECURITY: All-or-Nothing Validation
+///
+/// `configure` replaces several fields at once, so a single bad field must
+/// reject the whole update rather than leave the config half-written:
+/// 1. `new_fee` must not exceed `MAX_FEE_BASIS_POINTS`
+/// 2. `admins` must hold 1..=MAX_ADMINS entries, starting with super_admin
+///    (or, with `sorted_admins`, including super_admin anywhere)
+/// 3. No admin may be the default address or appear twice
+pub fn validate_config(super_admin: &Address, new_fee: u16, admins: &[Address]) -> ProgramResult {
+    if new_fee > MAX_FEE_BASIS_POINTS {
+        log!("SECURITY REJECTION: Fee exceeds maximum");
+        return Err(SecureError::FeeTooHigh.into());
+    }
+    if admins.len() > MAX_ADMINS {
+        return Err(SecureError::AdminListFull.into());
+    }
+    #[cfg(not(feature = "sorted_admins"))]
+    let keeps_super_admin = admins.first() == Some(super_admin);
+    #[cfg(feature = "sorted_admins")]
+    let keeps_super_admin = admins.contains(super_admin);
+    if !keeps_super_admin {
+        log!("SECURITY REJECTION: super_admin must stay in admin_list");
+        return Err(SecureError::CannotRemoveSuperAdmin.into());
+    }
+
+    for (i, admin) in admins.iter().enumerate() {
+        assert_not_default_address(admin)?;
+        if admins[..i].contains(admin) {
+            log!("SECURITY REJECTION: Duplicate admin in admin_list");
+            return Err(SecureError::DuplicateAdmin.into());
+        }
+    }
+
+    Ok(())
+}
+
+/// Requires fees to be paid to the configured `fee_recipient`.
+///
+/// # SECURITY: Fee Routing
+///
+/// Fee collection must credit `admin_config.fee_recipient`, never an account
+/// the caller picks, and not implicitly the super_admin: once the recipient
+/// is moved to a protocol treasury, fees routed to the super_admin's wallet
+/// would bypass it. Anchor equivalent:
+/// `constraint = fee_destination.key() == admin_config.fee_recipient`
+pub fn assert_fee_recipient(admin_config: &AdminConfig, destination: &Address) -> ProgramResult {
+    if &admin_config.fee_recipient != destination {
+        log!("SECURITY REJECTION: Fee destination is not the fee_recipient");
+        return Err(SecureError::FeeRecipientMismatch.into());
+    }
+
+    Ok(())
 }
 
 // =============================================================================
@@ -149,6 +394,7 @@ pub fn is_admin(admin_list: &[Address; MAX_ADMINS], admin_count: u8, key: &Addre
 // =============================================================================
 
 /// Global administrator configuration account.
+#[derive(Debug, Clone, PartialEq)]
 pub struct AdminConfig {
     /// The highest-privilege administrator
     pub super_admin: Address,
@@ -162,9 +408,16 @@ pub struct AdminConfig {
     pub paused: bool,
     /// PDA bump seed
     pub bump: u8,
+    /// Account credited with collected fees (super_admin at init)
+    pub fee_recipient: Address,
 }
 
 impl AdminConfig {
+    /// Bytes to allocate when creating an AdminConfig account: [`ADMIN_CONFIG_SIZE`].
+    pub const fn space() -> usize {
+        ADMIN_CONFIG_SIZE
+    }
+
     /// Deserialize AdminConfig from raw account data bytes.
     pub fn try_from_slice(data: &[u8]) -> Result<Self, ProgramError> {
         if data.len() < ADMIN_CONFIG_SIZE {
@@ -192,10 +445,27 @@ impl AdminConfig {
         let fee_basis_points = u16::from_le_bytes(
             data[129..131].try_into().map_err(|_| ProgramError::InvalidAccountData)?,
         );
+        // SECURITY: No instruction stores a fee above 100%, so a larger value
+        // means corrupted data that later fee math must never see
+        if fee_basis_points > MAX_FEE_BASIS_POINTS {
+            log!("SECURITY REJECTION: Stored fee exceeds MAX_FEE_BASIS_POINTS");
+            return Err(ProgramError::InvalidAccountData);
+        }
         let paused = data[131] != 0;
         let bump = data[132];
+        let fee_recipient = Address::new_from_array(
+            data[133..165].try_into().map_err(|_| ProgramError::InvalidAccountData)?,
+        );
 
-        Ok(Self { super_admin, admin_list, admin_count, fee_basis_points, paused, bump })
+        Ok(Self {
+            super_admin,
+            admin_list,
+            admin_count,
+            fee_basis_points,
+            paused,
+            bump,
+            fee_recipient,
+        })
     }
 
     /// Serialize AdminConfig into raw account data bytes.
@@ -216,12 +486,65 @@ impl AdminConfig {
         data[129..131].copy_from_slice(&self.fee_basis_points.to_le_bytes());
         data[131] = self.paused as u8;
         data[132] = self.bump;
+        data[133..165].copy_from_slice(self.fee_recipient.as_ref());
+
+        Ok(())
+    }
+
+    /// Add `admin` to admin_list, keeping it sorted with `sorted_admins`.
+    ///
+    /// Fails with `AdminListFull`, or `DuplicateAdmin` in sorted mode (the
+    /// binary search finds the existing entry for free). A stored
+    /// `admin_count` above `MAX_ADMINS` fails with `InvalidAccountData`.
+    pub fn insert_admin(&mut self, admin: &Address) -> ProgramResult {
+        let count = as_bounded_index(self.admin_count, MAX_ADMINS)?;
+        if count == MAX_ADMINS {
+            log!("Error: Admin list is full");
+            return Err(SecureError::AdminListFull.into());
+        }
+
+        #[cfg(feature = "sorted_admins")]
+        let index = match self.admin_list[..count].binary_search(admin) {
+            Ok(_) => return Err(SecureError::DuplicateAdmin.into()),
+            Err(index) => index,
+        };
+        #[cfg(not(feature = "sorted_admins"))]
+        let index = count;
+
+        // Shift later entries right to open the slot; the empty slot at
+        // `count` rotates into `index`
+        self.admin_list[index..=count].rotate_right(1);
+        self.admin_list[index] = Address::new_from_array(*admin.as_array());
+        self.admin_count += 1;
+
+        Ok(())
+    }
+
+    /// Remove `admin` from admin_list, preserving the order of the rest.
+    ///
+    /// Fails with `AdminNotFound`, or `InvalidAccountData` for a stored
+    /// `admin_count` above `MAX_ADMINS`.
+    pub fn remove_admin(&mut self, admin: &Address) -> ProgramResult {
+        let count = as_bounded_index(self.admin_count, MAX_ADMINS)?;
+        let Some(index) = find_admin(&self.admin_list, self.admin_count, admin) else {
+            log!("Error: Admin not found in admin list");
+            return Err(SecureError::AdminNotFound.into());
+        };
+
+        // Remove admin by shifting remaining entries left; it rotates into
+        // the last slot, which is cleared below
+        self.admin_list[index..count].rotate_left(1);
+
+        // Clear the last slot and decrement count
+        self.admin_list[count - 1] = Address::default();
+        self.admin_count -= 1;
 
         Ok(())
     }
 }
 
 /// Manager account with delegated administrative permissions.
+#[derive(Debug, Clone, PartialEq)]
 pub struct ManagerAccount {
     /// The admin who created this manager
     pub authority: Address,
@@ -235,9 +558,16 @@ pub struct ManagerAccount {
     pub is_active: bool,
     /// PDA bump seed
     pub bump: u8,
+    /// Largest fee change (in basis points) the manager may make per call
+    pub max_fee_delta: u16,
 }
 
 impl ManagerAccount {
+    /// Bytes to allocate when creating a manager account: [`MANAGER_ACCOUNT_SIZE`].
+    pub const fn space() -> usize {
+        MANAGER_ACCOUNT_SIZE
+    }
+
     /// Deserialize ManagerAccount from raw account data bytes.
     pub fn try_from_slice(data: &[u8]) -> Result<Self, ProgramError> {
         if data.len() < MANAGER_ACCOUNT_SIZE {
@@ -254,8 +584,11 @@ impl ManagerAccount {
         let can_pause = data[65] != 0;
         let is_active = data[66] != 0;
         let bump = data[67];
+        let max_fee_delta = u16::from_le_bytes(
+            data[68..70].try_into().map_err(|_| ProgramError::InvalidAccountData)?,
+        );
 
-        Ok(Self { authority, manager, can_modify_fees, can_pause, is_active, bump })
+        Ok(Self { authority, manager, can_modify_fees, can_pause, is_active, bump, max_fee_delta })
     }
 
     /// Serialize ManagerAccount into raw account data bytes.
@@ -270,6 +603,7 @@ impl ManagerAccount {
         data[65] = self.can_pause as u8;
         data[66] = self.is_active as u8;
         data[67] = self.bump;
+        data[68..70].copy_from_slice(&self.max_fee_delta.to_le_bytes());
 
         Ok(())
     }
@@ -299,6 +633,10 @@ pub fn process_instruction(
         CREATE_MANAGER_DISCRIMINATOR => create_manager(program_id, accounts, data),
         REMOVE_ADMIN_DISCRIMINATOR => remove_admin(program_id, accounts),
         DEACTIVATE_MANAGER_DISCRIMINATOR => deactivate_manager(program_id, accounts),
+        MANAGER_UPDATE_FEE_DISCRIMINATOR => manager_update_fee(program_id, accounts, data),
+        CONFIGURE_DISCRIMINATOR => configure(program_id, accounts, data),
+        SET_FEE_RECIPIENT_DISCRIMINATOR => set_fee_recipient(program_id, accounts),
+        CLOSE_MANAGER_DISCRIMINATOR => close_manager(program_id, accounts),
         _ => Err(ProgramError::InvalidInstructionData),
     }
 }
@@ -316,6 +654,7 @@ pub fn process_instruction(
 /// This instruction is SECURE because:
 /// - SECURITY: The `super_admin` is verified as a signer
 /// - SECURITY: Account ownership is verified
+/// - SECURITY: An already-initialized config is rejected (AccountAlreadyInitialized)
 /// - SECURITY: Initial state is set correctly with super_admin in admin_list
 fn initialize_config(program_id: &Address, accounts: &[AccountView], data: &[u8]) -> ProgramResult {
     let [admin_config_acc, super_admin] = accounts else {
@@ -332,6 +671,10 @@ fn initialize_config(program_id: &Address, accounts: &[AccountView], data: &[u8]
         return Err(ProgramError::IllegalOwner);
     }
 
+    // SECURITY: Refuse to overwrite an existing config (Anchor's `init`)
+    // Without this, anyone could re-run initialize_config and become super_admin
+    check_uninitialized(&admin_config_acc.try_borrow()?)?;
+
     let bump = if data.is_empty() { 0 } else { data[0] };
 
     // Initialize account data
@@ -350,6 +693,8 @@ fn initialize_config(program_id: &Address, accounts: &[AccountView], data: &[u8]
         fee_basis_points: 100,
         paused: false,
         bump,
+        // Fees go to the super_admin until a separate recipient is set
+        fee_recipient: Address::new_from_array(*super_admin.address().as_array()),
     };
 
     let mut account_data = admin_config_acc.try_borrow_mut()?;
@@ -367,21 +712,20 @@ fn initialize_config(program_id: &Address, accounts: &[AccountView], data: &[u8]
 /// This instruction is SECURE because:
 /// - SECURITY: Caller must be a signer (is_signer() check)
 /// - SECURITY: Caller must match admin_config.super_admin
-/// - SECURITY: Account ownership is verified
+/// - SECURITY: Account ownership is verified, and admin_config must be writable
+/// - SECURITY: new_admin must not be the default address
 fn add_admin(program_id: &Address, accounts: &[AccountView]) -> ProgramResult {
     let [admin_config_acc, caller, new_admin] = accounts else {
         return Err(ProgramError::NotEnoughAccountKeys);
     };
 
-    // SECURITY: Verify caller is a signer (Pinocchio equivalent of Signer<'info>)
-    if !caller.is_signer() {
-        return Err(ProgramError::MissingRequiredSignature);
-    }
+    // SECURITY: Reject the default address as a new admin
+    assert_not_default_address(new_admin.address())?;
 
-    // SECURITY: Verify account is owned by this program
-    if !admin_config_acc.owned_by(program_id) {
-        return Err(ProgramError::IllegalOwner);
-    }
+    // SECURITY: Caller signs (Signer<'info>); admin_config is this program's
+    // account (Account<'info, AdminConfig>) and is written below (mut)
+    Role::of(caller).signer().check()?;
+    Role::of(admin_config_acc).owned_by(program_id).writable().check()?;
 
     // Read current data
     let account_data = admin_config_acc.try_borrow()?;
@@ -394,16 +738,8 @@ fn add_admin(program_id: &Address, accounts: &[AccountView]) -> ProgramResult {
         return Err(SecureError::NotSuperAdmin.into());
     }
 
-    // Check if admin list is full
-    if admin_config.admin_count as usize >= MAX_ADMINS {
-        log!("Error: Admin list is full");
-        return Err(SecureError::AdminListFull.into());
-    }
-
     // SECURITY: Only super_admin can add admins (enforced above)
-    let index = admin_config.admin_count as usize;
-    admin_config.admin_list[index] = Address::new_from_array(*new_admin.address().as_array());
-    admin_config.admin_count += 1;
+    admin_config.insert_admin(new_admin.address())?;
 
     // Write updated data
     let mut account_data = admin_config_acc.try_borrow_mut()?;
@@ -420,11 +756,19 @@ fn add_admin(program_id: &Address, accounts: &[AccountView]) -> ProgramResult {
 ///
 /// This instruction is SECURE because:
 /// - SECURITY: Caller must be a signer (is_signer() check)
-/// - SECURITY: Caller must be in admin_list (is_admin() check)
+/// - SECURITY: Caller must be in admin_list, or an active manager with
+///   `can_modify_fees` (caller_is_authorized() check)
+/// - SECURITY: A manager caller is still bounded by its `max_fee_delta`
 /// - SECURITY: Account ownership is verified
+///
+/// A manager caller passes its manager account as a third account.
 fn update_fee(program_id: &Address, accounts: &[AccountView], data: &[u8]) -> ProgramResult {
-    let [admin_config_acc, caller] = accounts else {
-        return Err(ProgramError::NotEnoughAccountKeys);
+    let (admin_config_acc, caller, manager_account_acc) = match accounts {
+        [admin_config_acc, caller] => (admin_config_acc, caller, None),
+        [admin_config_acc, caller, manager_account_acc] => {
+            (admin_config_acc, caller, Some(manager_account_acc))
+        }
+        _ => return Err(ProgramError::NotEnoughAccountKeys),
     };
 
     // Parse new_fee from instruction data
@@ -450,20 +794,127 @@ fn update_fee(program_id: &Address, accounts: &[AccountView], data: &[u8]) -> Pr
     let mut admin_config = AdminConfig::try_from_slice(&account_data)?;
     drop(account_data);
 
-    // SECURITY: Verify caller is in admin_list (is_admin helper)
-    if !is_admin(&admin_config.admin_list, admin_config.admin_count, caller.address()) {
-        log!("SECURITY REJECTION: Only admins can modify fees");
+    let manager_data = load_manager_account(program_id, manager_account_acc)?;
+
+    // SECURITY: Verify caller is an admin or a manager holding fee permission
+    if !caller_is_authorized(
+        &admin_config,
+        manager_data.as_ref(),
+        caller.address(),
+        ManagerPermission::ModifyFees,
+    ) {
+        log!("SECURITY REJECTION: Only admins and fee managers can modify fees");
         return Err(SecureError::NotAdmin.into());
     }
 
-    // SECURITY: Only admins can modify protocol fees
+    // SECURITY: Managers keep their per-call bound on this path too, the same
+    // as in manager_update_fee
+    let caller_is_admin =
+        is_admin(&admin_config.admin_list, admin_config.admin_count, caller.address());
+    if let (false, Some(manager_data)) = (caller_is_admin, manager_data) {
+        if new_fee.abs_diff(admin_config.fee_basis_points) > manager_data.max_fee_delta {
+            log!(
+                "SECURITY REJECTION: Fee change exceeds manager max_fee_delta of {}",
+                manager_data.max_fee_delta
+            );
+            return Err(SecureError::FeeDeltaTooLarge.into());
+        }
+    }
+
+    admin_config.fee_basis_points = new_fee;
+
+    // Write updated data
+    let mut account_data = admin_config_acc.try_borrow_mut()?;
+    admin_config.serialize(&mut account_data)?;
+
+    log!("SECURITY VERIFIED: Fee updated to {} basis points", new_fee);
+
+    Ok(())
+}
+
+/// Updates the protocol fee on behalf of a manager with `can_modify_fees`.
+///
+/// Unlike `update_fee`, which admins use without limit, a manager may only
+/// move the fee by up to `max_fee_delta` basis points per call.
+///
+/// # Security
+///
+/// This instruction is SECURE because:
+/// - SECURITY: Manager must be a signer
+/// - SECURITY: Account ownership is verified
+/// - SECURITY: manager_account is re-derived as the manager PDA (load_manager())
+/// - SECURITY: Signer must be the manager recorded in the manager account
+/// - SECURITY: Manager must be active and hold `can_modify_fees`
+/// - SECURITY: Fee change is bounded by `max_fee_delta`
+fn manager_update_fee(
+    program_id: &Address,
+    accounts: &[AccountView],
+    data: &[u8],
+) -> ProgramResult {
+    let [admin_config_acc, manager_account_acc, manager] = accounts else {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    // Parse new_fee from instruction data
+    if data.len() < 2 {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    let new_fee = u16::from_le_bytes(
+        data[0..2].try_into().map_err(|_| ProgramError::InvalidInstructionData)?,
+    );
+
+    // SECURITY: Verify manager is a signer
+    if !manager.is_signer() {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    // SECURITY: Verify admin_config is owned by this program
+    if !admin_config_acc.owned_by(program_id) {
+        return Err(ProgramError::IllegalOwner);
+    }
+
+    // SECURITY: Verify manager_account is this program's manager PDA
+    let manager_data = load_manager(manager_account_acc, program_id)?;
+
+    // SECURITY: The signer must be the manager this account was created for
+    if manager_data.manager != *manager.address() {
+        log!("SECURITY REJECTION: Signer is not the manager");
+        return Err(SecureError::Unauthorized.into());
+    }
+
+    // SECURITY: Deactivated managers keep no permissions
+    if !manager_data.is_active {
+        log!("SECURITY REJECTION: Manager is not active");
+        return Err(SecureError::ManagerNotActive.into());
+    }
+
+    // SECURITY: Manager must have been granted fee permission
+    if !manager_data.can_modify_fees {
+        log!("SECURITY REJECTION: Manager cannot modify fees");
+        return Err(SecureError::Unauthorized.into());
+    }
+
+    // Read current data
+    let account_data = admin_config_acc.try_borrow()?;
+    let mut admin_config = AdminConfig::try_from_slice(&account_data)?;
+    drop(account_data);
+
+    // SECURITY: Bound how far a manager can move the fee in one call
+    if new_fee.abs_diff(admin_config.fee_basis_points) > manager_data.max_fee_delta {
+        log!(
+            "SECURITY REJECTION: Fee change exceeds manager max_fee_delta of {}",
+            manager_data.max_fee_delta
+        );
+        return Err(SecureError::FeeDeltaTooLarge.into());
+    }
+
     admin_config.fee_basis_points = new_fee;
 
     // Write updated data
     let mut account_data = admin_config_acc.try_borrow_mut()?;
     admin_config.serialize(&mut account_data)?;
 
-    log!("SECURITY VERIFIED: Fee updated to {} basis points by admin", new_fee);
+    log!("SECURITY VERIFIED: Fee updated to {} basis points by manager", new_fee);
 
     Ok(())
 }
@@ -474,11 +925,20 @@ fn update_fee(program_id: &Address, accounts: &[AccountView], data: &[u8]) -> Pr
 ///
 /// This instruction is SECURE because:
 /// - SECURITY: Caller must be a signer
-/// - SECURITY: Caller must be super_admin
-/// - SECURITY: Pause is a critical function requiring highest authority
+/// - SECURITY: Caller must be in admin_list, or an active manager with
+///   `can_pause` (caller_is_authorized() check)
+///
+/// Any admin can already grant `can_pause` through create_manager, so
+/// admins may pause directly. Unpausing stays with the super_admin.
+///
+/// A manager caller passes its manager account as a third account.
 fn pause_protocol(program_id: &Address, accounts: &[AccountView]) -> ProgramResult {
-    let [admin_config_acc, caller] = accounts else {
-        return Err(ProgramError::NotEnoughAccountKeys);
+    let (admin_config_acc, caller, manager_account_acc) = match accounts {
+        [admin_config_acc, caller] => (admin_config_acc, caller, None),
+        [admin_config_acc, caller, manager_account_acc] => {
+            (admin_config_acc, caller, Some(manager_account_acc))
+        }
+        _ => return Err(ProgramError::NotEnoughAccountKeys),
     };
 
     // SECURITY: Verify caller is a signer
@@ -496,20 +956,26 @@ fn pause_protocol(program_id: &Address, accounts: &[AccountView]) -> ProgramResu
     let mut admin_config = AdminConfig::try_from_slice(&account_data)?;
     drop(account_data);
 
-    // SECURITY: Verify caller is super_admin
-    if admin_config.super_admin.as_ref() != caller.address().as_ref() {
-        log!("SECURITY REJECTION: Only super_admin can pause protocol");
-        return Err(SecureError::NotSuperAdmin.into());
+    let manager_data = load_manager_account(program_id, manager_account_acc)?;
+
+    // SECURITY: Verify caller is an admin or a manager holding pause permission
+    if !caller_is_authorized(
+        &admin_config,
+        manager_data.as_ref(),
+        caller.address(),
+        ManagerPermission::Pause,
+    ) {
+        log!("SECURITY REJECTION: Only admins and pause managers can pause protocol");
+        return Err(SecureError::NotAdmin.into());
     }
 
-    // SECURITY: Only super_admin can pause
     admin_config.paused = true;
 
     // Write updated data
     let mut account_data = admin_config_acc.try_borrow_mut()?;
     admin_config.serialize(&mut account_data)?;
 
-    log!("SECURITY VERIFIED: Protocol paused by super_admin");
+    log!("SECURITY VERIFIED: Protocol paused");
 
     Ok(())
 }
@@ -518,7 +984,11 @@ fn pause_protocol(program_id: &Address, accounts: &[AccountView]) -> ProgramResu
 ///
 /// # Security
 ///
-/// Same security requirements as pause_protocol.
+/// This instruction is SECURE because:
+/// - SECURITY: Caller must be a signer
+/// - SECURITY: Account ownership is verified
+/// - SECURITY: Caller must be the super_admin; unlike pause_protocol, other
+///   admins and pause managers are rejected (NotSuperAdmin)
 fn unpause_protocol(program_id: &Address, accounts: &[AccountView]) -> ProgramResult {
     let [admin_config_acc, caller] = accounts else {
         return Err(ProgramError::NotEnoughAccountKeys);
@@ -560,39 +1030,65 @@ fn unpause_protocol(program_id: &Address, accounts: &[AccountView]) -> ProgramRe
 ///
 /// Note: Account must be pre-created by the test harness.
 ///
+/// `payer` mirrors Anchor's `payer = payer` in `CreateManager`. It is only
+/// validated for now; once this instruction creates the manager PDA itself,
+/// `payer` funds its rent.
+///
 /// # Security
 ///
 /// This instruction is SECURE because:
 /// - SECURITY: Admin must be a signer
+/// - SECURITY: Payer must be a signer (may differ from admin)
 /// - SECURITY: Admin must be in admin_list (is_admin() check)
 /// - SECURITY: Account ownership is verified
+/// - SECURITY: manager must not be the default address
+/// - SECURITY: manager_account must be the manager PDA at its canonical bump,
+///   so every later load_manager() call accepts it
+/// - SECURITY: admin_config is only read, so it must not be writable
 fn create_manager(program_id: &Address, accounts: &[AccountView], data: &[u8]) -> ProgramResult {
-    let [admin_config_acc, manager_account_acc, admin, manager] = accounts else {
+    let [admin_config_acc, manager_account_acc, admin, manager, payer] = accounts else {
         return Err(ProgramError::NotEnoughAccountKeys);
     };
 
+    // SECURITY: Reject the default address as a manager
+    assert_not_default_address(manager.address())?;
+
     // Parse instruction data
-    if data.len() < 3 {
+    if data.len() < 5 {
         return Err(ProgramError::InvalidInstructionData);
     }
     let can_modify_fees = data[0] != 0;
     let can_pause = data[1] != 0;
     let bump = data[2];
+    let max_fee_delta = u16::from_le_bytes(
+        data[3..5].try_into().map_err(|_| ProgramError::InvalidInstructionData)?,
+    );
 
     // SECURITY: Verify admin is a signer
     if !admin.is_signer() {
         return Err(ProgramError::MissingRequiredSignature);
     }
 
+    // SECURITY: Verify payer is a signer before it can be charged for rent
+    if !payer.is_signer() {
+        log!("SECURITY REJECTION: Payer must be a signer");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
     // SECURITY: Verify admin_config is owned by this program
     if !admin_config_acc.owned_by(program_id) {
         return Err(ProgramError::IllegalOwner);
     }
 
-    // SECURITY: Verify manager_account is owned by this program
-    if !manager_account_acc.owned_by(program_id) {
-        return Err(ProgramError::IllegalOwner);
-    }
+    // SECURITY: Verify manager_account is owned by this program and is the
+    // PDA for this manager at the canonical bump
+    SecurityChecks::new(program_id)
+        .owned(manager_account_acc)
+        .pda(&[MANAGER_SEED, manager.address().as_ref()], manager_account_acc, bump)
+        .run()?;
+
+    // SECURITY: admin_config is validation-only here
+    assert_read_only(admin_config_acc)?;
 
     // Read admin_config
     let account_data = admin_config_acc.try_borrow()?;
@@ -613,6 +1109,7 @@ fn create_manager(program_id: &Address, accounts: &[AccountView], data: &[u8]) -
         can_pause,
         is_active: true,
         bump,
+        max_fee_delta,
     };
 
     let mut account_data = manager_account_acc.try_borrow_mut()?;
@@ -631,11 +1128,15 @@ fn create_manager(program_id: &Address, accounts: &[AccountView], data: &[u8]) -
 /// - SECURITY: Caller must be a signer
 /// - SECURITY: Caller must be super_admin
 /// - SECURITY: Cannot remove super_admin from list
+/// - SECURITY: admin_to_remove must not be the default address
 fn remove_admin(program_id: &Address, accounts: &[AccountView]) -> ProgramResult {
     let [admin_config_acc, caller, admin_to_remove] = accounts else {
         return Err(ProgramError::NotEnoughAccountKeys);
     };
 
+    // SECURITY: Reject the default address as the admin to remove
+    assert_not_default_address(admin_to_remove.address())?;
+
     // SECURITY: Verify caller is a signer
     if !caller.is_signer() {
         return Err(ProgramError::MissingRequiredSignature);
@@ -663,35 +1164,7 @@ fn remove_admin(program_id: &Address, accounts: &[AccountView]) -> ProgramResult
         return Err(SecureError::CannotRemoveSuperAdmin.into());
     }
 
-    // Find the admin in the list
-    let count = admin_config.admin_count as usize;
-    let mut found_index: Option<usize> = None;
-
-    for i in 0..count {
-        if admin_config.admin_list[i].as_ref() == admin_to_remove.address().as_ref() {
-            found_index = Some(i);
-            break;
-        }
-    }
-
-    // Return error if admin not found
-    let index = match found_index {
-        Some(i) => i,
-        None => {
-            log!("Error: Admin not found in admin list");
-            return Err(SecureError::AdminNotFound.into());
-        }
-    };
-
-    // Remove admin by shifting remaining entries left
-    for i in index..count - 1 {
-        admin_config.admin_list[i] =
-            Address::new_from_array(*admin_config.admin_list[i + 1].as_array());
-    }
-
-    // Clear the last slot and decrement count
-    admin_config.admin_list[count - 1] = Address::new_from_array([0u8; 32]);
-    admin_config.admin_count -= 1;
+    admin_config.remove_admin(admin_to_remove.address())?;
 
     // Write updated data
     let mut account_data = admin_config_acc.try_borrow_mut()?;
@@ -709,6 +1182,8 @@ fn remove_admin(program_id: &Address, accounts: &[AccountView]) -> ProgramResult
 /// This instruction is SECURE because:
 /// - SECURITY: Caller must be a signer
 /// - SECURITY: Caller must be in admin_list
+/// - SECURITY: manager_account is re-derived as the manager PDA (load_manager())
+/// - SECURITY: admin_config is only read, so it must not be writable
 fn deactivate_manager(program_id: &Address, accounts: &[AccountView]) -> ProgramResult {
     let [admin_config_acc, manager_account_acc, caller] = accounts else {
         return Err(ProgramError::NotEnoughAccountKeys);
@@ -719,13 +1194,16 @@ fn deactivate_manager(program_id: &Address, accounts: &[AccountView]) -> Program
         return Err(ProgramError::MissingRequiredSignature);
     }
 
-    // SECURITY: Verify accounts are owned by this program
+    // SECURITY: Verify admin_config is owned by this program
     if !admin_config_acc.owned_by(program_id) {
         return Err(ProgramError::IllegalOwner);
     }
-    if !manager_account_acc.owned_by(program_id) {
-        return Err(ProgramError::IllegalOwner);
-    }
+
+    // SECURITY: Verify manager_account is this program's manager PDA
+    let mut manager_data = load_manager(manager_account_acc, program_id)?;
+
+    // SECURITY: admin_config is validation-only here
+    assert_read_only(admin_config_acc)?;
 
     // Read admin_config
     let account_data = admin_config_acc.try_borrow()?;
@@ -738,11 +1216,6 @@ fn deactivate_manager(program_id: &Address, accounts: &[AccountView]) -> Program
         return Err(SecureError::NotAdmin.into());
     }
 
-    // Read and update manager account
-    let account_data = manager_account_acc.try_borrow()?;
-    let mut manager_data = ManagerAccount::try_from_slice(&account_data)?;
-    drop(account_data);
-
     manager_data.is_active = false;
 
     let mut account_data = manager_account_acc.try_borrow_mut()?;
@@ -753,27 +1226,242 @@ fn deactivate_manager(program_id: &Address, accounts: &[AccountView]) -> Program
     Ok(())
 }
 
-// =============================================================================
-// TESTS
-// =============================================================================
+/// Closes a deactivated manager account, moving all its lamports to `recipient`.
+///
+/// Pinocchio equivalent of Anchor's `close = recipient`: the data is zeroed,
+/// then the rent is swept.
+///
+/// # Security
+///
+/// This instruction is SECURE because:
+/// - SECURITY: Caller must be a signer
+/// - SECURITY: Caller must be in admin_list
+/// - SECURITY: manager_account is re-derived as the manager PDA (load_manager())
+/// - SECURITY: admin_config is only read, so it must not be writable
+/// - SECURITY: An active manager is rejected; it must be deactivated first
+/// - SECURITY: The data is zeroed before the lamports leave, so nothing later
+///   in the transaction can read the old permissions
+fn close_manager(program_id: &Address, accounts: &[AccountView]) -> ProgramResult {
+    let [admin_config_acc, manager_account_acc, recipient, caller] = accounts else {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    // SECURITY: Verify caller is a signer
+    if !caller.is_signer() {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
 
-    #[test]
-    fn test_is_admin_helper() {
-        let admin1 = Address::new_from_array([1u8; 32]);
-        let admin2 = Address::new_from_array([2u8; 32]);
-        let non_admin = Address::new_from_array([99u8; 32]);
+    // SECURITY: Verify admin_config is owned by this program
+    if !admin_config_acc.owned_by(program_id) {
+        return Err(ProgramError::IllegalOwner);
+    }
 
-        // Create admin_list with fresh Address instances
-        let admin_list = [
-            Address::new_from_array([1u8; 32]),
-            Address::new_from_array([2u8; 32]),
-            Address::new_from_array([0u8; 32]),
-        ];
-        let admin_count = 2;
+    // SECURITY: Verify manager_account is this program's manager PDA
+    let manager_data = load_manager(manager_account_acc, program_id)?;
+
+    // SECURITY: admin_config is validation-only here
+    assert_read_only(admin_config_acc)?;
+
+    // SECURITY: Sweeping the lamports into the closed account itself would
+    // leave them on a zeroed account instead of returning them
+    if recipient.address() == manager_account_acc.address() {
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    // Read admin_config
+    let account_data = admin_config_acc.try_borrow()?;
+    let admin_config = AdminConfig::try_from_slice(&account_data)?;
+    drop(account_data);
+
+    // SECURITY: Verify caller is in admin_list
+    if !is_admin(&admin_config.admin_list, admin_config.admin_count, caller.address()) {
+        log!("SECURITY REJECTION: Only admins can close managers");
+        return Err(SecureError::NotAdmin.into());
+    }
+
+    // SECURITY: Only a deactivated manager can be closed
+    if manager_data.is_active {
+        log!("SECURITY REJECTION: Manager must be deactivated before closing");
+        return Err(SecureError::ManagerNotActive.into());
+    }
+
+    let recipient_lamports = recipient
+        .lamports()
+        .checked_add(manager_account_acc.lamports())
+        .ok_or(ProgramError::ArithmeticOverflow)?;
+
+    zero_account_data(manager_account_acc)?;
+    manager_account_acc.set_lamports(0);
+    recipient.set_lamports(recipient_lamports);
+
+    log!("SECURITY VERIFIED: Manager account closed by admin");
+
+    Ok(())
+}
+
+/// Atomically sets the fee, pause state and admin list.
+///
+/// Instruction data: `[new_fee (u16 LE)] [paused (u8)] [admin_count (u8)]
+/// [admins (admin_count * 32 bytes)]`.
+///
+/// # Security
+///
+/// This instruction is SECURE because:
+/// - SECURITY: Caller must be a signer
+/// - SECURITY: Caller must be super_admin
+/// - SECURITY: Account ownership is verified
+/// - SECURITY: Every field is validated by `validate_config()` before any is written
+fn configure(program_id: &Address, accounts: &[AccountView], data: &[u8]) -> ProgramResult {
+    let [admin_config_acc, caller] = accounts else {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    // Parse instruction data
+    if data.len() < 4 {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    let new_fee = u16::from_le_bytes(
+        data[0..2].try_into().map_err(|_| ProgramError::InvalidInstructionData)?,
+    );
+    let paused = data[2] != 0;
+    let admin_count = data[3] as usize;
+    if admin_count > MAX_ADMINS {
+        return Err(SecureError::AdminListFull.into());
+    }
+    if data.len() < 4 + admin_count * 32 {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    let mut admin_list = core::array::from_fn(|_| Address::default());
+    for (i, admin) in admin_list.iter_mut().take(admin_count).enumerate() {
+        let start = 4 + i * 32;
+        *admin = Address::new_from_array(
+            data[start..start + 32].try_into().map_err(|_| ProgramError::InvalidInstructionData)?,
+        );
+    }
+
+    // SECURITY: Verify caller is a signer
+    if !caller.is_signer() {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    // SECURITY: Verify account is owned by this program
+    if !admin_config_acc.owned_by(program_id) {
+        return Err(ProgramError::IllegalOwner);
+    }
+
+    // Read current data
+    let account_data = admin_config_acc.try_borrow()?;
+    let mut admin_config = AdminConfig::try_from_slice(&account_data)?;
+    drop(account_data);
+
+    // SECURITY: Verify caller is the super_admin
+    if admin_config.super_admin.as_ref() != caller.address().as_ref() {
+        log!("SECURITY REJECTION: Only super_admin can configure");
+        return Err(SecureError::NotSuperAdmin.into());
+    }
+
+    // SECURITY: Validate everything first so a bad field applies nothing
+    validate_config(&admin_config.super_admin, new_fee, &admin_list[..admin_count])?;
+
+    // Callers may list admins in any order; store them sorted for binary search
+    #[cfg(feature = "sorted_admins")]
+    admin_list[..admin_count].sort_unstable();
+
+    admin_config.fee_basis_points = new_fee;
+    admin_config.paused = paused;
+    admin_config.admin_list = admin_list;
+    admin_config.admin_count = admin_count as u8;
+
+    // Write updated data
+    let mut account_data = admin_config_acc.try_borrow_mut()?;
+    admin_config.serialize(&mut account_data)?;
+
+    log!("SECURITY VERIFIED: Config updated by super_admin");
+
+    Ok(())
+}
+
+/// Sets the account credited by fee collection.
+///
+/// # Security
+///
+/// This instruction is SECURE because:
+/// - SECURITY: Caller must be a signer
+/// - SECURITY: Caller must be super_admin; admins can change the fee but not
+///   where it is paid
+/// - SECURITY: Account ownership is verified
+/// - SECURITY: new_fee_recipient must not be the default address
+fn set_fee_recipient(program_id: &Address, accounts: &[AccountView]) -> ProgramResult {
+    let [admin_config_acc, caller, new_fee_recipient] = accounts else {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    // SECURITY: Fees paid to the default address would be unrecoverable
+    assert_not_default_address(new_fee_recipient.address())?;
+
+    // SECURITY: Verify caller is a signer
+    if !caller.is_signer() {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    // SECURITY: Verify account is owned by this program
+    if !admin_config_acc.owned_by(program_id) {
+        return Err(ProgramError::IllegalOwner);
+    }
+
+    // Read current data
+    let account_data = admin_config_acc.try_borrow()?;
+    let mut admin_config = AdminConfig::try_from_slice(&account_data)?;
+    drop(account_data);
+
+    // SECURITY: Verify caller is the super_admin
+    if admin_config.super_admin.as_ref() != caller.address().as_ref() {
+        log!("SECURITY REJECTION: Only super_admin can set the fee recipient");
+        return Err(SecureError::NotSuperAdmin.into());
+    }
+
+    admin_config.fee_recipient = Address::new_from_array(*new_fee_recipient.address().as_array());
+
+    // Write updated data
+    let mut account_data = admin_config_acc.try_borrow_mut()?;
+    admin_config.serialize(&mut account_data)?;
+
+    log!("SECURITY VERIFIED: Fee recipient updated by super_admin");
+
+    Ok(())
+}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pinocchio_security_common::{
+        assert_reinit_rejected, decode_address, find_program_address, CommonError, MockAccountView,
+        XorShift64,
+    };
+
+    #[test]
+    fn test_program_id_matches_documented_address() {
+        // Must match the doc comment on `ID` and Anchor.toml
+        assert_eq!(decode_address("3P6BDR7EK5DV7gWyVLSceYRbnUkywjDupYugSQre7eyp"), Some(ID));
+    }
+
+    #[test]
+    fn test_is_admin_helper() {
+        let admin1 = Address::new_from_array([1u8; 32]);
+        let admin2 = Address::new_from_array([2u8; 32]);
+        let non_admin = Address::new_from_array([99u8; 32]);
+
+        // Create admin_list with fresh Address instances
+        let admin_list = [
+            Address::new_from_array([1u8; 32]),
+            Address::new_from_array([2u8; 32]),
+            Address::new_from_array([0u8; 32]),
+        ];
+        let admin_count = 2;
 
         assert!(is_admin(&admin_list, admin_count, &admin1));
         assert!(is_admin(&admin_list, admin_count, &admin2));
@@ -797,17 +1485,24 @@ mod tests {
             fee_basis_points: 100,
             paused: false,
             bump: 255,
+            fee_recipient: SUPER_ADMIN,
         };
 
         let mut buffer = [0u8; ADMIN_CONFIG_SIZE];
         config.serialize(&mut buffer).unwrap();
 
         let deserialized = AdminConfig::try_from_slice(&buffer).unwrap();
-        assert_eq!(deserialized.super_admin, config.super_admin);
-        assert_eq!(deserialized.admin_count, config.admin_count);
-        assert_eq!(deserialized.fee_basis_points, config.fee_basis_points);
-        assert_eq!(deserialized.paused, config.paused);
-        assert_eq!(deserialized.bump, config.bump);
+        assert_eq!(deserialized, config);
+    }
+
+    #[test]
+    fn test_admin_config_rejects_out_of_range_fee() {
+        // 100% is the largest valid fee
+        let mut buffer = readonly_config_account(MAX_FEE_BASIS_POINTS).data().to_vec();
+        assert!(AdminConfig::try_from_slice(&buffer).is_ok());
+
+        buffer[129..131].copy_from_slice(&20_000u16.to_le_bytes());
+        assert_eq!(AdminConfig::try_from_slice(&buffer), Err(ProgramError::InvalidAccountData));
     }
 
     #[test]
@@ -819,17 +1514,686 @@ mod tests {
             can_pause: false,
             is_active: true,
             bump: 254,
+            max_fee_delta: 50,
         };
 
         let mut buffer = [0u8; MANAGER_ACCOUNT_SIZE];
         manager.serialize(&mut buffer).unwrap();
 
         let deserialized = ManagerAccount::try_from_slice(&buffer).unwrap();
-        assert_eq!(deserialized.authority, manager.authority);
-        assert_eq!(deserialized.manager, manager.manager);
-        assert_eq!(deserialized.can_modify_fees, manager.can_modify_fees);
-        assert_eq!(deserialized.can_pause, manager.can_pause);
-        assert_eq!(deserialized.is_active, manager.is_active);
-        assert_eq!(deserialized.bump, manager.bump);
+        assert_eq!(deserialized, manager);
+    }
+
+    #[test]
+    fn test_account_space_matches_size() {
+        // Clients allocate these exact sizes; changing one is a breaking layout change
+        assert_eq!(AdminConfig::space(), ADMIN_CONFIG_SIZE);
+        assert_eq!(ManagerAccount::space(), MANAGER_ACCOUNT_SIZE);
+        assert_eq!((ADMIN_CONFIG_SIZE, MANAGER_ACCOUNT_SIZE), (165, 70));
+    }
+
+    #[test]
+    fn test_default_address_rejected() {
+        // add_admin, create_manager and remove_admin all route through this check
+        let result = assert_not_default_address(&Address::default());
+        assert_eq!(result, Err(ProgramError::Custom(SecureError::InvalidAddress as u32)));
+
+        let zero = Address::new_from_array([0u8; 32]);
+        assert!(assert_not_default_address(&zero).is_err());
+    }
+
+    #[test]
+    fn test_non_default_address_accepted() {
+        let admin = Address::new_from_array([1u8; 32]);
+        assert!(assert_not_default_address(&admin).is_ok());
+    }
+
+    const MANAGER: Address = Address::new_from_array([2u8; 32]);
+
+    /// MANAGER's manager PDA and its canonical bump.
+    fn manager_pda() -> (Address, u8) {
+        find_program_address(&[MANAGER_SEED, MANAGER.as_ref()], &ID).unwrap()
+    }
+
+    /// Runs manager_update_fee against a config at `current_fee` and an
+    /// active fee manager bounded by `max_fee_delta`; returns the stored fee.
+    fn run_manager_update_fee(
+        current_fee: u16,
+        max_fee_delta: u16,
+        new_fee: u16,
+    ) -> Result<u16, ProgramError> {
+        let mut config_data = [0u8; ADMIN_CONFIG_SIZE];
+        AdminConfig {
+            super_admin: Address::new_from_array([1u8; 32]),
+            admin_list: [
+                Address::new_from_array([1u8; 32]),
+                Address::new_from_array([0u8; 32]),
+                Address::new_from_array([0u8; 32]),
+            ],
+            admin_count: 1,
+            fee_basis_points: current_fee,
+            paused: false,
+            bump: 255,
+            fee_recipient: SUPER_ADMIN,
+        }
+        .serialize(&mut config_data)?;
+
+        let (manager_address, manager_bump) = manager_pda();
+        let mut manager_data = [0u8; MANAGER_ACCOUNT_SIZE];
+        ManagerAccount {
+            authority: Address::new_from_array([1u8; 32]),
+            manager: MANAGER,
+            can_modify_fees: true,
+            can_pause: false,
+            is_active: true,
+            bump: manager_bump,
+            max_fee_delta,
+        }
+        .serialize(&mut manager_data)?;
+
+        let mut admin_config =
+            MockAccountView::new(Address::new_from_array([3u8; 32]), ID, 0, &config_data)
+                .writable();
+        let mut manager_account = MockAccountView::new(manager_address, ID, 0, &manager_data);
+        let mut manager = MockAccountView::new(MANAGER, Address::default(), 0, &[]).signer();
+
+        manager_update_fee(
+            &ID,
+            &[admin_config.view(), manager_account.view(), manager.view()],
+            &new_fee.to_le_bytes(),
+        )?;
+        Ok(AdminConfig::try_from_slice(admin_config.data())?.fee_basis_points)
+    }
+
+    #[test]
+    fn test_manager_fee_change_within_delta() {
+        // Up and down by exactly max_fee_delta are both allowed
+        assert_eq!(run_manager_update_fee(100, 50, 150), Ok(150));
+        assert_eq!(run_manager_update_fee(100, 50, 50), Ok(50));
+    }
+
+    #[test]
+    fn test_manager_fee_change_beyond_delta_rejected() {
+        let too_large = Err(SecureError::FeeDeltaTooLarge.into());
+        assert_eq!(run_manager_update_fee(100, 50, 151), too_large);
+        assert_eq!(run_manager_update_fee(100, 50, 49), too_large);
+        assert_eq!(run_manager_update_fee(100, 0, 10_000), too_large);
+    }
+
+    const SUPER_ADMIN: Address = Address::new_from_array([1u8; 32]);
+
+    fn config_account(fee_basis_points: u16) -> MockAccountView {
+        readonly_config_account(fee_basis_points).writable()
+    }
+
+    fn readonly_config_account(fee_basis_points: u16) -> MockAccountView {
+        let mut data = [0u8; ADMIN_CONFIG_SIZE];
+        AdminConfig {
+            super_admin: SUPER_ADMIN,
+            admin_list: [SUPER_ADMIN, Address::default(), Address::default()],
+            admin_count: 1,
+            fee_basis_points,
+            paused: false,
+            bump: 255,
+            fee_recipient: SUPER_ADMIN,
+        }
+        .serialize(&mut data)
+        .unwrap();
+        MockAccountView::new(Address::new_from_array([3u8; 32]), ID, 0, &data)
+    }
+
+    /// Manager account for MANAGER with the given permissions, at its PDA.
+    fn manager_account(can_modify_fees: bool, can_pause: bool) -> MockAccountView {
+        let (address, bump) = manager_pda();
+        let mut data = [0u8; MANAGER_ACCOUNT_SIZE];
+        ManagerAccount {
+            authority: SUPER_ADMIN,
+            manager: MANAGER,
+            can_modify_fees,
+            can_pause,
+            is_active: true,
+            bump,
+            max_fee_delta: 50,
+        }
+        .serialize(&mut data)
+        .unwrap();
+        MockAccountView::new(address, ID, 0, &data)
+    }
+
+    #[test]
+    fn test_caller_is_authorized() {
+        let config = AdminConfig::try_from_slice(config_account(100).data()).unwrap();
+        let fee_manager =
+            ManagerAccount::try_from_slice(manager_account(true, false).data()).unwrap();
+
+        // Admins need no manager account
+        assert!(caller_is_authorized(&config, None, &SUPER_ADMIN, ManagerPermission::Pause));
+
+        // A manager needs the permission, an active account, and to be the manager
+        let fees = ManagerPermission::ModifyFees;
+        assert!(caller_is_authorized(&config, Some(&fee_manager), &MANAGER, fees));
+        assert!(!caller_is_authorized(&config, None, &MANAGER, fees));
+        assert!(!caller_is_authorized(
+            &config,
+            Some(&fee_manager),
+            &MANAGER,
+            ManagerPermission::Pause
+        ));
+        let outsider = Address::new_from_array([9u8; 32]);
+        assert!(!caller_is_authorized(&config, Some(&fee_manager), &outsider, fees));
+        let inactive = ManagerAccount { is_active: false, ..fee_manager };
+        assert!(!caller_is_authorized(&config, Some(&inactive), &MANAGER, fees));
+    }
+
+    #[test]
+    fn test_update_fee_admin_and_fee_manager() {
+        let mut config = config_account(100);
+        let mut admin = MockAccountView::new(SUPER_ADMIN, Address::default(), 0, &[]).signer();
+        assert!(update_fee(&ID, &[config.view(), admin.view()], &500u16.to_le_bytes()).is_ok());
+        assert_eq!(AdminConfig::try_from_slice(config.data()).unwrap().fee_basis_points, 500);
+
+        let mut manager = MockAccountView::new(MANAGER, Address::default(), 0, &[]).signer();
+        let mut fee_manager = manager_account(true, false);
+        let accounts = [config.view(), manager.view(), fee_manager.view()];
+        assert!(update_fee(&ID, &accounts, &550u16.to_le_bytes()).is_ok());
+        assert_eq!(AdminConfig::try_from_slice(config.data()).unwrap().fee_basis_points, 550);
+
+        // The manager's max_fee_delta still applies
+        assert_eq!(
+            update_fee(&ID, &accounts, &601u16.to_le_bytes()),
+            Err(SecureError::FeeDeltaTooLarge.into())
+        );
+    }
+
+    #[test]
+    fn test_update_fee_and_pause_reject_unpermitted_manager() {
+        let mut config = config_account(100);
+        let mut manager = MockAccountView::new(MANAGER, Address::default(), 0, &[]).signer();
+
+        let mut pause_manager = manager_account(false, true);
+        let result =
+            update_fee(&ID, &[config.view(), manager.view(), pause_manager.view()], &[150, 0]);
+        assert_eq!(result, Err(SecureError::NotAdmin.into()));
+
+        let mut fee_manager = manager_account(true, false);
+        let result = pause_protocol(&ID, &[config.view(), manager.view(), fee_manager.view()]);
+        assert_eq!(result, Err(SecureError::NotAdmin.into()));
+
+        let after = AdminConfig::try_from_slice(config.data()).unwrap();
+        assert_eq!((after.fee_basis_points, after.paused), (100, false));
+    }
+
+    #[test]
+    fn test_pause_by_admin_and_pause_manager() {
+        let mut config = config_account(100);
+        let mut admin = MockAccountView::new(SUPER_ADMIN, Address::default(), 0, &[]).signer();
+        assert!(pause_protocol(&ID, &[config.view(), admin.view()]).is_ok());
+        assert!(AdminConfig::try_from_slice(config.data()).unwrap().paused);
+
+        let mut config = config_account(100);
+        let mut manager = MockAccountView::new(MANAGER, Address::default(), 0, &[]).signer();
+        let mut pause_manager = manager_account(false, true);
+        assert!(pause_protocol(&ID, &[config.view(), manager.view(), pause_manager.view()]).is_ok());
+        assert!(AdminConfig::try_from_slice(config.data()).unwrap().paused);
+    }
+
+    fn configure_data(new_fee: u16, paused: bool, admins: &[Address]) -> Vec<u8> {
+        let mut data = new_fee.to_le_bytes().to_vec();
+        data.push(paused as u8);
+        data.push(admins.len() as u8);
+        for admin in admins {
+            data.extend_from_slice(admin.as_ref());
+        }
+        data
+    }
+
+    fn run_configure(config: &mut MockAccountView, data: &[u8]) -> ProgramResult {
+        let mut caller = MockAccountView::new(SUPER_ADMIN, Address::default(), 0, &[]).signer();
+        configure(&ID, &[config.view(), caller.view()], data)
+    }
+
+    #[test]
+    fn test_configure_applies_all_fields() {
+        let second_admin = Address::new_from_array([5u8; 32]);
+        let mut config = config_account(100);
+
+        let result =
+            run_configure(&mut config, &configure_data(250, true, &[SUPER_ADMIN, second_admin]));
+        assert!(result.is_ok());
+
+        let updated = AdminConfig::try_from_slice(config.data()).unwrap();
+        assert_eq!(updated.fee_basis_points, 250);
+        assert!(updated.paused);
+        assert_eq!(updated.admin_count, 2);
+        assert_eq!(updated.admin_list[1], second_admin);
+    }
+
+    #[test]
+    fn test_configure_invalid_field_applies_nothing() {
+        let second_admin = Address::new_from_array([5u8; 32]);
+        let mut config = config_account(100);
+        let before = config.data().to_vec();
+
+        // Valid pause state and admin list, but the fee is over 100%
+        let result = run_configure(
+            &mut config,
+            &configure_data(MAX_FEE_BASIS_POINTS + 1, true, &[SUPER_ADMIN, second_admin]),
+        );
+        assert_eq!(result, Err(SecureError::FeeTooHigh.into()));
+        assert_eq!(config.data(), &before[..]);
+
+        // Valid fee, but a duplicated admin
+        let result =
+            run_configure(&mut config, &configure_data(250, true, &[SUPER_ADMIN, SUPER_ADMIN]));
+        assert_eq!(result, Err(SecureError::DuplicateAdmin.into()));
+        assert_eq!(config.data(), &before[..]);
+    }
+
+    #[test]
+    fn test_validate_config_rejections() {
+        let other = Address::new_from_array([5u8; 32]);
+        assert!(validate_config(&SUPER_ADMIN, MAX_FEE_BASIS_POINTS, &[SUPER_ADMIN]).is_ok());
+        assert_eq!(
+            validate_config(&SUPER_ADMIN, 100, &[other]),
+            Err(SecureError::CannotRemoveSuperAdmin.into())
+        );
+        assert_eq!(
+            validate_config(&SUPER_ADMIN, 100, &[]),
+            Err(SecureError::CannotRemoveSuperAdmin.into())
+        );
+        assert_eq!(
+            validate_config(&SUPER_ADMIN, 100, &[SUPER_ADMIN, Address::default()]),
+            Err(SecureError::InvalidAddress.into())
+        );
+    }
+
+    /// Random inserts and removes keep admin_list matching a simple model:
+    /// the same members, no stale entries past admin_count, and (with
+    /// `sorted_admins`) ascending order.
+    #[test]
+    fn test_admin_list_invariants_randomized() {
+        let mut rng = XorShift64::new(0x5EED_0002_AD31_0001);
+        let candidates: [Address; 6] =
+            core::array::from_fn(|i| Address::new_from_array([(i as u8 + 1) * 37; 32]));
+        let mut config = AdminConfig::try_from_slice(config_account(100).data()).unwrap();
+        config.admin_count = 0;
+        config.admin_list = [Address::default(); MAX_ADMINS];
+        let mut model: Vec<Address> = Vec::new();
+
+        for _ in 0..500 {
+            let candidate = candidates[rng.below(candidates.len() as u64) as usize];
+            if rng.below(2) == 0 {
+                let result = config.insert_admin(&candidate);
+                if model.contains(&candidate) {
+                    // Only sorted mode detects the duplicate; skip either way
+                    if result.is_ok() {
+                        config.remove_admin(&candidate).unwrap();
+                    }
+                } else if model.len() == MAX_ADMINS {
+                    assert_eq!(result, Err(SecureError::AdminListFull.into()));
+                } else {
+                    assert!(result.is_ok());
+                    model.push(candidate);
+                }
+            } else {
+                let result = config.remove_admin(&candidate);
+                if let Some(index) = model.iter().position(|admin| admin == &candidate) {
+                    assert!(result.is_ok());
+                    model.remove(index);
+                } else {
+                    assert_eq!(result, Err(SecureError::AdminNotFound.into()));
+                }
+            }
+
+            let count = config.admin_count as usize;
+            assert_eq!(count, model.len());
+            for candidate in &candidates {
+                assert_eq!(
+                    is_admin(&config.admin_list, config.admin_count, candidate),
+                    model.contains(candidate)
+                );
+            }
+            assert!(config.admin_list[count..].iter().all(|slot| slot == &Address::default()));
+            #[cfg(feature = "sorted_admins")]
+            assert!(config.admin_list[..count].windows(2).all(|pair| pair[0] < pair[1]));
+        }
+    }
+
+    #[test]
+    fn test_as_bounded_index() {
+        assert_eq!(as_bounded_index(0, MAX_ADMINS), Ok(0));
+        assert_eq!(as_bounded_index(MAX_ADMINS as u8, MAX_ADMINS), Ok(MAX_ADMINS));
+        assert_eq!(
+            as_bounded_index(MAX_ADMINS as u8 + 1, MAX_ADMINS),
+            Err(ProgramError::InvalidAccountData)
+        );
+        assert_eq!(as_bounded_index(u8::MAX, MAX_ADMINS), Err(ProgramError::InvalidAccountData));
+    }
+
+    #[test]
+    fn test_add_admin_account_roles() {
+        let new_admin = Address::new_from_array([5u8; 32]);
+        let mut admin = MockAccountView::new(new_admin, Address::default(), 0, &[]);
+        let mut signed = MockAccountView::new(SUPER_ADMIN, Address::default(), 0, &[]).signer();
+        let mut unsigned = MockAccountView::new(SUPER_ADMIN, Address::default(), 0, &[]);
+
+        let result = add_admin(&ID, &[config_account(100).view(), unsigned.view(), admin.view()]);
+        assert_eq!(result, Err(ProgramError::MissingRequiredSignature));
+
+        let result =
+            add_admin(&ID, &[readonly_config_account(100).view(), signed.view(), admin.view()]);
+        assert_eq!(result, Err(CommonError::AccountNotWritable.into()));
+
+        let data = config_account(100).data().to_vec();
+        let mut foreign =
+            MockAccountView::new(Address::new_from_array([3u8; 32]), Address::default(), 0, &data)
+                .writable();
+        let result = add_admin(&ID, &[foreign.view(), signed.view(), admin.view()]);
+        assert_eq!(result, Err(ProgramError::IllegalOwner));
+
+        let mut config = config_account(100);
+        add_admin(&ID, &[config.view(), signed.view(), admin.view()]).unwrap();
+        assert!(AdminConfig::try_from_slice(config.data())
+            .unwrap()
+            .admin_list
+            .contains(&new_admin));
+    }
+
+    #[test]
+    fn test_add_and_remove_admin_reject_corrupted_admin_count() {
+        let new_admin = Address::new_from_array([5u8; 32]);
+        let mut data = config_account(100).data().to_vec();
+        // admin_count byte, one past the end of admin_list
+        data[128] = MAX_ADMINS as u8 + 1;
+
+        let mut config =
+            MockAccountView::new(Address::new_from_array([3u8; 32]), ID, 0, &data).writable();
+        let mut caller = MockAccountView::new(SUPER_ADMIN, Address::default(), 0, &[]).signer();
+        let mut admin = MockAccountView::new(new_admin, Address::default(), 0, &[]);
+
+        let result = add_admin(&ID, &[config.view(), caller.view(), admin.view()]);
+        assert_eq!(result, Err(ProgramError::InvalidAccountData));
+
+        // Without the bound, removing would index admin_list[count - 1] and panic
+        let mut stored = AdminConfig::try_from_slice(&data).unwrap();
+        stored.admin_list[MAX_ADMINS - 1] = new_admin;
+        stored.serialize(&mut data).unwrap();
+        let mut config =
+            MockAccountView::new(Address::new_from_array([3u8; 32]), ID, 0, &data).writable();
+        let result = remove_admin(&ID, &[config.view(), caller.view(), admin.view()]);
+        assert_eq!(result, Err(ProgramError::InvalidAccountData));
+        assert_eq!(config.data(), &data[..]);
+    }
+
+    #[cfg(feature = "sorted_admins")]
+    #[test]
+    fn test_sorted_admins_rejects_duplicate_and_sorts_configure() {
+        let mut low = [1u8; 32];
+        low[0] = 0;
+        let low = Address::new_from_array(low);
+        let high = Address::new_from_array([9u8; 32]);
+        let mut config = config_account(100);
+
+        // super_admin need not come first; the list is stored sorted
+        let result =
+            run_configure(&mut config, &configure_data(100, false, &[high, SUPER_ADMIN, low]));
+        assert!(result.is_ok());
+        let mut updated = AdminConfig::try_from_slice(config.data()).unwrap();
+        assert_eq!(updated.admin_list, [low, SUPER_ADMIN, high]);
+
+        updated.remove_admin(&high).unwrap();
+        assert_eq!(updated.insert_admin(&low), Err(SecureError::DuplicateAdmin.into()));
+    }
+
+    fn run_create_manager(payer_signs: bool, bump: u8) -> ProgramResult {
+        let mut config = readonly_config_account(100);
+        let mut manager_account =
+            MockAccountView::new(manager_pda().0, ID, 0, &[0u8; MANAGER_ACCOUNT_SIZE]).writable();
+        let mut admin = MockAccountView::new(SUPER_ADMIN, Address::default(), 0, &[]).signer();
+        let mut manager = MockAccountView::new(MANAGER, Address::default(), 0, &[]);
+        let payer =
+            MockAccountView::new(Address::new_from_array([6u8; 32]), Address::default(), 0, &[]);
+        let mut payer = if payer_signs { payer.signer() } else { payer };
+
+        create_manager(
+            &ID,
+            &[config.view(), manager_account.view(), admin.view(), manager.view(), payer.view()],
+            &[1, 0, bump, 50, 0],
+        )
+    }
+
+    #[test]
+    fn test_create_manager_requires_payer_signature() {
+        let bump = manager_pda().1;
+        assert_eq!(run_create_manager(false, bump), Err(ProgramError::MissingRequiredSignature));
+        assert!(run_create_manager(true, bump).is_ok());
+    }
+
+    #[test]
+    fn test_create_manager_rejects_non_canonical_bump() {
+        let bump = manager_pda().1.wrapping_sub(1);
+        assert_eq!(run_create_manager(true, bump), Err(CommonError::InvalidBump.into()));
+    }
+
+    #[test]
+    fn test_load_manager_accepts_manager_pda() {
+        let mut manager_account = manager_account(true, false);
+        let loaded = load_manager(&manager_account.view(), &ID).unwrap();
+        assert_eq!((loaded.manager, loaded.bump), (MANAGER, manager_pda().1));
+    }
+
+    #[test]
+    fn test_load_manager_rejects_wrong_size_and_bump() {
+        let data = manager_account(true, false).data().to_vec();
+
+        let mut oversized = data.clone();
+        oversized.push(0);
+        let mut account = MockAccountView::new(manager_pda().0, ID, 0, &oversized);
+        assert_eq!(load_manager(&account.view(), &ID), Err(ProgramError::InvalidAccountData));
+
+        let mut manager_data = ManagerAccount::try_from_slice(&data).unwrap();
+        manager_data.bump = manager_data.bump.wrapping_sub(1);
+        let mut forged_bump = data;
+        manager_data.serialize(&mut forged_bump).unwrap();
+        let mut account = MockAccountView::new(manager_pda().0, ID, 0, &forged_bump);
+        assert_eq!(load_manager(&account.view(), &ID), Err(CommonError::InvalidBump.into()));
+    }
+
+    /// Runs `deactivate_manager` as SUPER_ADMIN against `manager_account`.
+    fn run_deactivate_manager(manager_account: &mut MockAccountView) -> ProgramResult {
+        let mut admin = MockAccountView::new(SUPER_ADMIN, Address::default(), 0, &[]).signer();
+        let mut config = readonly_config_account(100);
+        deactivate_manager(&ID, &[config.view(), manager_account.view(), admin.view()])
+    }
+
+    #[test]
+    fn test_deactivate_manager_rejects_foreign_owned_manager() {
+        // Valid manager bytes at the right address, but written by another program
+        let data = manager_account(true, false).data().to_vec();
+        let foreign_program = Address::new_from_array([8u8; 32]);
+        let mut manager_account =
+            MockAccountView::new(manager_pda().0, foreign_program, 0, &data).writable();
+
+        assert_eq!(run_deactivate_manager(&mut manager_account), Err(ProgramError::IllegalOwner));
+        assert!(ManagerAccount::try_from_slice(manager_account.data()).unwrap().is_active);
+    }
+
+    #[test]
+    fn test_deactivate_manager_rejects_forged_pda() {
+        // Program-owned account with valid manager bytes, but not at MANAGER's PDA
+        let data = manager_account(true, false).data().to_vec();
+        let mut manager_account =
+            MockAccountView::new(Address::new_from_array([4u8; 32]), ID, 0, &data).writable();
+
+        assert_eq!(
+            run_deactivate_manager(&mut manager_account),
+            Err(CommonError::InvalidPda.into())
+        );
+        assert!(ManagerAccount::try_from_slice(manager_account.data()).unwrap().is_active);
+    }
+
+    #[test]
+    fn test_update_fee_rejects_forged_manager_pda() {
+        let data = manager_account(true, false).data().to_vec();
+        let mut forged = MockAccountView::new(Address::new_from_array([4u8; 32]), ID, 0, &data);
+        let mut config = config_account(100);
+        let mut manager = MockAccountView::new(MANAGER, Address::default(), 0, &[]).signer();
+
+        let result =
+            update_fee(&ID, &[config.view(), manager.view(), forged.view()], &120u16.to_le_bytes());
+        assert_eq!(result, Err(CommonError::InvalidPda.into()));
+        assert_eq!(AdminConfig::try_from_slice(config.data()).unwrap().fee_basis_points, 100);
+    }
+
+    #[test]
+    fn test_deactivate_manager_rejects_writable_admin_config() {
+        let mut manager_account = manager_account(true, false).writable();
+        let mut admin = MockAccountView::new(SUPER_ADMIN, Address::default(), 0, &[]).signer();
+
+        let mut config = config_account(100);
+        let result =
+            deactivate_manager(&ID, &[config.view(), manager_account.view(), admin.view()]);
+        assert_eq!(result, Err(SecureError::UnexpectedWritableAccount.into()));
+        assert!(ManagerAccount::try_from_slice(manager_account.data()).unwrap().is_active);
+
+        let mut config = readonly_config_account(100);
+        deactivate_manager(&ID, &[config.view(), manager_account.view(), admin.view()]).unwrap();
+        assert!(!ManagerAccount::try_from_slice(manager_account.data()).unwrap().is_active);
+    }
+
+    /// Runs `close_manager` on MANAGER's account holding `lamports`, signed by
+    /// SUPER_ADMIN, with the given active flag.
+    fn run_close_manager(is_active: bool, lamports: u64) -> (ProgramResult, MockAccountView, u64) {
+        let mut data = manager_account(true, false).data().to_vec();
+        let mut manager_data = ManagerAccount::try_from_slice(&data).unwrap();
+        manager_data.is_active = is_active;
+        manager_data.serialize(&mut data).unwrap();
+
+        let mut manager_account =
+            MockAccountView::new(manager_pda().0, ID, lamports, &data).writable();
+        let mut recipient =
+            MockAccountView::new(FEE_TREASURY, Address::default(), 1_000, &[]).writable();
+        let mut admin = MockAccountView::new(SUPER_ADMIN, Address::default(), 0, &[]).signer();
+        let mut config = readonly_config_account(100);
+
+        let result = close_manager(
+            &ID,
+            &[config.view(), manager_account.view(), recipient.view(), admin.view()],
+        );
+        let recipient_lamports = recipient.view().lamports();
+        (result, manager_account, recipient_lamports)
+    }
+
+    #[test]
+    fn test_close_inactive_manager_returns_rent() {
+        let (result, mut manager_account, recipient_lamports) = run_close_manager(false, 5_000);
+        assert_eq!(result, Ok(()));
+        assert_eq!(manager_account.view().lamports(), 0);
+        assert_eq!(recipient_lamports, 6_000);
+        assert!(manager_account.data().iter().all(|byte| *byte == 0));
+    }
+
+    #[test]
+    fn test_close_active_manager_rejected() {
+        let (result, mut manager_account, recipient_lamports) = run_close_manager(true, 5_000);
+        assert_eq!(result, Err(SecureError::ManagerNotActive.into()));
+        assert_eq!(manager_account.view().lamports(), 5_000);
+        assert_eq!(recipient_lamports, 1_000);
+        assert!(ManagerAccount::try_from_slice(manager_account.data()).unwrap().is_active);
+    }
+
+    const FEE_TREASURY: Address = Address::new_from_array([7u8; 32]);
+
+    fn run_set_fee_recipient(
+        config: &mut MockAccountView,
+        caller: Address,
+        recipient: Address,
+    ) -> ProgramResult {
+        let mut caller = MockAccountView::new(caller, Address::default(), 0, &[]).signer();
+        let mut recipient = MockAccountView::new(recipient, Address::default(), 0, &[]);
+        set_fee_recipient(&ID, &[config.view(), caller.view(), recipient.view()])
+    }
+
+    #[test]
+    fn test_set_fee_recipient() {
+        // initialize_config defaults the recipient to the super_admin
+        let mut config = MockAccountView::new(
+            Address::new_from_array([3u8; 32]),
+            ID,
+            0,
+            &[0u8; ADMIN_CONFIG_SIZE],
+        )
+        .writable();
+        let mut super_admin =
+            MockAccountView::new(SUPER_ADMIN, Address::default(), 0, &[]).signer();
+        initialize_config(&ID, &[config.view(), super_admin.view()], &[255]).unwrap();
+        let stored = AdminConfig::try_from_slice(config.data()).unwrap();
+        assert_eq!(stored.fee_recipient, SUPER_ADMIN);
+
+        // A regular admin may not redirect fees
+        let admin = Address::new_from_array([5u8; 32]);
+        let mut data = [0u8; ADMIN_CONFIG_SIZE];
+        AdminConfig {
+            admin_list: [SUPER_ADMIN, admin, Address::default()],
+            admin_count: 2,
+            ..stored
+        }
+        .serialize(&mut data)
+        .unwrap();
+        let mut config = MockAccountView::new(*config.view().address(), ID, 0, &data).writable();
+        assert_eq!(
+            run_set_fee_recipient(&mut config, admin, FEE_TREASURY),
+            Err(SecureError::NotSuperAdmin.into())
+        );
+        assert_eq!(
+            run_set_fee_recipient(&mut config, SUPER_ADMIN, Address::default()),
+            Err(SecureError::InvalidAddress.into())
+        );
+
+        assert!(run_set_fee_recipient(&mut config, SUPER_ADMIN, FEE_TREASURY).is_ok());
+        let stored = AdminConfig::try_from_slice(config.data()).unwrap();
+        assert_eq!(stored.fee_recipient, FEE_TREASURY);
+    }
+
+    #[test]
+    fn test_fees_route_to_configured_recipient() {
+        let mut config = config_account(100);
+        let stored = AdminConfig::try_from_slice(config.data()).unwrap();
+        assert!(assert_fee_recipient(&stored, &SUPER_ADMIN).is_ok());
+
+        run_set_fee_recipient(&mut config, SUPER_ADMIN, FEE_TREASURY).unwrap();
+        let stored = AdminConfig::try_from_slice(config.data()).unwrap();
+        assert!(assert_fee_recipient(&stored, &FEE_TREASURY).is_ok());
+
+        // The super_admin no longer receives fees implicitly
+        let mismatch = Err(SecureError::FeeRecipientMismatch.into());
+        assert_eq!(assert_fee_recipient(&stored, &SUPER_ADMIN), mismatch);
+        assert_eq!(assert_fee_recipient(&stored, &Address::new_from_array([9u8; 32])), mismatch);
+    }
+
+    // =========================================================================
+    // RE-INITIALIZATION
+    // =========================================================================
+
+    #[test]
+    fn test_initialize_config_rejects_reinitialization() {
+        let mut config = MockAccountView::new(
+            Address::new_from_array([3u8; 32]),
+            ID,
+            0,
+            &[0u8; ADMIN_CONFIG_SIZE],
+        )
+        .writable();
+        let mut super_admin =
+            MockAccountView::new(SUPER_ADMIN, Address::default(), 0, &[]).signer();
+        initialize_config(&ID, &[config.view(), super_admin.view()], &[255]).unwrap();
+
+        // Any signer re-running init would otherwise become super_admin
+        let mut attacker =
+            MockAccountView::new(Address::new_from_array([9u8; 32]), Address::default(), 0, &[])
+                .signer();
+        assert_reinit_rejected(&mut config, |config| {
+            initialize_config(&ID, &[config.view(), attacker.view()], &[255])
+        });
     }
 }
@@ -0,0 +1,97 @@
+//! # Anchor Layout Equivalence
+//!
+//! The Pinocchio program reads and writes the same logical accounts as the
+//! Anchor program, without Anchor's 8-byte discriminator. These golden tests
+//! serialize one account in both frameworks and compare the bytes, so a
+//! reordered, resized or missing field in either program fails here first.
+
+use anchor_lang::{prelude::Pubkey, AccountSerialize, Discriminator};
+use pinocchio::Address;
+use pinocchio_secure_authority_checks::{
+    AdminConfig, ManagerAccount, ADMIN_CONFIG_SIZE, MANAGER_ACCOUNT_SIZE,
+};
+use secure_authority_checks::{
+    AdminConfig as AnchorAdminConfig, ManagerAccount as AnchorManagerAccount,
+};
+
+/// Length of the discriminator Anchor writes before every account.
+const DISCRIMINATOR_LEN: usize = 8;
+
+fn anchor_bytes<T: AccountSerialize>(account: &T) -> Vec<u8> {
+    let mut bytes = Vec::new();
+    account.try_serialize(&mut bytes).unwrap();
+    bytes
+}
+
+/// Asserts `pinocchio` is `anchor` without its discriminator, byte for byte.
+fn assert_layout_matches(anchor: &[u8], discriminator: &[u8], pinocchio: &[u8]) {
+    assert_eq!(&anchor[..DISCRIMINATOR_LEN], discriminator);
+    assert_eq!(pinocchio, &anchor[DISCRIMINATOR_LEN..]);
+}
+
+#[test]
+fn test_admin_config_layout_matches_anchor() {
+    // Distinct bytes in every field, so a swapped pair cannot compare equal
+    let anchor = AnchorAdminConfig {
+        super_admin: Pubkey::new_from_array([1u8; 32]),
+        admin_list: [
+            Pubkey::new_from_array([2u8; 32]),
+            Pubkey::new_from_array([3u8; 32]),
+            Pubkey::new_from_array([4u8; 32]),
+        ],
+        admin_count: 3,
+        fee_basis_points: 0x0506,
+        paused: true,
+        bump: 254,
+        fee_recipient: Pubkey::new_from_array([9u8; 32]),
+    };
+    let pinocchio = AdminConfig {
+        super_admin: Address::new_from_array([1u8; 32]),
+        admin_list: [
+            Address::new_from_array([2u8; 32]),
+            Address::new_from_array([3u8; 32]),
+            Address::new_from_array([4u8; 32]),
+        ],
+        admin_count: 3,
+        fee_basis_points: 0x0506,
+        paused: true,
+        bump: 254,
+        fee_recipient: Address::new_from_array([9u8; 32]),
+    };
+
+    let anchor = anchor_bytes(&anchor);
+    assert_eq!(anchor.len(), AnchorAdminConfig::ACCOUNT_SIZE);
+    let mut data = [0u8; ADMIN_CONFIG_SIZE];
+    pinocchio.serialize(&mut data).unwrap();
+
+    assert_layout_matches(&anchor, AnchorAdminConfig::DISCRIMINATOR, &data);
+}
+
+#[test]
+fn test_manager_account_layout_matches_anchor() {
+    let anchor = AnchorManagerAccount {
+        authority: Pubkey::new_from_array([1u8; 32]),
+        manager: Pubkey::new_from_array([2u8; 32]),
+        can_modify_fees: true,
+        can_pause: false,
+        is_active: true,
+        bump: 253,
+        max_fee_delta: 0x0304,
+    };
+    let pinocchio = ManagerAccount {
+        authority: Address::new_from_array([1u8; 32]),
+        manager: Address::new_from_array([2u8; 32]),
+        can_modify_fees: true,
+        can_pause: false,
+        is_active: true,
+        bump: 253,
+        max_fee_delta: 0x0304,
+    };
+
+    let anchor = anchor_bytes(&anchor);
+    assert_eq!(anchor.len(), AnchorManagerAccount::ACCOUNT_SIZE);
+    let mut data = [0u8; MANAGER_ACCOUNT_SIZE];
+    pinocchio.serialize(&mut data).unwrap();
+
+    assert_layout_matches(&anchor, AnchorManagerAccount::DISCRIMINATOR, &data);
+}
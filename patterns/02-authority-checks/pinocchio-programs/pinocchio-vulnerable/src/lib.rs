@@ -19,8 +19,19 @@
 #![allow(unexpected_cfgs)]
 
 use pinocchio::{entrypoint, error::ProgramError, AccountView, Address, ProgramResult};
+
+// Logging shim: `log!` forwards to `solana_program_log` with the default `logs`
+// feature and compiles to nothing under `--no-default-features`.
+#[cfg(feature = "logs")]
 use solana_program_log::log;
 
+#[cfg(not(feature = "logs"))]
+macro_rules! log {
+    ($($arg:tt)*) => {{
+        let _ = core::format_args!($($arg)*);
+    }};
+}
+
 // =============================================================================
 // PROGRAM ID
 // =============================================================================
@@ -85,6 +96,7 @@ pub const CREATE_MANAGER_DISCRIMINATOR: u8 = 5;
 ///
 /// This struct is identical to the Anchor version but uses manual serialization.
 /// In Pinocchio, there's no Anchor discriminator (8 bytes saved).
+#[derive(Debug, Clone, PartialEq)]
 pub struct AdminConfig {
     /// The highest-privilege administrator
     pub super_admin: Address,
@@ -176,6 +188,7 @@ impl AdminConfig {
 }
 
 /// Manager account with delegated administrative permissions.
+#[derive(Debug, Clone, PartialEq)]
 pub struct ManagerAccount {
     /// The admin who created this manager
     pub authority: Address,
@@ -578,11 +591,7 @@ mod tests {
         config.serialize(&mut buffer).unwrap();
 
         let deserialized = AdminConfig::try_from_slice(&buffer).unwrap();
-        assert_eq!(deserialized.super_admin, config.super_admin);
-        assert_eq!(deserialized.admin_count, config.admin_count);
-        assert_eq!(deserialized.fee_basis_points, config.fee_basis_points);
-        assert_eq!(deserialized.paused, config.paused);
-        assert_eq!(deserialized.bump, config.bump);
+        assert_eq!(deserialized, config);
     }
 
     #[test]
@@ -600,11 +609,6 @@ mod tests {
         manager.serialize(&mut buffer).unwrap();
 
         let deserialized = ManagerAccount::try_from_slice(&buffer).unwrap();
-        assert_eq!(deserialized.authority, manager.authority);
-        assert_eq!(deserialized.manager, manager.manager);
-        assert_eq!(deserialized.can_modify_fees, manager.can_modify_fees);
-        assert_eq!(deserialized.can_pause, manager.can_pause);
-        assert_eq!(deserialized.is_active, manager.is_active);
-        assert_eq!(deserialized.bump, manager.bump);
+        assert_eq!(deserialized, manager);
     }
 }
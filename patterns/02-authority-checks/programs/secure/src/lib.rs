@@ -52,6 +52,9 @@ declare_id!("7EjQ3phjWPknKc5ASAdcA91ikNXhNapNvbMRStxJ3R7f");
 /// Using a fixed-size array for predictable account sizing.
 pub const MAX_ADMINS: usize = 3;
 
+/// Highest fee an admin may configure: 10000 basis points = 100%.
+pub const MAX_FEE_BASIS_POINTS: u16 = 10_000;
+
 // =============================================================================
 // HELPER FUNCTIONS
 // =============================================================================
@@ -82,6 +85,28 @@ pub fn is_admin(admin_list: &[Pubkey; MAX_ADMINS], admin_count: u8, key: &Pubkey
     admin_list.iter().take(count).any(|admin| admin == key)
 }
 
+/// Validates every field of a bulk `configure` call before any is applied.
+///
+/// # SECURITY: All-or-Nothing Validation
+///
+/// `configure` replaces several fields at once, so a single bad field must
+/// reject the whole update rather than leave the config half-written:
+/// 1. `new_fee` must not exceed `MAX_FEE_BASIS_POINTS`
+/// 2. `admins` must hold 1..=MAX_ADMINS entries, starting with super_admin
+/// 3. No admin may be the default pubkey or appear twice
+pub fn validate_config(super_admin: &Pubkey, new_fee: u16, admins: &[Pubkey]) -> Result<()> {
+    require!(new_fee <= MAX_FEE_BASIS_POINTS, ErrorCode::FeeTooHigh);
+    require!(admins.len() <= MAX_ADMINS, ErrorCode::AdminListFull);
+    require!(admins.first() == Some(super_admin), ErrorCode::CannotRemoveSuperAdmin);
+
+    for (i, admin) in admins.iter().enumerate() {
+        require!(*admin != Pubkey::default(), ErrorCode::InvalidAddress);
+        require!(!admins[..i].contains(admin), ErrorCode::DuplicateAdmin);
+    }
+
+    Ok(())
+}
+
 // =============================================================================
 // ERROR CODES
 // =============================================================================
@@ -144,6 +169,23 @@ pub enum ErrorCode {
     /// Cannot remove an admin that doesn't exist.
     #[msg("Admin not found in admin list")]
     AdminNotFound,
+
+    /// The supplied pubkey is the zero/default address.
+    /// A default pubkey in admin_list or as a manager is a dead entry
+    /// that no one can sign for.
+    ///
+    /// SECURITY: This error is used in constraints like:
+    /// `constraint = new_admin.key() != Pubkey::default() @ ErrorCode::InvalidAddress`
+    #[msg("Address must not be the default (zero) pubkey")]
+    InvalidAddress,
+
+    /// The requested fee exceeds MAX_FEE_BASIS_POINTS (100%).
+    #[msg("Fee exceeds the maximum of 10000 basis points")]
+    FeeTooHigh,
+
+    /// The same pubkey appears more than once in a new admin list.
+    #[msg("Admin list contains a duplicate entry")]
+    DuplicateAdmin,
 }
 
 // =============================================================================
@@ -200,6 +242,9 @@ pub mod secure_authority_checks {
         // Store the bump seed for future PDA derivations
         admin_config.bump = ctx.bumps.admin_config;
 
+        // Fees go to the super_admin until it names another recipient
+        admin_config.fee_recipient = ctx.accounts.super_admin.key();
+
         // Log the initialization for on-chain transparency
         msg!("Admin config initialized with super_admin: {}", admin_config.super_admin);
 
@@ -267,6 +312,9 @@ pub mod secure_authority_checks {
     pub fn update_fee(ctx: Context<UpdateFee>, new_fee: u16) -> Result<()> {
         let admin_config = &mut ctx.accounts.admin_config;
 
+        // SECURITY: A corrupted stored fee is rejected before anything trusts it
+        admin_config.check_fee_bound()?;
+
         // SECURITY: Authority validation is done in the Accounts struct via constraint
         // The constraint `is_admin(&admin_config.admin_list, admin_config.admin_count, caller.key)`
         // ensures only admin_list members can reach this point
@@ -377,6 +425,7 @@ pub mod secure_authority_checks {
         ctx: Context<CreateManager>,
         can_modify_fees: bool,
         can_pause: bool,
+        max_fee_delta: u16,
     ) -> Result<()> {
         let manager_account = &mut ctx.accounts.manager_account;
 
@@ -392,6 +441,7 @@ pub mod secure_authority_checks {
         // Assign permissions (only an authorized admin can grant these)
         manager_account.can_modify_fees = can_modify_fees;
         manager_account.can_pause = can_pause;
+        manager_account.max_fee_delta = max_fee_delta;
 
         // Manager starts active
         manager_account.is_active = true;
@@ -496,6 +546,78 @@ pub mod secure_authority_checks {
 
         Ok(())
     }
+
+    // =========================================================================
+    // INSTRUCTION: close_manager (SECURE)
+    // =========================================================================
+
+    /// Closes a deactivated manager account, returning its rent to `recipient`.
+    ///
+    /// # Security
+    ///
+    /// This instruction is SECURE because:
+    /// - SECURITY: `caller` is `Signer<'info>` - enforces signature verification
+    /// - SECURITY: Constraint uses `is_admin()` to validate caller against admin_list
+    /// - SECURITY: Constraint rejects a manager that is still active
+    /// - SECURITY: `close = recipient` zeroes the account and moves all its lamports
+    ///
+    /// # Accounts
+    ///
+    /// - `admin_config`: The admin config PDA for authority validation
+    /// - `manager_account`: The deactivated manager account to close
+    /// - `recipient`: Receives the manager account's lamports
+    /// - `caller`: Must be in admin_list AND must sign the transaction
+    pub fn close_manager(ctx: Context<CloseManager>) -> Result<()> {
+        msg!("Manager account closed: {}", ctx.accounts.manager_account.manager);
+
+        Ok(())
+    }
+
+    // =========================================================================
+    // INSTRUCTION: configure (SECURE)
+    // =========================================================================
+
+    /// Atomically sets the fee, pause state and admin list.
+    ///
+    /// # Security
+    ///
+    /// This instruction is SECURE because:
+    /// - SECURITY: `caller` is `Signer<'info>` - enforces signature verification
+    /// - SECURITY: Constraint validates `caller.key() == admin_config.super_admin`
+    /// - SECURITY: Every field is validated by `validate_config()` before any is written
+    ///
+    /// # Accounts
+    ///
+    /// - `admin_config`: The admin config PDA to update
+    /// - `caller`: Must be super_admin AND must sign the transaction
+    pub fn configure(
+        ctx: Context<Configure>,
+        new_fee: u16,
+        paused: bool,
+        admins: Vec<Pubkey>,
+    ) -> Result<()> {
+        let admin_config = &mut ctx.accounts.admin_config;
+
+        // SECURITY: Validate everything first so a bad field applies nothing
+        validate_config(&admin_config.super_admin, new_fee, &admins)?;
+
+        let mut admin_list = [Pubkey::default(); MAX_ADMINS];
+        admin_list[..admins.len()].copy_from_slice(&admins);
+
+        admin_config.fee_basis_points = new_fee;
+        admin_config.paused = paused;
+        admin_config.admin_list = admin_list;
+        admin_config.admin_count = admins.len() as u8;
+
+        msg!(
+            "Config updated by super_admin: fee={}, paused={}, admins={}",
+            new_fee,
+            paused,
+            admins.len()
+        );
+
+        Ok(())
+    }
 }
 
 // =============================================================================
@@ -528,7 +650,8 @@ pub mod secure_authority_checks {
 /// | fee_basis_points | 2 |
 /// | paused | 1 |
 /// | bump | 1 |
-/// | **Total** | **141** |
+/// | fee_recipient | 32 |
+/// | **Total** | **173** |
 #[account]
 pub struct AdminConfig {
     /// The highest-privilege administrator who can:
@@ -559,12 +682,33 @@ pub struct AdminConfig {
     /// PDA bump seed for account derivation.
     /// Used to reconstruct the PDA address off-chain.
     pub bump: u8,
+
+    /// Account credited with collected fees.
+    /// Set to super_admin at initialization.
+    pub fee_recipient: Pubkey,
 }
 
 impl AdminConfig {
     /// Account size including Anchor discriminator.
-    /// 8 (discriminator) + 32 + 96 + 1 + 2 + 1 + 1 = 141 bytes
-    pub const ACCOUNT_SIZE: usize = 8 + 32 + 96 + 1 + 2 + 1 + 1;
+    /// 8 (discriminator) + 32 + 96 + 1 + 2 + 1 + 1 + 32 = 173 bytes
+    pub const ACCOUNT_SIZE: usize = 8 + 32 + 96 + 1 + 2 + 1 + 1 + 32;
+
+    /// Bytes to allocate when creating an AdminConfig account:
+    /// [`Self::ACCOUNT_SIZE`], discriminator included.
+    pub const fn space() -> usize {
+        Self::ACCOUNT_SIZE
+    }
+
+    /// Rejects a stored fee above `MAX_FEE_BASIS_POINTS` with `InvalidAccountData`.
+    ///
+    /// SECURITY: Anchor's generated deserializer accepts any `u16`; this is the
+    /// bound the Pinocchio `try_from_slice` applies while parsing.
+    pub fn check_fee_bound(&self) -> Result<()> {
+        if self.fee_basis_points > MAX_FEE_BASIS_POINTS {
+            return Err(ProgramError::InvalidAccountData.into());
+        }
+        Ok(())
+    }
 }
 
 /// Manager account with delegated administrative permissions.
@@ -590,7 +734,8 @@ impl AdminConfig {
 /// | can_pause | 1 |
 /// | is_active | 1 |
 /// | bump | 1 |
-/// | **Total** | **76** |
+/// | max_fee_delta | 2 |
+/// | **Total** | **78** |
 #[account]
 pub struct ManagerAccount {
     /// The admin who created this manager.
@@ -615,12 +760,22 @@ pub struct ManagerAccount {
 
     /// PDA bump seed for account derivation.
     pub bump: u8,
+
+    /// Largest change, in basis points, this manager may make to the fee
+    /// in one call. Matches the Pinocchio account layout.
+    pub max_fee_delta: u16,
 }
 
 impl ManagerAccount {
     /// Account size including Anchor discriminator.
-    /// 8 + 32 + 32 + 1 + 1 + 1 + 1 = 76 bytes
-    pub const ACCOUNT_SIZE: usize = 8 + 32 + 32 + 1 + 1 + 1 + 1;
+    /// 8 + 32 + 32 + 1 + 1 + 1 + 1 + 2 = 78 bytes
+    pub const ACCOUNT_SIZE: usize = 8 + 32 + 32 + 1 + 1 + 1 + 1 + 2;
+
+    /// Bytes to allocate when creating a manager account:
+    /// [`Self::ACCOUNT_SIZE`], discriminator included.
+    pub const fn space() -> usize {
+        Self::ACCOUNT_SIZE
+    }
 }
 
 // =============================================================================
@@ -684,6 +839,8 @@ pub struct AddAdmin<'info> {
 
     /// The new admin to add to the admin_list.
     /// CHECK: This account just provides a pubkey to add.
+    /// SECURITY: constraint rejects the default pubkey (dead admin_list entry).
+    #[account(constraint = new_admin.key() != Pubkey::default() @ ErrorCode::InvalidAddress)]
     pub new_admin: UncheckedAccount<'info>,
 }
 
@@ -817,6 +974,8 @@ pub struct CreateManager<'info> {
 
     /// The user who will become a manager.
     /// CHECK: This account just provides a pubkey for the manager role.
+    /// SECURITY: constraint rejects the default pubkey (unusable manager).
+    #[account(constraint = manager.key() != Pubkey::default() @ ErrorCode::InvalidAddress)]
     pub manager: UncheckedAccount<'info>,
 
     /// Account paying for manager account creation.
@@ -854,6 +1013,9 @@ pub struct RemoveAdmin<'info> {
 
     /// The admin to remove from the admin_list.
     /// CHECK: This account just provides a pubkey to remove.
+    /// SECURITY: constraint rejects the default pubkey, which would otherwise
+    /// match the empty slots past admin_count.
+    #[account(constraint = admin_to_remove.key() != Pubkey::default() @ ErrorCode::InvalidAddress)]
     pub admin_to_remove: UncheckedAccount<'info>,
 }
 
@@ -890,3 +1052,72 @@ pub struct DeactivateManager<'info> {
     /// SECURITY: Signer type enforces cryptographic signature verification.
     pub caller: Signer<'info>,
 }
+
+/// Accounts for the close_manager instruction.
+///
+/// ## SECURITY IMPLEMENTATION
+///
+/// 1. SECURITY: `caller` is `Signer<'info>` - enforces signature verification
+/// 2. SECURITY: `constraint` uses is_admin() to check admin_list membership
+/// 3. SECURITY: `constraint` requires the manager to be deactivated first
+/// 4. SECURITY: `close = recipient` sweeps the rent after zeroing the data
+#[derive(Accounts)]
+pub struct CloseManager<'info> {
+    /// The admin config for authority validation.
+    /// SECURITY: Seeds constraint ensures we're using the correct PDA.
+    /// SECURITY: Custom constraint validates caller is in admin_list.
+    #[account(
+        seeds = [b"admin_config"],
+        bump = admin_config.bump,
+        // SECURITY: Only admins can close managers
+        constraint = is_admin(&admin_config.admin_list, admin_config.admin_count, caller.key) @ ErrorCode::NotAdmin
+    )]
+    pub admin_config: Account<'info, AdminConfig>,
+
+    /// The manager account to close.
+    /// SECURITY: Seeds constraint ensures we're closing the correct manager PDA.
+    /// SECURITY: An active manager must go through deactivate_manager first.
+    #[account(
+        mut,
+        seeds = [b"manager", manager_account.manager.as_ref()],
+        bump = manager_account.bump,
+        constraint = !manager_account.is_active @ ErrorCode::ManagerNotActive,
+        close = recipient
+    )]
+    pub manager_account: Account<'info, ManagerAccount>,
+
+    /// Receives the manager account's rent.
+    /// CHECK: Only credited with lamports; any writable account may receive them.
+    #[account(mut)]
+    pub recipient: UncheckedAccount<'info>,
+
+    /// The caller attempting to close the manager.
+    /// SECURITY: Signer type enforces cryptographic signature verification.
+    pub caller: Signer<'info>,
+}
+
+/// Accounts for the configure instruction.
+///
+/// ## SECURITY IMPLEMENTATION
+///
+/// 1. SECURITY: `caller` is `Signer<'info>` - enforces signature verification
+/// 2. SECURITY: `constraint` validates caller equals super_admin
+/// 3. SECURITY: Field values are validated in the handler before being applied
+#[derive(Accounts)]
+pub struct Configure<'info> {
+    /// The admin config to update.
+    /// SECURITY: Seeds constraint ensures we're modifying the correct PDA.
+    /// SECURITY: constraint validates caller is the super_admin.
+    #[account(
+        mut,
+        seeds = [b"admin_config"],
+        bump = admin_config.bump,
+        // SECURITY: Bulk updates include pause and admin list, so super_admin only
+        constraint = caller.key() == admin_config.super_admin @ ErrorCode::NotSuperAdmin
+    )]
+    pub admin_config: Account<'info, AdminConfig>,
+
+    /// The caller attempting the update.
+    /// SECURITY: Signer type enforces cryptographic signature verification.
+    pub caller: Signer<'info>,
+}
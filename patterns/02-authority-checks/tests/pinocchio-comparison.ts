@@ -39,8 +39,11 @@ const CREATE_MANAGER_DISCRIMINATOR = 5;
 // AdminConfig size: super_admin (32) + admin_list (96) + admin_count (1) + fee_basis_points (2) + paused (1) + bump (1) = 133 bytes
 const ADMIN_CONFIG_SIZE = 133;
 
-// ManagerAccount size: authority (32) + manager (32) + can_modify_fees (1) + can_pause (1) + is_active (1) + bump (1) = 68 bytes
-const MANAGER_ACCOUNT_SIZE = 68;
+// The secure program also stores fee_recipient (32) = 165 bytes
+const SECURE_ADMIN_CONFIG_SIZE = ADMIN_CONFIG_SIZE + 32;
+
+// ManagerAccount size: authority (32) + manager (32) + can_modify_fees (1) + can_pause (1) + is_active (1) + bump (1) + max_fee_delta (2) = 70 bytes
+const MANAGER_ACCOUNT_SIZE = 70;
 
 // PDA seeds
 const ADMIN_CONFIG_SEED = Buffer.from("admin_config");
@@ -94,14 +97,20 @@ function buildPauseProtocolInstructionData(): Buffer {
 
 /**
  * Build instruction data for create_manager instruction.
- * Format: [discriminator (1 byte)] [can_modify_fees (1 byte)] [can_pause (1 byte)] [bump (1 byte)]
+ * Format: [discriminator (1 byte)] [can_modify_fees (1 byte)] [can_pause (1 byte)] [bump (1 byte)] [max_fee_delta (2 bytes, little-endian)]
  */
-function buildCreateManagerInstructionData(canModifyFees: boolean, canPause: boolean, bump: number): Buffer {
-    const data = Buffer.alloc(4);
+function buildCreateManagerInstructionData(
+    canModifyFees: boolean,
+    canPause: boolean,
+    bump: number,
+    maxFeeDelta: number
+): Buffer {
+    const data = Buffer.alloc(6);
     data.writeUInt8(CREATE_MANAGER_DISCRIMINATOR, 0);
     data.writeUInt8(canModifyFees ? 1 : 0, 1);
     data.writeUInt8(canPause ? 1 : 0, 2);
     data.writeUInt8(bump, 3);
+    data.writeUInt16LE(maxFeeDelta, 4);
     return data;
 }
 
@@ -142,6 +151,7 @@ function decodeManagerAccount(data: Buffer): {
     canPause: boolean;
     isActive: boolean;
     bump: number;
+    maxFeeDelta: number;
 } {
     return {
         authority: new PublicKey(data.slice(0, 32)),
@@ -150,6 +160,7 @@ function decodeManagerAccount(data: Buffer): {
         canPause: data[65] !== 0,
         isActive: data[66] !== 0,
         bump: data[67],
+        maxFeeDelta: data.readUInt16LE(68),
     };
 }
 
@@ -201,6 +212,7 @@ describe("Pinocchio Authority Checks Framework Comparison", () => {
     // Test constants
     const NEW_FEE = 500; // 5% fee
     const MALICIOUS_FEE = 10000; // 100% fee
+    const MANAGER_MAX_FEE_DELTA = 50; // manager may move the fee by 0.5% per call
 
     // =============================================================================
     // SETUP HOOKS
@@ -414,7 +426,7 @@ describe("Pinocchio Authority Checks Framework Comparison", () => {
                 connection,
                 superAdminKeypair,
                 PINOCCHIO_SECURE_PROGRAM_ID,
-                ADMIN_CONFIG_SIZE
+                SECURE_ADMIN_CONFIG_SIZE
             );
             console.log(`    Secure admin_config: ${secureAdminConfigAccount.publicKey.toBase58()}`);
 
@@ -662,8 +674,10 @@ describe("Pinocchio Authority Checks Framework Comparison", () => {
                         { pubkey: managerAccount.publicKey, isSigner: false, isWritable: true },
                         { pubkey: superAdminKeypair.publicKey, isSigner: true, isWritable: false },
                         { pubkey: managerKeypair.publicKey, isSigner: false, isWritable: false },
+                        // payer (same key as admin here, but checked as its own signer)
+                        { pubkey: superAdminKeypair.publicKey, isSigner: true, isWritable: true },
                     ],
-                    data: buildCreateManagerInstructionData(true, false, 0),
+                    data: buildCreateManagerInstructionData(true, false, 0, MANAGER_MAX_FEE_DELTA),
                 });
 
                 const createManagerTx = new Transaction().add(createManagerIx);
@@ -678,6 +692,7 @@ describe("Pinocchio Authority Checks Framework Comparison", () => {
                 expect(managerData.canModifyFees).to.be.true;
                 expect(managerData.canPause).to.be.false;
                 expect(managerData.isActive).to.be.true;
+                expect(managerData.maxFeeDelta).to.equal(MANAGER_MAX_FEE_DELTA);
 
                 console.log(`  Manager created: ${managerData.manager.toBase58()}`);
                 console.log(`  Authority: ${managerData.authority.toBase58()}`);
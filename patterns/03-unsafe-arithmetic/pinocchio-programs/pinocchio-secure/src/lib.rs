@@ -8,27 +8,46 @@
 //! - `checked_add()` with error handling for deposits
 //! - `checked_sub()` with error handling for withdrawals
 //! - `checked_mul()` with error handling for reward calculations
-//! - Input validation with maximum limits (MAX_DEPOSIT, MAX_REWARD_RATE)
+//! - Input validation with maximum limits (MAX_DEPOSIT, MAX_USER_BALANCE, MAX_REWARD_RATE)
+//! - Per-user reward cooldown (REWARD_INTERVAL) to stop reward farming
+//! - Batched reward distribution validated in full before any account is written
+//! - Optional balance-tiered reward rate (`tiered_rewards` feature)
+//! - Sysvars and the program account rejected where a data account is expected
 //! - Custom error enum for clear error messages
 //!
 //! ## Key Differences from Anchor
 //! - Manual error type definition instead of `#[error_code]`
 //! - Explicit if-checks instead of `require!()` macro
-//! - Manual instruction routing with discriminators
+//! - Manual instruction routing with discriminators (`Instruction` enum)
 
 #![allow(unexpected_cfgs)]
 
 use pinocchio::{entrypoint, error::ProgramError, AccountView, Address, ProgramResult};
+use pinocchio_security_common::{
+    assert_not_sysvar_or_program, check_batch_len, check_uninitialized, ClockSource, CommonError,
+    SafeMath, SyscallClock, MAX_BATCH,
+};
+
+// Logging shim: `log!` forwards to `solana_program_log` with the default `logs`
+// feature and compiles to nothing under `--no-default-features`.
+#[cfg(feature = "logs")]
 use solana_program_log::log;
 
+#[cfg(not(feature = "logs"))]
+macro_rules! log {
+    ($($arg:tt)*) => {{
+        let _ = core::format_args!($($arg)*);
+    }};
+}
+
 // =============================================================================
 // PROGRAM ID
 // =============================================================================
 
 /// Program ID: CVyZU6X4vBxHQaQar29cho6Gv9qYLX8wu1wBYCq1K4jW
 pub const ID: Address = Address::new_from_array([
-    0x6b, 0x3e, 0xea, 0x8a, 0xae, 0x8f, 0xcc, 0x38, 0xbe, 0x2d, 0x22, 0xfc, 0x70, 0xf6, 0xeb, 0x1f,
-    0x4c, 0xec, 0x3d, 0x00, 0x74, 0x24, 0xdc, 0xfb, 0x45, 0xf9, 0xe0, 0x1d, 0xe2, 0x91, 0xbf, 0x3c,
+    0xaa, 0xdc, 0xc5, 0x6c, 0xac, 0xd8, 0xdc, 0xe1, 0x21, 0x0a, 0x94, 0xbd, 0xb4, 0x2b, 0x7c, 0x7c,
+    0xae, 0x30, 0x47, 0xa0, 0xa5, 0x89, 0x36, 0x4f, 0x83, 0x5b, 0x89, 0x56, 0x85, 0xcb, 0x06, 0x1d,
 ]);
 
 // =============================================================================
@@ -41,9 +60,10 @@ pub const ID: Address = Address::new_from_array([
 /// - user_count (u64): 8 bytes
 /// - total_rewards (u64): 8 bytes
 /// - bump (u8): 1 byte
+/// - max_users (u64): 8 bytes
 ///
-/// Total: 57 bytes
-pub const VAULT_STATE_SIZE: usize = 32 + 8 + 8 + 8 + 1;
+/// Total: 65 bytes
+pub const VAULT_STATE_SIZE: usize = 32 + 8 + 8 + 8 + 1 + 8;
 
 /// UserBalance account size (no Anchor discriminator):
 /// - owner (Address): 32 bytes
@@ -51,9 +71,11 @@ pub const VAULT_STATE_SIZE: usize = 32 + 8 + 8 + 8 + 1;
 /// - deposits (u64): 8 bytes
 /// - withdrawals (u64): 8 bytes
 /// - bump (u8): 1 byte
+/// - last_reward_slot (u64): 8 bytes
+/// - min_reserve (u64): 8 bytes
 ///
-/// Total: 57 bytes
-pub const USER_BALANCE_SIZE: usize = 32 + 8 + 8 + 8 + 1;
+/// Total: 73 bytes
+pub const USER_BALANCE_SIZE: usize = 32 + 8 + 8 + 8 + 1 + 8 + 8;
 
 /// Seed for vault PDA
 pub const VAULT_SEED: &[u8] = b"vault";
@@ -72,6 +94,15 @@ pub const USER_SEED: &[u8] = b"user";
 /// SECURITY: Limits input to prevent crafted overflow-inducing values
 pub const MAX_DEPOSIT: u64 = 1_000_000_000_000;
 
+/// Maximum balance a user can build up through deposits: 1,000,000 SOL
+///
+/// Rationale: MAX_DEPOSIT only limits a single call, so repeated deposits
+/// could otherwise walk a balance up to u64::MAX and fail late with a bare
+/// ArithmeticOverflow. 10^15 lamports is 1,000 maximum-size deposits.
+///
+/// SECURITY: Checked before the add in `deposit`, with ExceedsMaxBalance
+pub const MAX_USER_BALANCE: u64 = 1_000_000_000_000_000;
+
 /// Maximum reward rate multiplier: 10,000 basis points = 100x max
 ///
 /// Rationale: Using basis points where 100 = 1x multiplier:
@@ -82,8 +113,41 @@ pub const MAX_DEPOSIT: u64 = 1_000_000_000_000;
 ///
 /// SECURITY: Prevents multiplication overflow in reward calculations
 /// Combined with MAX_DEPOSIT, worst case: 10^12 × 10^4 = 10^16 (safe for u64)
+/// Combined with MAX_USER_BALANCE: 10^15 × 10^4 = 10^19, still below u64::MAX
 pub const MAX_REWARD_RATE: u64 = 10_000;
 
+/// Minimum number of slots between two reward calculations for the same user
+///
+/// Rationale: ~400ms slots, so 216,000 slots is roughly one day.
+///
+/// SECURITY: Without a cooldown, calculate_rewards can be called back-to-back
+/// to compound a balance without limit (reward farming)
+pub const REWARD_INTERVAL: u64 = 216_000;
+
+/// Maximum number of user_balance accounts in one distribute_rewards call
+///
+/// SECURITY: Bounds the compute spent per instruction and the size of the
+/// staging buffer that keeps the batch atomic. Same as the shared MAX_BATCH.
+pub const MAX_DISTRIBUTION_USERS: usize = MAX_BATCH;
+
+/// Upper bound (inclusive) of the first reward tier: 10 SOL
+///
+/// Balances up to this amount earn the full base rate under `tiered_rewards`.
+pub const REWARD_TIER_1_MAX: u64 = 10_000_000_000;
+
+/// Upper bound (inclusive) of the second reward tier: 100 SOL
+///
+/// Balances above REWARD_TIER_1_MAX and up to this amount earn
+/// REWARD_TIER_2_PCT percent of the base rate; larger balances earn
+/// REWARD_TIER_3_PCT percent.
+pub const REWARD_TIER_2_MAX: u64 = 100_000_000_000;
+
+/// Percentage of the base rate paid in the second tier
+pub const REWARD_TIER_2_PCT: u64 = 75;
+
+/// Percentage of the base rate paid above REWARD_TIER_2_MAX
+pub const REWARD_TIER_3_PCT: u64 = 50;
+
 // =============================================================================
 // INSTRUCTION DISCRIMINATORS
 // =============================================================================
@@ -93,6 +157,72 @@ pub const CREATE_USER_DISCRIMINATOR: u8 = 1;
 pub const DEPOSIT_DISCRIMINATOR: u8 = 2;
 pub const WITHDRAW_DISCRIMINATOR: u8 = 3;
 pub const CALCULATE_REWARDS_DISCRIMINATOR: u8 = 4;
+pub const DISTRIBUTE_REWARDS_DISCRIMINATOR: u8 = 5;
+
+/// Last discriminator available to this program's own instructions.
+///
+/// Discriminators are assigned in order from 0 up to this value.
+pub const CORE_DISCRIMINATOR_END: u8 = 127;
+
+/// First discriminator of the range reserved for forks and extensions.
+///
+/// `128..=255` is never assigned upstream, so a fork can add instructions
+/// there (handled in `process_extension`) without colliding with new core
+/// instructions.
+pub const EXTENSION_DISCRIMINATOR_START: u8 = CORE_DISCRIMINATOR_END + 1;
+
+/// Core instructions of this program, keyed by their discriminator.
+///
+/// `process_instruction` matches on this enum without a wildcard arm, so a
+/// new variant without a handler does not compile, and a reused discriminant
+/// is rejected by the compiler as well.
+#[repr(u8)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Instruction {
+    InitializeVault = INITIALIZE_VAULT_DISCRIMINATOR,
+    CreateUser = CREATE_USER_DISCRIMINATOR,
+    Deposit = DEPOSIT_DISCRIMINATOR,
+    Withdraw = WITHDRAW_DISCRIMINATOR,
+    CalculateRewards = CALCULATE_REWARDS_DISCRIMINATOR,
+    DistributeRewards = DISTRIBUTE_REWARDS_DISCRIMINATOR,
+}
+
+impl Instruction {
+    /// Every instruction, in discriminator order.
+    pub const ALL: [Instruction; 6] = [
+        Instruction::InitializeVault,
+        Instruction::CreateUser,
+        Instruction::Deposit,
+        Instruction::Withdraw,
+        Instruction::CalculateRewards,
+        Instruction::DistributeRewards,
+    ];
+}
+
+// A core instruction placed in the extension range fails the build
+const _: () = {
+    let mut i = 0;
+    while i < Instruction::ALL.len() {
+        assert!(Instruction::ALL[i] as u8 <= CORE_DISCRIMINATOR_END);
+        i += 1;
+    }
+};
+
+impl TryFrom<u8> for Instruction {
+    type Error = ProgramError;
+
+    fn try_from(discriminator: u8) -> Result<Self, Self::Error> {
+        match discriminator {
+            INITIALIZE_VAULT_DISCRIMINATOR => Ok(Instruction::InitializeVault),
+            CREATE_USER_DISCRIMINATOR => Ok(Instruction::CreateUser),
+            DEPOSIT_DISCRIMINATOR => Ok(Instruction::Deposit),
+            WITHDRAW_DISCRIMINATOR => Ok(Instruction::Withdraw),
+            CALCULATE_REWARDS_DISCRIMINATOR => Ok(Instruction::CalculateRewards),
+            DISTRIBUTE_REWARDS_DISCRIMINATOR => Ok(Instruction::DistributeRewards),
+            _ => Err(ProgramError::InvalidInstructionData),
+        }
+    }
+}
 
 // =============================================================================
 // CUSTOM ERROR TYPES
@@ -109,12 +239,29 @@ pub enum SecureError {
     ArithmeticOverflow = 0,
     /// Arithmetic operation would underflow (e.g., balance - withdrawal < 0)
     ArithmeticUnderflow = 1,
-    /// User has insufficient balance for the requested withdrawal
-    InsufficientBalance = 2,
+    // 2 reserved: insufficient balance is reported as
+    // CommonError::InsufficientBalance (0x2001), shared by every pattern.
     /// Deposit amount exceeds the maximum allowed (MAX_DEPOSIT)
     ExceedsMaxDeposit = 3,
     /// Reward rate exceeds the maximum allowed (MAX_REWARD_RATE)
     ExceedsMaxRewardRate = 4,
+    /// Vault has reached its configured user capacity (max_users)
+    UserCapExceeded = 5,
+    /// An account the instruction mutates was not passed as writable
+    AccountNotWritable = 6,
+    /// Rewards were already calculated for this user within REWARD_INTERVAL
+    RewardTooSoon = 7,
+    /// distribute_rewards was given no users (too many is
+    /// CommonError::BatchTooLarge)
+    InvalidBatchSize = 8,
+    /// The same user_balance account appears twice in one distribution
+    DuplicateUserBalance = 9,
+    /// Deposit would take the user balance above MAX_USER_BALANCE
+    ExceedsMaxBalance = 10,
+    /// The same account was passed for two roles of one instruction
+    DuplicateAccount = 11,
+    /// Withdrawal would take the user balance below its min_reserve
+    WouldBreakReserve = 12,
 }
 
 impl From<SecureError> for ProgramError {
@@ -123,12 +270,45 @@ impl From<SecureError> for ProgramError {
     }
 }
 
+// =============================================================================
+// ACCOUNT CHECKS
+// =============================================================================
+
+/// Rejects an account the handler is about to mutate unless it was passed as
+/// writable.
+///
+/// // SECURITY: The `[writable]` markers in the handler docs are not enforced by
+/// // Pinocchio. Checking up front fails with a specific error before any data
+/// // is read, instead of late inside `try_borrow_mut` or at runtime commit.
+fn assert_writable(account: &AccountView) -> ProgramResult {
+    if !account.is_writable() {
+        log!("SECURITY REJECTION: account is not writable");
+        return Err(SecureError::AccountNotWritable.into());
+    }
+
+    Ok(())
+}
+
+/// Rejects two roles of one instruction being filled by the same account.
+///
+/// // SECURITY: An aliased account is borrowed, parsed and written under two
+/// // roles at once, so each role must name a different address.
+fn assert_distinct_accounts(a: &AccountView, b: &AccountView) -> ProgramResult {
+    if a.address() == b.address() {
+        log!("SECURITY REJECTION: Same account passed for two roles");
+        return Err(SecureError::DuplicateAccount.into());
+    }
+
+    Ok(())
+}
+
 // =============================================================================
 // DATA STRUCTURES
 // =============================================================================
 
 /// Vault state account - tracks global vault information.
 /// SECURITY: All numeric fields use checked arithmetic operations.
+#[derive(Debug, Clone, PartialEq)]
 pub struct VaultState {
     /// Authority who controls the vault (32 bytes)
     pub authority: Address,
@@ -140,6 +320,8 @@ pub struct VaultState {
     pub total_rewards: u64,
     /// PDA bump seed (1 byte)
     pub bump: u8,
+    /// Maximum number of users allowed to register, 0 = unlimited (8 bytes)
+    pub max_users: u64,
 }
 
 impl VaultState {
@@ -167,7 +349,11 @@ impl VaultState {
 
         let bump = data[56];
 
-        Ok(Self { authority, total_deposits, user_count, total_rewards, bump })
+        let max_users = u64::from_le_bytes(
+            data[57..65].try_into().map_err(|_| ProgramError::InvalidAccountData)?,
+        );
+
+        Ok(Self { authority, total_deposits, user_count, total_rewards, bump, max_users })
     }
 
     /// Serialize VaultState into raw account data bytes.
@@ -181,6 +367,31 @@ impl VaultState {
         data[40..48].copy_from_slice(&self.user_count.to_le_bytes());
         data[48..56].copy_from_slice(&self.total_rewards.to_le_bytes());
         data[56] = self.bump;
+        data[57..65].copy_from_slice(&self.max_users.to_le_bytes());
+
+        Ok(())
+    }
+
+    /// Register one more user against the vault's capacity.
+    ///
+    /// SECURITY: Rejects registration once `user_count` reaches `max_users`
+    /// (0 = unlimited) and uses checked_add so the counter can never wrap.
+    pub fn register_user(&mut self) -> Result<(), SecureError> {
+        if self.max_users != 0 && self.user_count >= self.max_users {
+            return Err(SecureError::UserCapExceeded);
+        }
+
+        self.user_count = self.user_count.add_or(1, SecureError::ArithmeticOverflow)?;
+
+        Ok(())
+    }
+
+    /// Add a deposit to the vault's lifetime deposit total.
+    ///
+    /// SECURITY: Uses checked_add so the total can never wrap.
+    pub fn record_deposit(&mut self, amount: u64) -> Result<(), SecureError> {
+        self.total_deposits =
+            self.total_deposits.add_or(amount, SecureError::ArithmeticOverflow)?;
 
         Ok(())
     }
@@ -188,6 +399,7 @@ impl VaultState {
 
 /// User balance account - tracks individual user's balance.
 /// SECURITY: All balance operations use checked arithmetic.
+#[derive(Debug, Clone, PartialEq)]
 pub struct UserBalance {
     /// User who owns this balance (32 bytes)
     pub owner: Address,
@@ -199,6 +411,11 @@ pub struct UserBalance {
     pub withdrawals: u64,
     /// PDA bump seed (1 byte)
     pub bump: u8,
+    /// Slot of the last reward calculation, 0 = never rewarded (8 bytes)
+    pub last_reward_slot: u64,
+    /// Part of the balance that can never be withdrawn, 0 = none (8 bytes).
+    /// Stands in for the rent floor if balances become lamport-backed.
+    pub min_reserve: u64,
 }
 
 impl UserBalance {
@@ -226,7 +443,15 @@ impl UserBalance {
 
         let bump = data[56];
 
-        Ok(Self { owner, balance, deposits, withdrawals, bump })
+        let last_reward_slot = u64::from_le_bytes(
+            data[57..65].try_into().map_err(|_| ProgramError::InvalidAccountData)?,
+        );
+
+        let min_reserve = u64::from_le_bytes(
+            data[65..73].try_into().map_err(|_| ProgramError::InvalidAccountData)?,
+        );
+
+        Ok(Self { owner, balance, deposits, withdrawals, bump, last_reward_slot, min_reserve })
     }
 
     /// Serialize UserBalance into raw account data bytes.
@@ -240,9 +465,106 @@ impl UserBalance {
         data[40..48].copy_from_slice(&self.deposits.to_le_bytes());
         data[48..56].copy_from_slice(&self.withdrawals.to_le_bytes());
         data[56] = self.bump;
+        data[57..65].copy_from_slice(&self.last_reward_slot.to_le_bytes());
+        data[65..73].copy_from_slice(&self.min_reserve.to_le_bytes());
 
         Ok(())
     }
+
+    /// Credit a deposit to this user's balance and deposit history.
+    ///
+    /// SECURITY: Uses checked_add() for both fields - returns an error on overflow.
+    pub fn apply_deposit(&mut self, amount: u64) -> Result<(), SecureError> {
+        self.balance = self.balance.add_or(amount, SecureError::ArithmeticOverflow)?;
+        self.deposits = self.deposits.add_or(amount, SecureError::ArithmeticOverflow)?;
+
+        Ok(())
+    }
+
+    /// Debit a withdrawal from this user's balance and add it to withdrawal history.
+    ///
+    /// SECURITY: Rejects withdrawals above the balance with the shared
+    /// `CommonError::InsufficientBalance` code and withdrawals that would leave
+    /// less than `min_reserve` with `WouldBreakReserve`, then uses checked_sub()
+    /// so the balance can never underflow.
+    pub fn apply_withdraw(&mut self, amount: u64) -> ProgramResult {
+        if self.balance < amount {
+            return Err(CommonError::InsufficientBalance.into());
+        }
+
+        let balance = self.balance.sub_or(amount, SecureError::ArithmeticUnderflow)?;
+        if balance < self.min_reserve {
+            return Err(SecureError::WouldBreakReserve.into());
+        }
+
+        self.balance = balance;
+        self.withdrawals = self.withdrawals.add_or(amount, SecureError::ArithmeticOverflow)?;
+
+        Ok(())
+    }
+
+    /// Record a reward calculation at `current_slot`.
+    ///
+    /// SECURITY: Rejects with RewardTooSoon until REWARD_INTERVAL slots have
+    /// passed since the last reward. The first reward is never throttled.
+    pub fn record_reward(&mut self, current_slot: u64) -> Result<(), SecureError> {
+        if self.last_reward_slot != 0
+            && current_slot.saturating_sub(self.last_reward_slot) < REWARD_INTERVAL
+        {
+            return Err(SecureError::RewardTooSoon);
+        }
+
+        self.last_reward_slot = current_slot;
+
+        Ok(())
+    }
+}
+
+/// Rejects a deposit of `amount` that would take `balance` above MAX_USER_BALANCE.
+///
+/// // SECURITY: Written as a subtraction from the cap, so the check itself
+/// // cannot overflow. A balance already above the cap (e.g. from rewards)
+/// // accepts no further deposits.
+pub fn check_max_balance(balance: u64, amount: u64) -> Result<(), SecureError> {
+    if amount > MAX_USER_BALANCE.saturating_sub(balance) {
+        log!("Deposit would take balance {} above maximum {}", balance, MAX_USER_BALANCE);
+        return Err(SecureError::ExceedsMaxBalance);
+    }
+    Ok(())
+}
+
+/// Reward rate actually paid on `balance` when the base rate is `base_rate_bps`.
+///
+/// | Balance                                       | Rate                       |
+/// |-----------------------------------------------|----------------------------|
+/// | `..= REWARD_TIER_1_MAX`                       | base                       |
+/// | `REWARD_TIER_1_MAX + 1 ..= REWARD_TIER_2_MAX` | base × REWARD_TIER_2_PCT % |
+/// | `REWARD_TIER_2_MAX + 1 ..`                    | base × REWARD_TIER_3_PCT % |
+///
+/// Scaled rates round down. The product is widened to u128 and each tier
+/// percentage is at most 100, so the result never exceeds `base_rate_bps`.
+pub fn effective_rate(balance: u64, base_rate_bps: u64) -> u64 {
+    if balance <= REWARD_TIER_1_MAX {
+        return base_rate_bps;
+    }
+
+    let pct = if balance <= REWARD_TIER_2_MAX { REWARD_TIER_2_PCT } else { REWARD_TIER_3_PCT };
+
+    // SECURITY: pct <= 100, so the quotient fits back into u64
+    (u128::from(base_rate_bps) * u128::from(pct) / 100) as u64
+}
+
+/// Reward owed on `balance` at `reward_rate`.
+///
+/// With the `tiered_rewards` feature the rate is first scaled by
+/// [`effective_rate`], so larger balances earn a lower rate.
+///
+/// SECURITY: Uses checked_mul() - returns ArithmeticOverflow instead of wrapping.
+pub fn compute_reward(balance: u64, reward_rate: u64) -> Result<u64, SecureError> {
+    #[cfg(feature = "tiered_rewards")]
+    let reward_rate = effective_rate(balance, reward_rate);
+
+    balance.mul_or(reward_rate, SecureError::ArithmeticOverflow)
 }
 
 // =============================================================================
@@ -260,13 +582,58 @@ pub fn process_instruction(
     let (discriminator, data) =
         instruction_data.split_first().ok_or(ProgramError::InvalidInstructionData)?;
 
-    match *discriminator {
-        INITIALIZE_VAULT_DISCRIMINATOR => initialize_vault(program_id, accounts, data),
-        CREATE_USER_DISCRIMINATOR => create_user(program_id, accounts, data),
-        DEPOSIT_DISCRIMINATOR => deposit(accounts, data),
-        WITHDRAW_DISCRIMINATOR => withdraw(accounts, data),
-        CALCULATE_REWARDS_DISCRIMINATOR => calculate_rewards(accounts, data),
-        _ => Err(ProgramError::InvalidInstructionData),
+    if *discriminator >= EXTENSION_DISCRIMINATOR_START {
+        return process_extension(*discriminator, program_id, accounts, data);
+    }
+
+    match Instruction::try_from(*discriminator)? {
+        Instruction::InitializeVault => initialize_vault(program_id, accounts, data),
+        Instruction::CreateUser => create_user(program_id, accounts, data),
+        Instruction::Deposit => deposit(program_id, accounts, data),
+        Instruction::Withdraw => withdraw(program_id, accounts, data),
+        Instruction::CalculateRewards => {
+            calculate_rewards(program_id, &SyscallClock::get()?, accounts, data)
+        }
+        Instruction::DistributeRewards => {
+            distribute_rewards(program_id, &SyscallClock::get()?, accounts, data)
+        }
+    }
+}
+
+/// Handler for the reserved extension range (`EXTENSION_DISCRIMINATOR_START..`).
+///
+/// Upstream defines no extension instructions; forks route theirs from here.
+fn process_extension(
+    _discriminator: u8,
+    _program_id: &Address,
+    _accounts: &[AccountView],
+    _data: &[u8],
+) -> ProgramResult {
+    Err(ProgramError::InvalidInstructionData)
+}
+
+// =============================================================================
+// DRY RUN
+// =============================================================================
+
+/// Balances a deposit or withdraw committed, or would commit on a dry run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Projection {
+    /// User balance after the instruction
+    pub user_balance: u64,
+    /// Vault total_deposits after the instruction (`None` for withdraw, which
+    /// does not touch the vault)
+    pub total_deposits: Option<u64>,
+}
+
+/// Read the optional dry_run byte that follows a deposit/withdraw amount.
+///
+/// Omitted means a normal (committing) call, so existing clients are unaffected.
+fn parse_dry_run(data: &[u8]) -> Result<bool, ProgramError> {
+    match data.get(8) {
+        None | Some(0) => Ok(false),
+        Some(1) => Ok(true),
+        Some(_) => Err(ProgramError::InvalidInstructionData),
     }
 }
 
@@ -282,6 +649,7 @@ pub fn process_instruction(
 ///
 /// # Instruction Data
 /// - bump (u8): The PDA bump seed
+/// - max_users (u64, optional): User capacity, 8 bytes little-endian (0 or omitted = unlimited)
 fn initialize_vault(program_id: &Address, accounts: &[AccountView], data: &[u8]) -> ProgramResult {
     let [vault_state_acc, authority] = accounts else {
         return Err(ProgramError::NotEnoughAccountKeys);
@@ -291,18 +659,37 @@ fn initialize_vault(program_id: &Address, accounts: &[AccountView], data: &[u8])
         return Err(ProgramError::MissingRequiredSignature);
     }
 
+    // SECURITY: The vault may not be a sysvar or this program's account
+    assert_not_sysvar_or_program(vault_state_acc, program_id)?;
+
     if !vault_state_acc.owned_by(program_id) {
         return Err(ProgramError::IllegalOwner);
     }
 
+    // SECURITY: Refuse to overwrite a live vault and its authority
+    check_uninitialized(&vault_state_acc.try_borrow()?)?;
+
     let bump = if data.is_empty() { 0 } else { data[0] };
 
+    // SECURITY: A max_users field shorter than 8 bytes is malformed, not
+    // "unlimited"; only an absent field means no cap
+    let max_users = match data.get(1..) {
+        None | Some([]) => 0,
+        Some(bytes) => u64::from_le_bytes(
+            bytes
+                .get(..8)
+                .and_then(|bytes| bytes.try_into().ok())
+                .ok_or(ProgramError::InvalidInstructionData)?,
+        ),
+    };
+
     let vault_state = VaultState {
         authority: Address::new_from_array(*authority.address().as_array()),
         total_deposits: 0,
         user_count: 0,
         total_rewards: 0,
         bump,
+        max_users,
     };
 
     let mut account_data = vault_state_acc.try_borrow_mut()?;
@@ -331,10 +718,17 @@ fn create_user(program_id: &Address, accounts: &[AccountView], data: &[u8]) -> P
         return Err(ProgramError::MissingRequiredSignature);
     }
 
+    // SECURITY: Neither data account may be a sysvar or this program's account
+    assert_not_sysvar_or_program(vault_state_acc, program_id)?;
+    assert_not_sysvar_or_program(user_balance_acc, program_id)?;
+
     if !vault_state_acc.owned_by(program_id) || !user_balance_acc.owned_by(program_id) {
         return Err(ProgramError::IllegalOwner);
     }
 
+    // SECURITY: Refuse to reset a live balance or count its owner twice
+    check_uninitialized(&user_balance_acc.try_borrow()?)?;
+
     let bump = if data.is_empty() { 0 } else { data[0] };
 
     // Read and update vault state
@@ -342,9 +736,15 @@ fn create_user(program_id: &Address, accounts: &[AccountView], data: &[u8]) -> P
     let mut vault_state = VaultState::try_from_slice(&vault_data)?;
     drop(vault_data);
 
-    // SECURITY: Use checked_add for user count increment
-    vault_state.user_count =
-        vault_state.user_count.checked_add(1).ok_or(SecureError::ArithmeticOverflow)?;
+    // SECURITY: Enforce user capacity and use checked_add for the count increment
+    if let Err(e) = vault_state.register_user() {
+        log!(
+            "User registration rejected: {} of {} users",
+            vault_state.user_count,
+            vault_state.max_users
+        );
+        return Err(e.into());
+    }
 
     let mut vault_data = vault_state_acc.try_borrow_mut()?;
     vault_state.serialize(&mut vault_data)?;
@@ -357,6 +757,9 @@ fn create_user(program_id: &Address, accounts: &[AccountView], data: &[u8]) -> P
         deposits: 0,
         withdrawals: 0,
         bump,
+        last_reward_slot: 0,
+        // No reserve until a lamport-backed balance needs a rent floor
+        min_reserve: 0,
     };
 
     let mut account_data = user_balance_acc.try_borrow_mut()?;
@@ -370,7 +773,10 @@ fn create_user(program_id: &Address, accounts: &[AccountView], data: &[u8]) -> P
 /// Deposit funds into user balance.
 ///
 /// # Security Features
+/// - SECURITY: Rejects read-only vault_state / user_balance before reading them
+/// - SECURITY: Rejects vault_state / user_balance not owned by this program (IllegalOwner)
 /// - SECURITY: Validates deposit amount against MAX_DEPOSIT limit
+/// - SECURITY: Rejects deposits that would exceed MAX_USER_BALANCE (ExceedsMaxBalance)
 /// - SECURITY: Uses checked_add() for all balance updates
 /// - SECURITY: Returns ArithmeticOverflow error on failure
 ///
@@ -381,7 +787,18 @@ fn create_user(program_id: &Address, accounts: &[AccountView], data: &[u8]) -> P
 ///
 /// # Instruction Data
 /// - amount (u64): The amount to deposit (8 bytes, little-endian)
-fn deposit(accounts: &[AccountView], data: &[u8]) -> ProgramResult {
+/// - dry_run (u8, optional): 1 = validate and log the projected balances without
+///   writing them (0 or omitted = commit)
+fn deposit(program_id: &Address, accounts: &[AccountView], data: &[u8]) -> ProgramResult {
+    process_deposit(program_id, accounts, data).map(|_| ())
+}
+
+/// Runs `deposit` and returns the balances it wrote (or would write on a dry run).
+fn process_deposit(
+    program_id: &Address,
+    accounts: &[AccountView],
+    data: &[u8],
+) -> Result<Projection, ProgramError> {
     let [vault_state_acc, user_balance_acc, owner] = accounts else {
         return Err(ProgramError::NotEnoughAccountKeys);
     };
@@ -390,12 +807,27 @@ fn deposit(accounts: &[AccountView], data: &[u8]) -> ProgramResult {
         return Err(ProgramError::MissingRequiredSignature);
     }
 
+    // SECURITY: Both the vault totals and the user balance are written below
+    assert_writable(vault_state_acc)?;
+    assert_writable(user_balance_acc)?;
+
+    // SECURITY: Neither data account may be a sysvar or this program's account
+    assert_not_sysvar_or_program(vault_state_acc, program_id)?;
+    assert_not_sysvar_or_program(user_balance_acc, program_id)?;
+
+    // SECURITY: A look-alike account owned by another program must not be
+    // parsed as program state
+    if !vault_state_acc.owned_by(program_id) || !user_balance_acc.owned_by(program_id) {
+        return Err(ProgramError::IllegalOwner);
+    }
+
     if data.len() < 8 {
         return Err(ProgramError::InvalidInstructionData);
     }
     let amount_to_add = u64::from_le_bytes(
         data[0..8].try_into().map_err(|_| ProgramError::InvalidInstructionData)?,
     );
+    let dry_run = parse_dry_run(data)?;
 
     // Read user balance
     let user_data = user_balance_acc.try_borrow()?;
@@ -416,19 +848,13 @@ fn deposit(accounts: &[AccountView], data: &[u8]) -> ProgramResult {
         return Err(SecureError::ExceedsMaxDeposit.into());
     }
 
-    // SECURITY: Use checked_add() for balance update - returns None on overflow
-    // If overflow would occur, we return an error instead of wrapping
-    user_balance.balance =
-        user_balance.balance.checked_add(amount_to_add).ok_or(SecureError::ArithmeticOverflow)?;
-
-    // SECURITY: Use checked_add() for deposit tracking
-    user_balance.deposits =
-        user_balance.deposits.checked_add(amount_to_add).ok_or(SecureError::ArithmeticOverflow)?;
+    // SECURITY: Lifetime cap, checked before the add so repeated deposits fail
+    // with a specific error long before the balance nears u64::MAX
+    check_max_balance(user_balance.balance, amount_to_add)?;
 
-    // Write updated user balance
-    let mut user_data = user_balance_acc.try_borrow_mut()?;
-    user_balance.serialize(&mut user_data)?;
-    drop(user_data);
+    // SECURITY: Use checked_add() for balance and deposit tracking - returns an
+    // error on overflow instead of wrapping
+    user_balance.apply_deposit(amount_to_add)?;
 
     // Update vault totals
     let vault_data = vault_state_acc.try_borrow()?;
@@ -436,25 +862,45 @@ fn deposit(accounts: &[AccountView], data: &[u8]) -> ProgramResult {
     drop(vault_data);
 
     // SECURITY: Use checked_add() for vault total tracking
-    vault_state.total_deposits = vault_state
-        .total_deposits
-        .checked_add(amount_to_add)
-        .ok_or(SecureError::ArithmeticOverflow)?;
+    vault_state.record_deposit(amount_to_add)?;
+
+    let projection = Projection {
+        user_balance: user_balance.balance,
+        total_deposits: Some(vault_state.total_deposits),
+    };
+
+    // Every check above ran; a dry run stops short of the writes
+    if dry_run {
+        log!(
+            "DRY RUN deposit - projected user balance: {}, projected vault total: {}",
+            user_balance.balance,
+            vault_state.total_deposits
+        );
+        return Ok(projection);
+    }
+
+    // Write updated user balance
+    let mut user_data = user_balance_acc.try_borrow_mut()?;
+    user_balance.serialize(&mut user_data)?;
+    drop(user_data);
 
     let mut vault_data = vault_state_acc.try_borrow_mut()?;
     vault_state.serialize(&mut vault_data)?;
 
     log!("After deposit - User balance: {}", user_balance.balance);
 
-    Ok(())
+    Ok(projection)
 }
 
 /// Withdraw funds from user balance.
 ///
 /// # Security Features
+/// - SECURITY: Rejects a read-only user_balance before reading it
+/// - SECURITY: Rejects a user_balance not owned by this program (IllegalOwner)
 /// - SECURITY: Validates sufficient balance before any arithmetic
+/// - SECURITY: Keeps at least `min_reserve` in the balance (WouldBreakReserve)
 /// - SECURITY: Uses checked_sub() for defense in depth
-/// - SECURITY: Returns InsufficientBalance or ArithmeticUnderflow error on failure
+/// - SECURITY: Returns CommonError::InsufficientBalance (0x2001) on failure
 ///
 /// # Accounts
 /// 0. `[writable]` user_balance - The user's balance account
@@ -462,7 +908,18 @@ fn deposit(accounts: &[AccountView], data: &[u8]) -> ProgramResult {
 ///
 /// # Instruction Data
 /// - amount (u64): The amount to withdraw (8 bytes, little-endian)
-fn withdraw(accounts: &[AccountView], data: &[u8]) -> ProgramResult {
+/// - dry_run (u8, optional): 1 = validate and log the projected balance without
+///   writing it (0 or omitted = commit)
+fn withdraw(program_id: &Address, accounts: &[AccountView], data: &[u8]) -> ProgramResult {
+    process_withdraw(program_id, accounts, data).map(|_| ())
+}
+
+/// Runs `withdraw` and returns the balance it wrote (or would write on a dry run).
+fn process_withdraw(
+    program_id: &Address,
+    accounts: &[AccountView],
+    data: &[u8],
+) -> Result<Projection, ProgramError> {
     let [user_balance_acc, owner] = accounts else {
         return Err(ProgramError::NotEnoughAccountKeys);
     };
@@ -471,12 +928,25 @@ fn withdraw(accounts: &[AccountView], data: &[u8]) -> ProgramResult {
         return Err(ProgramError::MissingRequiredSignature);
     }
 
+    // SECURITY: The user balance is written below
+    assert_writable(user_balance_acc)?;
+
+    // SECURITY: The user balance may not be a sysvar or this program's account
+    assert_not_sysvar_or_program(user_balance_acc, program_id)?;
+
+    // SECURITY: A look-alike account owned by another program must not be
+    // parsed as program state
+    if !user_balance_acc.owned_by(program_id) {
+        return Err(ProgramError::IllegalOwner);
+    }
+
     if data.len() < 8 {
         return Err(ProgramError::InvalidInstructionData);
     }
     let amount_to_subtract = u64::from_le_bytes(
         data[0..8].try_into().map_err(|_| ProgramError::InvalidInstructionData)?,
     );
+    let dry_run = parse_dry_run(data)?;
 
     // Read user balance
     let user_data = user_balance_acc.try_borrow()?;
@@ -498,21 +968,20 @@ fn withdraw(accounts: &[AccountView], data: &[u8]) -> ProgramResult {
     // This is the primary defense against underflow attacks
     if user_balance.balance < amount_to_subtract {
         log!("Insufficient balance: {} < {}", user_balance.balance, amount_to_subtract);
-        return Err(SecureError::InsufficientBalance.into());
+        return Err(CommonError::InsufficientBalance.into());
     }
 
     // SECURITY: Use checked_sub() for defense in depth
     // Even after the balance check, we use safe arithmetic as a second layer
-    user_balance.balance = user_balance
-        .balance
-        .checked_sub(amount_to_subtract)
-        .ok_or(SecureError::ArithmeticUnderflow)?;
+    user_balance.apply_withdraw(amount_to_subtract)?;
+
+    let projection = Projection { user_balance: user_balance.balance, total_deposits: None };
 
-    // SECURITY: Use checked_add() for withdrawal tracking
-    user_balance.withdrawals = user_balance
-        .withdrawals
-        .checked_add(amount_to_subtract)
-        .ok_or(SecureError::ArithmeticOverflow)?;
+    // Every check above ran; a dry run stops short of the write
+    if dry_run {
+        log!("DRY RUN withdraw - projected user balance: {}", user_balance.balance);
+        return Ok(projection);
+    }
 
     // Write updated user balance
     let mut user_data = user_balance_acc.try_borrow_mut()?;
@@ -520,15 +989,20 @@ fn withdraw(accounts: &[AccountView], data: &[u8]) -> ProgramResult {
 
     log!("After withdraw - User balance: {}", user_balance.balance);
 
-    Ok(())
+    Ok(projection)
 }
 
 /// Calculate rewards based on balance and rate.
 ///
 /// # Security Features
+/// - SECURITY: Rejects vault_state / user_balance not owned by this program (IllegalOwner)
+/// - SECURITY: Rejects any two of the three accounts sharing an address (DuplicateAccount)
 /// - SECURITY: Validates reward rate against MAX_REWARD_RATE limit
+/// - SECURITY: Enforces a REWARD_INTERVAL cooldown per user (RewardTooSoon)
 /// - SECURITY: Uses checked_mul() for reward calculation
 /// - SECURITY: Uses checked_add() for adding rewards
+/// - SECURITY: Both checked_add() results are computed before any account is
+///   written, so an overflow leaves vault_state and user_balance unchanged
 /// - SECURITY: Returns ArithmeticOverflow or ExceedsMaxRewardRate error on failure
 ///
 /// # Accounts
@@ -538,7 +1012,12 @@ fn withdraw(accounts: &[AccountView], data: &[u8]) -> ProgramResult {
 ///
 /// # Instruction Data
 /// - reward_rate (u64): The reward rate multiplier (8 bytes, little-endian)
-fn calculate_rewards(accounts: &[AccountView], data: &[u8]) -> ProgramResult {
+fn calculate_rewards(
+    program_id: &Address,
+    clock: &impl ClockSource,
+    accounts: &[AccountView],
+    data: &[u8],
+) -> ProgramResult {
     let [vault_state_acc, user_balance_acc, authority] = accounts else {
         return Err(ProgramError::NotEnoughAccountKeys);
     };
@@ -547,6 +1026,20 @@ fn calculate_rewards(accounts: &[AccountView], data: &[u8]) -> ProgramResult {
         return Err(ProgramError::MissingRequiredSignature);
     }
 
+    // SECURITY: The signer and both data accounts are three different accounts
+    assert_distinct_accounts(vault_state_acc, user_balance_acc)?;
+    assert_distinct_accounts(vault_state_acc, authority)?;
+    assert_distinct_accounts(user_balance_acc, authority)?;
+
+    // SECURITY: Neither data account may be a sysvar or this program's account
+    assert_not_sysvar_or_program(vault_state_acc, program_id)?;
+    assert_not_sysvar_or_program(user_balance_acc, program_id)?;
+
+    // SECURITY: Only program-owned vault and user state may be credited
+    if !vault_state_acc.owned_by(program_id) || !user_balance_acc.owned_by(program_id) {
+        return Err(ProgramError::IllegalOwner);
+    }
+
     if data.len() < 8 {
         return Err(ProgramError::InvalidInstructionData);
     }
@@ -568,31 +1061,38 @@ fn calculate_rewards(accounts: &[AccountView], data: &[u8]) -> ProgramResult {
         return Err(SecureError::ExceedsMaxRewardRate.into());
     }
 
+    // SECURITY: Throttle per user so rewards cannot be compounded back-to-back
+    if let Err(e) = user_balance.record_reward(clock.slot()) {
+        log!(
+            "Reward too soon - last: {}, current: {}",
+            user_balance.last_reward_slot,
+            clock.slot()
+        );
+        return Err(e.into());
+    }
+
     // SECURITY: Use checked_mul() for reward calculation - returns None on overflow
     // This prevents multiplication overflow attacks
-    let reward_amount =
-        user_balance.balance.checked_mul(reward_rate).ok_or(SecureError::ArithmeticOverflow)?;
+    let reward_amount = compute_reward(user_balance.balance, reward_rate)?;
 
     // Read and update vault state
     let vault_data = vault_state_acc.try_borrow()?;
     let mut vault_state = VaultState::try_from_slice(&vault_data)?;
     drop(vault_data);
 
-    // SECURITY: Use checked_add() for vault reward tracking
-    vault_state.total_rewards = vault_state
-        .total_rewards
-        .checked_add(reward_amount)
-        .ok_or(SecureError::ArithmeticOverflow)?;
+    // SECURITY: Use checked_add() for vault reward tracking and the user balance.
+    // Both sums are computed before either account is written, so an overflow in
+    // one cannot leave the other already credited.
+    vault_state.total_rewards =
+        vault_state.total_rewards.add_or(reward_amount, SecureError::ArithmeticOverflow)?;
+    user_balance.balance =
+        user_balance.balance.add_or(reward_amount, SecureError::ArithmeticOverflow)?;
 
+    // Write updated vault state and user balance
     let mut vault_data = vault_state_acc.try_borrow_mut()?;
     vault_state.serialize(&mut vault_data)?;
     drop(vault_data);
 
-    // SECURITY: Use checked_add() for adding reward to balance
-    user_balance.balance =
-        user_balance.balance.checked_add(reward_amount).ok_or(SecureError::ArithmeticOverflow)?;
-
-    // Write updated user balance
     let mut user_data = user_balance_acc.try_borrow_mut()?;
     user_balance.serialize(&mut user_data)?;
 
@@ -601,6 +1101,130 @@ fn calculate_rewards(accounts: &[AccountView], data: &[u8]) -> ProgramResult {
     Ok(())
 }
 
+/// Apply `calculate_rewards` to a batch of users in one instruction.
+///
+/// # Security Features
+/// - SECURITY: Signer must be the vault's stored authority
+/// - SECURITY: vault_state and every user_balance must be owned by this program
+/// - SECURITY: Validates reward rate against MAX_REWARD_RATE limit
+/// - SECURITY: Batch size is capped at MAX_DISTRIBUTION_USERS (CommonError::BatchTooLarge;
+///   an empty batch is InvalidBatchSize)
+/// - SECURITY: Rejects a user_balance passed twice (DuplicateUserBalance), which
+///   would otherwise be rewarded twice past its cooldown
+/// - SECURITY: Every reward, balance and the vault total are computed with checked
+///   arithmetic before anything is written, so one overflow (or one user still in
+///   cooldown) fails the whole batch and no account is updated
+///
+/// # Accounts
+/// 0. `[writable]` vault_state - The vault account
+/// 1. `[signer]` authority - The vault authority
+/// 2. `[writable]` user_balance, ... - 1 to MAX_DISTRIBUTION_USERS user balance accounts
+///
+/// # Instruction Data
+/// - reward_rate (u64): The reward rate multiplier (8 bytes, little-endian)
+fn distribute_rewards(
+    program_id: &Address,
+    clock: &impl ClockSource,
+    accounts: &[AccountView],
+    data: &[u8],
+) -> ProgramResult {
+    let [vault_state_acc, authority, user_balance_accs @ ..] = accounts else {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    if !authority.is_signer() {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    if data.len() < 8 {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    let reward_rate = u64::from_le_bytes(
+        data[0..8].try_into().map_err(|_| ProgramError::InvalidInstructionData)?,
+    );
+
+    // SECURITY: Validate reward rate against maximum
+    if reward_rate > MAX_REWARD_RATE {
+        log!("Reward rate {} exceeds maximum {}", reward_rate, MAX_REWARD_RATE);
+        return Err(SecureError::ExceedsMaxRewardRate.into());
+    }
+
+    if user_balance_accs.is_empty() {
+        log!("distribute_rewards needs at least one user");
+        return Err(SecureError::InvalidBatchSize.into());
+    }
+
+    // SECURITY: Reject an oversized batch before any account is read or written
+    check_batch_len(user_balance_accs.len())?;
+
+    assert_writable(vault_state_acc)?;
+    assert_not_sysvar_or_program(vault_state_acc, program_id)?;
+
+    if !vault_state_acc.owned_by(program_id) {
+        return Err(ProgramError::IllegalOwner);
+    }
+
+    let vault_data = vault_state_acc.try_borrow()?;
+    let mut vault_state = VaultState::try_from_slice(&vault_data)?;
+    drop(vault_data);
+
+    if vault_state.authority.as_ref() != authority.address().as_ref() {
+        log!("SECURITY REJECTION: signer is not the vault authority");
+        return Err(ProgramError::IncorrectAuthority);
+    }
+
+    // Stage every update first; nothing below this loop can fail arithmetically
+    let mut staged: [Option<UserBalance>; MAX_DISTRIBUTION_USERS] =
+        [const { None }; MAX_DISTRIBUTION_USERS];
+
+    for (i, user_balance_acc) in user_balance_accs.iter().enumerate() {
+        assert_writable(user_balance_acc)?;
+        assert_not_sysvar_or_program(user_balance_acc, program_id)?;
+
+        if !user_balance_acc.owned_by(program_id) {
+            return Err(ProgramError::IllegalOwner);
+        }
+
+        if user_balance_accs[..i].iter().any(|prev| prev.address() == user_balance_acc.address()) {
+            log!("SECURITY REJECTION: user_balance passed twice");
+            return Err(SecureError::DuplicateUserBalance.into());
+        }
+
+        let user_data = user_balance_acc.try_borrow()?;
+        let mut user_balance = UserBalance::try_from_slice(&user_data)?;
+        drop(user_data);
+
+        user_balance.record_reward(clock.slot())?;
+
+        let reward_amount = compute_reward(user_balance.balance, reward_rate)?;
+        user_balance.balance =
+            user_balance.balance.add_or(reward_amount, SecureError::ArithmeticOverflow)?;
+        vault_state.total_rewards =
+            vault_state.total_rewards.add_or(reward_amount, SecureError::ArithmeticOverflow)?;
+
+        staged[i] = Some(user_balance);
+    }
+
+    // Commit: the whole batch validated, so write every account
+    for (user_balance_acc, user_balance) in user_balance_accs.iter().zip(staged.iter()) {
+        if let Some(user_balance) = user_balance {
+            let mut user_data = user_balance_acc.try_borrow_mut()?;
+            user_balance.serialize(&mut user_data)?;
+        }
+    }
+
+    let mut vault_data = vault_state_acc.try_borrow_mut()?;
+    vault_state.serialize(&mut vault_data)?;
+
+    log!(
+        "Rewards distributed to {} users, vault total rewards: {}",
+        user_balance_accs.len(),
+        vault_state.total_rewards
+    );
+
+    Ok(())
+}
+
 // =============================================================================
 // TESTS
 // =============================================================================
@@ -608,6 +1232,16 @@ fn calculate_rewards(accounts: &[AccountView], data: &[u8]) -> ProgramResult {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use pinocchio_security_common::{
+        account_hash, assert_reinit_rejected, decode_address, MockAccountView, MockClock,
+        XorShift64,
+    };
+
+    #[test]
+    fn test_program_id_matches_documented_address() {
+        // Must match the doc comment on `ID` and Anchor.toml
+        assert_eq!(decode_address("CVyZU6X4vBxHQaQar29cho6Gv9qYLX8wu1wBYCq1K4jW"), Some(ID));
+    }
 
     #[test]
     fn test_vault_state_serialization() {
@@ -617,17 +1251,49 @@ mod tests {
             user_count: 5,
             total_rewards: 500,
             bump: 255,
+            max_users: 10,
         };
 
         let mut buffer = [0u8; VAULT_STATE_SIZE];
         vault.serialize(&mut buffer).unwrap();
 
         let deserialized = VaultState::try_from_slice(&buffer).unwrap();
-        assert_eq!(deserialized.authority, vault.authority);
-        assert_eq!(deserialized.total_deposits, vault.total_deposits);
-        assert_eq!(deserialized.user_count, vault.user_count);
-        assert_eq!(deserialized.total_rewards, vault.total_rewards);
-        assert_eq!(deserialized.bump, vault.bump);
+        assert_eq!(deserialized, vault);
+    }
+
+    fn empty_vault(max_users: u64) -> VaultState {
+        VaultState {
+            authority: Address::new_from_array([1u8; 32]),
+            total_deposits: 0,
+            user_count: 0,
+            total_rewards: 0,
+            bump: 255,
+            max_users,
+        }
+    }
+
+    #[test]
+    fn test_register_user_fills_to_capacity() {
+        let mut vault = empty_vault(3);
+        for _ in 0..3 {
+            vault.register_user().unwrap();
+        }
+        assert_eq!(vault.user_count, 3);
+
+        let result = vault.register_user();
+        assert!(matches!(result, Err(SecureError::UserCapExceeded)));
+        assert_eq!(vault.user_count, 3, "Rejected registration must not change the count");
+    }
+
+    #[test]
+    fn test_register_user_unlimited_uses_checked_add() {
+        let mut vault = empty_vault(0);
+        vault.register_user().unwrap();
+        assert_eq!(vault.user_count, 1);
+
+        vault.user_count = u64::MAX;
+        let result = vault.register_user();
+        assert!(matches!(result, Err(SecureError::ArithmeticOverflow)));
     }
 
     #[test]
@@ -638,17 +1304,15 @@ mod tests {
             deposits: 15000,
             withdrawals: 5000,
             bump: 254,
+            last_reward_slot: 123_456,
+            min_reserve: 42,
         };
 
         let mut buffer = [0u8; USER_BALANCE_SIZE];
         user.serialize(&mut buffer).unwrap();
 
         let deserialized = UserBalance::try_from_slice(&buffer).unwrap();
-        assert_eq!(deserialized.owner, user.owner);
-        assert_eq!(deserialized.balance, user.balance);
-        assert_eq!(deserialized.deposits, user.deposits);
-        assert_eq!(deserialized.withdrawals, user.withdrawals);
-        assert_eq!(deserialized.bump, user.bump);
+        assert_eq!(deserialized, user);
     }
 
     #[test]
@@ -687,12 +1351,833 @@ mod tests {
         assert!(rate > MAX_REWARD_RATE, "Rate should exceed MAX_REWARD_RATE");
     }
 
+    #[test]
+    fn test_effective_rate_tier_boundaries() {
+        let base = 1_000;
+
+        assert_eq!(effective_rate(0, base), base);
+        assert_eq!(effective_rate(REWARD_TIER_1_MAX, base), base);
+        assert_eq!(effective_rate(REWARD_TIER_1_MAX + 1, base), 750);
+        assert_eq!(effective_rate(REWARD_TIER_2_MAX, base), 750);
+        assert_eq!(effective_rate(REWARD_TIER_2_MAX + 1, base), 500);
+        assert_eq!(effective_rate(u64::MAX, base), 500);
+    }
+
+    #[test]
+    fn test_effective_rate_rounds_down_and_never_exceeds_base() {
+        // 3 × 75 / 100 = 2.25 -> 2
+        assert_eq!(effective_rate(REWARD_TIER_2_MAX, 3), 2);
+        assert_eq!(effective_rate(u64::MAX, 1), 0);
+        assert_eq!(effective_rate(u64::MAX, u64::MAX), u64::MAX / 2);
+    }
+
+    #[cfg(feature = "tiered_rewards")]
+    #[test]
+    fn test_compute_reward_uses_tiered_rate() {
+        assert_eq!(compute_reward(REWARD_TIER_1_MAX, 100).ok(), Some(REWARD_TIER_1_MAX * 100));
+        assert_eq!(compute_reward(REWARD_TIER_2_MAX, 100).ok(), Some(REWARD_TIER_2_MAX * 75));
+        assert_eq!(compute_reward(MAX_DEPOSIT, 100).ok(), Some(MAX_DEPOSIT * 50));
+    }
+
     #[test]
     fn test_error_codes() {
         assert_eq!(SecureError::ArithmeticOverflow as u32, 0);
         assert_eq!(SecureError::ArithmeticUnderflow as u32, 1);
-        assert_eq!(SecureError::InsufficientBalance as u32, 2);
+        assert_eq!(CommonError::InsufficientBalance as u32, 0x2001);
         assert_eq!(SecureError::ExceedsMaxDeposit as u32, 3);
         assert_eq!(SecureError::ExceedsMaxRewardRate as u32, 4);
+        assert_eq!(SecureError::UserCapExceeded as u32, 5);
+        assert_eq!(SecureError::AccountNotWritable as u32, 6);
+        assert_eq!(SecureError::RewardTooSoon as u32, 7);
+        assert_eq!(SecureError::ExceedsMaxBalance as u32, 10);
+        assert_eq!(SecureError::DuplicateAccount as u32, 11);
+        assert_eq!(SecureError::WouldBreakReserve as u32, 12);
+    }
+
+    #[test]
+    fn test_withdraw_insufficient_balance_uses_common_code() {
+        let mut user = user_balance_account(100).writable();
+        let mut owner = owner_account();
+
+        let result = withdraw(&ID, &[user.view(), owner.view()], &101u64.to_le_bytes());
+        assert_eq!(result, Err(ProgramError::Custom(0x2001)));
+        assert_eq!(result, Err(CommonError::InsufficientBalance.into()));
+
+        // Nothing is debited on rejection
+        let user = UserBalance::try_from_slice(user.data()).unwrap();
+        assert_eq!((user.balance, user.withdrawals), (100, 0));
+    }
+
+    /// Runs `withdraw` of `amount` from a balance of 100 holding `min_reserve`
+    /// back, returning the result and the stored balance afterwards.
+    fn withdraw_with_reserve(min_reserve: u64, amount: u64) -> (ProgramResult, u64) {
+        let mut data = user_balance_account(100).data().to_vec();
+        let mut user = UserBalance::try_from_slice(&data).unwrap();
+        user.min_reserve = min_reserve;
+        user.serialize(&mut data).unwrap();
+
+        let mut user =
+            MockAccountView::new(Address::new_from_array([3u8; 32]), ID, 0, &data).writable();
+        let mut owner = owner_account();
+        let result = withdraw(&ID, &[user.view(), owner.view()], &amount.to_le_bytes());
+        (result, UserBalance::try_from_slice(user.data()).unwrap().balance)
+    }
+
+    #[test]
+    fn test_withdraw_down_to_reserve() {
+        assert_eq!(withdraw_with_reserve(30, 70), (Ok(()), 30));
+
+        // The default reserve of zero lets the whole balance go
+        assert_eq!(withdraw_with_reserve(0, 100), (Ok(()), 0));
+    }
+
+    #[test]
+    fn test_withdraw_past_reserve_rejected() {
+        let rejected = (Err(SecureError::WouldBreakReserve.into()), 100);
+        assert_eq!(withdraw_with_reserve(30, 71), rejected);
+        assert_eq!(withdraw_with_reserve(30, 100), rejected);
+
+        // Overdrawing still reports the shared insufficient-balance code
+        let result = withdraw_with_reserve(30, 101);
+        assert_eq!(result, (Err(CommonError::InsufficientBalance.into()), 100));
+    }
+
+    // =========================================================================
+    // WRITABILITY PRECHECKS
+    // =========================================================================
+
+    const OWNER: Address = Address::new_from_array([2u8; 32]);
+
+    fn user_balance_account(balance: u64) -> MockAccountView {
+        let user = UserBalance {
+            owner: OWNER,
+            balance,
+            deposits: balance,
+            withdrawals: 0,
+            bump: 254,
+            last_reward_slot: 0,
+            min_reserve: 0,
+        };
+        let mut data = [0u8; USER_BALANCE_SIZE];
+        user.serialize(&mut data).unwrap();
+        MockAccountView::new(Address::new_from_array([3u8; 32]), ID, 0, &data)
+    }
+
+    fn vault_state_account() -> MockAccountView {
+        let vault = VaultState {
+            authority: Address::new_from_array([1u8; 32]),
+            total_deposits: 100,
+            user_count: 1,
+            total_rewards: 0,
+            bump: 255,
+            max_users: 0,
+        };
+        let mut data = [0u8; VAULT_STATE_SIZE];
+        vault.serialize(&mut data).unwrap();
+        MockAccountView::new(Address::new_from_array([4u8; 32]), ID, 0, &data)
+    }
+
+    fn owner_account() -> MockAccountView {
+        MockAccountView::new(OWNER, Address::default(), 0, &[]).signer()
+    }
+
+    #[test]
+    fn test_deposit_rejects_read_only_accounts() {
+        let amount = 50u64.to_le_bytes();
+        let mut owner = owner_account();
+
+        let mut vault = vault_state_account();
+        let mut user = user_balance_account(100).writable();
+        let result = deposit(&ID, &[vault.view(), user.view(), owner.view()], &amount);
+        assert!(matches!(result, Err(ProgramError::Custom(6))));
+
+        let mut vault = vault_state_account().writable();
+        let mut user = user_balance_account(100);
+        let result = deposit(&ID, &[vault.view(), user.view(), owner.view()], &amount);
+        assert!(matches!(result, Err(ProgramError::Custom(6))));
+
+        // Nothing is written on rejection
+        assert_eq!(UserBalance::try_from_slice(user.data()).unwrap().balance, 100);
+    }
+
+    #[test]
+    fn test_deposit_accepts_writable_accounts() {
+        let mut vault = vault_state_account().writable();
+        let mut user = user_balance_account(100).writable();
+        let mut owner = owner_account();
+
+        deposit(&ID, &[vault.view(), user.view(), owner.view()], &50u64.to_le_bytes()).unwrap();
+
+        assert_eq!(UserBalance::try_from_slice(user.data()).unwrap().balance, 150);
+        assert_eq!(VaultState::try_from_slice(vault.data()).unwrap().total_deposits, 150);
+    }
+
+    #[test]
+    fn test_repeated_max_deposits_hit_lifetime_cap() {
+        let mut vault = vault_state_account().writable();
+        let mut user = user_balance_account(0).writable();
+        let mut owner = owner_account();
+        let max = MAX_DEPOSIT.to_le_bytes();
+
+        // Exactly MAX_USER_BALANCE / MAX_DEPOSIT maximum deposits fit
+        for _ in 0..MAX_USER_BALANCE / MAX_DEPOSIT {
+            deposit(&ID, &[vault.view(), user.view(), owner.view()], &max).unwrap();
+        }
+        assert_eq!(UserBalance::try_from_slice(user.data()).unwrap().balance, MAX_USER_BALANCE);
+
+        // One more - even a single lamport - is rejected with the specific error
+        let result = deposit(&ID, &[vault.view(), user.view(), owner.view()], &max);
+        assert!(matches!(result, Err(ProgramError::Custom(10))));
+        let result = deposit(&ID, &[vault.view(), user.view(), owner.view()], &1u64.to_le_bytes());
+        assert!(matches!(result, Err(ProgramError::Custom(10))));
+        assert_eq!(UserBalance::try_from_slice(user.data()).unwrap().balance, MAX_USER_BALANCE);
+    }
+
+    #[test]
+    fn test_check_max_balance() {
+        assert!(check_max_balance(MAX_USER_BALANCE - 5, 5).is_ok());
+        assert!(check_max_balance(MAX_USER_BALANCE - 5, 6).is_err());
+        // Balances pushed past the cap (e.g. by rewards) cannot underflow the check
+        assert!(check_max_balance(u64::MAX, 0).is_ok());
+        assert!(matches!(check_max_balance(u64::MAX, 1), Err(SecureError::ExceedsMaxBalance)));
+    }
+
+    #[test]
+    fn test_withdraw_rejects_read_only_user_balance() {
+        let amount = 40u64.to_le_bytes();
+        let mut owner = owner_account();
+
+        let mut user = user_balance_account(100);
+        let result = withdraw(&ID, &[user.view(), owner.view()], &amount);
+        assert!(matches!(result, Err(ProgramError::Custom(6))));
+        assert_eq!(UserBalance::try_from_slice(user.data()).unwrap().balance, 100);
+
+        let mut user = user_balance_account(100).writable();
+        withdraw(&ID, &[user.view(), owner.view()], &amount).unwrap();
+        assert_eq!(UserBalance::try_from_slice(user.data()).unwrap().balance, 60);
+    }
+
+    /// Same account data, but owned by some other program.
+    fn foreign(account: &MockAccountView, address: [u8; 32]) -> MockAccountView {
+        MockAccountView::new(
+            Address::new_from_array(address),
+            Address::default(),
+            0,
+            account.data(),
+        )
+        .writable()
+    }
+
+    #[test]
+    fn test_handlers_reject_foreign_owned_accounts() {
+        let amount = 10u64.to_le_bytes();
+        let mut owner = owner_account();
+        let clock = MockClock::new(1_000, 0);
+        let mut authority =
+            MockAccountView::new(Address::new_from_array([1u8; 32]), Address::default(), 0, &[])
+                .signer();
+
+        let mut vault = vault_state_account().writable();
+        let mut user = user_balance_account(100).writable();
+        let mut fake_vault = foreign(&vault, [8u8; 32]);
+        let mut fake_user = foreign(&user, [9u8; 32]);
+
+        let result = deposit(&ID, &[fake_vault.view(), user.view(), owner.view()], &amount);
+        assert_eq!(result, Err(ProgramError::IllegalOwner));
+        let result = deposit(&ID, &[vault.view(), fake_user.view(), owner.view()], &amount);
+        assert_eq!(result, Err(ProgramError::IllegalOwner));
+
+        let result = withdraw(&ID, &[fake_user.view(), owner.view()], &amount);
+        assert_eq!(result, Err(ProgramError::IllegalOwner));
+
+        let accounts = [vault.view(), fake_user.view(), authority.view()];
+        let result = calculate_rewards(&ID, &clock, &accounts, &1u64.to_le_bytes());
+        assert_eq!(result, Err(ProgramError::IllegalOwner));
+        let accounts = [fake_vault.view(), user.view(), authority.view()];
+        let result = calculate_rewards(&ID, &clock, &accounts, &1u64.to_le_bytes());
+        assert_eq!(result, Err(ProgramError::IllegalOwner));
+
+        // The look-alikes were never written
+        assert_eq!(UserBalance::try_from_slice(fake_user.data()).unwrap().balance, 100);
+        assert_eq!(VaultState::try_from_slice(fake_vault.data()).unwrap().total_deposits, 100);
+    }
+
+    /// Stand-in for `account` at `address`, still owned by this program so
+    /// only the denylist can reject it.
+    fn impostor(account: &MockAccountView, address: Address) -> MockAccountView {
+        MockAccountView::new(address, ID, 0, account.data()).writable()
+    }
+
+    #[test]
+    fn test_handlers_reject_program_and_sysvar_as_data_accounts() {
+        let amount = 10u64.to_le_bytes();
+        let mut owner = owner_account();
+        let clock = MockClock::new(1_000, 0);
+        let mut authority =
+            MockAccountView::new(Address::new_from_array([1u8; 32]), Address::default(), 0, &[])
+                .signer();
+
+        let mut vault = vault_state_account().writable();
+        let mut user = user_balance_account(100).writable();
+        let mut program_as_vault = impostor(&vault, ID);
+        let mut program_as_user = impostor(&user, ID);
+        let mut clock_as_user = impostor(&user, pinocchio_security_common::SYSVAR_IDS[0]);
+
+        let accounts = [program_as_vault.view(), user.view(), owner.view()];
+        assert_eq!(deposit(&ID, &accounts, &amount), Err(ProgramError::InvalidAccountData));
+        let accounts = [vault.view(), program_as_user.view(), owner.view()];
+        assert_eq!(deposit(&ID, &accounts, &amount), Err(ProgramError::InvalidAccountData));
+
+        let accounts = [clock_as_user.view(), owner.view()];
+        assert_eq!(withdraw(&ID, &accounts, &amount), Err(ProgramError::InvalidAccountData));
+
+        let accounts = [vault.view(), program_as_user.view(), authority.view()];
+        let result = calculate_rewards(&ID, &clock, &accounts, &1u64.to_le_bytes());
+        assert_eq!(result, Err(ProgramError::InvalidAccountData));
+
+        let accounts = [vault.view(), authority.view(), program_as_user.view()];
+        let result = distribute_rewards(&ID, &clock, &accounts, &1u64.to_le_bytes());
+        assert_eq!(result, Err(ProgramError::InvalidAccountData));
+
+        assert_eq!(UserBalance::try_from_slice(program_as_user.data()).unwrap().balance, 100);
+    }
+
+    // =========================================================================
+    // DRY RUN
+    // =========================================================================
+
+    fn amount_data(amount: u64, dry_run: u8) -> [u8; 9] {
+        let mut data = [dry_run; 9];
+        data[0..8].copy_from_slice(&amount.to_le_bytes());
+        data
+    }
+
+    #[test]
+    fn test_deposit_dry_run_projects_without_writing() {
+        let mut vault = vault_state_account().writable();
+        let mut user = user_balance_account(100).writable();
+        let mut owner = owner_account();
+        let (vault_before, user_before) = (account_hash(vault.data()), account_hash(user.data()));
+
+        let accounts = [vault.view(), user.view(), owner.view()];
+        let projection = process_deposit(&ID, &accounts, &amount_data(50, 1)).unwrap();
+        assert_eq!(projection, Projection { user_balance: 150, total_deposits: Some(150) });
+        assert_eq!(account_hash(vault.data()), vault_before);
+        assert_eq!(account_hash(user.data()), user_before);
+
+        // The committed call writes exactly what the dry run projected
+        let accounts = [vault.view(), user.view(), owner.view()];
+        assert_eq!(process_deposit(&ID, &accounts, &amount_data(50, 0)).unwrap(), projection);
+        assert_eq!(UserBalance::try_from_slice(user.data()).unwrap().balance, 150);
+        assert_eq!(VaultState::try_from_slice(vault.data()).unwrap().total_deposits, 150);
+    }
+
+    #[test]
+    fn test_withdraw_dry_run_validates_and_projects_without_writing() {
+        let mut user = user_balance_account(100).writable();
+        let mut owner = owner_account();
+        let before = account_hash(user.data());
+
+        let projection =
+            process_withdraw(&ID, &[user.view(), owner.view()], &amount_data(40, 1)).unwrap();
+        assert_eq!(projection, Projection { user_balance: 60, total_deposits: None });
+        assert_eq!(account_hash(user.data()), before);
+
+        // Validation still runs on a dry run
+        let result = process_withdraw(&ID, &[user.view(), owner.view()], &amount_data(101, 1));
+        assert_eq!(result, Err(CommonError::InsufficientBalance.into()));
+        let result = withdraw(&ID, &[user.view(), owner.view()], &amount_data(40, 2));
+        assert_eq!(result, Err(ProgramError::InvalidInstructionData));
+        assert_eq!(account_hash(user.data()), before);
+    }
+
+    // =========================================================================
+    // REWARD COOLDOWN
+    // =========================================================================
+
+    fn calculate_rewards_at(
+        clock: &MockClock,
+        vault: &mut MockAccountView,
+        user: &mut MockAccountView,
+    ) -> ProgramResult {
+        calculate_rewards_with_rate(clock, vault, user, 1)
+    }
+
+    fn calculate_rewards_with_rate(
+        clock: &MockClock,
+        vault: &mut MockAccountView,
+        user: &mut MockAccountView,
+        reward_rate: u64,
+    ) -> ProgramResult {
+        let mut authority =
+            MockAccountView::new(Address::new_from_array([1u8; 32]), Address::default(), 0, &[])
+                .signer();
+        calculate_rewards(
+            &ID,
+            clock,
+            &[vault.view(), user.view(), authority.view()],
+            &reward_rate.to_le_bytes(),
+        )
+    }
+
+    #[test]
+    fn test_calculate_rewards_throttled_within_interval() {
+        let mut vault = vault_state_account().writable();
+        let mut user = user_balance_account(100).writable();
+        let mut clock = MockClock::new(1_000, 0);
+
+        calculate_rewards_at(&clock, &mut vault, &mut user).unwrap();
+        let rewarded = UserBalance::try_from_slice(user.data()).unwrap();
+        assert_eq!((rewarded.balance, rewarded.last_reward_slot), (200, 1_000));
+
+        // One slot short of the interval is still too soon
+        clock.advance_slots(REWARD_INTERVAL - 1);
+        let result = calculate_rewards_at(&clock, &mut vault, &mut user);
+        assert!(matches!(result, Err(ProgramError::Custom(7))));
+
+        // Nothing is credited on rejection
+        let after = UserBalance::try_from_slice(user.data()).unwrap();
+        assert_eq!((after.balance, after.last_reward_slot), (200, 1_000));
+        assert_eq!(VaultState::try_from_slice(vault.data()).unwrap().total_rewards, 100);
+    }
+
+    #[test]
+    fn test_calculate_rewards_allowed_after_interval() {
+        let mut vault = vault_state_account().writable();
+        let mut user = user_balance_account(100).writable();
+        let mut clock = MockClock::new(1_000, 0);
+
+        calculate_rewards_at(&clock, &mut vault, &mut user).unwrap();
+
+        clock.advance_slots(REWARD_INTERVAL);
+        calculate_rewards_at(&clock, &mut vault, &mut user).unwrap();
+
+        let after = UserBalance::try_from_slice(user.data()).unwrap();
+        assert_eq!(after.balance, 400);
+        assert_eq!(after.last_reward_slot, 1_000 + REWARD_INTERVAL);
+    }
+
+    #[test]
+    fn test_calculate_rewards_overflow_writes_nothing() {
+        let clock = MockClock::new(1_000, 0);
+
+        // total_rewards overflows
+        let state = VaultState {
+            authority: Address::new_from_array([1u8; 32]),
+            total_deposits: 100,
+            user_count: 1,
+            total_rewards: u64::MAX - 10,
+            bump: 255,
+            max_users: 0,
+        };
+        let mut data = [0u8; VAULT_STATE_SIZE];
+        state.serialize(&mut data).unwrap();
+        let mut vault =
+            MockAccountView::new(Address::new_from_array([4u8; 32]), ID, 0, &data).writable();
+        let mut user = user_balance_account(100).writable();
+        let (vault_before, user_before) = (vault.data().to_vec(), user.data().to_vec());
+
+        let result = calculate_rewards_at(&clock, &mut vault, &mut user);
+        assert!(matches!(result, Err(ProgramError::Custom(0))));
+        assert_eq!(vault.data(), &vault_before[..]);
+        assert_eq!(user.data(), &user_before[..]);
+
+        // The user balance overflows after total_rewards would have been credited.
+        // The reward equals the balance: rate 1, or rate 2 halved by tiered_rewards.
+        let rate = if cfg!(feature = "tiered_rewards") { 2 } else { 1 };
+        let mut vault = vault_state_account().writable();
+        let mut user = user_balance_account(u64::MAX / 2 + 1).writable();
+        let (vault_before, user_before) = (vault.data().to_vec(), user.data().to_vec());
+
+        let result = calculate_rewards_with_rate(&clock, &mut vault, &mut user, rate);
+        assert!(matches!(result, Err(ProgramError::Custom(0))));
+        assert_eq!(vault.data(), &vault_before[..]);
+        assert_eq!(user.data(), &user_before[..]);
+    }
+
+    // =========================================================================
+    // REWARD BOUNDS
+    // =========================================================================
+
+    /// The reward `compute_reward` should return, computed without overflow,
+    /// or `None` if it does not fit in a u64.
+    fn exact_reward(balance: u64, reward_rate: u64) -> Option<u64> {
+        #[cfg(feature = "tiered_rewards")]
+        let reward_rate = effective_rate(balance, reward_rate);
+        u64::try_from(u128::from(balance) * u128::from(reward_rate)).ok()
+    }
+
+    #[test]
+    fn test_compute_reward_never_wraps_at_boundaries() {
+        // The documented worst cases fit
+        assert_eq!(u128::from(MAX_DEPOSIT) * u128::from(MAX_REWARD_RATE), 10u128.pow(16));
+        assert!(u128::from(MAX_USER_BALANCE) * u128::from(MAX_REWARD_RATE) <= u128::from(u64::MAX));
+
+        let overflow_edge = u64::MAX / MAX_REWARD_RATE;
+        for balance in
+            [0, 1, MAX_DEPOSIT, MAX_USER_BALANCE, overflow_edge, overflow_edge + 1, u64::MAX]
+        {
+            for reward_rate in [0, 1, MAX_REWARD_RATE] {
+                // Either the exact product, or ArithmeticOverflow - never a wrapped value
+                match (compute_reward(balance, reward_rate), exact_reward(balance, reward_rate)) {
+                    (Ok(reward), Some(exact)) => assert_eq!(reward, exact),
+                    (Err(SecureError::ArithmeticOverflow), None) => {}
+                    _ => panic!("compute_reward({balance}, {reward_rate}) disagrees with u128"),
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_max_rate_reward_on_lifetime_cap_balance() {
+        let mut vault = vault_state_account().writable();
+        let mut user = user_balance_account(0).writable();
+        let mut owner = owner_account();
+        let max = MAX_DEPOSIT.to_le_bytes();
+
+        // Worst-case balance: maximum deposits up to the lifetime cap
+        for _ in 0..MAX_USER_BALANCE / MAX_DEPOSIT {
+            deposit(&ID, &[vault.view(), user.view(), owner.view()], &max).unwrap();
+        }
+        assert_eq!(UserBalance::try_from_slice(user.data()).unwrap().balance, MAX_USER_BALANCE);
+
+        // The first max-rate reward on it stays in range
+        let mut clock = MockClock::new(1_000, 0);
+        calculate_rewards_with_rate(&clock, &mut vault, &mut user, MAX_REWARD_RATE).unwrap();
+        let reward = exact_reward(MAX_USER_BALANCE, MAX_REWARD_RATE).unwrap();
+        let rewarded = UserBalance::try_from_slice(user.data()).unwrap();
+        assert_eq!(rewarded.balance, MAX_USER_BALANCE + reward);
+        assert_eq!(VaultState::try_from_slice(vault.data()).unwrap().total_rewards, reward);
+
+        // Compounding again would exceed u64, and fails cleanly instead of wrapping
+        clock.advance_slots(REWARD_INTERVAL);
+        let (vault_before, user_before) = (vault.data().to_vec(), user.data().to_vec());
+        let result = calculate_rewards_with_rate(&clock, &mut vault, &mut user, MAX_REWARD_RATE);
+        assert!(matches!(result, Err(ProgramError::Custom(0))));
+        assert_eq!(vault.data(), &vault_before[..]);
+        assert_eq!(user.data(), &user_before[..]);
+    }
+
+    #[test]
+    fn test_calculate_rewards_rejects_authority_as_user_balance() {
+        let clock = MockClock::new(1_000, 0);
+
+        // A program-owned balance record sitting at the authority's address
+        let balance_data = user_balance_account(100).data().to_vec();
+        let authority_key = Address::new_from_array([1u8; 32]);
+        let mut user = MockAccountView::new(authority_key, ID, 0, &balance_data).writable();
+        let mut vault = vault_state_account().writable();
+        let vault_before = vault.data().to_vec();
+
+        let result = calculate_rewards_at(&clock, &mut vault, &mut user);
+        assert_eq!(result, Err(SecureError::DuplicateAccount.into()));
+        assert_eq!(vault.data(), &vault_before[..]);
+        assert_eq!(user.data(), &balance_data[..]);
+
+        // The vault passed as its own user balance is rejected the same way
+        let mut aliased = vault.clone();
+        let result = calculate_rewards_at(&clock, &mut vault, &mut aliased);
+        assert_eq!(result, Err(SecureError::DuplicateAccount.into()));
+        assert_eq!(vault.data(), &vault_before[..]);
+    }
+
+    // =========================================================================
+    // BATCH REWARD DISTRIBUTION
+    // =========================================================================
+
+    fn batch_user(index: u8, balance: u64) -> MockAccountView {
+        let user = UserBalance {
+            owner: Address::new_from_array([index; 32]),
+            balance,
+            deposits: balance,
+            withdrawals: 0,
+            bump: 254,
+            last_reward_slot: 0,
+            min_reserve: 0,
+        };
+        let mut data = [0u8; USER_BALANCE_SIZE];
+        user.serialize(&mut data).unwrap();
+        MockAccountView::new(Address::new_from_array([index + 100; 32]), ID, 0, &data).writable()
+    }
+
+    fn distribute_at(
+        clock: &MockClock,
+        vault: &mut MockAccountView,
+        users: &mut [MockAccountView],
+        reward_rate: u64,
+    ) -> ProgramResult {
+        let mut authority =
+            MockAccountView::new(Address::new_from_array([1u8; 32]), Address::default(), 0, &[])
+                .signer();
+        let mut accounts = vec![vault.view(), authority.view()];
+        accounts.extend(users.iter_mut().map(|u| u.view()));
+        distribute_rewards(&ID, clock, &accounts, &reward_rate.to_le_bytes())
+    }
+
+    #[test]
+    fn test_distribute_rewards_credits_every_user() {
+        let mut vault = vault_state_account().writable();
+        let mut users = [batch_user(10, 100), batch_user(11, 200), batch_user(12, 300)];
+        let clock = MockClock::new(1_000, 0);
+
+        distribute_at(&clock, &mut vault, &mut users, 2).unwrap();
+
+        let balances: Vec<u64> =
+            users.iter().map(|u| UserBalance::try_from_slice(u.data()).unwrap().balance).collect();
+        assert_eq!(balances, [300, 600, 900]);
+        assert_eq!(VaultState::try_from_slice(vault.data()).unwrap().total_rewards, 1_200);
+    }
+
+    #[test]
+    fn test_distribute_rewards_overflow_updates_no_one() {
+        let mut vault = vault_state_account().writable();
+        // The middle user's reward overflows u64
+        let mut users = [batch_user(10, 100), batch_user(11, u64::MAX / 2), batch_user(12, 300)];
+        let before: Vec<Vec<u8>> = users.iter().map(|u| u.data().to_vec()).collect();
+        let vault_before = vault.data().to_vec();
+        let clock = MockClock::new(1_000, 0);
+
+        // Rate 10 overflows at the full rate and at the tiered_rewards half rate
+        let result = distribute_at(&clock, &mut vault, &mut users, 10);
+        assert!(matches!(result, Err(ProgramError::Custom(0))));
+
+        // Not even the first user, who was processed before the overflow
+        for (user, before) in users.iter().zip(before.iter()) {
+            assert_eq!(user.data(), &before[..]);
+        }
+        assert_eq!(vault.data(), &vault_before[..]);
+    }
+
+    #[test]
+    fn test_distribute_rewards_rejects_bad_batches() {
+        let clock = MockClock::new(1_000, 0);
+        let mut vault = vault_state_account().writable();
+
+        let result = distribute_at(&clock, &mut vault, &mut [], 1);
+        assert!(matches!(result, Err(ProgramError::Custom(8))));
+
+        let mut too_many: Vec<MockAccountView> =
+            (0..=MAX_DISTRIBUTION_USERS as u8).map(|i| batch_user(i, 1)).collect();
+        let before: Vec<Vec<u8>> = too_many.iter().map(|u| u.data().to_vec()).collect();
+        let result = distribute_at(&clock, &mut vault, &mut too_many, 1);
+        assert_eq!(result, Err(CommonError::BatchTooLarge.into()));
+        for (user, before) in too_many.iter().zip(before.iter()) {
+            assert_eq!(user.data(), &before[..]);
+        }
+
+        let mut duplicated = [batch_user(10, 100), batch_user(10, 100)];
+        let result = distribute_at(&clock, &mut vault, &mut duplicated, 1);
+        assert!(matches!(result, Err(ProgramError::Custom(9))));
+        assert_eq!(VaultState::try_from_slice(vault.data()).unwrap().total_rewards, 0);
+    }
+
+    // =========================================================================
+    // DEPOSIT/WITHDRAW SYMMETRY MODEL TEST
+    // =========================================================================
+
+    /// Number of randomized deposit/withdraw steps in the model test.
+    const MODEL_STEPS: usize = 1_000;
+
+    /// Number of users sharing the vault in the model test.
+    const MODEL_USERS: usize = 4;
+
+    /// Runs `deposit` or `withdraw` of `amount` through `process_instruction`,
+    /// the program's real entry point.
+    fn run_amount_ix(accounts: &[AccountView], discriminator: u8, amount: u64) -> ProgramResult {
+        let mut data = [discriminator; 9];
+        data[1..9].copy_from_slice(&amount.to_le_bytes());
+        process_instruction(&ID, accounts, &data)
+    }
+
+    /// `total_deposits` is a lifetime counter (withdraw does not touch the vault),
+    /// so the symmetry invariant is: sum(balances) == total_deposits - sum(withdrawals).
+    fn assert_symmetry_invariant(vault: &MockAccountView, users: &[MockAccountView], step: usize) {
+        let vault_state = VaultState::try_from_slice(vault.data()).unwrap();
+
+        let mut sum_balances: u128 = 0;
+        let mut sum_deposits: u128 = 0;
+        let mut sum_withdrawals: u128 = 0;
+        for user in users {
+            let user = UserBalance::try_from_slice(user.data()).unwrap();
+            assert_eq!(
+                user.balance as u128,
+                user.deposits as u128 - user.withdrawals as u128,
+                "step {}: user balance drifted from deposits - withdrawals",
+                step
+            );
+            sum_balances += user.balance as u128;
+            sum_deposits += user.deposits as u128;
+            sum_withdrawals += user.withdrawals as u128;
+        }
+
+        assert_eq!(
+            sum_deposits, vault_state.total_deposits as u128,
+            "step {}: sum(user deposits) != vault total_deposits",
+            step
+        );
+        assert_eq!(
+            sum_balances,
+            vault_state.total_deposits as u128 - sum_withdrawals,
+            "step {}: sum(balances) != total_deposits - sum(withdrawals)",
+            step
+        );
+    }
+
+    #[test]
+    fn test_deposit_withdraw_symmetry_randomized() {
+        let mut rng = XorShift64::new(0x5EED_0003_D00D_F00D);
+
+        let mut vault_data = [0u8; VAULT_STATE_SIZE];
+        empty_vault(0).serialize(&mut vault_data).unwrap();
+        let mut vault =
+            MockAccountView::new(Address::new_from_array([4u8; 32]), ID, 0, &vault_data).writable();
+
+        let owner_key = |i: usize| Address::new_from_array([i as u8 + 10; 32]);
+        let mut users: Vec<MockAccountView> = (0..MODEL_USERS)
+            .map(|i| {
+                let user = UserBalance {
+                    owner: owner_key(i),
+                    balance: 0,
+                    deposits: 0,
+                    withdrawals: 0,
+                    bump: 255,
+                    last_reward_slot: 0,
+                    min_reserve: 0,
+                };
+                let mut data = [0u8; USER_BALANCE_SIZE];
+                user.serialize(&mut data).unwrap();
+                MockAccountView::new(Address::new_from_array([i as u8 + 20; 32]), ID, 0, &data)
+                    .writable()
+            })
+            .collect();
+        let mut owners: Vec<MockAccountView> = (0..MODEL_USERS)
+            .map(|i| MockAccountView::new(owner_key(i), Address::default(), 0, &[]).signer())
+            .collect();
+
+        let (mut accepted, mut rejected) = (0usize, 0usize);
+        for step in 0..MODEL_STEPS {
+            let user_index = rng.below(MODEL_USERS as u64) as usize;
+            let vault_before = vault.data().to_vec();
+            let user_before = users[user_index].data().to_vec();
+            let (user, owner) = (&mut users[user_index], &mut owners[user_index]);
+
+            let result = if rng.below(2) == 0 {
+                // Occasionally exceed MAX_DEPOSIT to exercise the rejection path
+                let amount = rng.below(MAX_DEPOSIT + MAX_DEPOSIT / 10);
+                run_amount_ix(
+                    &[vault.view(), user.view(), owner.view()],
+                    DEPOSIT_DISCRIMINATOR,
+                    amount,
+                )
+            } else {
+                // Occasionally exceed the balance to exercise the rejection path
+                let balance = UserBalance::try_from_slice(user.data()).unwrap().balance;
+                let amount = rng.below(balance + balance / 4 + 2);
+                run_amount_ix(&[user.view(), owner.view()], WITHDRAW_DISCRIMINATOR, amount)
+            };
+
+            match result {
+                Ok(()) => accepted += 1,
+                Err(_) => {
+                    // Rejected operations must leave state untouched
+                    assert_eq!(
+                        vault.data(),
+                        &vault_before[..],
+                        "step {}: rejected op mutated vault",
+                        step
+                    );
+                    assert_eq!(
+                        users[user_index].data(),
+                        &user_before[..],
+                        "step {}: rejected op mutated user",
+                        step
+                    );
+                    rejected += 1;
+                }
+            }
+
+            assert_symmetry_invariant(&vault, &users, step);
+        }
+
+        // Both paths must actually have been exercised
+        assert!(accepted > 0 && rejected > 0);
+    }
+
+    #[test]
+    fn test_every_discriminator_maps_to_one_instruction() {
+        let mut assigned = Vec::new();
+        for discriminator in 0..=u8::MAX {
+            if let Ok(instruction) = Instruction::try_from(discriminator) {
+                // Round-trips to the discriminant it was decoded from
+                assert_eq!(instruction as u8, discriminator);
+                assigned.push(instruction);
+            }
+        }
+
+        // Exactly the listed instructions decode, each from one discriminator
+        assert_eq!(assigned, Instruction::ALL);
+        assert!(assigned.iter().all(|i| (*i as u8) <= CORE_DISCRIMINATOR_END));
+
+        // The extension range is reserved and rejected upstream
+        for discriminator in EXTENSION_DISCRIMINATOR_START..=u8::MAX {
+            assert_eq!(
+                process_instruction(&ID, &[], &[discriminator]),
+                Err(ProgramError::InvalidInstructionData)
+            );
+        }
+    }
+
+    // =========================================================================
+    // RE-INITIALIZATION
+    // =========================================================================
+
+    #[test]
+    fn test_initialize_vault_rejects_reinitialization() {
+        // vault_state_account() is a live vault with deposits and a user
+        let mut vault = vault_state_account().writable();
+        let mut attacker =
+            MockAccountView::new(Address::new_from_array([9u8; 32]), Address::default(), 0, &[])
+                .signer();
+        assert_reinit_rejected(&mut vault, |vault| {
+            initialize_vault(&ID, &[vault.view(), attacker.view()], &[255])
+        });
+    }
+
+    #[test]
+    fn test_initialize_vault_max_users_field() {
+        let run = |data: &[u8]| {
+            let mut vault = MockAccountView::new(
+                Address::new_from_array([4u8; 32]),
+                ID,
+                0,
+                &[0u8; VAULT_STATE_SIZE],
+            )
+            .writable();
+            let mut authority = owner_account();
+            initialize_vault(&ID, &[vault.view(), authority.view()], data)
+                .map(|()| VaultState::try_from_slice(vault.data()).unwrap().max_users)
+        };
+
+        // Absent means unlimited
+        assert_eq!(run(&[255]), Ok(0));
+        assert_eq!(run(&[255, 10, 0, 0, 0, 0, 0, 0, 0]), Ok(10));
+
+        // Present but short is malformed, not unlimited
+        assert_eq!(run(&[255, 10]), Err(ProgramError::InvalidInstructionData));
+        assert_eq!(run(&[255, 10, 0, 0, 0, 0, 0, 0]), Err(ProgramError::InvalidInstructionData));
+    }
+
+    #[test]
+    fn test_create_user_rejects_reinitialization() {
+        let mut vault = vault_state_account().writable();
+        let vault_before = vault.data().to_vec();
+        let mut user = user_balance_account(100).writable();
+        let mut owner = owner_account();
+
+        assert_reinit_rejected(&mut user, |user| {
+            create_user(&ID, &[vault.view(), user.view(), owner.view()], &[254])
+        });
+        // The owner is not counted as a second user either
+        assert_eq!(vault.data(), &vault_before[..]);
     }
 }
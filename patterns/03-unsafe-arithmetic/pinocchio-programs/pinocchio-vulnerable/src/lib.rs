@@ -19,8 +19,19 @@
 #![allow(unexpected_cfgs)]
 
 use pinocchio::{entrypoint, error::ProgramError, AccountView, Address, ProgramResult};
+
+// Logging shim: `log!` forwards to `solana_program_log` with the default `logs`
+// feature and compiles to nothing under `--no-default-features`.
+#[cfg(feature = "logs")]
 use solana_program_log::log;
 
+#[cfg(not(feature = "logs"))]
+macro_rules! log {
+    ($($arg:tt)*) => {{
+        let _ = core::format_args!($($arg)*);
+    }};
+}
+
 // =============================================================================
 // PROGRAM ID
 // =============================================================================
@@ -79,6 +90,7 @@ pub const CALCULATE_REWARDS_DISCRIMINATOR: u8 = 4;
 ///
 /// This struct is identical to the Anchor version but uses manual serialization.
 /// In Pinocchio, there's no Anchor discriminator (8 bytes saved).
+#[derive(Debug, Clone, PartialEq)]
 pub struct VaultState {
     /// Authority who controls the vault (32 bytes)
     pub authority: Address,
@@ -151,6 +163,7 @@ impl VaultState {
 }
 
 /// User balance account - tracks individual user's balance.
+#[derive(Debug, Clone, PartialEq)]
 pub struct UserBalance {
     /// User who owns this balance (32 bytes)
     pub owner: Address,
@@ -599,11 +612,7 @@ mod tests {
         vault.serialize(&mut buffer).unwrap();
 
         let deserialized = VaultState::try_from_slice(&buffer).unwrap();
-        assert_eq!(deserialized.authority, vault.authority);
-        assert_eq!(deserialized.total_deposits, vault.total_deposits);
-        assert_eq!(deserialized.user_count, vault.user_count);
-        assert_eq!(deserialized.total_rewards, vault.total_rewards);
-        assert_eq!(deserialized.bump, vault.bump);
+        assert_eq!(deserialized, vault);
     }
 
     #[test]
@@ -620,11 +629,7 @@ mod tests {
         user.serialize(&mut buffer).unwrap();
 
         let deserialized = UserBalance::try_from_slice(&buffer).unwrap();
-        assert_eq!(deserialized.owner, user.owner);
-        assert_eq!(deserialized.balance, user.balance);
-        assert_eq!(deserialized.deposits, user.deposits);
-        assert_eq!(deserialized.withdrawals, user.withdrawals);
-        assert_eq!(deserialized.bump, user.bump);
+        assert_eq!(deserialized, user);
     }
 
     #[test]
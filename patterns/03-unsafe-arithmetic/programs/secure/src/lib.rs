@@ -11,8 +11,8 @@ declare_id!("9tncVxSh8pPnfwrzStTwnmaNd9Zi8PoQZugTBtqUV1ji");
 /// Anchor discriminator size (8 bytes)
 pub const DISCRIMINATOR_SIZE: usize = 8;
 
-/// VaultState account size: 8 + 32 + 8 + 8 + 8 + 1 = 65 bytes
-pub const VAULT_STATE_SIZE: usize = DISCRIMINATOR_SIZE + 32 + 8 + 8 + 8 + 1;
+/// VaultState account size: 8 + 32 + 8 + 8 + 8 + 1 + 8 = 73 bytes
+pub const VAULT_STATE_SIZE: usize = DISCRIMINATOR_SIZE + 32 + 8 + 8 + 8 + 1 + 8;
 
 /// UserBalance account size: 8 + 32 + 8 + 8 + 8 + 1 = 65 bytes
 pub const USER_BALANCE_SIZE: usize = DISCRIMINATOR_SIZE + 32 + 8 + 8 + 8 + 1;
@@ -55,15 +55,19 @@ pub mod secure_unsafe_arithmetic {
     use super::*;
 
     /// Initialize the vault with the given authority
-    pub fn initialize_vault(ctx: Context<InitializeVault>) -> Result<()> {
+    ///
+    /// `max_users` caps how many user accounts may register (0 = unlimited).
+    pub fn initialize_vault(ctx: Context<InitializeVault>, max_users: u64) -> Result<()> {
         let vault = &mut ctx.accounts.vault_state;
         vault.authority = ctx.accounts.authority.key();
         vault.total_deposits = 0;
         vault.user_count = 0;
         vault.total_rewards = 0;
         vault.bump = ctx.bumps.vault_state;
+        vault.max_users = max_users;
 
         msg!("Vault initialized with authority: {}", vault.authority);
+        msg!("Max users: {} (0 = unlimited)", vault.max_users);
         Ok(())
     }
 
@@ -77,6 +81,12 @@ pub mod secure_unsafe_arithmetic {
         user_balance.bump = ctx.bumps.user_balance;
 
         let vault = &mut ctx.accounts.vault_state;
+        // SECURITY: Enforce the vault's user capacity before registering (0 = unlimited)
+        require!(
+            vault.max_users == 0 || vault.user_count < vault.max_users,
+            ErrorCode::UserCapExceeded
+        );
+
         // SECURITY: Use checked_add for user count increment
         vault.user_count = vault.user_count.checked_add(1).ok_or(ErrorCode::ArithmeticOverflow)?;
 
@@ -201,6 +211,8 @@ pub struct VaultState {
     pub total_rewards: u64,
     /// PDA bump seed (1 byte)
     pub bump: u8,
+    /// Maximum number of users allowed to register, 0 = unlimited (8 bytes)
+    pub max_users: u64,
 }
 
 /// User balance account - tracks individual user's balance
@@ -244,6 +256,10 @@ pub enum ErrorCode {
     /// Reward rate exceeds maximum allowed
     #[msg("Reward rate exceeds maximum allowed")]
     ExceedsMaxRewardRate,
+
+    /// Vault has reached its configured user capacity
+    #[msg("Vault user capacity exceeded")]
+    UserCapExceeded,
 }
 
 // ============================================================================
@@ -130,7 +130,7 @@ describe("Edge Case & Boundary Condition Tests", () => {
 
     try {
       await secureProgram.methods
-        .initializeVault()
+        .initializeVault(new BN(0))
         .accounts({
           authority: deployerKeypair.publicKey,
           vaultState: secureVaultPda,
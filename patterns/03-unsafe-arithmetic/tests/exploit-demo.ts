@@ -414,7 +414,7 @@ describe("Unsafe Arithmetic Pattern", () => {
       console.log("\n    Initializing secure program vault...");
       try {
         await secureProgram.methods
-          .initializeVault()
+          .initializeVault(new BN(0))
           .accounts({
             authority: deployerKeypair.publicKey,
             vaultState: secureVaultPda,
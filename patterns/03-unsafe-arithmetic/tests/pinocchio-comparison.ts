@@ -52,7 +52,9 @@ const CALCULATE_REWARDS_DISCRIMINATOR = 4;
 
 // Account sizes (no Anchor discriminator - 8 bytes less)
 const VAULT_STATE_SIZE = 57; // 32 + 8 + 8 + 8 + 1
+const SECURE_VAULT_STATE_SIZE = 65; // 32 + 8 + 8 + 8 + 1 + 8 (max_users)
 const USER_BALANCE_SIZE = 57; // 32 + 8 + 8 + 8 + 1
+const SECURE_USER_BALANCE_SIZE = 73; // 32 + 8 + 8 + 8 + 1 + 8 (last_reward_slot) + 8 (min_reserve)
 
 // PDA seeds
 const VAULT_SEED = Buffer.from("vault");
@@ -93,6 +95,18 @@ function buildInitializeVaultInstructionData(bump: number): Buffer {
   return data;
 }
 
+/**
+ * Build instruction data for the secure program's initialize_vault with a user cap.
+ * Format: [discriminator (1 byte)] [bump (1 byte)] [max_users (8 bytes, little-endian)]
+ */
+function buildSecureInitializeVaultInstructionData(bump: number, maxUsers: BN): Buffer {
+  const data = Buffer.alloc(10);
+  data.writeUInt8(INITIALIZE_VAULT_DISCRIMINATOR, 0);
+  data.writeUInt8(bump, 1);
+  data.writeBigUInt64LE(BigInt(maxUsers.toString()), 2);
+  return data;
+}
+
 /**
  * Build instruction data for create_user.
  * Format: [discriminator (1 byte)] [bump (1 byte)]
@@ -106,23 +120,29 @@ function buildCreateUserInstructionData(bump: number): Buffer {
 
 /**
  * Build instruction data for deposit.
- * Format: [discriminator (1 byte)] [amount (8 bytes, little-endian)]
+ * Format: [discriminator (1 byte)] [amount (8 bytes, little-endian)] [dry_run (1 byte, optional)]
  */
-function buildDepositInstructionData(amount: BN): Buffer {
-  const data = Buffer.alloc(9);
+function buildDepositInstructionData(amount: BN, dryRun = false): Buffer {
+  const data = Buffer.alloc(dryRun ? 10 : 9);
   data.writeUInt8(DEPOSIT_DISCRIMINATOR, 0);
   data.writeBigUInt64LE(BigInt(amount.toString()), 1);
+  if (dryRun) {
+    data.writeUInt8(1, 9);
+  }
   return data;
 }
 
 /**
  * Build instruction data for withdraw.
- * Format: [discriminator (1 byte)] [amount (8 bytes, little-endian)]
+ * Format: [discriminator (1 byte)] [amount (8 bytes, little-endian)] [dry_run (1 byte, optional)]
  */
-function buildWithdrawInstructionData(amount: BN): Buffer {
-  const data = Buffer.alloc(9);
+function buildWithdrawInstructionData(amount: BN, dryRun = false): Buffer {
+  const data = Buffer.alloc(dryRun ? 10 : 9);
   data.writeUInt8(WITHDRAW_DISCRIMINATOR, 0);
   data.writeBigUInt64LE(BigInt(amount.toString()), 1);
+  if (dryRun) {
+    data.writeUInt8(1, 9);
+  }
   return data;
 }
 
@@ -541,7 +561,7 @@ describe("Pinocchio vs Anchor Unsafe Arithmetic Comparison", () => {
       console.log("    Initializing Anchor secure vault...");
       try {
         await secureProgram.methods
-          .initializeVault()
+          .initializeVault(new BN(0))
           .accounts({
             authority: deployerKeypair.publicKey,
             vaultState: anchorSecureVaultPda,
@@ -686,7 +706,7 @@ describe("Pinocchio vs Anchor Unsafe Arithmetic Comparison", () => {
         connection,
         deployerKeypair,
         PINOCCHIO_SECURE_PROGRAM_ID,
-        VAULT_STATE_SIZE
+        SECURE_VAULT_STATE_SIZE
       );
 
       // Initialize vault
@@ -706,7 +726,7 @@ describe("Pinocchio vs Anchor Unsafe Arithmetic Comparison", () => {
         connection,
         pinocchioSecureUserKeypair,
         PINOCCHIO_SECURE_PROGRAM_ID,
-        USER_BALANCE_SIZE
+        SECURE_USER_BALANCE_SIZE
       );
 
       // Initialize user
@@ -788,8 +808,9 @@ describe("Pinocchio vs Anchor Unsafe Arithmetic Comparison", () => {
         await sendAndConfirmTransaction(connection, new Transaction().add(withdrawIx), [pinocchioSecureUserKeypair]);
         attackSucceeded = true;
       } catch (err: any) {
-        // Error code 2 = InsufficientBalance
+        // 0x2001 = CommonError::InsufficientBalance (shared across patterns)
         console.log("      Transaction rejected (as expected)");
+        expect(err.message).to.include("0x2001");
       }
 
       expect(attackSucceeded).to.be.false;
@@ -829,6 +850,81 @@ describe("Pinocchio vs Anchor Unsafe Arithmetic Comparison", () => {
       console.log("      \x1b[32m✓ PINOCCHIO SECURE: Excessive reward rate blocked\x1b[0m");
     });
 
+    it("✓ PINOCCHIO SECURE: Rejects create_user beyond max_users capacity", async () => {
+      console.log("\n      [Pinocchio] User registration capped by max_users");
+
+      // Fresh vault with room for exactly one user
+      const cappedVaultAccount = await createFundedAccount(
+        connection,
+        deployerKeypair,
+        PINOCCHIO_SECURE_PROGRAM_ID,
+        SECURE_VAULT_STATE_SIZE
+      );
+
+      const initIx = new TransactionInstruction({
+        programId: PINOCCHIO_SECURE_PROGRAM_ID,
+        keys: [
+          { pubkey: cappedVaultAccount.publicKey, isSigner: false, isWritable: true },
+          { pubkey: deployerKeypair.publicKey, isSigner: true, isWritable: false },
+        ],
+        data: buildSecureInitializeVaultInstructionData(0, new BN(1)),
+      });
+      await sendAndConfirmTransaction(connection, new Transaction().add(initIx), [deployerKeypair]);
+
+      const createUserIx = (userBalance: PublicKey, owner: PublicKey) =>
+        new TransactionInstruction({
+          programId: PINOCCHIO_SECURE_PROGRAM_ID,
+          keys: [
+            { pubkey: cappedVaultAccount.publicKey, isSigner: false, isWritable: true },
+            { pubkey: userBalance, isSigner: false, isWritable: true },
+            { pubkey: owner, isSigner: true, isWritable: false },
+          ],
+          data: buildCreateUserInstructionData(0),
+        });
+
+      // First user fills the vault to capacity
+      const firstBalance = await createFundedAccount(
+        connection,
+        deployerKeypair,
+        PINOCCHIO_SECURE_PROGRAM_ID,
+        SECURE_USER_BALANCE_SIZE
+      );
+      await sendAndConfirmTransaction(
+        connection,
+        new Transaction().add(createUserIx(firstBalance.publicKey, deployerKeypair.publicKey)),
+        [deployerKeypair]
+      );
+
+      // Second user must be rejected
+      const secondBalance = await createFundedAccount(
+        connection,
+        attackerKeypair,
+        PINOCCHIO_SECURE_PROGRAM_ID,
+        SECURE_USER_BALANCE_SIZE
+      );
+      let attackSucceeded = false;
+
+      try {
+        await sendAndConfirmTransaction(
+          connection,
+          new Transaction().add(createUserIx(secondBalance.publicKey, attackerKeypair.publicKey)),
+          [attackerKeypair]
+        );
+        attackSucceeded = true;
+      } catch (err: any) {
+        // Error code 5 = UserCapExceeded
+        console.log("      Transaction rejected (as expected)");
+      }
+
+      expect(attackSucceeded).to.be.false;
+
+      const accountInfo = await connection.getAccountInfo(cappedVaultAccount.publicKey);
+      const vaultState = decodeVaultState(accountInfo!.data);
+      expect(vaultState.userCount.toString()).to.equal("1");
+
+      console.log("      \x1b[32m✓ PINOCCHIO SECURE: User cap enforced\x1b[0m");
+    });
+
     describe("Authorized Operations", () => {
       it("✓ PINOCCHIO SECURE: Allows valid deposit within limits", async () => {
         console.log("\n      [Pinocchio] Valid deposit accepted");
@@ -842,7 +938,7 @@ describe("Pinocchio vs Anchor Unsafe Arithmetic Comparison", () => {
           connection,
           validUserKeypair,
           PINOCCHIO_SECURE_PROGRAM_ID,
-          USER_BALANCE_SIZE
+          SECURE_USER_BALANCE_SIZE
         );
 
         // Create user
@@ -908,6 +1004,40 @@ describe("Pinocchio vs Anchor Unsafe Arithmetic Comparison", () => {
         console.log(`      Balance after: ${userBalanceAfter.balance.toString()}`);
         console.log("      \x1b[32m✓ SUCCESS: Valid reward calculation accepted\x1b[0m");
       });
+
+      it("✓ PINOCCHIO SECURE: Throttles back-to-back reward calculations", async () => {
+        console.log("\n      [Pinocchio] Second reward within REWARD_INTERVAL rejected");
+
+        const rewardsIx = new TransactionInstruction({
+          programId: PINOCCHIO_SECURE_PROGRAM_ID,
+          keys: [
+            { pubkey: pinocchioSecureVaultAccount.publicKey, isSigner: false, isWritable: true },
+            { pubkey: pinocchioSecureUserBalanceAccount.publicKey, isSigner: false, isWritable: true },
+            { pubkey: deployerKeypair.publicKey, isSigner: true, isWritable: false },
+          ],
+          data: buildCalculateRewardsInstructionData(new BN(100)),
+        });
+
+        const balanceBefore = await connection.getAccountInfo(pinocchioSecureUserBalanceAccount.publicKey);
+        let farmingSucceeded = false;
+
+        try {
+          await sendAndConfirmTransaction(connection, new Transaction().add(rewardsIx), [deployerKeypair]);
+          farmingSucceeded = true;
+        } catch (err: any) {
+          // Error code 7 = RewardTooSoon
+          console.log("      Transaction rejected (as expected)");
+        }
+
+        expect(farmingSucceeded).to.be.false;
+
+        const balanceAfter = await connection.getAccountInfo(pinocchioSecureUserBalanceAccount.publicKey);
+        expect(decodeUserBalance(balanceAfter!.data).balance.toString()).to.equal(
+          decodeUserBalance(balanceBefore!.data).balance.toString()
+        );
+
+        console.log("      \x1b[32m✓ SUCCESS: Reward farming blocked by cooldown\x1b[0m");
+      });
     });
   });
 
@@ -15,8 +15,19 @@ use pinocchio::{
     instruction::{InstructionAccount, InstructionView},
     AccountView, Address, ProgramResult,
 };
+
+// Logging shim: `log!` forwards to `solana_program_log` with the default `logs`
+// feature and compiles to nothing under `--no-default-features`.
+#[cfg(feature = "logs")]
 use solana_program_log::log;
 
+#[cfg(not(feature = "logs"))]
+macro_rules! log {
+    ($($arg:tt)*) => {{
+        let _ = core::format_args!($($arg)*);
+    }};
+}
+
 // =============================================================================
 // PROGRAM ID
 // =============================================================================
@@ -56,6 +67,7 @@ pub const RESET_ATTACK_DISCRIMINATOR: u8 = 2;
 // =============================================================================
 
 /// Tracks attack state to prevent infinite recursion
+#[derive(Debug, Clone, PartialEq)]
 pub struct AttackState {
     pub reentered: bool,
     pub attack_count: u64,
@@ -262,8 +274,6 @@ mod tests {
         attack_state.serialize(&mut buffer).unwrap();
 
         let deserialized = AttackState::try_from_slice(&buffer).unwrap();
-        assert_eq!(deserialized.reentered, attack_state.reentered);
-        assert_eq!(deserialized.attack_count, attack_state.attack_count);
-        assert_eq!(deserialized.bump, attack_state.bump);
+        assert_eq!(deserialized, attack_state);
     }
 }
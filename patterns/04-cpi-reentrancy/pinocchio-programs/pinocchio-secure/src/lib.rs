@@ -17,8 +17,19 @@ use pinocchio::{
     instruction::{InstructionAccount, InstructionView},
     AccountView, Address, ProgramResult,
 };
+
+// Logging shim: `log!` forwards to `solana_program_log` with the default `logs`
+// feature and compiles to nothing under `--no-default-features`.
+#[cfg(feature = "logs")]
 use solana_program_log::log;
 
+#[cfg(not(feature = "logs"))]
+macro_rules! log {
+    ($($arg:tt)*) => {{
+        let _ = core::format_args!($($arg)*);
+    }};
+}
+
 // =============================================================================
 // PROGRAM ID
 // =============================================================================
@@ -73,6 +84,7 @@ impl From<SecureError> for ProgramError {
 // =============================================================================
 
 /// Vault account WITH re-entrancy guard (secure version)
+#[derive(Debug, Clone, PartialEq)]
 pub struct Vault {
     pub authority: Address,
     pub balance: u64,
@@ -118,6 +130,7 @@ impl Vault {
 }
 
 /// User deposit tracking account
+#[derive(Debug, Clone, PartialEq)]
 pub struct UserDeposit {
     pub owner: Address,
     pub amount: u64,
@@ -439,9 +452,7 @@ mod tests {
         vault.serialize(&mut buffer).unwrap();
 
         let deserialized = Vault::try_from_slice(&buffer).unwrap();
-        assert_eq!(deserialized.authority, vault.authority);
-        assert_eq!(deserialized.balance, vault.balance);
-        assert_eq!(deserialized.reentrancy_guard, vault.reentrancy_guard);
+        assert_eq!(deserialized, vault);
     }
 
     #[test]
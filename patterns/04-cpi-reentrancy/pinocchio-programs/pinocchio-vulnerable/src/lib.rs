@@ -26,8 +26,19 @@ use pinocchio::{
     instruction::{InstructionAccount, InstructionView},
     AccountView, Address, ProgramResult,
 };
+
+// Logging shim: `log!` forwards to `solana_program_log` with the default `logs`
+// feature and compiles to nothing under `--no-default-features`.
+#[cfg(feature = "logs")]
 use solana_program_log::log;
 
+#[cfg(not(feature = "logs"))]
+macro_rules! log {
+    ($($arg:tt)*) => {{
+        let _ = core::format_args!($($arg)*);
+    }};
+}
+
 // =============================================================================
 // PROGRAM ID
 // =============================================================================
@@ -107,6 +118,7 @@ impl From<VulnerableError> for ProgramError {
 ///
 /// This struct mirrors the Anchor Vault but uses manual serialization.
 /// In Pinocchio, there's no Anchor discriminator (8 bytes saved).
+#[derive(Debug, Clone, PartialEq)]
 pub struct Vault {
     /// Vault owner/authority (32 bytes)
     pub authority: Address,
@@ -169,6 +181,7 @@ impl Vault {
 }
 
 /// User deposit tracking account
+#[derive(Debug, Clone, PartialEq)]
 pub struct UserDeposit {
     /// Depositor's public key (32 bytes)
     pub owner: Address,
@@ -542,10 +555,7 @@ mod tests {
         vault.serialize(&mut buffer).unwrap();
 
         let deserialized = Vault::try_from_slice(&buffer).unwrap();
-        assert_eq!(deserialized.authority, vault.authority);
-        assert_eq!(deserialized.balance, vault.balance);
-        assert_eq!(deserialized.withdrawals_pending, vault.withdrawals_pending);
-        assert_eq!(deserialized.bump, vault.bump);
+        assert_eq!(deserialized, vault);
     }
 
     #[test]
@@ -557,8 +567,6 @@ mod tests {
         user.serialize(&mut buffer).unwrap();
 
         let deserialized = UserDeposit::try_from_slice(&buffer).unwrap();
-        assert_eq!(deserialized.owner, user.owner);
-        assert_eq!(deserialized.amount, user.amount);
-        assert_eq!(deserialized.bump, user.bump);
+        assert_eq!(deserialized, user);
     }
 }
@@ -34,101 +34,77 @@
 //! - Explicit canonical bump verification
 //! - Manual relationship validation (treasury <-> user_deposit)
 //! - Program ownership validation using `owned_by()`
+//! - Off-curve validation so wallet keys are rejected where a PDA is expected
 //! - Signer validation using `is_signer()`
+//! - Rejection of sysvars and the program account where a data account is expected
 //!
 //! **This program is safe for production use (as a reference pattern).**
 
 #![allow(unexpected_cfgs)]
 
-extern crate alloc;
-
-use pinocchio::{entrypoint, error::ProgramError, AccountView, Address, ProgramResult};
+use pinocchio::{cpi::Seed, entrypoint, error::ProgramError, AccountView, Address, ProgramResult};
+use pinocchio_security_common::{
+    address_hex, assert_not_sysvar_or_program, check_not_closed, check_schema_version,
+    check_uninitialized, close_account_data, create_account_signed, find_program_address,
+    format_address, format_amount, is_on_curve, load_typed, rent_exempt_minimum, rent_shortfall,
+    transfer, AccountData, CommonError, SafeMath, SecurityChecks, ADDRESS_LOG_LEN, AMOUNT_LOG_LEN,
+    CLOSED_ACCOUNT_TAG, SYSTEM_PROGRAM_ID,
+};
+
+// Logging shim: `log!` forwards to `solana_program_log` with the default `logs`
+// feature and compiles to nothing under `--no-default-features`. Unit tests
+// capture each line instead, so they can assert on what a handler logged.
+#[cfg(all(feature = "logs", not(test)))]
 use solana_program_log::log;
 
-// Syscalls are only available on Solana runtime
-#[cfg(target_os = "solana")]
-use pinocchio::syscalls;
-
-// =============================================================================
-// PDA DERIVATION SYSCALL WRAPPER
-// =============================================================================
+#[cfg(all(not(feature = "logs"), not(test)))]
+macro_rules! log {
+    ($($arg:tt)*) => {{
+        let _ = core::format_args!($($arg)*);
+    }};
+}
 
-/// Find a valid program derived address and its canonical bump seed.
-///
-/// This wraps the `sol_try_find_program_address` syscall to derive PDAs
-/// on-chain. The syscall efficiently finds the canonical (highest valid)
-/// bump seed that produces an off-curve address.
-///
-/// ## Anchor Comparison
-/// This is equivalent to what Anchor does internally when you use:
-/// ```ignore
-/// #[account(seeds = [...], bump)]
-/// ```
-///
-/// ## Implementation Note
-/// On the Solana runtime (target_os = "solana"), this calls the efficient
-/// syscall. In tests (not on Solana), it falls back to a simplified implementation.
-#[cfg(target_os = "solana")]
-#[inline]
-fn find_program_address(seeds: &[&[u8]], program_id: &Address) -> (Address, u8) {
-    let mut pda_bytes = core::mem::MaybeUninit::<[u8; 32]>::uninit();
-    let mut bump_seed = u8::MAX;
-
-    let result = unsafe {
-        syscalls::sol_try_find_program_address(
-            seeds as *const _ as *const u8,
-            seeds.len() as u64,
-            program_id as *const _ as *const u8,
-            pda_bytes.as_mut_ptr() as *mut u8,
-            &mut bump_seed as *mut u8,
-        )
+#[cfg(test)]
+macro_rules! log {
+    ($($arg:tt)*) => {
+        tests::capture_log(std::format!($($arg)*))
     };
-
-    if result == 0 {
-        (Address::new_from_array(unsafe { pda_bytes.assume_init() }), bump_seed)
-    } else {
-        panic!("Unable to find a viable program address bump seed")
-    }
 }
 
-/// Test-only implementation of find_program_address.
-///
-/// This is a simplified implementation for unit tests that don't run on Solana.
-/// It produces deterministic addresses for testing serialization/deserialization,
-/// but does NOT perform actual cryptographic PDA derivation.
-///
-/// **WARNING:** This implementation is NOT secure and should ONLY be used in tests.
-/// Real PDA derivation requires the Solana runtime syscall.
-#[cfg(not(target_os = "solana"))]
-#[inline]
-fn find_program_address(seeds: &[&[u8]], program_id: &Address) -> (Address, u8) {
-    use alloc::vec::Vec;
+/// Whether logs shorten addresses and hide amounts (`redact` feature).
+pub const REDACT_LOGS: bool = cfg!(feature = "redact");
 
-    // For non-Solana targets (tests), we create a deterministic but
-    // not cryptographically correct address. This allows unit tests
-    // to run without the Solana runtime.
-    let mut hasher_input = Vec::new();
-    for seed in seeds {
-        hasher_input.extend_from_slice(seed);
-    }
-    hasher_input.extend_from_slice(program_id.as_ref());
+/// `log!` with one address argument, shortened to its first bytes under
+/// the `redact` feature.
+macro_rules! log_addr {
+    ($fmt:literal, $address:expr) => {{
+        let mut buf = [0u8; ADDRESS_LOG_LEN];
+        log!($fmt, format_address($address, REDACT_LOGS, &mut buf));
+    }};
+}
 
-    // Simple XOR hash for testing - NOT cryptographically secure
-    let mut result = [0u8; 32];
-    for (i, byte) in hasher_input.iter().enumerate() {
-        result[i % 32] ^= byte;
-        result[(i + 7) % 32] = result[(i + 7) % 32].wrapping_add(*byte);
-    }
+/// `log!` with one amount argument, hidden under the `redact` feature.
+macro_rules! log_amount {
+    ($fmt:literal, $amount:expr) => {{
+        let mut buf = [0u8; AMOUNT_LOG_LEN];
+        log!($fmt, format_amount($amount, REDACT_LOGS, &mut buf));
+    }};
+}
 
-    // Return with a deterministic "canonical" bump of 255
-    (Address::new_from_array(result), 255)
+/// `log!` a rejection message and return `Err($error.into())` in one step, so
+/// a message cannot drift away from the error it explains.
+macro_rules! reject {
+    ($error:expr, $($msg:tt)+) => {{
+        log!($($msg)+);
+        return Err($error.into());
+    }};
 }
 
 // =============================================================================
 // PROGRAM ID
 // =============================================================================
 
-/// Program ID for the secure PDA derivation program.
+/// Program ID: 7NsBNd6X6TPNQdNGzoZ6nGENdp945jRxZKCrQjafVyk5
 /// Unique identifier distinguishing this from the vulnerable version.
 pub const ID: Address = Address::new_from_array([
     0x5e, 0xc0, 0x0e, 0x52, 0x71, 0xab, 0xcd, 0xef, 0x12, 0x34, 0x56, 0x78, 0x9a, 0xbc, 0xde, 0xf0,
@@ -143,18 +119,72 @@ pub const ID: Address = Address::new_from_array([
 /// - authority (Address): 32 bytes
 /// - balance (u64): 8 bytes
 /// - bump (u8): 1 byte
+/// - co_authorities ([Address; 2]): 64 bytes
+/// - version (u8): 1 byte
+/// - reserved ([u8; 15]): 15 bytes
 ///
-/// Total: 41 bytes
-pub const TREASURY_SIZE: usize = 32 + 8 + 1;
+/// Total: 121 bytes
+pub const TREASURY_SIZE: usize = TREASURY_RESERVED_OFFSET + TREASURY_RESERVED_LEN;
+
+/// Treasury layout version this program reads and writes.
+///
+/// Stored in the byte at [`TREASURY_VERSION_OFFSET`] (and
+/// [`USER_DEPOSIT_VERSION_OFFSET`]); accounts written by a newer version are
+/// rejected with `CommonError::UnsupportedVersion`, and closed accounts, whose
+/// version byte is `CLOSED_ACCOUNT_TAG`, with `CommonError::AccountClosed`.
+pub const SCHEMA_VERSION: u8 = 0;
+
+/// Offset of the layout version byte in Treasury data
+pub const TREASURY_VERSION_OFFSET: usize = 32 + 8 + 1 + 32 * MAX_CO_AUTHORITIES;
+
+/// Offset of the reserved trailing region in Treasury data
+pub const TREASURY_RESERVED_OFFSET: usize = TREASURY_VERSION_OFFSET + 1;
+
+/// Length of the reserved trailing region in Treasury data.
+///
+/// Room for future fields without changing the account size. `serialize` and
+/// `decode` leave these bytes alone; `initialize_treasury` zeroes
+/// them, and a later version may only assign meaning to a zero value that
+/// matches today's behavior.
+pub const TREASURY_RESERVED_LEN: usize = 15;
+
+/// Number of co-authority slots on a treasury
+pub const MAX_CO_AUTHORITIES: usize = 2;
+
+/// Value of an unused co-authority slot
+pub const NO_CO_AUTHORITIES: [Address; MAX_CO_AUTHORITIES] =
+    [Address::new_from_array([0u8; 32]), Address::new_from_array([0u8; 32])];
 
 /// Size of UserDeposit account in bytes (no Anchor discriminator):
 /// - owner (Address): 32 bytes
 /// - treasury (Address): 32 bytes
 /// - amount (u64): 8 bytes
 /// - bump (u8): 1 byte
+/// - deposit_count (u32): 4 bytes
+/// - withdraw_count (u32): 4 bytes
+/// - allowed_destination (Address): 32 bytes
+/// - version (u8): 1 byte
+///
+/// Total: 114 bytes
+pub const USER_DEPOSIT_SIZE: usize = USER_DEPOSIT_VERSION_OFFSET + 1;
+
+/// Offset of the layout version byte in UserDeposit data
+pub const USER_DEPOSIT_VERSION_OFFSET: usize = 32 + 32 + 8 + 1 + 4 + 4 + 32;
+
+/// Size of a Treasury written by the vulnerable program:
+/// authority (32), balance (8) and bump (1), with no co-authorities and no
+/// version byte. Treasuries this program created before co-authorities were
+/// added share the layout, and `heal_bump` migrates both.
+///
+/// Total: 41 bytes
+pub const LEGACY_TREASURY_SIZE: usize = 32 + 8 + 1;
+
+/// Size of a UserDeposit written by the vulnerable program:
+/// owner (32), treasury (32), amount (8) and bump (1), with no counters and
+/// no version byte.
 ///
 /// Total: 73 bytes
-pub const USER_DEPOSIT_SIZE: usize = 32 + 32 + 8 + 1;
+pub const LEGACY_USER_DEPOSIT_SIZE: usize = 32 + 32 + 8 + 1;
 
 /// Seed prefix for treasury PDA derivation
 pub const TREASURY_SEED: &[u8] = b"treasury";
@@ -174,6 +204,33 @@ pub const DEPOSIT_DISCRIMINATOR: u8 = 2;
 /// Instruction discriminator for withdraw
 pub const WITHDRAW_DISCRIMINATOR: u8 = 3;
 
+/// Instruction discriminator for heal_bump
+pub const HEAL_BUMP_DISCRIMINATOR: u8 = 4;
+
+/// heal_bump account kind: Treasury
+pub const HEAL_KIND_TREASURY: u8 = 0;
+
+/// heal_bump account kind: UserDeposit
+pub const HEAL_KIND_USER_DEPOSIT: u8 = 1;
+
+/// Instruction discriminator for add_co_authority
+pub const ADD_CO_AUTHORITY_DISCRIMINATOR: u8 = 5;
+
+/// Instruction discriminator for remove_co_authority
+pub const REMOVE_CO_AUTHORITY_DISCRIMINATOR: u8 = 6;
+
+/// Instruction discriminator for transfer_deposit
+pub const TRANSFER_DEPOSIT_DISCRIMINATOR: u8 = 7;
+
+/// Instruction discriminator for derive_address
+pub const DERIVE_ADDRESS_DISCRIMINATOR: u8 = 8;
+
+/// Instruction discriminator for repair_bump
+pub const REPAIR_BUMP_DISCRIMINATOR: u8 = 9;
+
+/// Instruction discriminator for sweep_surplus
+pub const SWEEP_SURPLUS_DISCRIMINATOR: u8 = 10;
+
 // =============================================================================
 // ERROR CODES
 // =============================================================================
@@ -213,8 +270,25 @@ pub enum SecureError {
     /// Account not initialized or invalid state.
     NotInitialized = 0x1004,
 
-    /// Insufficient funds for withdrawal.
-    InsufficientFunds = 0x1005,
+    // 0x1005 reserved: insufficient funds are reported as
+    // CommonError::InsufficientBalance (0x2001), shared by every pattern.
+    /// The same account was supplied for two distinct account roles.
+    /// // SECURITY: Prevents aliased borrows from corrupting overlapping state
+    DuplicateAccount = 0x1006,
+
+    /// Address is a valid ed25519 point (a wallet key), not a program address.
+    /// // SECURITY: No PDA is ever on the curve, so this rejects wallet keys
+    NotAProgramAddress = 0x1007,
+
+    /// Debit would leave a program account below its rent-exempt minimum.
+    /// // SECURITY: Keeps the treasury PDA from being drained to dust and reclaimed
+    WouldBreakRentExemption = 0x1008,
+
+    /// Every co-authority slot is already taken.
+    CoAuthorityListFull = 0x1009,
+
+    /// Key is not a co-authority of this treasury.
+    CoAuthorityNotFound = 0x100a,
 }
 
 impl From<SecureError> for ProgramError {
@@ -243,7 +317,10 @@ impl From<SecureError> for ProgramError {
 /// In Anchor, this derivation happens automatically. In Pinocchio,
 /// we must call it explicitly and compare the result.
 #[inline]
-fn derive_treasury_pda(authority: &Address, program_id: &Address) -> (Address, u8) {
+fn derive_treasury_pda(
+    authority: &Address,
+    program_id: &Address,
+) -> Result<(Address, u8), ProgramError> {
     find_program_address(&[TREASURY_SEED, authority.as_ref()], program_id)
 }
 
@@ -267,10 +344,72 @@ fn derive_user_deposit_pda(
     treasury: &Address,
     owner: &Address,
     program_id: &Address,
-) -> (Address, u8) {
+) -> Result<(Address, u8), ProgramError> {
     find_program_address(&[USER_DEPOSIT_SEED, treasury.as_ref(), owner.as_ref()], program_id)
 }
 
+// =============================================================================
+// ACCOUNT VALIDATION HELPERS
+// =============================================================================
+
+/// Verify that two account roles were not filled by the same account.
+///
+/// If `user_deposit` and `treasury` alias the same account, the handler's
+/// second `try_borrow_mut` either conflicts with the first or writes a
+/// `Treasury` layout over the bytes of a `UserDeposit` (or vice versa).
+///
+/// ## Anchor Comparison
+/// Anchor rejects duplicate mutable accounts in a context automatically
+/// unless the field is marked `#[account(dup)]`.
+#[inline]
+fn assert_distinct_accounts(a: &Address, b: &Address) -> ProgramResult {
+    if a == b {
+        log!("SECURITY REJECTION: Same account passed for two roles");
+        return Err(SecureError::DuplicateAccount.into());
+    }
+    Ok(())
+}
+
+/// Lamports left after debiting `amount` from an account holding `lamports`
+/// with `data_len` bytes of data, if it stays rent-exempt.
+///
+/// A PDA drained below the minimum can be reclaimed by the runtime, taking
+/// its recorded state with it.
+///
+/// ## Anchor Comparison
+/// Anchor does not check this on a direct lamport debit either; the explicit
+/// check is `require!(remaining >= Rent::get()?.minimum_balance(len), ...)`.
+fn rent_exempt_after_debit(
+    lamports: u64,
+    amount: u64,
+    data_len: usize,
+) -> Result<u64, ProgramError> {
+    let remaining = lamports.sub_or(amount, SecureError::WouldBreakRentExemption)?;
+    if remaining < rent_exempt_minimum(data_len)? {
+        log!("SECURITY REJECTION: Debit would leave account below rent-exempt minimum");
+        return Err(SecureError::WouldBreakRentExemption.into());
+    }
+    Ok(remaining)
+}
+
+/// Verify that an address is off the ed25519 curve, i.e. could be a PDA.
+///
+/// Every address returned by `find_program_address()` is off-curve, while
+/// every wallet key is on it. Checking this before the derivation comparison
+/// turns "wallet passed where a PDA is expected" into a specific error.
+///
+/// ## Anchor Comparison
+/// Anchor's `seeds` constraint covers this implicitly; the explicit check is
+/// `require!(!pubkey.is_on_curve(), ...)`.
+#[inline]
+fn assert_off_curve(address: &Address) -> ProgramResult {
+    if is_on_curve(address) {
+        log!("SECURITY REJECTION: Address is on the ed25519 curve, not a PDA");
+        return Err(SecureError::NotAProgramAddress.into());
+    }
+    Ok(())
+}
+
 // =============================================================================
 // DATA STRUCTURES
 // =============================================================================
@@ -281,6 +420,7 @@ fn derive_user_deposit_pda(
 ///
 /// This struct is identical to the vulnerable version, but the difference
 /// is in how validation is performed in instruction handlers.
+#[derive(Debug, Clone, PartialEq)]
 pub struct Treasury {
     /// Treasury admin who can manage funds.
     /// // SECURITY: Used as seed component, validated via PDA re-derivation.
@@ -292,15 +432,50 @@ pub struct Treasury {
     /// PDA bump seed - always canonical (highest valid).
     /// // SECURITY: Validated against re-derived canonical bump on every access.
     pub bump: u8,
+
+    /// Extra keys allowed to run privileged treasury operations; unused slots
+    /// are all zeroes.
+    /// // SECURITY: Not a PDA seed - only the primary `authority` derives the
+    /// // address, and only the primary can change this list.
+    pub co_authorities: [Address; MAX_CO_AUTHORITIES],
 }
 
 impl Treasury {
+    /// Bytes to allocate when creating a Treasury account: [`TREASURY_SIZE`].
+    pub const fn space() -> usize {
+        TREASURY_SIZE
+    }
+
     /// Deserialize Treasury from raw account data bytes.
-    pub fn try_from_slice(data: &[u8]) -> Result<Self, ProgramError> {
+    ///
+    /// With the `strict_pda` feature, also rejects a stored bump that is not
+    /// the canonical bump for `program_id` with `InvalidBump`.
+    pub fn try_from_slice(
+        data: &[u8],
+        #[cfg(feature = "strict_pda")] program_id: &Address,
+    ) -> Result<Self, ProgramError> {
+        let treasury = Self::decode(data)?;
+
+        // SECURITY: Fail at load, before any instruction logic sees the record
+        #[cfg(feature = "strict_pda")]
+        if treasury.bump != derive_treasury_pda(&treasury.authority, program_id)?.1 {
+            log!("SECURITY REJECTION: Treasury non-canonical bump");
+            return Err(SecureError::InvalidBump.into());
+        }
+
+        Ok(treasury)
+    }
+
+    /// Deserialize Treasury without checking its stored bump, so legacy
+    /// records can still be read and healed.
+    pub fn decode(data: &[u8]) -> Result<Self, ProgramError> {
         if data.len() < TREASURY_SIZE {
             return Err(ProgramError::InvalidAccountData);
         }
 
+        // SECURITY: A newer layout may give meaning to bytes this version ignores
+        check_schema_version(data[TREASURY_VERSION_OFFSET], SCHEMA_VERSION)?;
+
         let authority = Address::new_from_array(
             data[0..32].try_into().map_err(|_| ProgramError::InvalidAccountData)?,
         );
@@ -311,7 +486,33 @@ impl Treasury {
 
         let bump = data[40];
 
-        Ok(Self { authority, balance, bump })
+        let mut co_authorities = NO_CO_AUTHORITIES;
+        for (i, co_authority) in co_authorities.iter_mut().enumerate() {
+            let start = 41 + 32 * i;
+            *co_authority = Address::new_from_array(
+                data[start..start + 32].try_into().map_err(|_| ProgramError::InvalidAccountData)?,
+            );
+        }
+
+        Ok(Self { authority, balance, bump, co_authorities })
+    }
+
+    /// Deserialize a Treasury written by the vulnerable program, which is
+    /// exactly [`LEGACY_TREASURY_SIZE`] bytes; it has no co-authorities.
+    pub fn decode_legacy(data: &[u8]) -> Result<Self, ProgramError> {
+        if data.len() != LEGACY_TREASURY_SIZE {
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        let authority = Address::new_from_array(
+            data[0..32].try_into().map_err(|_| ProgramError::InvalidAccountData)?,
+        );
+
+        let balance = u64::from_le_bytes(
+            data[32..40].try_into().map_err(|_| ProgramError::InvalidAccountData)?,
+        );
+
+        Ok(Self { authority, balance, bump: data[40], co_authorities: NO_CO_AUTHORITIES })
     }
 
     /// Serialize Treasury into raw account data bytes.
@@ -323,17 +524,93 @@ impl Treasury {
         data[0..32].copy_from_slice(self.authority.as_ref());
         data[32..40].copy_from_slice(&self.balance.to_le_bytes());
         data[40] = self.bump;
+        for (i, co_authority) in self.co_authorities.iter().enumerate() {
+            let start = 41 + 32 * i;
+            data[start..start + 32].copy_from_slice(co_authority.as_ref());
+        }
+        data[TREASURY_VERSION_OFFSET] = SCHEMA_VERSION;
+
+        Ok(())
+    }
+
+    /// Tag treasury data as closed.
+    ///
+    /// // SECURITY: Leftover bytes of a closed treasury then fail both
+    /// // `try_from_slice` and `initialize_treasury` with `AccountClosed`,
+    /// // so the account cannot be revived before the runtime reclaims it.
+    pub fn mark_closed(data: &mut [u8]) -> ProgramResult {
+        let version =
+            data.get_mut(TREASURY_VERSION_OFFSET).ok_or(ProgramError::AccountDataTooSmall)?;
+        *version = CLOSED_ACCOUNT_TAG;
+        Ok(())
+    }
+
+    /// Put `key` in the first free co-authority slot.
+    ///
+    /// Rejects the primary authority, an existing co-authority and the zero key
+    /// (which marks a free slot) with `InvalidArgument`.
+    pub fn add_co_authority(&mut self, key: &Address) -> ProgramResult {
+        if key == &Address::default() || is_treasury_authority(self, key) {
+            return Err(ProgramError::InvalidArgument);
+        }
+
+        let slot = self
+            .co_authorities
+            .iter_mut()
+            .find(|slot| **slot == Address::default())
+            .ok_or(SecureError::CoAuthorityListFull)?;
+        *slot = Address::new_from_array(*key.as_array());
+
+        Ok(())
+    }
+
+    /// Clear the co-authority slot holding `key`.
+    pub fn remove_co_authority(&mut self, key: &Address) -> ProgramResult {
+        if key == &Address::default() {
+            return Err(SecureError::CoAuthorityNotFound.into());
+        }
+
+        let slot = self
+            .co_authorities
+            .iter_mut()
+            .find(|slot| *slot == key)
+            .ok_or(SecureError::CoAuthorityNotFound)?;
+        *slot = Address::default();
 
         Ok(())
     }
 }
 
+/// Whether `key` may run privileged treasury operations: the primary
+/// authority or any co-authority.
+///
+/// // SECURITY: Free slots hold the zero key, which never matches, so an empty
+/// // list does not authorize the system program address.
+pub fn is_treasury_authority(treasury: &Treasury, key: &Address) -> bool {
+    key == &treasury.authority
+        || (key != &Address::default() && treasury.co_authorities.contains(key))
+}
+
+impl AccountData for Treasury {
+    const LEN: usize = TREASURY_SIZE;
+
+    fn try_from_slice(data: &[u8]) -> Result<Self, ProgramError> {
+        Treasury::decode(data)
+    }
+
+    #[cfg(feature = "strict_pda")]
+    fn try_from_owned_slice(data: &[u8], program_id: &Address) -> Result<Self, ProgramError> {
+        Treasury::try_from_slice(data, program_id)
+    }
+}
+
 /// UserDeposit account - tracks individual user deposits.
 ///
 /// PDA seeds: `["user_deposit", treasury, owner]`
 ///
 /// The hierarchical relationship ensures each deposit is uniquely tied
 /// to a specific treasury and user combination.
+#[derive(Debug, Clone, PartialEq)]
 pub struct UserDeposit {
     /// Depositor's pubkey.
     /// // SECURITY: Validated via has_one equivalent check.
@@ -349,11 +626,50 @@ pub struct UserDeposit {
     /// PDA bump seed - always canonical.
     /// // SECURITY: Validated against re-derived canonical bump.
     pub bump: u8,
+
+    /// Number of successful deposits into this record.
+    pub deposit_count: u32,
+
+    /// Number of successful withdrawals from this record.
+    pub withdraw_count: u32,
+
+    /// Only address withdrawals may be sent to; the default address means
+    /// unrestricted.
+    /// // SECURITY: Set once at creation, enforced on every withdrawal.
+    pub allowed_destination: Address,
 }
 
 impl UserDeposit {
+    /// Bytes to allocate when creating a UserDeposit account: [`USER_DEPOSIT_SIZE`].
+    pub const fn space() -> usize {
+        USER_DEPOSIT_SIZE
+    }
+
     /// Deserialize UserDeposit from raw account data bytes.
-    pub fn try_from_slice(data: &[u8]) -> Result<Self, ProgramError> {
+    ///
+    /// With the `strict_pda` feature, also rejects a non-canonical stored
+    /// bump; see [`Treasury::try_from_slice`].
+    pub fn try_from_slice(
+        data: &[u8],
+        #[cfg(feature = "strict_pda")] program_id: &Address,
+    ) -> Result<Self, ProgramError> {
+        let user_deposit = Self::decode(data)?;
+
+        // SECURITY: Fail at load, before any instruction logic sees the record
+        #[cfg(feature = "strict_pda")]
+        if user_deposit.bump
+            != derive_user_deposit_pda(&user_deposit.treasury, &user_deposit.owner, program_id)?.1
+        {
+            log!("SECURITY REJECTION: UserDeposit non-canonical bump");
+            return Err(SecureError::InvalidBump.into());
+        }
+
+        Ok(user_deposit)
+    }
+
+    /// Deserialize UserDeposit without checking its stored bump; see
+    /// [`Treasury::decode`].
+    pub fn decode(data: &[u8]) -> Result<Self, ProgramError> {
         if data.len() < USER_DEPOSIT_SIZE {
             return Err(ProgramError::InvalidAccountData);
         }
@@ -366,13 +682,67 @@ impl UserDeposit {
             data[32..64].try_into().map_err(|_| ProgramError::InvalidAccountData)?,
         );
 
+        // SECURITY: Rejects closed and newer-version deposits
+        check_schema_version(data[USER_DEPOSIT_VERSION_OFFSET], SCHEMA_VERSION)?;
+
         let amount = u64::from_le_bytes(
             data[64..72].try_into().map_err(|_| ProgramError::InvalidAccountData)?,
         );
 
         let bump = data[72];
 
-        Ok(Self { owner, treasury, amount, bump })
+        let deposit_count = u32::from_le_bytes(
+            data[73..77].try_into().map_err(|_| ProgramError::InvalidAccountData)?,
+        );
+
+        let withdraw_count = u32::from_le_bytes(
+            data[77..81].try_into().map_err(|_| ProgramError::InvalidAccountData)?,
+        );
+
+        let allowed_destination = Address::new_from_array(
+            data[81..113].try_into().map_err(|_| ProgramError::InvalidAccountData)?,
+        );
+
+        Ok(Self {
+            owner,
+            treasury,
+            amount,
+            bump,
+            deposit_count,
+            withdraw_count,
+            allowed_destination,
+        })
+    }
+
+    /// Deserialize a UserDeposit written by the vulnerable program, which is
+    /// exactly [`LEGACY_USER_DEPOSIT_SIZE`] bytes; its counters start at zero
+    /// and it has no destination allowlist.
+    pub fn decode_legacy(data: &[u8]) -> Result<Self, ProgramError> {
+        if data.len() != LEGACY_USER_DEPOSIT_SIZE {
+            return Err(ProgramError::InvalidAccountData);
+        }
+
+        let owner = Address::new_from_array(
+            data[0..32].try_into().map_err(|_| ProgramError::InvalidAccountData)?,
+        );
+
+        let treasury = Address::new_from_array(
+            data[32..64].try_into().map_err(|_| ProgramError::InvalidAccountData)?,
+        );
+
+        let amount = u64::from_le_bytes(
+            data[64..72].try_into().map_err(|_| ProgramError::InvalidAccountData)?,
+        );
+
+        Ok(Self {
+            owner,
+            treasury,
+            amount,
+            bump: data[72],
+            deposit_count: 0,
+            withdraw_count: 0,
+            allowed_destination: Address::new_from_array([0u8; 32]),
+        })
     }
 
     /// Serialize UserDeposit into raw account data bytes.
@@ -385,11 +755,224 @@ impl UserDeposit {
         data[32..64].copy_from_slice(self.treasury.as_ref());
         data[64..72].copy_from_slice(&self.amount.to_le_bytes());
         data[72] = self.bump;
+        data[73..77].copy_from_slice(&self.deposit_count.to_le_bytes());
+        data[77..81].copy_from_slice(&self.withdraw_count.to_le_bytes());
+        data[81..113].copy_from_slice(self.allowed_destination.as_ref());
+        data[USER_DEPOSIT_VERSION_OFFSET] = SCHEMA_VERSION;
+
+        Ok(())
+    }
+
+    /// Whether a withdrawal may be paid to `destination`: any address when
+    /// no allowlist was set, otherwise only `allowed_destination`.
+    pub fn allows_destination(&self, destination: &Address) -> bool {
+        self.allowed_destination.as_ref() == [0u8; 32] || &self.allowed_destination == destination
+    }
 
+    /// Tag user deposit data as closed; see [`Treasury::mark_closed`].
+    pub fn mark_closed(data: &mut [u8]) -> ProgramResult {
+        let version =
+            data.get_mut(USER_DEPOSIT_VERSION_OFFSET).ok_or(ProgramError::AccountDataTooSmall)?;
+        *version = CLOSED_ACCOUNT_TAG;
         Ok(())
     }
 }
 
+impl AccountData for UserDeposit {
+    const LEN: usize = USER_DEPOSIT_SIZE;
+
+    fn try_from_slice(data: &[u8]) -> Result<Self, ProgramError> {
+        UserDeposit::decode(data)
+    }
+
+    #[cfg(feature = "strict_pda")]
+    fn try_from_owned_slice(data: &[u8], program_id: &Address) -> Result<Self, ProgramError> {
+        UserDeposit::try_from_slice(data, program_id)
+    }
+}
+
+// =============================================================================
+// ACCOUNTING
+// =============================================================================
+
+/// Debits a withdrawal from both the user's deposit and the treasury balance.
+///
+/// // SECURITY: Rejects withdrawals above the user's deposit with the shared
+/// // `CommonError::InsufficientBalance` code before touching either side.
+pub fn apply_withdraw(
+    user_deposit: &mut UserDeposit,
+    treasury: &mut Treasury,
+    amount: u64,
+) -> ProgramResult {
+    if user_deposit.amount < amount {
+        return Err(CommonError::InsufficientBalance.into());
+    }
+
+    let user_amount = user_deposit.amount.sub_or(amount, ProgramError::ArithmeticOverflow)?;
+    let treasury_balance = treasury.balance.sub_or(amount, ProgramError::ArithmeticOverflow)?;
+    let withdraw_count =
+        user_deposit.withdraw_count.checked_add(1).ok_or(ProgramError::ArithmeticOverflow)?;
+
+    user_deposit.amount = user_amount;
+    user_deposit.withdraw_count = withdraw_count;
+    treasury.balance = treasury_balance;
+
+    Ok(())
+}
+
+/// Closes an emptied user deposit, moving all its lamports to `owner`.
+///
+/// // SECURITY: The data is zeroed and tagged closed before the lamports
+/// // leave, so nothing later in the same transaction can read the record
+/// // or revive it through `create_user_deposit`.
+pub fn close_user_deposit(user_deposit_acc: &AccountView, owner: &AccountView) -> ProgramResult {
+    let owner_lamports =
+        owner.lamports().add_or(user_deposit_acc.lamports(), ProgramError::ArithmeticOverflow)?;
+
+    close_account_data(user_deposit_acc, USER_DEPOSIT_VERSION_OFFSET)?;
+
+    user_deposit_acc.set_lamports(0);
+    owner.set_lamports(owner_lamports);
+
+    Ok(())
+}
+
+/// Moves `amount` from one user's deposit to another's.
+///
+/// The treasury balance is unchanged: the funds never leave it.
+///
+/// // SECURITY: Same `CommonError::InsufficientBalance` rejection as
+/// // [`apply_withdraw`], and neither side is written unless both succeed.
+pub fn apply_transfer(
+    sender: &mut UserDeposit,
+    recipient: &mut UserDeposit,
+    amount: u64,
+) -> ProgramResult {
+    if sender.amount < amount {
+        return Err(CommonError::InsufficientBalance.into());
+    }
+
+    let sender_amount = sender.amount.sub_or(amount, ProgramError::ArithmeticOverflow)?;
+    let recipient_amount = recipient.amount.add_or(amount, ProgramError::ArithmeticOverflow)?;
+
+    sender.amount = sender_amount;
+    recipient.amount = recipient_amount;
+
+    Ok(())
+}
+
+// =============================================================================
+// SECURITY REPORT
+// =============================================================================
+
+/// Prefix of the machine-readable check summary logged by withdraw.
+pub const CHECK_SUMMARY_PREFIX: &str = "CHECKS|";
+
+/// Length of the summary line: prefix plus one digit per [`WithdrawCheck`].
+pub const CHECK_SUMMARY_LEN: usize = CHECK_SUMMARY_PREFIX.len() + WithdrawCheck::ALL.len();
+
+/// The checks withdraw performs, in the order of its `CHECKS|` digits.
+///
+/// Digit `i` of the summary is `1` if check `i` ran and passed, `0` if it
+/// was skipped. A failed check aborts the instruction before the summary is
+/// logged, so a failing withdraw produces no summary at all.
+#[repr(u8)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WithdrawCheck {
+    /// withdrawer signed
+    Signer = 0,
+    /// both accounts owned by this program
+    ProgramOwnership = 1,
+    /// user_deposit is the PDA for `[USER_DEPOSIT_SEED, treasury, withdrawer]`
+    UserDepositPda = 2,
+    /// user_deposit stores its canonical bump
+    UserDepositBump = 3,
+    /// treasury is the PDA for `[TREASURY_SEED, authority]`
+    TreasuryPda = 4,
+    /// treasury stores its canonical bump
+    TreasuryBump = 5,
+    /// user_deposit.treasury == treasury
+    TreasuryRelationship = 6,
+    /// user_deposit.owner == withdrawer
+    OwnerAuthorization = 7,
+    /// user_deposit.amount covers the withdrawal
+    SufficientFunds = 8,
+    /// treasury stays rent-exempt after the debit
+    RentExemption = 9,
+}
+
+impl WithdrawCheck {
+    /// Every check, in summary digit order.
+    pub const ALL: [WithdrawCheck; 10] = [
+        WithdrawCheck::Signer,
+        WithdrawCheck::ProgramOwnership,
+        WithdrawCheck::UserDepositPda,
+        WithdrawCheck::UserDepositBump,
+        WithdrawCheck::TreasuryPda,
+        WithdrawCheck::TreasuryBump,
+        WithdrawCheck::TreasuryRelationship,
+        WithdrawCheck::OwnerAuthorization,
+        WithdrawCheck::SufficientFunds,
+        WithdrawCheck::RentExemption,
+    ];
+}
+
+/// Record of which withdraw checks passed, rendered as `CHECKS|<digits>`.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SecurityReport {
+    /// Bit `i` set once `WithdrawCheck::ALL[i]` has passed.
+    passed: u16,
+}
+
+impl SecurityReport {
+    /// Mark `check` as passed.
+    pub fn pass(&mut self, check: WithdrawCheck) {
+        self.passed |= 1 << check as u8;
+    }
+
+    /// Whether `check` was marked as passed.
+    pub fn passed(&self, check: WithdrawCheck) -> bool {
+        self.passed & (1 << check as u8) != 0
+    }
+
+    /// Whether every check in [`WithdrawCheck::ALL`] passed.
+    pub fn all_passed(&self) -> bool {
+        WithdrawCheck::ALL.iter().all(|check| self.passed(*check))
+    }
+
+    /// Render the summary line, e.g. `CHECKS|1111111111`.
+    pub fn summary(&self) -> [u8; CHECK_SUMMARY_LEN] {
+        let mut line = [0u8; CHECK_SUMMARY_LEN];
+        let (prefix, digits) = line.split_at_mut(CHECK_SUMMARY_PREFIX.len());
+        prefix.copy_from_slice(CHECK_SUMMARY_PREFIX.as_bytes());
+        for (digit, check) in digits.iter_mut().zip(WithdrawCheck::ALL) {
+            *digit = if self.passed(check) { b'1' } else { b'0' };
+        }
+        line
+    }
+
+    /// Parse a summary line produced by [`SecurityReport::summary`].
+    ///
+    /// Returns `None` unless `line` is the prefix followed by exactly one
+    /// `0`/`1` digit per check.
+    pub fn parse(line: &str) -> Option<Self> {
+        let digits = line.strip_prefix(CHECK_SUMMARY_PREFIX)?.as_bytes();
+        if digits.len() != WithdrawCheck::ALL.len() {
+            return None;
+        }
+
+        let mut report = Self::default();
+        for (digit, check) in digits.iter().zip(WithdrawCheck::ALL) {
+            match digit {
+                b'1' => report.pass(check),
+                b'0' => {}
+                _ => return None,
+            }
+        }
+        Some(report)
+    }
+}
+
 // =============================================================================
 // ENTRYPOINT
 // =============================================================================
@@ -404,6 +987,13 @@ entrypoint!(process_instruction);
 /// | 1 | create_user_deposit |
 /// | 2 | deposit |
 /// | 3 | withdraw |
+/// | 4 | heal_bump |
+/// | 5 | add_co_authority |
+/// | 6 | remove_co_authority |
+/// | 7 | transfer_deposit |
+/// | 8 | derive_address |
+/// | 9 | repair_bump |
+/// | 10 | sweep_surplus |
 pub fn process_instruction(
     program_id: &Address,
     accounts: &[AccountView],
@@ -417,6 +1007,13 @@ pub fn process_instruction(
         CREATE_USER_DEPOSIT_DISCRIMINATOR => create_user_deposit(program_id, accounts, data),
         DEPOSIT_DISCRIMINATOR => deposit(program_id, accounts, data),
         WITHDRAW_DISCRIMINATOR => withdraw(program_id, accounts, data),
+        HEAL_BUMP_DISCRIMINATOR => heal_bump(program_id, accounts, data),
+        ADD_CO_AUTHORITY_DISCRIMINATOR => add_co_authority(program_id, accounts, data),
+        REMOVE_CO_AUTHORITY_DISCRIMINATOR => remove_co_authority(program_id, accounts, data),
+        TRANSFER_DEPOSIT_DISCRIMINATOR => transfer_deposit(program_id, accounts, data),
+        DERIVE_ADDRESS_DISCRIMINATOR => derive_address(program_id, accounts, data),
+        REPAIR_BUMP_DISCRIMINATOR => repair_bump(program_id, accounts, data),
+        SWEEP_SURPLUS_DISCRIMINATOR => sweep_surplus(program_id, accounts, data),
         _ => Err(ProgramError::InvalidInstructionData),
     }
 }
@@ -429,14 +1026,20 @@ pub fn process_instruction(
 ///
 /// # Accounts
 /// 0. `[writable]` treasury - The treasury PDA account
-/// 1. `[signer]` authority - The treasury authority
+/// 1. `[signer, writable]` authority - The treasury authority, pays for creation
+/// 2. `[]` system_program - Only when the treasury does not exist yet
+///
+/// If the treasury is still a System Program account it is created here
+/// (rent-exempt, owned by this program) with the treasury PDA seeds;
+/// otherwise it must already be allocated and owned by this program.
 ///
 /// # Instruction Data
 /// - (empty) - bump is derived, not accepted from user
 ///
 /// # Security Validations
 /// // SECURITY: Signer validation - authority must sign
-/// // SECURITY: Program ownership - treasury owned by this program
+/// // SECURITY: Program ownership - treasury owned by this program (or created by it)
+/// // SECURITY: Off-curve - treasury address cannot be a wallet key
 /// // SECURITY: PDA derivation - verify treasury matches expected PDA
 /// // SECURITY: Canonical bump - derive and store only canonical bump
 ///
@@ -455,7 +1058,7 @@ fn initialize_treasury(
     accounts: &[AccountView],
     _data: &[u8],
 ) -> ProgramResult {
-    let [treasury_acc, authority] = accounts else {
+    let [treasury_acc, authority, remaining @ ..] = accounts else {
         return Err(ProgramError::NotEnoughAccountKeys);
     };
 
@@ -476,27 +1079,63 @@ fn initialize_treasury(
     // ==========================================================================
     // SECURITY: Verify treasury account is owned by this program.
     // This ensures we're initializing a legitimate treasury account.
-    if !treasury_acc.owned_by(program_id) {
+    // A System Program account has not been created yet; this handler
+    // creates it below, once its address is verified.
+    assert_not_sysvar_or_program(treasury_acc, program_id)?;
+    let needs_create = treasury_acc.owned_by(&SYSTEM_PROGRAM_ID);
+    if !needs_create && !treasury_acc.owned_by(program_id) {
         log!("SECURITY REJECTION: Treasury not owned by this program");
         return Err(ProgramError::IllegalOwner);
     }
 
+    // SECURITY: A closed treasury cannot be revived over its old data, and a
+    // live one cannot be re-initialized to reset its balance and authorities.
+    if !needs_create {
+        let account_data = treasury_acc.try_borrow()?;
+        if let Some(&version) = account_data.get(TREASURY_VERSION_OFFSET) {
+            check_not_closed(version)?;
+        }
+        check_uninitialized(&account_data)?;
+    }
+
     // ==========================================================================
     // SECURITY CHECK 3: PDA derivation and bump verification
     // Anchor equivalent: seeds = [...], bump (on init)
     // ==========================================================================
+    // SECURITY: A wallet key can never be the treasury PDA.
+    assert_off_curve(treasury_acc.address())?;
+
     // SECURITY: Derive the expected PDA and canonical bump.
     // This ensures the treasury account address is deterministically correct.
-    let (expected_pda, canonical_bump) = derive_treasury_pda(authority.address(), program_id);
+    let (expected_pda, canonical_bump) = derive_treasury_pda(authority.address(), program_id)?;
 
     // SECURITY: Verify the provided account matches the expected PDA.
     if treasury_acc.address() != &expected_pda {
         log!("SECURITY REJECTION: Treasury PDA mismatch");
-        log!("  Expected: derived from authority");
-        log!("  Got: different address");
+        log_addr!("  Expected: {}", &expected_pda);
+        log_addr!("  Got: {}", treasury_acc.address());
         return Err(SecureError::InvalidPda.into());
     }
 
+    // Anchor equivalent: init, payer = authority, space = TREASURY_SIZE
+    if needs_create {
+        let [system_program, ..] = remaining else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+        if system_program.address() != &SYSTEM_PROGRAM_ID {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+
+        // SECURITY: Sign with the canonical bump verified above
+        let bump = [canonical_bump];
+        let seeds = [
+            Seed::from(TREASURY_SEED),
+            Seed::from(authority.address().as_ref()),
+            Seed::from(&bump),
+        ];
+        create_account_signed(authority, treasury_acc, program_id, Treasury::space(), &seeds)?;
+    }
+
     // Initialize treasury with canonical bump (not user-provided!)
     let treasury = Treasury {
         authority: Address::new_from_array(*authority.address().as_array()),
@@ -504,13 +1143,16 @@ fn initialize_treasury(
         // SECURITY: Store the canonical bump from derivation
         // This is equivalent to Anchor's ctx.bumps.treasury
         bump: canonical_bump,
+        co_authorities: NO_CO_AUTHORITIES,
     };
 
     let mut account_data = treasury_acc.try_borrow_mut()?;
     treasury.serialize(&mut account_data)?;
+    // Reserved bytes must start out zero for future layout versions
+    account_data[TREASURY_RESERVED_OFFSET..TREASURY_SIZE].fill(0);
 
     log!("SECURITY VERIFIED: Treasury initialized");
-    log!("  Authority: verified signer");
+    log_addr!("  Authority: {} (verified signer)", authority.address());
     log!("  PDA: verified derivation");
     log!("  Bump: {} (canonical)", canonical_bump);
 
@@ -525,14 +1167,19 @@ fn initialize_treasury(
 /// 2. `[signer]` owner - The depositor
 ///
 /// # Instruction Data
-/// - (empty) - bump is derived, not accepted from user
+/// - allowed_destination (Address, optional): 32 bytes; the only address
+///   withdrawals may pay out to. Absent or the default address leaves
+///   withdrawals unrestricted. The bump is derived, not accepted from user.
 ///
 /// # Security Validations
 /// // SECURITY: Signer validation - owner must sign
 /// // SECURITY: Program ownership - both accounts owned by this program
+/// // SECURITY: Re-initialization - a closed or live user deposit is rejected
 /// // SECURITY: Treasury PDA verification - verify treasury is genuine
+/// // SECURITY: Off-curve - user deposit address cannot be a wallet key
 /// // SECURITY: User deposit PDA verification - verify correct derivation
 /// // SECURITY: Canonical bumps for both accounts
+/// // SECURITY: The destination allowlist is fixed at creation
 ///
 /// ## Anchor Comparison
 /// ```ignore
@@ -547,12 +1194,18 @@ fn initialize_treasury(
 fn create_user_deposit(
     program_id: &Address,
     accounts: &[AccountView],
-    _data: &[u8],
+    data: &[u8],
 ) -> ProgramResult {
     let [user_deposit_acc, treasury_acc, owner] = accounts else {
         return Err(ProgramError::NotEnoughAccountKeys);
     };
 
+    let allowed_destination = match data.len() {
+        0 => [0u8; 32],
+        32 => data.try_into().map_err(|_| ProgramError::InvalidInstructionData)?,
+        _ => return Err(ProgramError::InvalidInstructionData),
+    };
+
     // ==========================================================================
     // SECURITY CHECK 1: Signer validation
     // Anchor equivalent: owner: Signer<'info>
@@ -566,17 +1219,28 @@ fn create_user_deposit(
     // SECURITY CHECK 2: Program ownership for user_deposit
     // Anchor equivalent: Account<'info, UserDeposit> type enforcement
     // ==========================================================================
+    assert_not_sysvar_or_program(user_deposit_acc, program_id)?;
     if !user_deposit_acc.owned_by(program_id) {
         log!("SECURITY REJECTION: UserDeposit not owned by this program");
         return Err(ProgramError::IllegalOwner);
     }
 
+    // SECURITY: A closed deposit cannot be revived over its old data, and a
+    // live one cannot be re-initialized to zero its recorded amount.
+    let account_data = user_deposit_acc.try_borrow()?;
+    if let Some(&version) = account_data.get(USER_DEPOSIT_VERSION_OFFSET) {
+        check_not_closed(version)?;
+    }
+    check_uninitialized(&account_data)?;
+    drop(account_data);
+
     // ==========================================================================
     // SECURITY CHECK 3: Program ownership for treasury
     // Anchor equivalent: Account<'info, Treasury> type enforcement
     // ==========================================================================
     // SECURITY: Verify treasury is owned by this program.
     // This prevents linking to a fake treasury from another program.
+    assert_not_sysvar_or_program(treasury_acc, program_id)?;
     if !treasury_acc.owned_by(program_id) {
         log!("SECURITY REJECTION: Treasury not owned by this program");
         return Err(ProgramError::IllegalOwner);
@@ -587,12 +1251,12 @@ fn create_user_deposit(
     // Anchor equivalent: Implicit via Account<Treasury> type + seeds on init
     // ==========================================================================
     // SECURITY: Deserialize and verify treasury is a valid PDA.
-    let treasury_data = treasury_acc.try_borrow()?;
-    let treasury = Treasury::try_from_slice(&treasury_data)?;
-    drop(treasury_data);
+    // `load_typed` rejects any other program-owned account (e.g. a UserDeposit)
+    // whose data is merely long enough to parse as a Treasury.
+    let treasury: Treasury = load_typed(treasury_acc, program_id)?;
 
     let (expected_treasury_pda, expected_treasury_bump) =
-        derive_treasury_pda(&treasury.authority, program_id);
+        derive_treasury_pda(&treasury.authority, program_id)?;
 
     if treasury_acc.address() != &expected_treasury_pda {
         log!("SECURITY REJECTION: Treasury PDA mismatch");
@@ -608,8 +1272,11 @@ fn create_user_deposit(
     // SECURITY CHECK 5: UserDeposit PDA verification
     // Anchor equivalent: seeds = [...], bump on init
     // ==========================================================================
+    // SECURITY: A wallet key can never be the user deposit PDA.
+    assert_off_curve(user_deposit_acc.address())?;
+
     let (expected_user_deposit_pda, canonical_bump) =
-        derive_user_deposit_pda(treasury_acc.address(), owner.address(), program_id);
+        derive_user_deposit_pda(treasury_acc.address(), owner.address(), program_id)?;
 
     if user_deposit_acc.address() != &expected_user_deposit_pda {
         log!("SECURITY REJECTION: UserDeposit PDA mismatch");
@@ -622,13 +1289,16 @@ fn create_user_deposit(
         treasury: Address::new_from_array(*treasury_acc.address().as_array()),
         amount: 0,
         bump: canonical_bump,
+        deposit_count: 0,
+        withdraw_count: 0,
+        allowed_destination: Address::new_from_array(allowed_destination),
     };
 
     let mut account_data = user_deposit_acc.try_borrow_mut()?;
     user_deposit.serialize(&mut account_data)?;
 
     log!("SECURITY VERIFIED: UserDeposit created");
-    log!("  Owner: verified signer");
+    log_addr!("  Owner: {} (verified signer)", owner.address());
     log!("  Treasury: verified PDA");
     log!("  UserDeposit: verified PDA, bump={}", canonical_bump);
 
@@ -637,23 +1307,33 @@ fn create_user_deposit(
 
 /// Deposit funds with SECURE validation.
 ///
+/// `amount` lamports are transferred from the depositor to the treasury PDA
+/// through the System Program, then credited to both records.
+///
 /// # Accounts
 /// 0. `[writable]` user_deposit - The user deposit account
 /// 1. `[writable]` treasury - The treasury account
-/// 2. `[signer]` depositor - The user making the deposit
+/// 2. `[writable, signer]` depositor - The user making the deposit
 /// 3. `[]` system_program - System program
 ///
 /// # Instruction Data
 /// - amount (u64): Amount to deposit (8 bytes, little-endian)
 ///
 /// # Security Validations
+/// // SECURITY: Duplicate account rejection (user_deposit != treasury)
 /// // SECURITY: Signer validation
 /// // SECURITY: Program ownership for both accounts
+/// // SECURITY: Owner validation (depositor == user_deposit.owner)
 /// // SECURITY: PDA re-derivation for user_deposit
 /// // SECURITY: PDA re-derivation for treasury
 /// // SECURITY: Canonical bump verification for both
 /// // SECURITY: Relationship validation (user_deposit.treasury == treasury)
-/// // SECURITY: Owner validation (depositor == user_deposit.owner)
+/// // SECURITY: Depositor holds `amount` lamports; new totals are checked
+/// // before the transfer and written only after it succeeds
+///
+/// The owner check is a plain comparison, so it runs before the PDA
+/// derivations and an unauthorized caller is turned away without paying for
+/// them.
 ///
 /// ## Anchor Comparison
 /// ```ignore
@@ -667,10 +1347,16 @@ fn create_user_deposit(
 /// pub user_deposit: Account<'info, UserDeposit>,
 /// ```
 fn deposit(program_id: &Address, accounts: &[AccountView], data: &[u8]) -> ProgramResult {
-    let [user_deposit_acc, treasury_acc, depositor, _system_program] = accounts else {
+    let [user_deposit_acc, treasury_acc, depositor, system_program] = accounts else {
         return Err(ProgramError::NotEnoughAccountKeys);
     };
 
+    // ==========================================================================
+    // SECURITY CHECK 0: Duplicate account rejection
+    // Anchor equivalent: automatic duplicate mutable account check
+    // ==========================================================================
+    assert_distinct_accounts(user_deposit_acc.address(), treasury_acc.address())?;
+
     // ==========================================================================
     // SECURITY CHECK 1: Signer validation
     // Anchor equivalent: depositor: Signer<'info>
@@ -684,31 +1370,39 @@ fn deposit(program_id: &Address, accounts: &[AccountView], data: &[u8]) -> Progr
     // SECURITY CHECK 2: Program ownership validation
     // Anchor equivalent: Account<'info, T> type enforcement
     // ==========================================================================
+    assert_not_sysvar_or_program(user_deposit_acc, program_id)?;
     if !user_deposit_acc.owned_by(program_id) {
         log!("SECURITY REJECTION: UserDeposit not owned by this program");
         return Err(ProgramError::IllegalOwner);
     }
 
+    assert_not_sysvar_or_program(treasury_acc, program_id)?;
     if !treasury_acc.owned_by(program_id) {
         log!("SECURITY REJECTION: Treasury not owned by this program");
         return Err(ProgramError::IllegalOwner);
     }
 
     // Deserialize account data
-    let user_deposit_data = user_deposit_acc.try_borrow()?;
-    let mut user_deposit = UserDeposit::try_from_slice(&user_deposit_data)?;
-    drop(user_deposit_data);
+    // SECURITY: Exact-length check so neither account can stand in for the other
+    let mut user_deposit: UserDeposit = load_typed(user_deposit_acc, program_id)?;
+    let mut treasury: Treasury = load_typed(treasury_acc, program_id)?;
 
-    let treasury_data = treasury_acc.try_borrow()?;
-    let mut treasury = Treasury::try_from_slice(&treasury_data)?;
-    drop(treasury_data);
+    // ==========================================================================
+    // SECURITY CHECK 3: Owner validation (has_one = owner)
+    // Anchor equivalent: has_one = owner (or depositor == user_deposit.owner)
+    // ==========================================================================
+    // SECURITY: Cheapest check first - no derivation for a caller who is not the owner
+    if &user_deposit.owner != depositor.address() {
+        log!("SECURITY REJECTION: Depositor is not the owner");
+        return Err(SecureError::Unauthorized.into());
+    }
 
     // ==========================================================================
-    // SECURITY CHECK 3: UserDeposit PDA re-derivation
+    // SECURITY CHECK 4: UserDeposit PDA re-derivation
     // Anchor equivalent: seeds = [...], bump = user_deposit.bump
     // ==========================================================================
     let (expected_user_deposit_pda, expected_ud_bump) =
-        derive_user_deposit_pda(treasury_acc.address(), depositor.address(), program_id);
+        derive_user_deposit_pda(treasury_acc.address(), depositor.address(), program_id)?;
 
     if user_deposit_acc.address() != &expected_user_deposit_pda {
         log!("SECURITY REJECTION: UserDeposit PDA mismatch");
@@ -721,11 +1415,11 @@ fn deposit(program_id: &Address, accounts: &[AccountView], data: &[u8]) -> Progr
     }
 
     // ==========================================================================
-    // SECURITY CHECK 4: Treasury PDA re-derivation
+    // SECURITY CHECK 5: Treasury PDA re-derivation
     // Anchor equivalent: seeds = [...], bump = treasury.bump
     // ==========================================================================
     let (expected_treasury_pda, expected_t_bump) =
-        derive_treasury_pda(&treasury.authority, program_id);
+        derive_treasury_pda(&treasury.authority, program_id)?;
 
     if treasury_acc.address() != &expected_treasury_pda {
         log!("SECURITY REJECTION: Treasury PDA mismatch");
@@ -738,7 +1432,7 @@ fn deposit(program_id: &Address, accounts: &[AccountView], data: &[u8]) -> Progr
     }
 
     // ==========================================================================
-    // SECURITY CHECK 5: Relationship validation (has_one = treasury)
+    // SECURITY CHECK 6: Relationship validation (has_one = treasury)
     // Anchor equivalent: has_one = treasury
     // ==========================================================================
     if &user_deposit.treasury != treasury_acc.address() {
@@ -746,15 +1440,6 @@ fn deposit(program_id: &Address, accounts: &[AccountView], data: &[u8]) -> Progr
         return Err(SecureError::InvalidTreasury.into());
     }
 
-    // ==========================================================================
-    // SECURITY CHECK 6: Owner validation (has_one = owner)
-    // Anchor equivalent: has_one = owner (or depositor == user_deposit.owner)
-    // ==========================================================================
-    if &user_deposit.owner != depositor.address() {
-        log!("SECURITY REJECTION: Depositor is not the owner");
-        return Err(SecureError::Unauthorized.into());
-    }
-
     // Parse amount from instruction data
     if data.len() < 8 {
         return Err(ProgramError::InvalidInstructionData);
@@ -763,12 +1448,36 @@ fn deposit(program_id: &Address, accounts: &[AccountView], data: &[u8]) -> Progr
         data[0..8].try_into().map_err(|_| ProgramError::InvalidInstructionData)?,
     );
 
-    // All security checks passed - update balances
-    user_deposit.amount =
-        user_deposit.amount.checked_add(amount).ok_or(ProgramError::ArithmeticOverflow)?;
+    // ==========================================================================
+    // SECURITY CHECK 7: Depositor can fund the transfer
+    // ==========================================================================
+    if depositor.lamports() < amount {
+        log_amount!("Deposit available: {}", depositor.lamports());
+        log_amount!("Deposit requested: {}", amount);
+        reject!(
+            CommonError::InsufficientBalance,
+            "SECURITY REJECTION: Insufficient depositor lamports"
+        );
+    }
+
+    // SECURITY: Every new total is computed before the transfer, so an
+    // overflow fails the instruction before any lamports move
+    let user_amount = user_deposit.amount.add_or(amount, ProgramError::ArithmeticOverflow)?;
+    let deposit_count =
+        user_deposit.deposit_count.checked_add(1).ok_or(ProgramError::ArithmeticOverflow)?;
+    let treasury_balance = treasury.balance.add_or(amount, ProgramError::ArithmeticOverflow)?;
+
+    if system_program.address() != &SYSTEM_PROGRAM_ID {
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    // Anchor equivalent: system_program::transfer(depositor -> treasury, amount)
+    transfer(depositor, treasury_acc, amount)?;
 
-    treasury.balance =
-        treasury.balance.checked_add(amount).ok_or(ProgramError::ArithmeticOverflow)?;
+    // SECURITY: The records are credited only once the lamports have arrived
+    user_deposit.amount = user_amount;
+    user_deposit.deposit_count = deposit_count;
+    treasury.balance = treasury_balance;
 
     // Write updated data
     let mut user_deposit_data = user_deposit_acc.try_borrow_mut()?;
@@ -778,7 +1487,7 @@ fn deposit(program_id: &Address, accounts: &[AccountView], data: &[u8]) -> Progr
     let mut treasury_data = treasury_acc.try_borrow_mut()?;
     treasury.serialize(&mut treasury_data)?;
 
-    log!("SECURITY VERIFIED: Deposit of {} approved", amount);
+    log_amount!("SECURITY VERIFIED: Deposit of {} approved", amount);
     log!("  PDA: both accounts verified");
     log!("  Bumps: both canonical");
     log!("  Relationships: verified");
@@ -788,19 +1497,27 @@ fn deposit(program_id: &Address, accounts: &[AccountView], data: &[u8]) -> Progr
 
 /// Withdraw funds with COMPREHENSIVE SECURITY VALIDATION.
 ///
-/// This is the most security-critical instruction as it moves funds.
-/// All 7 security checks must pass before any withdrawal occurs.
+/// This is the most security-critical instruction as it moves funds:
+/// `amount` lamports are debited from the treasury PDA and credited to the
+/// withdrawer, or to `destination` when one is passed. All security checks
+/// must pass before any withdrawal occurs.
 ///
 /// # Accounts
 /// 0. `[writable]` user_deposit - The user deposit account
 /// 1. `[writable]` treasury - The treasury account
-/// 2. `[signer]` withdrawer - The user requesting withdrawal
+/// 2. `[writable, signer]` withdrawer - The user requesting withdrawal
 /// 3. `[]` system_program - System program
+/// 4. `[writable, signer]` destination (optional) - Wallet credited instead of
+///    the withdrawer; must co-sign unless it is the withdrawer
 ///
 /// # Instruction Data
 /// - amount (u64): Amount to withdraw (8 bytes, little-endian)
+/// - auto_close (u8, optional): `1` closes the user deposit when this
+///   withdrawal empties it, returning its rent to the withdrawer; `0` or
+///   absent keeps the record
 ///
 /// # Security Validations (ALL REQUIRED)
+/// 0. Duplicate account rejection - user_deposit and treasury must differ
 /// 1. Signer validation - withdrawer must sign
 /// 2. Program ownership - both accounts owned by this program
 /// 3. UserDeposit PDA re-derivation - verify account is genuine
@@ -809,6 +1526,17 @@ fn deposit(program_id: &Address, accounts: &[AccountView], data: &[u8]) -> Progr
 /// 6. Relationship validation - user_deposit.treasury == treasury
 /// 7. Authority validation - withdrawer == user_deposit.owner
 /// 8. Sufficient funds check
+/// 9. Rent exemption - the treasury stays rent-exempt after the debit
+///
+/// A separate destination must also sign (`Unauthorized` otherwise), so a
+/// withdrawal only ever lands in a wallet the owner proves they control. A
+/// deposit created with an `allowed_destination` pays out only to that
+/// address, whether it is passed as `destination` or is the withdrawer
+/// (`Unauthorized` otherwise).
+///
+/// Checks 1-7 are written as `SecurityChecks` chains. Each failure keeps this
+/// program's code via `.or()`: `InvalidPda` (0x1000), `InvalidBump` (0x1001),
+/// `InvalidTreasury` (0x1002) and `Unauthorized` (0x1003).
 ///
 /// ## Anchor Comparison
 /// This shows exactly what Anchor does behind the scenes with:
@@ -828,108 +1556,125 @@ fn deposit(program_id: &Address, accounts: &[AccountView], data: &[u8]) -> Progr
 ///     bump = treasury.bump,
 /// )]
 /// pub treasury: Account<'info, Treasury>,
+///
+/// #[account(
+///     mut,
+///     constraint = user_deposit.allowed_destination == Pubkey::default()
+///         || destination.key() == user_deposit.allowed_destination
+///         @ PdaError::UnauthorizedAccess
+/// )]
+/// pub destination: AccountInfo<'info>,
 /// ```
 fn withdraw(program_id: &Address, accounts: &[AccountView], data: &[u8]) -> ProgramResult {
-    let [user_deposit_acc, treasury_acc, withdrawer, _system_program] = accounts else {
-        return Err(ProgramError::NotEnoughAccountKeys);
-    };
+    let report = process_withdraw(program_id, accounts, data)?;
 
-    // ==========================================================================
-    // SECURITY CHECK 1: Signer validation
-    // Anchor equivalent: withdrawer: Signer<'info>
-    // ==========================================================================
-    if !withdrawer.is_signer() {
-        log!("SECURITY REJECTION: Withdrawer must be a signer");
-        return Err(ProgramError::MissingRequiredSignature);
-    }
-
-    // ==========================================================================
-    // SECURITY CHECK 2: Program ownership validation
-    // Anchor equivalent: Account<'info, T> type enforcement
-    // ==========================================================================
-    if !user_deposit_acc.owned_by(program_id) {
-        log!("SECURITY REJECTION: UserDeposit not owned by this program");
-        return Err(ProgramError::IllegalOwner);
-    }
-
-    if !treasury_acc.owned_by(program_id) {
-        log!("SECURITY REJECTION: Treasury not owned by this program");
-        return Err(ProgramError::IllegalOwner);
-    }
-
-    // Deserialize account data
-    let user_deposit_data = user_deposit_acc.try_borrow()?;
-    let mut user_deposit = UserDeposit::try_from_slice(&user_deposit_data)?;
-    drop(user_deposit_data);
-
-    let treasury_data = treasury_acc.try_borrow()?;
-    let mut treasury = Treasury::try_from_slice(&treasury_data)?;
-    drop(treasury_data);
+    // Machine-readable summary for clients and tests, after the human lines
+    let summary = report.summary();
+    log!("{}", core::str::from_utf8(&summary).unwrap_or(CHECK_SUMMARY_PREFIX));
 
-    // ==========================================================================
-    // SECURITY CHECK 3: UserDeposit PDA re-derivation
-    // Anchor equivalent: seeds = [USER_DEPOSIT_SEED, treasury.key(), withdrawer.key()]
-    // ==========================================================================
-    let (expected_user_deposit_pda, expected_ud_bump) =
-        derive_user_deposit_pda(treasury_acc.address(), withdrawer.address(), program_id);
+    Ok(())
+}
 
-    if user_deposit_acc.address() != &expected_user_deposit_pda {
-        log!("SECURITY REJECTION: UserDeposit PDA mismatch");
-        log!("  This could indicate a fake user_deposit account");
-        return Err(SecureError::InvalidPda.into());
-    }
+/// Body of [`withdraw`], returning which checks passed for the summary line.
+fn process_withdraw(
+    program_id: &Address,
+    accounts: &[AccountView],
+    data: &[u8],
+) -> Result<SecurityReport, ProgramError> {
+    let (user_deposit_acc, treasury_acc, withdrawer, destination) = match accounts {
+        [user_deposit_acc, treasury_acc, withdrawer, _system_program] => {
+            (user_deposit_acc, treasury_acc, withdrawer, withdrawer)
+        }
+        [user_deposit_acc, treasury_acc, withdrawer, _system_program, destination] => {
+            (user_deposit_acc, treasury_acc, withdrawer, destination)
+        }
+        _ => return Err(ProgramError::NotEnoughAccountKeys),
+    };
+    let mut report = SecurityReport::default();
 
     // ==========================================================================
-    // SECURITY CHECK 4: UserDeposit canonical bump verification
-    // Anchor equivalent: bump = user_deposit.bump
+    // SECURITY CHECK 0: Duplicate account rejection
+    // Anchor equivalent: automatic duplicate mutable account check
     // ==========================================================================
-    if user_deposit.bump != expected_ud_bump {
-        log!("SECURITY REJECTION: UserDeposit non-canonical bump");
-        log!("  Stored: {}, Expected: {}", user_deposit.bump, expected_ud_bump);
-        return Err(SecureError::InvalidBump.into());
-    }
+    assert_distinct_accounts(user_deposit_acc.address(), treasury_acc.address())?;
 
     // ==========================================================================
-    // SECURITY CHECK 5: Treasury PDA re-derivation
-    // Anchor equivalent: seeds = [TREASURY_SEED, treasury.authority.as_ref()]
+    // SECURITY CHECKS 1-2: Signer and program ownership
+    // Anchor equivalent: withdrawer: Signer<'info>, Account<'info, T>
     // ==========================================================================
-    let (expected_treasury_pda, expected_t_bump) =
-        derive_treasury_pda(&treasury.authority, program_id);
-
-    if treasury_acc.address() != &expected_treasury_pda {
-        log!("SECURITY REJECTION: Treasury PDA mismatch");
-        log!("  This could indicate a fake treasury account");
-        return Err(SecureError::InvalidPda.into());
+    // SECURITY: Account-level checks run before any account data is parsed.
+    SecurityChecks::new(program_id)
+        .signer(withdrawer)
+        .not_sysvar_or_program(user_deposit_acc)
+        .owned(user_deposit_acc)
+        .not_sysvar_or_program(treasury_acc)
+        .owned(treasury_acc)
+        .run()
+        .inspect_err(|_| log!("SECURITY REJECTION: Withdraw signer/ownership check failed"))?;
+    report.pass(WithdrawCheck::Signer);
+    report.pass(WithdrawCheck::ProgramOwnership);
+
+    // SECURITY: Dual signature for a separate destination - the owner's
+    // signature alone cannot send funds to a wallet nobody proved control of
+    if destination.address() != withdrawer.address() {
+        assert_distinct_accounts(destination.address(), treasury_acc.address())?;
+        assert_distinct_accounts(destination.address(), user_deposit_acc.address())?;
+        if !destination.is_signer() {
+            reject!(
+                SecureError::Unauthorized,
+                "SECURITY REJECTION: Withdraw destination did not sign"
+            );
+        }
     }
 
-    // ==========================================================================
-    // SECURITY CHECK 6: Treasury canonical bump verification
-    // Anchor equivalent: bump = treasury.bump
-    // ==========================================================================
-    if treasury.bump != expected_t_bump {
-        log!("SECURITY REJECTION: Treasury non-canonical bump");
-        log!("  Stored: {}, Expected: {}", treasury.bump, expected_t_bump);
-        return Err(SecureError::InvalidBump.into());
-    }
+    // Deserialize account data
+    // SECURITY: Exact-length check so neither account can stand in for the other
+    let mut user_deposit: UserDeposit = load_typed(user_deposit_acc, program_id)?;
+    let mut treasury: Treasury = load_typed(treasury_acc, program_id)?;
 
     // ==========================================================================
-    // SECURITY CHECK 7: Relationship validation (has_one = treasury)
-    // Anchor equivalent: has_one = treasury
+    // SECURITY CHECKS 3-8: PDAs, canonical bumps, and relationships
+    // Anchor equivalent:
+    //   seeds = [USER_DEPOSIT_SEED, treasury.key(), withdrawer.key()], bump = user_deposit.bump
+    //   seeds = [TREASURY_SEED, treasury.authority.as_ref()], bump = treasury.bump
+    //   has_one = treasury, has_one = owner @ PdaError::UnauthorizedAccess
     // ==========================================================================
-    if &user_deposit.treasury != treasury_acc.address() {
-        log!("SECURITY REJECTION: UserDeposit treasury mismatch");
-        log!("  Stored treasury doesn't match provided treasury");
-        return Err(SecureError::InvalidTreasury.into());
+    SecurityChecks::new(program_id)
+        .pda_address(
+            &[USER_DEPOSIT_SEED, treasury_acc.address().as_ref(), withdrawer.address().as_ref()],
+            user_deposit_acc,
+        )
+        .or(SecureError::InvalidPda)
+        .bump(user_deposit.bump)
+        .or(SecureError::InvalidBump)
+        .pda_address(&[TREASURY_SEED, treasury.authority.as_ref()], treasury_acc)
+        .or(SecureError::InvalidPda)
+        .bump(treasury.bump)
+        .or(SecureError::InvalidBump)
+        .has_one(&user_deposit.treasury, treasury_acc)
+        .or(SecureError::InvalidTreasury)
+        .has_one(&user_deposit.owner, withdrawer)
+        .or(SecureError::Unauthorized)
+        .run()
+        .inspect_err(|_| log!("SECURITY REJECTION: Withdraw PDA/relationship check failed"))?;
+    for check in [
+        WithdrawCheck::UserDepositPda,
+        WithdrawCheck::UserDepositBump,
+        WithdrawCheck::TreasuryPda,
+        WithdrawCheck::TreasuryBump,
+        WithdrawCheck::TreasuryRelationship,
+        WithdrawCheck::OwnerAuthorization,
+    ] {
+        report.pass(check);
     }
 
-    // ==========================================================================
-    // SECURITY CHECK 8: Authority validation (has_one = owner)
-    // Anchor equivalent: has_one = owner @ PdaError::UnauthorizedAccess
-    // ==========================================================================
-    if &user_deposit.owner != withdrawer.address() {
-        log!("SECURITY REJECTION: Withdrawer is not the owner");
-        log!("  Only the deposit owner can withdraw");
-        return Err(SecureError::Unauthorized.into());
+    // SECURITY: An allowlisted deposit only pays out to its destination
+    // Anchor equivalent: constraint on `destination` @ PdaError::UnauthorizedAccess
+    if !user_deposit.allows_destination(destination.address()) {
+        reject!(
+            SecureError::Unauthorized,
+            "SECURITY REJECTION: Withdraw destination not allowlisted"
+        );
     }
 
     // Parse amount from instruction data
@@ -939,22 +1684,35 @@ fn withdraw(program_id: &Address, accounts: &[AccountView], data: &[u8]) -> Prog
     let amount = u64::from_le_bytes(
         data[0..8].try_into().map_err(|_| ProgramError::InvalidInstructionData)?,
     );
+    let auto_close = match data.get(8) {
+        None | Some(0) => false,
+        Some(1) => true,
+        Some(_) => return Err(ProgramError::InvalidInstructionData),
+    };
 
     // ==========================================================================
     // SECURITY CHECK 9: Sufficient funds
     // ==========================================================================
     if user_deposit.amount < amount {
-        log!("SECURITY REJECTION: Insufficient funds");
-        log!("  Available: {}, Requested: {}", user_deposit.amount, amount);
-        return Err(SecureError::InsufficientFunds.into());
+        log_amount!("Withdraw available: {}", user_deposit.amount);
+        log_amount!("Withdraw requested: {}", amount);
+        reject!(CommonError::InsufficientBalance, "SECURITY REJECTION: Insufficient funds");
     }
+    report.pass(WithdrawCheck::SufficientFunds);
 
-    // All security checks passed - proceed with withdrawal
-    user_deposit.amount =
-        user_deposit.amount.checked_sub(amount).ok_or(ProgramError::ArithmeticOverflow)?;
+    // ==========================================================================
+    // SECURITY CHECK 10: Treasury stays rent-exempt
+    // ==========================================================================
+    // SECURITY: The lamports leave the PDA directly, so nothing else stops a
+    // withdrawal from draining it below the rent-exempt minimum.
+    let treasury_lamports =
+        rent_exempt_after_debit(treasury_acc.lamports(), amount, TREASURY_SIZE)?;
+    let destination_lamports =
+        destination.lamports().add_or(amount, ProgramError::ArithmeticOverflow)?;
+    report.pass(WithdrawCheck::RentExemption);
 
-    treasury.balance =
-        treasury.balance.checked_sub(amount).ok_or(ProgramError::ArithmeticOverflow)?;
+    // All security checks passed - proceed with withdrawal
+    apply_withdraw(&mut user_deposit, &mut treasury, amount)?;
 
     // Write updated data
     let mut user_deposit_data = user_deposit_acc.try_borrow_mut()?;
@@ -963,9 +1721,20 @@ fn withdraw(program_id: &Address, accounts: &[AccountView], data: &[u8]) -> Prog
 
     let mut treasury_data = treasury_acc.try_borrow_mut()?;
     treasury.serialize(&mut treasury_data)?;
+    drop(treasury_data);
+
+    // Move the lamports: the program owns the treasury, so it can debit it directly
+    treasury_acc.set_lamports(treasury_lamports);
+    destination.set_lamports(destination_lamports);
 
-    log!("SECURITY VERIFIED: Withdrawal of {} approved", amount);
-    log!("  All 9 security checks passed:");
+    // The owner (verified above) gets the rent back, not the destination
+    if auto_close && user_deposit.amount == 0 {
+        close_user_deposit(user_deposit_acc, withdrawer)?;
+        log!("UserDeposit emptied and closed, rent returned to owner");
+    }
+
+    log_amount!("SECURITY VERIFIED: Withdrawal of {} approved", amount);
+    log!("  All 10 security checks passed:");
     log!("  [1] Signer validation");
     log!("  [2] Program ownership");
     log!("  [3] UserDeposit PDA");
@@ -975,91 +1744,2367 @@ fn withdraw(program_id: &Address, accounts: &[AccountView], data: &[u8]) -> Prog
     log!("  [7] Treasury relationship");
     log!("  [8] Owner authorization");
     log!("  [9] Sufficient funds");
+    log!("  [10] Treasury rent exemption");
 
-    Ok(())
+    Ok(report)
 }
 
-// =============================================================================
-// TESTS
-// =============================================================================
+/// Rewrites a stored bump to the canonical bump if the account is genuine.
+///
+/// Returns `Ok(true)` if the bump was rewritten and `Ok(false)` if it was
+/// already canonical. The account is genuine only if `account_address` is the
+/// canonical derivation of the seeds stored in its own data; anything else
+/// cannot be healed and is rejected with `InvalidPda`.
+///
+/// // SECURITY: The address check comes first. A record whose address was
+/// // derived from a non-canonical bump is a *different* account from the
+/// // canonical PDA, so rewriting its bump would legitimise a forgery.
+pub fn heal_account_bump(
+    program_id: &Address,
+    account_address: &Address,
+    kind: u8,
+    data: &mut [u8],
+) -> Result<bool, ProgramError> {
+    let (expected_pda, canonical_bump, stored_bump) = match kind {
+        HEAL_KIND_TREASURY => {
+            let treasury = Treasury::decode(data)?;
+            let (pda, bump) = derive_treasury_pda(&treasury.authority, program_id)?;
+            (pda, bump, treasury.bump)
+        }
+        HEAL_KIND_USER_DEPOSIT => {
+            let user_deposit = UserDeposit::decode(data)?;
+            let (pda, bump) =
+                derive_user_deposit_pda(&user_deposit.treasury, &user_deposit.owner, program_id)?;
+            (pda, bump, user_deposit.bump)
+        }
+        _ => return Err(ProgramError::InvalidInstructionData),
+    };
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    if account_address != &expected_pda {
+        log!("HEAL REJECTED: Address is not the canonical PDA for its stored seeds");
+        return Err(SecureError::InvalidPda.into());
+    }
 
-    /// Test Treasury serialization and deserialization roundtrip.
-    #[test]
-    fn test_treasury_serialization() {
-        let treasury = Treasury {
-            authority: Address::new_from_array([1u8; 32]),
-            balance: 1_000_000_000,
-            bump: 255,
-        };
+    if stored_bump == canonical_bump {
+        return Ok(false);
+    }
 
-        let mut buffer = [0u8; TREASURY_SIZE];
-        treasury.serialize(&mut buffer).unwrap();
+    log!("HEAL: Stored bump {} -> canonical bump {}", stored_bump, canonical_bump);
+    match kind {
+        HEAL_KIND_TREASURY => {
+            let mut treasury = Treasury::decode(data)?;
+            treasury.bump = canonical_bump;
+            treasury.serialize(data)?;
+        }
+        _ => {
+            let mut user_deposit = UserDeposit::decode(data)?;
+            user_deposit.bump = canonical_bump;
+            user_deposit.serialize(data)?;
+        }
+    }
+
+    Ok(true)
+}
 
-        let deserialized = Treasury::try_from_slice(&buffer).unwrap();
-        assert_eq!(deserialized.authority, treasury.authority);
-        assert_eq!(deserialized.balance, treasury.balance);
-        assert_eq!(deserialized.bump, treasury.bump);
+/// Heals the stored bump of `account` as `kind`, first migrating a record
+/// still in the vulnerable program's layout. Shared by `heal_bump` and
+/// `repair_bump`.
+///
+/// A current-layout record goes straight to `heal_account_bump`. A record of
+/// exactly [`LEGACY_TREASURY_SIZE`] or [`LEGACY_USER_DEPOSIT_SIZE`] bytes is
+/// decoded with `decode_legacy`, checked against the canonical PDA of its
+/// stored seeds, grown to the current size with `funding` (payer, system
+/// program) covering any extra rent, rewritten in the current layout and then
+/// healed.
+///
+/// // SECURITY: A legacy record passes the same address check before it is
+/// // funded or resized, so a forgery is rejected untouched in either layout.
+fn heal_account(
+    program_id: &Address,
+    account: &AccountView,
+    funding: &[AccountView],
+    kind: u8,
+) -> Result<bool, ProgramError> {
+    assert_not_sysvar_or_program(account, program_id)?;
+    if !account.owned_by(program_id) {
+        log!("SECURITY REJECTION: Account not owned by this program");
+        return Err(ProgramError::IllegalOwner);
     }
 
-    /// Test UserDeposit serialization and deserialization roundtrip.
-    #[test]
-    fn test_user_deposit_serialization() {
-        let user_deposit = UserDeposit {
-            owner: Address::new_from_array([2u8; 32]),
-            treasury: Address::new_from_array([3u8; 32]),
-            amount: 500_000_000,
-            bump: 254,
-        };
+    let (legacy_size, size) = match kind {
+        HEAL_KIND_TREASURY => (LEGACY_TREASURY_SIZE, TREASURY_SIZE),
+        HEAL_KIND_USER_DEPOSIT => (LEGACY_USER_DEPOSIT_SIZE, USER_DEPOSIT_SIZE),
+        _ => return Err(ProgramError::InvalidInstructionData),
+    };
 
-        let mut buffer = [0u8; USER_DEPOSIT_SIZE];
-        user_deposit.serialize(&mut buffer).unwrap();
+    if account.data_len() != legacy_size {
+        let mut account_data = account.try_borrow_mut()?;
+        return heal_account_bump(program_id, account.address(), kind, &mut account_data);
+    }
+
+    let legacy_data = account.try_borrow()?;
+    let (expected_pda, held) = if kind == HEAL_KIND_TREASURY {
+        let treasury = Treasury::decode_legacy(&legacy_data)?;
+        (derive_treasury_pda(&treasury.authority, program_id)?.0, treasury.balance)
+    } else {
+        let user_deposit = UserDeposit::decode_legacy(&legacy_data)?;
+        (derive_user_deposit_pda(&user_deposit.treasury, &user_deposit.owner, program_id)?.0, 0)
+    };
+    drop(legacy_data);
 
-        let deserialized = UserDeposit::try_from_slice(&buffer).unwrap();
-        assert_eq!(deserialized.owner, user_deposit.owner);
-        assert_eq!(deserialized.treasury, user_deposit.treasury);
-        assert_eq!(deserialized.amount, user_deposit.amount);
-        assert_eq!(deserialized.bump, user_deposit.bump);
+    if account.address() != &expected_pda {
+        log!("HEAL REJECTED: Address is not the canonical PDA for its stored seeds");
+        return Err(SecureError::InvalidPda.into());
     }
 
-    /// Test SecureError conversion to ProgramError.
-    #[test]
-    fn test_error_conversion() {
-        let err: ProgramError = SecureError::InvalidPda.into();
-        assert!(matches!(err, ProgramError::Custom(0x1000)));
+    // The treasury's lamports also back its tracked balance, which must stay
+    // withdrawable after the account grows
+    let minimum = rent_exempt_minimum(size)?.add_or(held, ProgramError::ArithmeticOverflow)?;
+    let shortfall = rent_shortfall(account.lamports(), minimum);
+    if shortfall > 0 {
+        let [payer, system_program, ..] = funding else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+        if !payer.is_signer() {
+            log!("SECURITY REJECTION: Rent payer must sign");
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+        if system_program.address() != &SYSTEM_PROGRAM_ID {
+            return Err(ProgramError::IncorrectProgramId);
+        }
+        transfer(payer, account, shortfall)?;
+    }
 
-        let err: ProgramError = SecureError::InvalidBump.into();
-        assert!(matches!(err, ProgramError::Custom(0x1001)));
+    account.resize(size)?;
+    let mut account_data = account.try_borrow_mut()?;
+    if kind == HEAL_KIND_TREASURY {
+        Treasury::decode_legacy(&account_data[..legacy_size])?.serialize(&mut account_data)?;
+    } else {
+        UserDeposit::decode_legacy(&account_data[..legacy_size])?.serialize(&mut account_data)?;
+    }
+    log!("HEAL: Migrated legacy record to layout version {}", SCHEMA_VERSION);
 
-        let err: ProgramError = SecureError::InvalidTreasury.into();
-        assert!(matches!(err, ProgramError::Custom(0x1002)));
+    heal_account_bump(program_id, account.address(), kind, &mut account_data)?;
+    Ok(true)
+}
 
-        let err: ProgramError = SecureError::Unauthorized.into();
-        assert!(matches!(err, ProgramError::Custom(0x1003)));
+/// Heal a non-canonical bump left behind by the vulnerable program.
+///
+/// For teaching migrations: the vulnerable program stored whatever bump the
+/// client passed in. After upgrading the program in place to the secure code,
+/// every later instruction rejects those accounts with `InvalidBump`. This
+/// instruction repairs them instead of forcing users to close and recreate.
+///
+/// Accounts still in the vulnerable program's layout, which has no version
+/// byte and fewer fields, are migrated to the current layout on the way.
+///
+/// Permissionless: the only possible write replaces the stored bump with the
+/// canonical one re-derived on-chain, so no caller can choose the outcome.
+///
+/// # Accounts
+/// 0. `[writable]` account - Treasury or UserDeposit owned by this program
+/// 1. `[signer, writable]` payer - Funds the extra rent of a legacy account
+///    (only needed when it is short of the current size's minimum)
+/// 2. `[]` system_program - System Program (with the payer)
+///
+/// # Instruction Data
+/// - kind (u8): `0` = Treasury, `1` = UserDeposit
+///
+/// # Security Validations
+/// // SECURITY: Program ownership - account owned by this program
+/// // SECURITY: PDA re-derivation from the account's own stored seeds
+/// // SECURITY: Only the canonical bump is ever written
+fn heal_bump(program_id: &Address, accounts: &[AccountView], data: &[u8]) -> ProgramResult {
+    let [account, funding @ ..] = accounts else {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
 
-        let err: ProgramError = SecureError::NotInitialized.into();
-        assert!(matches!(err, ProgramError::Custom(0x1004)));
+    let kind = *data.first().ok_or(ProgramError::InvalidInstructionData)?;
 
-        let err: ProgramError = SecureError::InsufficientFunds.into();
-        assert!(matches!(err, ProgramError::Custom(0x1005)));
+    if heal_account(program_id, account, funding, kind)? {
+        log!("HEALED: Stored bump is now canonical");
+    } else {
+        log!("HEAL: Stored bump already canonical, nothing to do");
     }
 
-    /// Test Treasury deserialization with insufficient data.
-    #[test]
-    fn test_treasury_invalid_data_length() {
-        let short_buffer = [0u8; 10];
-        let result = Treasury::try_from_slice(&short_buffer);
-        assert!(result.is_err());
+    Ok(())
+}
+
+/// Repair the stored bump of a single account, telling its type from its layout.
+///
+/// `heal_bump` takes the account kind from the client. This instruction
+/// needs no instruction data: an account of exactly `TREASURY_SIZE` or
+/// `LEGACY_TREASURY_SIZE` bytes is repaired as a Treasury and one of exactly
+/// `USER_DEPOSIT_SIZE` or `LEGACY_USER_DEPOSIT_SIZE` bytes as a UserDeposit,
+/// so a remediation script can send the same instruction for every account
+/// the program owns. The repair itself is `heal_account`, shared with
+/// `heal_bump`, so legacy records are migrated the same way.
+///
+/// # Accounts
+/// 0. `[writable]` account - Treasury or UserDeposit owned by this program
+/// 1. `[signer, writable]` payer - Funds the extra rent of a legacy account
+///    (only needed when it is short of the current size's minimum)
+/// 2. `[]` system_program - System Program (with the payer)
+///
+/// # Instruction Data
+/// - (empty)
+///
+/// # Security Validations
+/// // SECURITY: Program ownership - account owned by this program
+/// // SECURITY: Exact layout length selects the type (AccountTypeMismatch otherwise)
+/// // SECURITY: Address must be the canonical PDA of the stored seeds (InvalidPda)
+/// // SECURITY: Only the canonical bump is ever written
+fn repair_bump(program_id: &Address, accounts: &[AccountView], _data: &[u8]) -> ProgramResult {
+    let [account, funding @ ..] = accounts else {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    let kind = match account.data_len() {
+        TREASURY_SIZE | LEGACY_TREASURY_SIZE => HEAL_KIND_TREASURY,
+        USER_DEPOSIT_SIZE | LEGACY_USER_DEPOSIT_SIZE => HEAL_KIND_USER_DEPOSIT,
+        _ => return Err(CommonError::AccountTypeMismatch.into()),
+    };
+
+    if heal_account(program_id, account, funding, kind)? {
+        log!("REPAIRED: Stored bump is now canonical");
+    } else {
+        log!("REPAIR: Stored bump already canonical, nothing to do");
     }
 
-    /// Test UserDeposit deserialization with insufficient data.
-    #[test]
-    fn test_user_deposit_invalid_data_length() {
-        let short_buffer = [0u8; 50];
-        let result = UserDeposit::try_from_slice(&short_buffer);
-        assert!(result.is_err());
+    Ok(())
+}
+
+/// Add a co-authority to a treasury.
+///
+/// # Accounts
+/// 0. `[writable]` treasury - The treasury PDA account
+/// 1. `[signer]` authority - The treasury's primary authority
+///
+/// # Instruction Data
+/// - co_authority (Address): 32 bytes
+///
+/// # Security Validations
+/// // SECURITY: Signer validation - authority must sign
+/// // SECURITY: Program ownership and exact Treasury layout
+/// // SECURITY: PDA derivation and canonical bump from the stored authority
+/// // SECURITY: Primary only - a co-authority cannot grow or shrink the list
+///
+/// ## Anchor Comparison
+/// ```ignore
+/// #[account(
+///     mut,
+///     seeds = [TREASURY_SEED, authority.key().as_ref()],
+///     bump = treasury.bump,
+///     has_one = authority @ PdaError::UnauthorizedAccess,
+/// )]
+/// pub treasury: Account<'info, Treasury>,
+/// pub authority: Signer<'info>,
+/// ```
+fn add_co_authority(program_id: &Address, accounts: &[AccountView], data: &[u8]) -> ProgramResult {
+    update_co_authorities(program_id, accounts, data, Treasury::add_co_authority)
+}
+
+/// Remove a co-authority from a treasury.
+///
+/// Accounts, instruction data and validations are the same as
+/// `add_co_authority`.
+fn remove_co_authority(
+    program_id: &Address,
+    accounts: &[AccountView],
+    data: &[u8],
+) -> ProgramResult {
+    update_co_authorities(program_id, accounts, data, Treasury::remove_co_authority)
+}
+
+fn update_co_authorities(
+    program_id: &Address,
+    accounts: &[AccountView],
+    data: &[u8],
+    update: fn(&mut Treasury, &Address) -> ProgramResult,
+) -> ProgramResult {
+    let [treasury_acc, authority] = accounts else {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    let key = Address::new_from_array(
+        data.get(0..32)
+            .ok_or(ProgramError::InvalidInstructionData)?
+            .try_into()
+            .map_err(|_| ProgramError::InvalidInstructionData)?,
+    );
+
+    SecurityChecks::new(program_id)
+        .signer(authority)
+        .not_sysvar_or_program(treasury_acc)
+        .owned(treasury_acc)
+        .run()
+        .inspect_err(|_| log!("SECURITY REJECTION: Co-authority signer/ownership check failed"))?;
+
+    let mut treasury: Treasury = load_typed(treasury_acc, program_id)?;
+
+    // SECURITY: Only the primary authority manages co-authorities
+    SecurityChecks::new(program_id)
+        .pda(&[TREASURY_SEED, treasury.authority.as_ref()], treasury_acc, treasury.bump)
+        .has_one(&treasury.authority, authority)
+        .or(SecureError::Unauthorized)
+        .run()
+        .inspect_err(|_| log!("SECURITY REJECTION: Signer is not the primary authority"))?;
+
+    update(&mut treasury, &key)?;
+
+    let mut treasury_data = treasury_acc.try_borrow_mut()?;
+    treasury.serialize(&mut treasury_data)?;
+
+    log!("SECURITY VERIFIED: Co-authorities updated by primary authority");
+
+    Ok(())
+}
+
+/// Sweep lamports the treasury holds beyond its rent and tracked balance.
+///
+/// Lamports sent straight to the treasury PDA, rather than through `deposit`,
+/// back no UserDeposit, so no withdrawal can ever reach them. Any treasury
+/// authority - the primary or a co-authority - may move that surplus out.
+///
+/// # Accounts
+/// 0. `[writable]` treasury - The treasury PDA account
+/// 1. `[signer]` authority - The primary authority or a co-authority
+/// 2. `[writable]` destination - Receives the surplus
+///
+/// # Instruction Data
+/// - (empty)
+///
+/// # Security Validations
+/// // SECURITY: Signer validation - authority must sign
+/// // SECURITY: Program ownership and exact Treasury layout
+/// // SECURITY: PDA derivation and canonical bump from the stored authority
+/// // SECURITY: `is_treasury_authority` - primary or co-authority only
+/// // SECURITY: Only lamports above rent plus `balance` move, so every
+/// // deposit stays backed
+///
+/// ## Anchor Comparison
+/// ```ignore
+/// #[account(
+///     mut,
+///     seeds = [TREASURY_SEED, treasury.authority.as_ref()],
+///     bump = treasury.bump,
+///     constraint = is_treasury_authority(&treasury, authority.key)
+///         @ PdaError::UnauthorizedAccess,
+/// )]
+/// pub treasury: Account<'info, Treasury>,
+/// pub authority: Signer<'info>,
+/// ```
+fn sweep_surplus(program_id: &Address, accounts: &[AccountView], _data: &[u8]) -> ProgramResult {
+    let [treasury_acc, authority, destination] = accounts else {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    SecurityChecks::new(program_id)
+        .signer(authority)
+        .not_sysvar_or_program(treasury_acc)
+        .owned(treasury_acc)
+        .run()
+        .inspect_err(|_| log!("SECURITY REJECTION: Sweep signer/ownership check failed"))?;
+    assert_distinct_accounts(destination.address(), treasury_acc.address())?;
+
+    let treasury: Treasury = load_typed(treasury_acc, program_id)?;
+
+    SecurityChecks::new(program_id)
+        .pda(&[TREASURY_SEED, treasury.authority.as_ref()], treasury_acc, treasury.bump)
+        .run()
+        .inspect_err(|_| log!("SECURITY REJECTION: Sweep treasury PDA check failed"))?;
+
+    // SECURITY: The primary or any co-authority, and nobody else
+    if !is_treasury_authority(&treasury, authority.address()) {
+        reject!(
+            SecureError::Unauthorized,
+            "SECURITY REJECTION: Signer is not a treasury authority"
+        );
+    }
+
+    let backed = rent_exempt_minimum(TREASURY_SIZE)?
+        .add_or(treasury.balance, ProgramError::ArithmeticOverflow)?;
+    let surplus = treasury_acc.lamports().saturating_sub(backed);
+    if surplus == 0 {
+        log!("SWEEP: No surplus, nothing to do");
+        return Ok(());
+    }
+
+    let destination_lamports =
+        destination.lamports().add_or(surplus, ProgramError::ArithmeticOverflow)?;
+    treasury_acc.set_lamports(backed);
+    destination.set_lamports(destination_lamports);
+
+    log_amount!("SECURITY VERIFIED: Swept {} surplus lamports", surplus);
+    Ok(())
+}
+
+/// Move part of one user's deposit to another user under the same treasury.
+///
+/// # Accounts
+/// 0. `[writable]` sender_deposit - The sender's UserDeposit PDA
+/// 1. `[writable]` recipient_deposit - The recipient's UserDeposit PDA
+/// 2. `[]` treasury - The treasury both deposits belong to
+/// 3. `[signer]` sender - Owner of sender_deposit
+///
+/// # Instruction Data
+/// - amount (u64): 8 bytes, little-endian
+///
+/// # Security Validations
+/// // SECURITY: Duplicate rejection - sender and recipient deposits must differ
+/// // SECURITY: Signer validation and program ownership of all three accounts
+/// // SECURITY: PDA derivation and canonical bump for both deposits and the treasury
+/// // SECURITY: Relationship - both deposits link to this treasury (InvalidTreasury)
+/// // SECURITY: Owner validation - only the sender's owner can move its funds
+///
+/// ## Anchor Comparison
+/// ```ignore
+/// #[account(
+///     mut,
+///     seeds = [USER_DEPOSIT_SEED, treasury.key().as_ref(), sender.key().as_ref()],
+///     bump = sender_deposit.bump,
+///     has_one = treasury,
+///     constraint = sender_deposit.owner == sender.key() @ PdaError::UnauthorizedAccess,
+/// )]
+/// pub sender_deposit: Account<'info, UserDeposit>,
+///
+/// #[account(
+///     mut,
+///     seeds = [USER_DEPOSIT_SEED, treasury.key().as_ref(), recipient_deposit.owner.as_ref()],
+///     bump = recipient_deposit.bump,
+///     has_one = treasury,
+/// )]
+/// pub recipient_deposit: Account<'info, UserDeposit>,
+/// ```
+fn transfer_deposit(program_id: &Address, accounts: &[AccountView], data: &[u8]) -> ProgramResult {
+    let [sender_deposit_acc, recipient_deposit_acc, treasury_acc, sender] = accounts else {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    let amount = u64::from_le_bytes(
+        data.get(0..8)
+            .ok_or(ProgramError::InvalidInstructionData)?
+            .try_into()
+            .map_err(|_| ProgramError::InvalidInstructionData)?,
+    );
+
+    // SECURITY: Two mutable views of one deposit would credit what they debit
+    assert_distinct_accounts(sender_deposit_acc.address(), recipient_deposit_acc.address())?;
+
+    SecurityChecks::new(program_id)
+        .signer(sender)
+        .not_sysvar_or_program(sender_deposit_acc)
+        .owned(sender_deposit_acc)
+        .not_sysvar_or_program(recipient_deposit_acc)
+        .owned(recipient_deposit_acc)
+        .not_sysvar_or_program(treasury_acc)
+        .owned(treasury_acc)
+        .run()
+        .inspect_err(|_| log!("SECURITY REJECTION: Transfer signer/ownership check failed"))?;
+
+    let mut sender_deposit: UserDeposit = load_typed(sender_deposit_acc, program_id)?;
+    let mut recipient_deposit: UserDeposit = load_typed(recipient_deposit_acc, program_id)?;
+    let treasury: Treasury = load_typed(treasury_acc, program_id)?;
+
+    // SECURITY: Each deposit is a genuine PDA of the treasury it records, so
+    // the relationship check below compares real treasury links
+    SecurityChecks::new(program_id)
+        .pda(
+            &[USER_DEPOSIT_SEED, sender_deposit.treasury.as_ref(), sender_deposit.owner.as_ref()],
+            sender_deposit_acc,
+            sender_deposit.bump,
+        )
+        .pda(
+            &[
+                USER_DEPOSIT_SEED,
+                recipient_deposit.treasury.as_ref(),
+                recipient_deposit.owner.as_ref(),
+            ],
+            recipient_deposit_acc,
+            recipient_deposit.bump,
+        )
+        .pda(&[TREASURY_SEED, treasury.authority.as_ref()], treasury_acc, treasury.bump)
+        .run()
+        .inspect_err(|_| log!("SECURITY REJECTION: Transfer PDA check failed"))?;
+
+    // SECURITY: Both sides must belong to the treasury passed in; moving a
+    // balance across treasuries would leave one over- and one under-backed
+    SecurityChecks::new(program_id)
+        .has_one(&sender_deposit.treasury, treasury_acc)
+        .or(SecureError::InvalidTreasury)
+        .has_one(&recipient_deposit.treasury, treasury_acc)
+        .or(SecureError::InvalidTreasury)
+        .run()
+        .inspect_err(|_| log!("SECURITY REJECTION: Cross-treasury transfer"))?;
+
+    SecurityChecks::new(program_id)
+        .has_one(&sender_deposit.owner, sender)
+        .or(SecureError::Unauthorized)
+        .run()
+        .inspect_err(|_| log!("SECURITY REJECTION: Signer does not own the sender deposit"))?;
+
+    apply_transfer(&mut sender_deposit, &mut recipient_deposit, amount)?;
+
+    let mut sender_data = sender_deposit_acc.try_borrow_mut()?;
+    sender_deposit.serialize(&mut sender_data)?;
+    drop(sender_data);
+
+    let mut recipient_data = recipient_deposit_acc.try_borrow_mut()?;
+    recipient_deposit.serialize(&mut recipient_data)?;
+
+    log_amount!("SECURITY VERIFIED: Transfer of {} between deposits approved", amount);
+
+    Ok(())
+}
+
+/// Re-derive a Treasury or UserDeposit PDA from seeds in instruction data.
+///
+/// `kind` takes the heal_bump values: `HEAL_KIND_TREASURY` reads one seed
+/// (authority), `HEAL_KIND_USER_DEPOSIT` two (treasury, owner). Fails with
+/// `InvalidInstructionData` for an unknown kind or missing seed bytes.
+pub fn derive_pda_from_seeds(
+    program_id: &Address,
+    data: &[u8],
+) -> Result<(Address, u8), ProgramError> {
+    let (kind, seeds) = data.split_first().ok_or(ProgramError::InvalidInstructionData)?;
+    let seed = |i: usize| -> Result<Address, ProgramError> {
+        let bytes = seeds.get(32 * i..32 * (i + 1)).ok_or(ProgramError::InvalidInstructionData)?;
+        Ok(Address::new_from_array(
+            bytes.try_into().map_err(|_| ProgramError::InvalidInstructionData)?,
+        ))
+    };
+
+    match *kind {
+        HEAL_KIND_TREASURY => derive_treasury_pda(&seed(0)?, program_id),
+        HEAL_KIND_USER_DEPOSIT => derive_user_deposit_pda(&seed(0)?, &seed(1)?, program_id),
+        _ => Err(ProgramError::InvalidInstructionData),
+    }
+}
+
+/// Log the PDA the program derives for the given seeds.
+///
+/// Read-only helper for clients: simulate it and compare the logged
+/// `derive_address: <hex address> <bump>` line with the address computed
+/// off-chain. Touches no accounts.
+///
+/// # Accounts
+/// (none)
+///
+/// # Instruction Data
+/// - kind (u8): `0` = Treasury, `1` = UserDeposit
+/// - seeds: authority (Treasury) or treasury + owner (UserDeposit), 32 bytes each
+///
+/// ## Anchor Comparison
+/// Anchor clients call `PublicKey.findProgramAddressSync` with seeds copied
+/// from the `#[account(seeds = ...)]` attribute; nothing checks that the
+/// copy is right.
+fn derive_address(program_id: &Address, _accounts: &[AccountView], data: &[u8]) -> ProgramResult {
+    let (address, bump) = derive_pda_from_seeds(program_id, data)?;
+
+    let hex = address_hex(&address);
+    let hex = core::str::from_utf8(&hex).map_err(|_| ProgramError::InvalidArgument)?;
+    log!("derive_address: {} {}", hex, bump);
+
+    Ok(())
+}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pinocchio_security_common::{
+        account_hash, assert_reinit_rejected, decode_address, AccountMeta, MockAccountView,
+        ProgramTest,
+    };
+    use pinocchio_vulnerable_pda_derivation as vulnerable;
+    use std::cell::RefCell;
+
+    thread_local! {
+        static LOG_LINES: RefCell<Vec<String>> = const { RefCell::new(Vec::new()) };
+    }
+
+    /// Log sink used by `log!` in unit tests.
+    pub(super) fn capture_log(line: String) {
+        LOG_LINES.with(|lines| lines.borrow_mut().push(line));
+    }
+
+    /// Returns and clears the lines logged so far on this thread.
+    fn take_logs() -> Vec<String> {
+        LOG_LINES.with(|lines| lines.take())
+    }
+
+    #[test]
+    fn test_program_id_matches_documented_address() {
+        // Must match the doc comment on `ID`
+        assert_eq!(decode_address("7NsBNd6X6TPNQdNGzoZ6nGENdp945jRxZKCrQjafVyk5"), Some(ID));
+    }
+
+    /// Test Treasury serialization and deserialization roundtrip.
+    #[test]
+    fn test_treasury_serialization() {
+        let treasury = Treasury {
+            authority: Address::new_from_array([1u8; 32]),
+            balance: 1_000_000_000,
+            bump: 255,
+            co_authorities: [Address::new_from_array([7u8; 32]), Address::default()],
+        };
+
+        let mut buffer = [0u8; TREASURY_SIZE];
+        treasury.serialize(&mut buffer).unwrap();
+
+        let deserialized = Treasury::decode(&buffer).unwrap();
+        assert_eq!(deserialized, treasury);
+    }
+
+    /// Test that a treasury written by a newer schema version is rejected.
+    #[test]
+    fn test_treasury_future_version_rejected() {
+        let treasury = Treasury {
+            authority: Address::new_from_array([1u8; 32]),
+            balance: 500,
+            bump: 254,
+            co_authorities: NO_CO_AUTHORITIES,
+        };
+        let mut buffer = [0u8; TREASURY_SIZE];
+        treasury.serialize(&mut buffer).unwrap();
+        assert_eq!(buffer[TREASURY_VERSION_OFFSET], SCHEMA_VERSION);
+
+        buffer[TREASURY_VERSION_OFFSET] = SCHEMA_VERSION + 1;
+        assert_eq!(Treasury::decode(&buffer), Err(CommonError::UnsupportedVersion.into()));
+    }
+
+    /// Test that serialization keeps the reserved region zero and never clobbers it.
+    #[test]
+    fn test_treasury_reserved_padding_preserved() {
+        let treasury = Treasury {
+            authority: Address::new_from_array([1u8; 32]),
+            balance: 500,
+            bump: 254,
+            co_authorities: NO_CO_AUTHORITIES,
+        };
+        let reserved = TREASURY_RESERVED_OFFSET..TREASURY_SIZE;
+
+        let mut buffer = [0u8; TREASURY_SIZE];
+        treasury.serialize(&mut buffer).unwrap();
+        let deserialized = Treasury::decode(&buffer).unwrap();
+        deserialized.serialize(&mut buffer).unwrap();
+        assert_eq!(buffer[reserved.clone()], [0u8; TREASURY_RESERVED_LEN]);
+
+        // Bytes a future version wrote survive an update by this version
+        buffer[reserved.clone()].copy_from_slice(&[0xab; TREASURY_RESERVED_LEN]);
+        let mut updated = Treasury::decode(&buffer).unwrap();
+        assert_eq!(updated.balance, 500);
+        updated.balance = 700;
+        updated.serialize(&mut buffer).unwrap();
+        assert_eq!(buffer[reserved], [0xab; TREASURY_RESERVED_LEN]);
+        assert_eq!(Treasury::decode(&buffer).unwrap().balance, 700);
+    }
+
+    /// Test UserDeposit serialization and deserialization roundtrip.
+    #[test]
+    fn test_user_deposit_serialization() {
+        let user_deposit = UserDeposit {
+            owner: Address::new_from_array([2u8; 32]),
+            treasury: Address::new_from_array([3u8; 32]),
+            amount: 500_000_000,
+            bump: 254,
+            deposit_count: 7,
+            withdraw_count: 3,
+            allowed_destination: Address::new_from_array([4u8; 32]),
+        };
+
+        let mut buffer = [0u8; USER_DEPOSIT_SIZE];
+        user_deposit.serialize(&mut buffer).unwrap();
+
+        let deserialized = UserDeposit::decode(&buffer).unwrap();
+        assert_eq!(deserialized, user_deposit);
+    }
+
+    /// Test that each account's `space()` is its layout size.
+    #[test]
+    fn test_account_space_matches_size() {
+        assert_eq!(Treasury::space(), TREASURY_SIZE);
+        assert_eq!(UserDeposit::space(), USER_DEPOSIT_SIZE);
+        assert_eq!(Treasury::space(), <Treasury as AccountData>::LEN);
+        assert_eq!(UserDeposit::space(), <UserDeposit as AccountData>::LEN);
+    }
+
+    /// Test that closed accounts can be neither read nor initialized again.
+    #[test]
+    fn test_closed_accounts_rejected_on_read_and_reinit() {
+        let authority_key = Address::new_from_array([1u8; 32]);
+        let owner_key = Address::new_from_array([2u8; 32]);
+        let (treasury_pda, _) = derive_treasury_pda(&authority_key, &ID).unwrap();
+        let (deposit_pda, _) = derive_user_deposit_pda(&treasury_pda, &owner_key, &ID).unwrap();
+        let mut authority =
+            MockAccountView::new(authority_key, Address::default(), 0, &[]).signer();
+        let mut owner = MockAccountView::new(owner_key, Address::default(), 0, &[]).signer();
+
+        // A live treasury and deposit, created by the real handlers
+        let mut treasury =
+            MockAccountView::new(treasury_pda, ID, 0, &[0u8; TREASURY_SIZE]).writable();
+        initialize_treasury(&ID, &[treasury.view(), authority.view()], &[]).unwrap();
+        let mut deposit =
+            MockAccountView::new(deposit_pda, ID, 0, &[0u8; USER_DEPOSIT_SIZE]).writable();
+        create_user_deposit(&ID, &[deposit.view(), treasury.view(), owner.view()], &[]).unwrap();
+
+        // close -> read
+        let mut closed_deposit = deposit.data().to_vec();
+        UserDeposit::mark_closed(&mut closed_deposit).unwrap();
+        assert_eq!(UserDeposit::decode(&closed_deposit), Err(CommonError::AccountClosed.into()));
+        let mut closed_treasury = treasury.data().to_vec();
+        Treasury::mark_closed(&mut closed_treasury).unwrap();
+        assert_eq!(Treasury::decode(&closed_treasury), Err(CommonError::AccountClosed.into()));
+
+        // close -> reinit, rejected before anything is written
+        let mut deposit = MockAccountView::new(deposit_pda, ID, 0, &closed_deposit).writable();
+        let result =
+            create_user_deposit(&ID, &[deposit.view(), treasury.view(), owner.view()], &[]);
+        assert_eq!(result, Err(CommonError::AccountClosed.into()));
+        assert_eq!(deposit.data(), &closed_deposit[..]);
+
+        let mut treasury = MockAccountView::new(treasury_pda, ID, 0, &closed_treasury).writable();
+        let result = initialize_treasury(&ID, &[treasury.view(), authority.view()], &[]);
+        assert_eq!(result, Err(CommonError::AccountClosed.into()));
+        assert_eq!(treasury.data(), &closed_treasury[..]);
+    }
+
+    /// Test that a treasury not yet created is created through the System Program.
+    #[test]
+    fn test_initialize_treasury_creates_account() {
+        let authority_key = Address::new_from_array([1u8; 32]);
+        let (treasury_pda, bump) = derive_treasury_pda(&authority_key, &ID).unwrap();
+        let mut authority = MockAccountView::new(authority_key, SYSTEM_PROGRAM_ID, 10_000_000, &[])
+            .signer()
+            .writable();
+        let mut treasury =
+            MockAccountView::new(treasury_pda, SYSTEM_PROGRAM_ID, 0, &[0u8; TREASURY_SIZE])
+                .writable();
+
+        // The System Program must be supplied for the create path
+        let result = initialize_treasury(&ID, &[treasury.view(), authority.view()], &[]);
+        assert_eq!(result, Err(ProgramError::NotEnoughAccountKeys));
+
+        let mut fake_system = MockAccountView::new(Address::new_from_array([9u8; 32]), ID, 0, &[]);
+        let result =
+            initialize_treasury(&ID, &[treasury.view(), authority.view(), fake_system.view()], &[]);
+        assert_eq!(result, Err(ProgramError::IncorrectProgramId));
+
+        let mut system_program = MockAccountView::new(SYSTEM_PROGRAM_ID, ID, 0, &[]);
+        initialize_treasury(&ID, &[treasury.view(), authority.view(), system_program.view()], &[])
+            .unwrap();
+
+        let state = Treasury::decode(treasury.data()).unwrap();
+        assert_eq!(state.authority, authority_key);
+        assert_eq!(state.bump, bump);
+    }
+
+    /// Test that security logs carry full addresses and amounts, or redacted
+    /// ones under the `redact` feature.
+    #[test]
+    fn test_security_logs_redaction() {
+        let authority_key = Address::new_from_array([0xab; 32]);
+        let (treasury_pda, _) = derive_treasury_pda(&authority_key, &ID).unwrap();
+        let mut authority =
+            MockAccountView::new(authority_key, Address::default(), 0, &[]).signer();
+        let mut treasury =
+            MockAccountView::new(treasury_pda, ID, 0, &[0u8; TREASURY_SIZE]).writable();
+
+        take_logs();
+        initialize_treasury(&ID, &[treasury.view(), authority.view()], &[]).unwrap();
+        log_amount!("SECURITY VERIFIED: Deposit of {} approved", 1_500);
+        let logs = take_logs();
+
+        let full_address = "ab".repeat(32);
+        let (authority_line, amount_line) = if REDACT_LOGS {
+            (
+                "  Authority: abababab.. (verified signer)".to_string(),
+                "SECURITY VERIFIED: Deposit of <redacted> approved".to_string(),
+            )
+        } else {
+            (
+                format!("  Authority: {} (verified signer)", full_address),
+                "SECURITY VERIFIED: Deposit of 1500 approved".to_string(),
+            )
+        };
+        assert!(logs.contains(&authority_line), "{:?}", logs);
+        assert!(logs.contains(&amount_line), "{:?}", logs);
+        assert_eq!(logs.iter().any(|line| line.contains(&full_address)), !REDACT_LOGS);
+    }
+
+    /// Test SecureError conversion to ProgramError.
+    #[test]
+    fn test_error_conversion() {
+        let err: ProgramError = SecureError::InvalidPda.into();
+        assert!(matches!(err, ProgramError::Custom(0x1000)));
+
+        let err: ProgramError = SecureError::InvalidBump.into();
+        assert!(matches!(err, ProgramError::Custom(0x1001)));
+
+        let err: ProgramError = SecureError::InvalidTreasury.into();
+        assert!(matches!(err, ProgramError::Custom(0x1002)));
+
+        let err: ProgramError = SecureError::Unauthorized.into();
+        assert!(matches!(err, ProgramError::Custom(0x1003)));
+
+        let err: ProgramError = SecureError::NotInitialized.into();
+        assert!(matches!(err, ProgramError::Custom(0x1004)));
+
+        let err: ProgramError = CommonError::InsufficientBalance.into();
+        assert!(matches!(err, ProgramError::Custom(0x2001)));
+
+        let err: ProgramError = SecureError::DuplicateAccount.into();
+        assert!(matches!(err, ProgramError::Custom(0x1006)));
+
+        let err: ProgramError = SecureError::NotAProgramAddress.into();
+        assert!(matches!(err, ProgramError::Custom(0x1007)));
+
+        let err: ProgramError = SecureError::WouldBreakRentExemption.into();
+        assert!(matches!(err, ProgramError::Custom(0x1008)));
+
+        let err: ProgramError = SecureError::CoAuthorityListFull.into();
+        assert!(matches!(err, ProgramError::Custom(0x1009)));
+
+        let err: ProgramError = SecureError::CoAuthorityNotFound.into();
+        assert!(matches!(err, ProgramError::Custom(0x100a)));
+    }
+
+    /// Build genuine treasury/user_deposit PDAs for a withdraw.
+    fn withdraw_accounts(
+        stored_treasury: Option<Address>,
+        user_deposit_bump_offset: u8,
+    ) -> (MockAccountView, MockAccountView, MockAccountView) {
+        let authority = Address::new_from_array([1u8; 32]);
+        let owner = Address::new_from_array([2u8; 32]);
+        let (treasury_pda, treasury_bump) = derive_treasury_pda(&authority, &ID).unwrap();
+        let (user_deposit_pda, user_deposit_bump) =
+            derive_user_deposit_pda(&treasury_pda, &owner, &ID).unwrap();
+
+        let mut treasury_data = [0u8; TREASURY_SIZE];
+        Treasury {
+            authority,
+            balance: 1_000,
+            bump: treasury_bump,
+            co_authorities: NO_CO_AUTHORITIES,
+        }
+        .serialize(&mut treasury_data)
+        .unwrap();
+
+        let mut user_deposit_data = [0u8; USER_DEPOSIT_SIZE];
+        UserDeposit {
+            owner,
+            treasury: stored_treasury.unwrap_or(treasury_pda),
+            amount: 1_000,
+            bump: user_deposit_bump.wrapping_sub(user_deposit_bump_offset),
+            deposit_count: 0,
+            withdraw_count: 0,
+            allowed_destination: Address::default(),
+        }
+        .serialize(&mut user_deposit_data)
+        .unwrap();
+
+        (
+            MockAccountView::new(user_deposit_pda, ID, 0, &user_deposit_data).writable(),
+            MockAccountView::new(treasury_pda, ID, treasury_rent() + 1_000, &treasury_data)
+                .writable(),
+            MockAccountView::new(owner, Address::default(), 0, &[]).signer().writable(),
+        )
+    }
+
+    fn treasury_rent() -> u64 {
+        rent_exempt_minimum(TREASURY_SIZE).unwrap()
+    }
+
+    fn run_withdraw(
+        accounts: (MockAccountView, MockAccountView, MockAccountView),
+    ) -> (ProgramResult, MockAccountView) {
+        let (result, user_deposit, ..) = run_withdraw_amount(accounts, 400);
+        (result, user_deposit)
+    }
+
+    fn run_withdraw_amount(
+        accounts: (MockAccountView, MockAccountView, MockAccountView),
+        amount: u64,
+    ) -> (ProgramResult, MockAccountView, MockAccountView, MockAccountView) {
+        run_withdraw_data(accounts, &amount.to_le_bytes())
+    }
+
+    fn run_withdraw_data(
+        (mut user_deposit, mut treasury, mut withdrawer): (
+            MockAccountView,
+            MockAccountView,
+            MockAccountView,
+        ),
+        data: &[u8],
+    ) -> (ProgramResult, MockAccountView, MockAccountView, MockAccountView) {
+        let mut system = MockAccountView::new(Address::default(), Address::default(), 0, &[]);
+        let result = withdraw(
+            &ID,
+            &[user_deposit.view(), treasury.view(), withdrawer.view(), system.view()],
+            data,
+        );
+        (result, user_deposit, treasury, withdrawer)
+    }
+
+    /// Test that withdrawing to zero keeps the user deposit unless auto_close
+    /// is set, in which case its rent goes back to the owner.
+    #[test]
+    fn test_withdraw_to_zero_auto_close() {
+        let deposit_rent = rent_exempt_minimum(USER_DEPOSIT_SIZE).unwrap();
+        let with_rent = || {
+            let mut accounts = withdraw_accounts(None, 0);
+            accounts.0.view().set_lamports(deposit_rent);
+            accounts
+        };
+        let full = 1_000u64.to_le_bytes();
+        let with_flag = |flag: u8| {
+            let mut data = [0u8; 9];
+            data[..8].copy_from_slice(&full);
+            data[8] = flag;
+            data
+        };
+
+        // Default and explicit `0`: the emptied record stays open
+        for data in [&full[..], &with_flag(0)[..]] {
+            let (result, user_deposit, _, withdrawer) = run_withdraw_data(with_rent(), data);
+            assert!(result.is_ok());
+            assert_eq!(UserDeposit::decode(user_deposit.data()).unwrap().amount, 0);
+            assert_eq!((user_deposit.lamports(), withdrawer.lamports()), (deposit_rent, 1_000));
+        }
+
+        // auto_close: record closed, rent and withdrawal both reach the owner
+        let (result, user_deposit, treasury, withdrawer) =
+            run_withdraw_data(with_rent(), &with_flag(1));
+        assert!(result.is_ok());
+        assert_eq!(
+            UserDeposit::decode(user_deposit.data()),
+            Err(CommonError::AccountClosed.into())
+        );
+        assert_eq!(user_deposit.lamports(), 0);
+        assert_eq!(withdrawer.lamports(), 1_000 + deposit_rent);
+        assert_eq!(treasury.lamports(), treasury_rent());
+
+        // auto_close only acts on an emptied record
+        let mut partial = with_flag(1);
+        partial[..8].copy_from_slice(&400u64.to_le_bytes());
+        let (result, user_deposit, ..) = run_withdraw_data(with_rent(), &partial);
+        assert!(result.is_ok());
+        assert_eq!(UserDeposit::decode(user_deposit.data()).unwrap().amount, 600);
+        assert_eq!(user_deposit.lamports(), deposit_rent);
+
+        // Any other flag value is malformed
+        let (result, ..) = run_withdraw_data(with_rent(), &with_flag(2));
+        assert_eq!(result, Err(ProgramError::InvalidInstructionData));
+    }
+
+    /// Test that a successful withdraw reports every check as passed.
+    #[test]
+    fn test_withdraw_summary_reports_all_checks() {
+        let (mut user_deposit, mut treasury, mut withdrawer) = withdraw_accounts(None, 0);
+        let mut system = MockAccountView::new(Address::default(), Address::default(), 0, &[]);
+
+        let report = process_withdraw(
+            &ID,
+            &[user_deposit.view(), treasury.view(), withdrawer.view(), system.view()],
+            &400u64.to_le_bytes(),
+        )
+        .unwrap();
+        assert!(report.all_passed());
+        assert_eq!(&report.summary(), b"CHECKS|1111111111");
+        assert_eq!(SecurityReport::parse("CHECKS|1111111111"), Some(report));
+    }
+
+    /// Test summary digit meaning and parse rejections.
+    #[test]
+    fn test_security_report_parse() {
+        let mut report = SecurityReport::default();
+        report.pass(WithdrawCheck::Signer);
+        report.pass(WithdrawCheck::RentExemption);
+        assert_eq!(&report.summary(), b"CHECKS|1000000001");
+        assert!(!report.all_passed());
+
+        let parsed = SecurityReport::parse("CHECKS|1000000001").unwrap();
+        assert!(parsed.passed(WithdrawCheck::Signer));
+        assert!(!parsed.passed(WithdrawCheck::TreasuryPda));
+
+        assert_eq!(SecurityReport::parse("CHECKS|111111111"), None);
+        assert_eq!(SecurityReport::parse("CHECKS|11111111112"), None);
+        assert_eq!(SecurityReport::parse("CHECKS|111111111x"), None);
+        assert_eq!(SecurityReport::parse("1111111111"), None);
+    }
+
+    /// Test that withdraw moves lamports but never leaves the treasury PDA below
+    /// its rent-exempt minimum.
+    #[test]
+    fn test_withdraw_keeps_treasury_rent_exempt() {
+        let (result, _, treasury, withdrawer) =
+            run_withdraw_amount(withdraw_accounts(None, 0), 400);
+        assert!(result.is_ok());
+        assert_eq!(treasury.lamports(), treasury_rent() + 600);
+        assert_eq!(withdrawer.lamports(), 400);
+
+        // Drain the treasury's last lamports: covered by the deposit, but not by rent
+        let mut accounts = withdraw_accounts(None, 0);
+        accounts.1.view().set_lamports(treasury_rent() + 999);
+        let (result, user_deposit, treasury, withdrawer) = run_withdraw_amount(accounts, 1_000);
+        assert_eq!(result, Err(SecureError::WouldBreakRentExemption.into()));
+        assert_eq!(treasury.lamports(), treasury_rent() + 999);
+        assert_eq!(withdrawer.lamports(), 0);
+        assert_eq!(UserDeposit::decode(user_deposit.data()).unwrap().amount, 1_000);
+    }
+
+    /// Test that withdraw rejects the treasury passed as its own user deposit
+    /// before borrowing either account.
+    #[test]
+    fn test_withdraw_rejects_aliased_treasury_and_user_deposit() {
+        let (_, mut treasury, mut withdrawer) = withdraw_accounts(None, 0);
+        let mut system = MockAccountView::new(Address::default(), Address::default(), 0, &[]);
+        let treasury_before = treasury.data().to_vec();
+
+        let result = withdraw(
+            &ID,
+            &[treasury.view(), treasury.view(), withdrawer.view(), system.view()],
+            &400u64.to_le_bytes(),
+        );
+        assert_eq!(result, Err(SecureError::DuplicateAccount.into()));
+        assert_eq!(treasury.data(), &treasury_before[..]);
+        assert_eq!(treasury.lamports(), treasury_rent() + 1_000);
+        assert_eq!(withdrawer.lamports(), 0);
+    }
+
+    /// Test that deposit rejects the treasury passed as its own user deposit
+    /// before borrowing either account.
+    #[test]
+    fn test_deposit_rejects_aliased_treasury_and_user_deposit() {
+        let (_, mut treasury, _) = withdraw_accounts(None, 0);
+        let authority = Treasury::decode(treasury.data()).unwrap().authority;
+        let mut depositor =
+            MockAccountView::new(authority, Address::default(), 400, &[]).signer().writable();
+        let mut system = MockAccountView::new(SYSTEM_PROGRAM_ID, Address::default(), 0, &[]);
+        let treasury_before = treasury.data().to_vec();
+
+        let result = deposit(
+            &ID,
+            &[treasury.view(), treasury.view(), depositor.view(), system.view()],
+            &400u64.to_le_bytes(),
+        );
+        assert_eq!(result, Err(SecureError::DuplicateAccount.into()));
+        assert_eq!(treasury.data(), &treasury_before[..]);
+        assert_eq!((treasury.lamports(), depositor.lamports()), (treasury_rent() + 1_000, 400));
+    }
+
+    /// Test that deposit turns away a caller who is not the owner before any
+    /// PDA derivation check runs.
+    #[test]
+    fn test_deposit_unauthorized_rejected_before_derivation() {
+        let (mut user_deposit, mut treasury, _) = withdraw_accounts(None, 0);
+        let mut intruder =
+            MockAccountView::new(Address::new_from_array([7u8; 32]), Address::default(), 0, &[])
+                .signer();
+        let mut system = MockAccountView::new(Address::default(), Address::default(), 0, &[]);
+
+        take_logs();
+        let result = deposit(
+            &ID,
+            &[user_deposit.view(), treasury.view(), intruder.view(), system.view()],
+            &400u64.to_le_bytes(),
+        );
+        assert_eq!(result, Err(SecureError::Unauthorized.into()));
+        assert_eq!(take_logs(), ["SECURITY REJECTION: Depositor is not the owner"]);
+    }
+
+    /// Test that the depositor's lamports are checked against the amount up
+    /// front: one lamport short is rejected with `InsufficientBalance` and the
+    /// pre-check's log, and neither record is written.
+    #[test]
+    fn test_deposit_short_of_lamports_rejected_before_transfer() {
+        let (mut user_deposit, mut treasury, _) = withdraw_accounts(None, 0);
+        let owner = UserDeposit::decode(user_deposit.data()).unwrap().owner;
+        let mut depositor =
+            MockAccountView::new(owner, Address::default(), 399, &[]).signer().writable();
+        let mut system = MockAccountView::new(SYSTEM_PROGRAM_ID, Address::default(), 0, &[]);
+        let user_deposit_before = account_hash(user_deposit.data());
+        let treasury_before = account_hash(treasury.data());
+
+        take_logs();
+        let result = deposit(
+            &ID,
+            &[user_deposit.view(), treasury.view(), depositor.view(), system.view()],
+            &400u64.to_le_bytes(),
+        );
+        assert_eq!(result, Err(CommonError::InsufficientBalance.into()));
+        assert_eq!(
+            take_logs().last().map(String::as_str),
+            Some("SECURITY REJECTION: Insufficient depositor lamports")
+        );
+        assert_eq!(account_hash(user_deposit.data()), user_deposit_before);
+        assert_eq!(account_hash(treasury.data()), treasury_before);
+        assert_eq!((depositor.lamports(), treasury.lamports()), (399, treasury_rent() + 1_000));
+
+        // With the lamports available the same deposit is credited
+        let mut depositor =
+            MockAccountView::new(owner, Address::default(), 400, &[]).signer().writable();
+        deposit(
+            &ID,
+            &[user_deposit.view(), treasury.view(), depositor.view(), system.view()],
+            &400u64.to_le_bytes(),
+        )
+        .unwrap();
+        assert_eq!(UserDeposit::decode(user_deposit.data()).unwrap().amount, 1_400);
+        assert_eq!(Treasury::decode(treasury.data()).unwrap().balance, 1_400);
+    }
+
+    /// Test that each `reject!` in withdraw logs its message as the last line
+    /// and returns the error paired with it.
+    #[test]
+    fn test_withdraw_rejection_logs_match_errors() {
+        take_logs();
+        let (result, ..) = run_withdraw_amount(withdraw_accounts(None, 0), 1_001);
+        assert_eq!(result, Err(CommonError::InsufficientBalance.into()));
+        let logs = take_logs();
+        assert_eq!(logs.last().map(String::as_str), Some("SECURITY REJECTION: Insufficient funds"));
+
+        let mut unsigned =
+            MockAccountView::new(Address::new_from_array([8u8; 32]), Address::default(), 0, &[])
+                .writable();
+        let (result, ..) = run_withdraw_to(withdraw_accounts(None, 0), &mut unsigned);
+        assert_eq!(result, Err(SecureError::Unauthorized.into()));
+        let logs = take_logs();
+        assert_eq!(
+            logs.last().map(String::as_str),
+            Some("SECURITY REJECTION: Withdraw destination did not sign")
+        );
+    }
+
+    fn run_withdraw_to(
+        (mut user_deposit, mut treasury, mut withdrawer): (
+            MockAccountView,
+            MockAccountView,
+            MockAccountView,
+        ),
+        destination: &mut MockAccountView,
+    ) -> (ProgramResult, MockAccountView, MockAccountView) {
+        let mut system = MockAccountView::new(Address::default(), Address::default(), 0, &[]);
+        let result = withdraw(
+            &ID,
+            &[
+                user_deposit.view(),
+                treasury.view(),
+                withdrawer.view(),
+                system.view(),
+                destination.view(),
+            ],
+            &400u64.to_le_bytes(),
+        );
+        (result, treasury, withdrawer)
+    }
+
+    const COLD_WALLET: Address = Address::new_from_array([6u8; 32]);
+
+    /// Test that a separate destination must co-sign the withdrawal.
+    #[test]
+    fn test_withdraw_to_other_wallet_requires_destination_signature() {
+        let mut destination =
+            MockAccountView::new(COLD_WALLET, Address::default(), 0, &[]).writable();
+        let (result, treasury, withdrawer) =
+            run_withdraw_to(withdraw_accounts(None, 0), &mut destination);
+        assert_eq!(result, Err(SecureError::Unauthorized.into()));
+        assert_eq!(treasury.lamports(), treasury_rent() + 1_000);
+        assert_eq!((withdrawer.lamports(), destination.lamports()), (0, 0));
+    }
+
+    /// Test that a co-signed destination receives the withdrawal.
+    #[test]
+    fn test_withdraw_to_other_wallet_with_dual_signature() {
+        let mut destination =
+            MockAccountView::new(COLD_WALLET, Address::default(), 0, &[]).signer().writable();
+        let (result, treasury, withdrawer) =
+            run_withdraw_to(withdraw_accounts(None, 0), &mut destination);
+        assert!(result.is_ok());
+        assert_eq!(treasury.lamports(), treasury_rent() + 600);
+        assert_eq!((withdrawer.lamports(), destination.lamports()), (0, 400));
+
+        // Naming the withdrawer as destination needs no second signature
+        let owner = Address::new_from_array([2u8; 32]);
+        let mut same = MockAccountView::new(owner, Address::default(), 0, &[]).writable();
+        let (result, ..) = run_withdraw_to(withdraw_accounts(None, 0), &mut same);
+        assert!(result.is_ok());
+        assert_eq!(same.lamports(), 400);
+    }
+
+    /// Test that withdraw's SecurityChecks chain passes for genuine accounts
+    /// and surfaces each rejection with the expected code.
+    #[test]
+    fn test_withdraw_security_checks() {
+        let (result, user_deposit) = run_withdraw(withdraw_accounts(None, 0));
+        assert!(result.is_ok());
+        assert_eq!(UserDeposit::decode(user_deposit.data()).unwrap().amount, 600);
+
+        // Non-canonical stored bump: `.or()` keeps SecureError::InvalidBump,
+        // the same code strict_pda returns at load
+        let (result, _) = run_withdraw(withdraw_accounts(None, 1));
+        assert_eq!(result, Err(SecureError::InvalidBump.into()));
+
+        // Genuine record data at an address that is not its PDA
+        let (user_deposit, treasury, owner) = withdraw_accounts(None, 0);
+        let copied =
+            MockAccountView::new(Address::new_from_array([6u8; 32]), ID, 0, user_deposit.data())
+                .writable();
+        let (result, _) = run_withdraw((copied, treasury, owner));
+        assert_eq!(result, Err(SecureError::InvalidPda.into()));
+
+        // Stored treasury differs: `.or()` keeps SecureError::InvalidTreasury.
+        // Under strict_pda the stored bump no longer matches the stored seeds,
+        // so the load fails first.
+        let expected = if cfg!(feature = "strict_pda") { 0x1001 } else { 0x1002 };
+        let forged = Address::new_from_array([5u8; 32]);
+        let (result, user_deposit) = run_withdraw(withdraw_accounts(Some(forged), 0));
+        assert!(matches!(result, Err(ProgramError::Custom(code)) if code == expected));
+        assert_eq!(UserDeposit::decode(user_deposit.data()).unwrap().amount, 1_000);
+    }
+
+    /// Test that a UserDeposit passed as the treasury is rejected even though
+    /// its 73 bytes are enough to parse as a 41-byte Treasury.
+    #[test]
+    fn test_foreign_but_large_enough_account_rejected() {
+        let (mut user_deposit, _, mut withdrawer) = withdraw_accounts(None, 0);
+        let mut other_user_deposit =
+            MockAccountView::new(Address::new_from_array([6u8; 32]), ID, 0, user_deposit.data())
+                .writable();
+        let mut system = MockAccountView::new(Address::default(), Address::default(), 0, &[]);
+
+        let result = deposit(
+            &ID,
+            &[user_deposit.view(), other_user_deposit.view(), withdrawer.view(), system.view()],
+            &400u64.to_le_bytes(),
+        );
+        assert_eq!(result, Err(CommonError::AccountTypeMismatch.into()));
+
+        let mut padded = [0u8; TREASURY_SIZE + 1];
+        Treasury {
+            authority: Address::new_from_array([1u8; 32]),
+            balance: 0,
+            bump: 0,
+            co_authorities: NO_CO_AUTHORITIES,
+        }
+        .serialize(&mut padded)
+        .unwrap();
+        let mut treasury = MockAccountView::new(Address::new_from_array([6u8; 32]), ID, 0, &padded);
+        let result = load_typed::<Treasury>(&treasury.view(), &ID);
+        assert!(matches!(result, Err(e) if e == CommonError::AccountTypeMismatch.into()));
+    }
+
+    /// The ed25519 base point: a valid public key, so never a PDA.
+    fn on_curve_address() -> Address {
+        let mut bytes = [0x66u8; 32];
+        bytes[0] = 0x58;
+        Address::new_from_array(bytes)
+    }
+
+    /// Test that derived PDAs and other off-curve addresses pass.
+    #[test]
+    fn test_off_curve_address_accepted() {
+        let (treasury_pda, _) =
+            derive_treasury_pda(&Address::new_from_array([1u8; 32]), &ID).unwrap();
+        assert!(assert_off_curve(&treasury_pda).is_ok());
+
+        let (user_deposit_pda, _) =
+            derive_user_deposit_pda(&treasury_pda, &Address::new_from_array([2u8; 32]), &ID)
+                .unwrap();
+        assert!(assert_off_curve(&user_deposit_pda).is_ok());
+
+        // y = 2 has no x on edwards25519
+        let mut bytes = [0u8; 32];
+        bytes[0] = 2;
+        assert!(assert_off_curve(&Address::new_from_array(bytes)).is_ok());
+    }
+
+    /// Test that a wallet key passed as the treasury is rejected on init.
+    #[test]
+    fn test_on_curve_address_rejected() {
+        let result = assert_off_curve(&on_curve_address());
+        assert!(matches!(result, Err(ProgramError::Custom(0x1007))));
+
+        let mut treasury =
+            MockAccountView::new(on_curve_address(), ID, 0, &[0; TREASURY_SIZE]).writable();
+        let mut authority =
+            MockAccountView::new(Address::new_from_array([1u8; 32]), Address::default(), 0, &[])
+                .signer();
+
+        let result = initialize_treasury(&ID, &[treasury.view(), authority.view()], &[]);
+        assert!(matches!(result, Err(ProgramError::Custom(0x1007))));
+        assert_eq!(treasury.data(), &[0; TREASURY_SIZE]);
+    }
+
+    /// Test that the same account supplied for both roles is rejected.
+    #[test]
+    fn test_duplicate_account_rejected() {
+        let account = Address::new_from_array([4u8; 32]);
+        let result = assert_distinct_accounts(&account, &account);
+        assert!(matches!(result, Err(ProgramError::Custom(0x1006))));
+    }
+
+    /// Test that distinct accounts pass the duplicate check.
+    #[test]
+    fn test_distinct_accounts_accepted() {
+        let user_deposit = Address::new_from_array([4u8; 32]);
+        let treasury = Address::new_from_array([5u8; 32]);
+        assert!(assert_distinct_accounts(&user_deposit, &treasury).is_ok());
+    }
+
+    /// Test that an oversized withdrawal returns the shared InsufficientBalance code.
+    #[test]
+    fn test_withdraw_insufficient_balance_uses_common_code() {
+        let (result, user_deposit, treasury, withdrawer) =
+            run_withdraw_amount(withdraw_accounts(None, 0), 1_001);
+        assert_eq!(result, Err(ProgramError::Custom(0x2001)));
+        assert_eq!(result, Err(CommonError::InsufficientBalance.into()));
+
+        // Nothing is debited on rejection
+        assert_eq!(UserDeposit::decode(user_deposit.data()).unwrap().amount, 1_000);
+        assert_eq!(Treasury::decode(treasury.data()).unwrap().balance, 1_000);
+        assert_eq!((treasury.lamports(), withdrawer.lamports()), (treasury_rent() + 1_000, 0));
+
+        // The whole deposit is still withdrawable
+        let (result, user_deposit, ..) = run_withdraw_amount(withdraw_accounts(None, 0), 1_000);
+        assert!(result.is_ok());
+        assert_eq!(UserDeposit::decode(user_deposit.data()).unwrap().amount, 0);
+    }
+
+    /// Test that derive_address resolves the same PDAs the handlers check.
+    #[test]
+    fn test_derive_address_matches_handler_derivation() {
+        let authority = Address::new_from_array([1u8; 32]);
+        let owner = Address::new_from_array([2u8; 32]);
+        let (treasury_pda, treasury_bump) = derive_treasury_pda(&authority, &ID).unwrap();
+
+        let mut data = vec![DERIVE_ADDRESS_DISCRIMINATOR, HEAL_KIND_TREASURY];
+        data.extend_from_slice(authority.as_ref());
+        assert_eq!(derive_pda_from_seeds(&ID, &data[1..]), Ok((treasury_pda, treasury_bump)));
+        assert!(process_instruction(&ID, &[], &data).is_ok());
+
+        let mut data = vec![DERIVE_ADDRESS_DISCRIMINATOR, HEAL_KIND_USER_DEPOSIT];
+        data.extend_from_slice(treasury_pda.as_ref());
+        data.extend_from_slice(owner.as_ref());
+        let expected = derive_user_deposit_pda(&treasury_pda, &owner, &ID).unwrap();
+        assert_eq!(derive_pda_from_seeds(&ID, &data[1..]), Ok(expected));
+        assert!(process_instruction(&ID, &[], &data).is_ok());
+
+        // Unknown kind, or a seed cut short
+        let bad = Err(ProgramError::InvalidInstructionData);
+        assert_eq!(derive_pda_from_seeds(&ID, &[2]), bad);
+        assert_eq!(derive_pda_from_seeds(&ID, &data[1..65]), bad);
+    }
+
+    /// Test that a genuine treasury with a non-canonical bump is healed.
+    #[test]
+    fn test_heal_bump_treasury_healable() {
+        let authority = Address::new_from_array([1u8; 32]);
+        let (treasury_pda, canonical_bump) = derive_treasury_pda(&authority, &ID).unwrap();
+
+        // Created by the vulnerable program with a client-supplied bump
+        let mut data = [0u8; TREASURY_SIZE];
+        Treasury {
+            authority,
+            balance: 5_000,
+            bump: canonical_bump - 3,
+            co_authorities: NO_CO_AUTHORITIES,
+        }
+        .serialize(&mut data)
+        .unwrap();
+
+        let healed = heal_account_bump(&ID, &treasury_pda, HEAL_KIND_TREASURY, &mut data);
+        assert!(matches!(healed, Ok(true)));
+
+        let treasury = Treasury::decode(&data).unwrap();
+        assert_eq!(treasury.bump, canonical_bump);
+        assert_eq!(treasury.balance, 5_000);
+
+        // Healing is idempotent
+        let healed = heal_account_bump(&ID, &treasury_pda, HEAL_KIND_TREASURY, &mut data);
+        assert!(matches!(healed, Ok(false)));
+    }
+
+    /// Test that a genuine user deposit with a non-canonical bump is healed.
+    #[test]
+    fn test_heal_bump_user_deposit_healable() {
+        let owner = Address::new_from_array([2u8; 32]);
+        let treasury = Address::new_from_array([3u8; 32]);
+        let (user_deposit_pda, canonical_bump) =
+            derive_user_deposit_pda(&treasury, &owner, &ID).unwrap();
+
+        let mut data = [0u8; USER_DEPOSIT_SIZE];
+        UserDeposit {
+            owner,
+            treasury,
+            amount: 700,
+            bump: 0,
+            deposit_count: 0,
+            withdraw_count: 0,
+            allowed_destination: Address::default(),
+        }
+        .serialize(&mut data)
+        .unwrap();
+
+        let healed = heal_account_bump(&ID, &user_deposit_pda, HEAL_KIND_USER_DEPOSIT, &mut data);
+        assert!(matches!(healed, Ok(true)));
+
+        let user_deposit = UserDeposit::decode(&data).unwrap();
+        assert_eq!(user_deposit.bump, canonical_bump);
+        assert_eq!(user_deposit.amount, 700);
+    }
+
+    /// Test that an account that is not the canonical PDA cannot be healed.
+    #[test]
+    fn test_heal_bump_unhealable_rejected() {
+        let authority = Address::new_from_array([1u8; 32]);
+        // e.g. derived from a non-canonical bump, or an arbitrary keypair account
+        let forged_address = Address::new_from_array([9u8; 32]);
+
+        let mut data = [0u8; TREASURY_SIZE];
+        Treasury { authority, balance: 5_000, bump: 200, co_authorities: NO_CO_AUTHORITIES }
+            .serialize(&mut data)
+            .unwrap();
+        let original = data;
+
+        let result = heal_account_bump(&ID, &forged_address, HEAL_KIND_TREASURY, &mut data);
+        assert!(matches!(result, Err(ProgramError::Custom(0x1000))));
+        assert_eq!(data, original, "rejected heal must not modify the account");
+
+        let result = heal_account_bump(&ID, &forged_address, 7, &mut data);
+        assert!(matches!(result, Err(ProgramError::InvalidInstructionData)));
+    }
+
+    fn run_heal_bump(accounts: &mut [MockAccountView], kind: u8) -> ProgramResult {
+        let views: Vec<AccountView> = accounts.iter_mut().map(|account| account.view()).collect();
+        process_instruction(&ID, &views, &[HEAL_BUMP_DISCRIMINATOR, kind])
+    }
+
+    /// Test that records written by the vulnerable program are migrated to the
+    /// current layout and healed.
+    #[test]
+    fn test_heal_bump_migrates_legacy_layouts() {
+        let authority = Address::new_from_array([1u8; 32]);
+        let (treasury_pda, treasury_bump) = derive_treasury_pda(&authority, &ID).unwrap();
+        let mut data = [0u8; vulnerable::TREASURY_SIZE];
+        vulnerable::Treasury { authority, balance: 5_000, bump: treasury_bump - 3 }
+            .serialize(&mut data)
+            .unwrap();
+        assert_eq!(data.len(), LEGACY_TREASURY_SIZE);
+
+        // Already holds the current size's rent on top of its balance: no payer
+        let lamports = rent_exempt_minimum(TREASURY_SIZE).unwrap() + 5_000;
+        let mut treasury = [MockAccountView::new(treasury_pda, ID, lamports, &data).writable()];
+        run_heal_bump(&mut treasury, HEAL_KIND_TREASURY).unwrap();
+        assert_eq!(treasury[0].data().len(), TREASURY_SIZE);
+        let healed = Treasury::decode(treasury[0].data()).unwrap();
+        assert_eq!(
+            healed,
+            Treasury {
+                authority,
+                balance: 5_000,
+                bump: treasury_bump,
+                co_authorities: NO_CO_AUTHORITIES
+            }
+        );
+        assert!(load_typed::<Treasury>(&treasury[0].view(), &ID).is_ok());
+
+        let owner = Address::new_from_array([2u8; 32]);
+        let (deposit_pda, deposit_bump) =
+            derive_user_deposit_pda(&treasury_pda, &owner, &ID).unwrap();
+        let mut data = [0u8; vulnerable::USER_DEPOSIT_SIZE];
+        vulnerable::UserDeposit { owner, treasury: treasury_pda, amount: 700, bump: 13 }
+            .serialize(&mut data)
+            .unwrap();
+        assert_eq!(data.len(), LEGACY_USER_DEPOSIT_SIZE);
+        let deposit = MockAccountView::new(deposit_pda, ID, 0, &data).writable();
+        let payer = MockAccountView::new(owner, SYSTEM_PROGRAM_ID, 1_000_000, &[]).writable();
+        let system = MockAccountView::new(SYSTEM_PROGRAM_ID, Address::default(), 0, &[]);
+
+        // Short of rent: growing the account needs a signing payer
+        for (mut accounts, error) in [
+            (vec![deposit.clone()], ProgramError::NotEnoughAccountKeys),
+            (
+                vec![deposit.clone(), payer.clone(), system.clone()],
+                ProgramError::MissingRequiredSignature,
+            ),
+        ] {
+            assert_eq!(run_heal_bump(&mut accounts, HEAL_KIND_USER_DEPOSIT), Err(error));
+            assert_eq!(accounts[0].data(), &data[..]);
+        }
+
+        let mut accounts = [deposit, payer.signer(), system];
+        run_heal_bump(&mut accounts, HEAL_KIND_USER_DEPOSIT).unwrap();
+        let healed = UserDeposit::decode(accounts[0].data()).unwrap();
+        assert_eq!(
+            healed,
+            UserDeposit {
+                owner,
+                treasury: treasury_pda,
+                amount: 700,
+                bump: deposit_bump,
+                deposit_count: 0,
+                withdraw_count: 0,
+                allowed_destination: Address::default()
+            }
+        );
+    }
+
+    /// Test that a legacy record at a non-canonical address is not migrated.
+    #[test]
+    fn test_heal_bump_rejects_forged_legacy_record() {
+        let authority = Address::new_from_array([1u8; 32]);
+        let mut data = [0u8; vulnerable::TREASURY_SIZE];
+        vulnerable::Treasury { authority, balance: 5_000, bump: 200 }.serialize(&mut data).unwrap();
+
+        let forged_address = Address::new_from_array([9u8; 32]);
+        let mut forged = [MockAccountView::new(forged_address, ID, 0, &data).writable()];
+        let result = run_heal_bump(&mut forged, HEAL_KIND_TREASURY);
+        assert_eq!(result, Err(SecureError::InvalidPda.into()));
+        assert_eq!(forged[0].data(), &data[..]);
+    }
+
+    /// A genuine treasury PDA whose stored bump is not canonical.
+    fn legacy_treasury_data() -> (Address, [u8; TREASURY_SIZE]) {
+        let authority = Address::new_from_array([1u8; 32]);
+        let (treasury_pda, canonical_bump) = derive_treasury_pda(&authority, &ID).unwrap();
+        let mut data = [0u8; TREASURY_SIZE];
+        Treasury {
+            authority,
+            balance: 5_000,
+            bump: canonical_bump - 1,
+            co_authorities: NO_CO_AUTHORITIES,
+        }
+        .serialize(&mut data)
+        .unwrap();
+        (treasury_pda, data)
+    }
+
+    #[cfg(not(feature = "strict_pda"))]
+    #[test]
+    fn test_non_canonical_record_loads_by_default() {
+        let (treasury_pda, data) = legacy_treasury_data();
+        assert_eq!(Treasury::try_from_slice(&data).unwrap().balance, 5_000);
+
+        let mut treasury = MockAccountView::new(treasury_pda, ID, 0, &data);
+        assert!(load_typed::<Treasury>(&treasury.view(), &ID).is_ok());
+    }
+
+    #[cfg(feature = "strict_pda")]
+    #[test]
+    fn test_strict_pda_rejects_non_canonical_record_at_load() {
+        let (treasury_pda, data) = legacy_treasury_data();
+        let invalid_bump: ProgramError = SecureError::InvalidBump.into();
+        assert_eq!(Treasury::try_from_slice(&data, &ID), Err(invalid_bump.clone()));
+
+        let mut treasury = MockAccountView::new(treasury_pda, ID, 0, &data).writable();
+        assert_eq!(load_typed::<Treasury>(&treasury.view(), &ID), Err(invalid_bump.clone()));
+
+        let owner = Address::new_from_array([2u8; 32]);
+        let (_, deposit_bump) = derive_user_deposit_pda(&treasury_pda, &owner, &ID).unwrap();
+        let mut deposit = UserDeposit {
+            owner,
+            treasury: treasury_pda,
+            amount: 700,
+            bump: deposit_bump.wrapping_add(1),
+            deposit_count: 0,
+            withdraw_count: 0,
+            allowed_destination: Address::default(),
+        };
+        let mut deposit_data = [0u8; USER_DEPOSIT_SIZE];
+        deposit.serialize(&mut deposit_data).unwrap();
+        assert_eq!(UserDeposit::try_from_slice(&deposit_data, &ID), Err(invalid_bump));
+        deposit.bump = deposit_bump;
+        deposit.serialize(&mut deposit_data).unwrap();
+        assert_eq!(UserDeposit::try_from_slice(&deposit_data, &ID), Ok(deposit));
+
+        // Healing still reads the legacy record, after which it loads
+        run_repair_bump(&mut treasury).unwrap();
+        assert!(load_typed::<Treasury>(&treasury.view(), &ID).is_ok());
+    }
+
+    fn run_repair_bump(account: &mut MockAccountView) -> ProgramResult {
+        process_instruction(&ID, &[account.view()], &[REPAIR_BUMP_DISCRIMINATOR])
+    }
+
+    #[test]
+    fn test_repair_bump_repairs_both_layouts() {
+        let authority = Address::new_from_array([1u8; 32]);
+        let (treasury_pda, treasury_bump) = derive_treasury_pda(&authority, &ID).unwrap();
+        let mut data = [0u8; TREASURY_SIZE];
+        Treasury { authority, balance: 5_000, bump: 0, co_authorities: NO_CO_AUTHORITIES }
+            .serialize(&mut data)
+            .unwrap();
+        let mut treasury = MockAccountView::new(treasury_pda, ID, 0, &data).writable();
+
+        run_repair_bump(&mut treasury).unwrap();
+        let repaired = Treasury::decode(treasury.data()).unwrap();
+        assert_eq!((repaired.bump, repaired.balance), (treasury_bump, 5_000));
+
+        let owner = Address::new_from_array([2u8; 32]);
+        let (deposit_pda, deposit_bump) =
+            derive_user_deposit_pda(&treasury_pda, &owner, &ID).unwrap();
+        let mut data = [0u8; USER_DEPOSIT_SIZE];
+        UserDeposit {
+            owner,
+            treasury: treasury_pda,
+            amount: 700,
+            bump: 13,
+            deposit_count: 0,
+            withdraw_count: 0,
+            allowed_destination: Address::default(),
+        }
+        .serialize(&mut data)
+        .unwrap();
+        let mut deposit = MockAccountView::new(deposit_pda, ID, 0, &data).writable();
+
+        run_repair_bump(&mut deposit).unwrap();
+        let repaired = UserDeposit::decode(deposit.data()).unwrap();
+        assert_eq!((repaired.bump, repaired.amount), (deposit_bump, 700));
+
+        // Already canonical: nothing changes
+        let before = deposit.data().to_vec();
+        run_repair_bump(&mut deposit).unwrap();
+        assert_eq!(deposit.data(), &before[..]);
+    }
+
+    /// Test that repair_bump recognises the vulnerable program's layouts by
+    /// length and migrates them like heal_bump.
+    #[test]
+    fn test_repair_bump_migrates_legacy_layouts() {
+        let authority = Address::new_from_array([1u8; 32]);
+        let (treasury_pda, treasury_bump) = derive_treasury_pda(&authority, &ID).unwrap();
+        let mut data = [0u8; vulnerable::TREASURY_SIZE];
+        vulnerable::Treasury { authority, balance: 5_000, bump: 0 }.serialize(&mut data).unwrap();
+        let lamports = rent_exempt_minimum(TREASURY_SIZE).unwrap() + 5_000;
+        let mut treasury = MockAccountView::new(treasury_pda, ID, lamports, &data).writable();
+
+        run_repair_bump(&mut treasury).unwrap();
+        let repaired = Treasury::decode(treasury.data()).unwrap();
+        assert_eq!((repaired.bump, repaired.balance), (treasury_bump, 5_000));
+        assert_eq!(repaired.co_authorities, NO_CO_AUTHORITIES);
+
+        let owner = Address::new_from_array([2u8; 32]);
+        let (deposit_pda, deposit_bump) =
+            derive_user_deposit_pda(&treasury_pda, &owner, &ID).unwrap();
+        let mut data = [0u8; vulnerable::USER_DEPOSIT_SIZE];
+        vulnerable::UserDeposit { owner, treasury: treasury_pda, amount: 700, bump: 13 }
+            .serialize(&mut data)
+            .unwrap();
+        let mut deposit = MockAccountView::new(deposit_pda, ID, 0, &data).writable();
+
+        // Short of rent and no payer: left untouched
+        assert_eq!(run_repair_bump(&mut deposit), Err(ProgramError::NotEnoughAccountKeys));
+        assert_eq!(deposit.data(), &data[..]);
+
+        let mut payer =
+            MockAccountView::new(owner, SYSTEM_PROGRAM_ID, 1_000_000, &[]).signer().writable();
+        let mut system = MockAccountView::new(SYSTEM_PROGRAM_ID, Address::default(), 0, &[]);
+        process_instruction(
+            &ID,
+            &[deposit.view(), payer.view(), system.view()],
+            &[REPAIR_BUMP_DISCRIMINATOR],
+        )
+        .unwrap();
+        let repaired = UserDeposit::decode(deposit.data()).unwrap();
+        assert_eq!((repaired.bump, repaired.amount), (deposit_bump, 700));
+    }
+
+    #[test]
+    fn test_repair_bump_rejects_unrepairable_accounts() {
+        let authority = Address::new_from_array([1u8; 32]);
+        let mut data = [0u8; TREASURY_SIZE];
+        Treasury { authority, balance: 5_000, bump: 0, co_authorities: NO_CO_AUTHORITIES }
+            .serialize(&mut data)
+            .unwrap();
+
+        // Not the canonical PDA of its stored authority
+        let forged_address = Address::new_from_array([9u8; 32]);
+        let mut forged = MockAccountView::new(forged_address, ID, 0, &data).writable();
+        assert_eq!(run_repair_bump(&mut forged), Err(SecureError::InvalidPda.into()));
+        assert_eq!(forged.data(), &data[..]);
+
+        // Neither layout
+        let (treasury_pda, _) = derive_treasury_pda(&authority, &ID).unwrap();
+        let mut padded = MockAccountView::new(treasury_pda, ID, 0, &[0u8; TREASURY_SIZE + 1]);
+        assert_eq!(run_repair_bump(&mut padded), Err(CommonError::AccountTypeMismatch.into()));
+
+        // Not this program's account
+        let mut foreign = MockAccountView::new(treasury_pda, Address::default(), 0, &data);
+        assert_eq!(run_repair_bump(&mut foreign), Err(ProgramError::IllegalOwner));
+    }
+
+    // =========================================================================
+    // CO-AUTHORITIES
+    // =========================================================================
+
+    const PRIMARY: Address = Address::new_from_array([1u8; 32]);
+    const CO_AUTHORITY: Address = Address::new_from_array([7u8; 32]);
+    const OUTSIDER: Address = Address::new_from_array([8u8; 32]);
+
+    /// A genuine treasury PDA for PRIMARY with the given co-authorities.
+    fn co_authority_treasury(co_authorities: [Address; MAX_CO_AUTHORITIES]) -> MockAccountView {
+        let (treasury_pda, bump) = derive_treasury_pda(&PRIMARY, &ID).unwrap();
+        let mut data = [0u8; TREASURY_SIZE];
+        Treasury { authority: PRIMARY, balance: 0, bump, co_authorities }
+            .serialize(&mut data)
+            .unwrap();
+        MockAccountView::new(treasury_pda, ID, 0, &data).writable()
+    }
+
+    fn run_co_authority_update(
+        discriminator: u8,
+        treasury: &mut MockAccountView,
+        signer: Address,
+        key: &Address,
+    ) -> ProgramResult {
+        let mut signer = MockAccountView::new(signer, Address::default(), 0, &[]).signer();
+        let mut data = [discriminator; 33];
+        data[1..].copy_from_slice(key.as_ref());
+        process_instruction(&ID, &[treasury.view(), signer.view()], &data)
+    }
+
+    /// Runs sweep_surplus signed by `signer` on a treasury of PRIMARY with
+    /// `co_authorities`, holding 500 lamports above its rent and balance.
+    /// Returns the result and the treasury and destination lamports afterwards.
+    fn run_sweep(
+        signer: Address,
+        co_authorities: [Address; MAX_CO_AUTHORITIES],
+    ) -> (ProgramResult, u64, u64) {
+        let (treasury_pda, bump) = derive_treasury_pda(&PRIMARY, &ID).unwrap();
+        let mut data = [0u8; TREASURY_SIZE];
+        Treasury { authority: PRIMARY, balance: 1_000, bump, co_authorities }
+            .serialize(&mut data)
+            .unwrap();
+        let lamports = treasury_rent() + 1_000 + 500;
+        let mut treasury = MockAccountView::new(treasury_pda, ID, lamports, &data).writable();
+        let mut signer = MockAccountView::new(signer, Address::default(), 0, &[]).signer();
+        let mut destination =
+            MockAccountView::new(Address::new_from_array([9u8; 32]), Address::default(), 0, &[])
+                .writable();
+
+        let result = process_instruction(
+            &ID,
+            &[treasury.view(), signer.view(), destination.view()],
+            &[SWEEP_SURPLUS_DISCRIMINATOR],
+        );
+        (result, treasury.lamports(), destination.lamports())
+    }
+
+    /// Test that any treasury authority can sweep the surplus and nobody else can.
+    #[test]
+    fn test_sweep_surplus_accepts_any_treasury_authority() {
+        let swept = (Ok(()), treasury_rent() + 1_000, 500);
+        assert_eq!(run_sweep(PRIMARY, NO_CO_AUTHORITIES), swept);
+        assert_eq!(run_sweep(CO_AUTHORITY, [CO_AUTHORITY, Address::default()]), swept);
+
+        let unchanged = (Err(SecureError::Unauthorized.into()), treasury_rent() + 1_500, 0);
+        assert_eq!(run_sweep(OUTSIDER, [CO_AUTHORITY, Address::default()]), unchanged);
+        // A removed co-authority is an outsider again
+        assert_eq!(run_sweep(CO_AUTHORITY, NO_CO_AUTHORITIES), unchanged);
+    }
+
+    #[test]
+    fn test_is_treasury_authority() {
+        let treasury = Treasury {
+            authority: PRIMARY,
+            balance: 0,
+            bump: 255,
+            co_authorities: [CO_AUTHORITY, Address::default()],
+        };
+        assert!(is_treasury_authority(&treasury, &PRIMARY));
+        assert!(is_treasury_authority(&treasury, &CO_AUTHORITY));
+        assert!(!is_treasury_authority(&treasury, &OUTSIDER));
+        // A free slot never authorizes the zero key
+        assert!(!is_treasury_authority(&treasury, &Address::default()));
+    }
+
+    #[test]
+    fn test_primary_adds_and_removes_co_authorities() {
+        let mut treasury = co_authority_treasury(NO_CO_AUTHORITIES);
+        let second = Address::new_from_array([9u8; 32]);
+
+        run_co_authority_update(
+            ADD_CO_AUTHORITY_DISCRIMINATOR,
+            &mut treasury,
+            PRIMARY,
+            &CO_AUTHORITY,
+        )
+        .unwrap();
+        run_co_authority_update(ADD_CO_AUTHORITY_DISCRIMINATOR, &mut treasury, PRIMARY, &second)
+            .unwrap();
+        let stored = Treasury::decode(treasury.data()).unwrap();
+        assert!(is_treasury_authority(&stored, &CO_AUTHORITY));
+        assert!(is_treasury_authority(&stored, &second));
+
+        // Both slots taken; duplicates and the primary are rejected outright
+        let result = run_co_authority_update(
+            ADD_CO_AUTHORITY_DISCRIMINATOR,
+            &mut treasury,
+            PRIMARY,
+            &OUTSIDER,
+        );
+        assert!(matches!(result, Err(ProgramError::Custom(0x1009))));
+        let result = run_co_authority_update(
+            ADD_CO_AUTHORITY_DISCRIMINATOR,
+            &mut treasury,
+            PRIMARY,
+            &PRIMARY,
+        );
+        assert_eq!(result, Err(ProgramError::InvalidArgument));
+
+        run_co_authority_update(
+            REMOVE_CO_AUTHORITY_DISCRIMINATOR,
+            &mut treasury,
+            PRIMARY,
+            &CO_AUTHORITY,
+        )
+        .unwrap();
+        let stored = Treasury::decode(treasury.data()).unwrap();
+        assert!(!is_treasury_authority(&stored, &CO_AUTHORITY));
+        assert!(is_treasury_authority(&stored, &second));
+
+        let result = run_co_authority_update(
+            REMOVE_CO_AUTHORITY_DISCRIMINATOR,
+            &mut treasury,
+            PRIMARY,
+            &CO_AUTHORITY,
+        );
+        assert!(matches!(result, Err(ProgramError::Custom(0x100a))));
+    }
+
+    #[test]
+    fn test_only_primary_manages_co_authorities() {
+        let mut treasury = co_authority_treasury([CO_AUTHORITY, Address::default()]);
+        let before = treasury.data().to_vec();
+
+        // A co-authority is authorized for treasury operations, not for the list
+        let result = run_co_authority_update(
+            ADD_CO_AUTHORITY_DISCRIMINATOR,
+            &mut treasury,
+            CO_AUTHORITY,
+            &OUTSIDER,
+        );
+        assert!(matches!(result, Err(ProgramError::Custom(0x1003))));
+        let result = run_co_authority_update(
+            REMOVE_CO_AUTHORITY_DISCRIMINATOR,
+            &mut treasury,
+            CO_AUTHORITY,
+            &CO_AUTHORITY,
+        );
+        assert!(matches!(result, Err(ProgramError::Custom(0x1003))));
+
+        let result = run_co_authority_update(
+            ADD_CO_AUTHORITY_DISCRIMINATOR,
+            &mut treasury,
+            OUTSIDER,
+            &OUTSIDER,
+        );
+        assert!(matches!(result, Err(ProgramError::Custom(0x1003))));
+        assert_eq!(treasury.data(), &before[..]);
+    }
+
+    const SENDER: Address = Address::new_from_array([2u8; 32]);
+    const RECIPIENT: Address = Address::new_from_array([3u8; 32]);
+
+    /// Build a genuine UserDeposit PDA for `owner` under the treasury of `authority`.
+    fn user_deposit_under(authority: &Address, owner: Address, amount: u64) -> MockAccountView {
+        let (treasury_pda, _) = derive_treasury_pda(authority, &ID).unwrap();
+        let (address, bump) = derive_user_deposit_pda(&treasury_pda, &owner, &ID).unwrap();
+        let mut data = [0u8; USER_DEPOSIT_SIZE];
+        UserDeposit {
+            owner,
+            treasury: treasury_pda,
+            amount,
+            bump,
+            deposit_count: 0,
+            withdraw_count: 0,
+            allowed_destination: Address::default(),
+        }
+        .serialize(&mut data)
+        .unwrap();
+        MockAccountView::new(address, ID, 0, &data).writable()
+    }
+
+    fn run_transfer(
+        sender_deposit: &mut MockAccountView,
+        recipient_deposit: &mut MockAccountView,
+        amount: u64,
+    ) -> ProgramResult {
+        let mut treasury = co_authority_treasury(NO_CO_AUTHORITIES);
+        let mut sender = MockAccountView::new(SENDER, Address::default(), 0, &[]).signer();
+        transfer_deposit(
+            &ID,
+            &[sender_deposit.view(), recipient_deposit.view(), treasury.view(), sender.view()],
+            &amount.to_le_bytes(),
+        )
+    }
+
+    /// Test that a transfer within one treasury moves the amount between deposits.
+    #[test]
+    fn test_transfer_deposit_same_treasury() {
+        let mut sender_deposit = user_deposit_under(&PRIMARY, SENDER, 1_000);
+        let mut recipient_deposit = user_deposit_under(&PRIMARY, RECIPIENT, 50);
+
+        assert!(run_transfer(&mut sender_deposit, &mut recipient_deposit, 400).is_ok());
+        let sender = UserDeposit::decode(sender_deposit.data()).unwrap();
+        let recipient = UserDeposit::decode(recipient_deposit.data()).unwrap();
+        assert_eq!((sender.amount, recipient.amount), (600, 450));
+
+        // More than the sender holds is rejected without writing either side
+        let result = run_transfer(&mut sender_deposit, &mut recipient_deposit, 601);
+        assert!(matches!(result, Err(ProgramError::Custom(0x2001))));
+        let sender = UserDeposit::decode(sender_deposit.data()).unwrap();
+        assert_eq!(sender.amount, 600);
+    }
+
+    /// Test that a recipient deposit under another treasury is rejected.
+    #[test]
+    fn test_transfer_deposit_cross_treasury_rejected() {
+        let mut sender_deposit = user_deposit_under(&PRIMARY, SENDER, 1_000);
+        let mut recipient_deposit = user_deposit_under(&OUTSIDER, RECIPIENT, 50);
+        let before = (sender_deposit.data().to_vec(), recipient_deposit.data().to_vec());
+
+        let result = run_transfer(&mut sender_deposit, &mut recipient_deposit, 400);
+        assert!(matches!(result, Err(ProgramError::Custom(0x1002))));
+        assert_eq!(sender_deposit.data(), &before.0[..]);
+        assert_eq!(recipient_deposit.data(), &before.1[..]);
+
+        // Swapping roles is rejected the same way
+        let result = run_transfer(&mut recipient_deposit, &mut sender_deposit, 10);
+        assert!(matches!(result, Err(ProgramError::Custom(0x1002))));
+    }
+
+    /// Test that the program account or a sysvar passed as a data account is rejected.
+    #[test]
+    fn test_program_and_sysvar_rejected_as_data_accounts() {
+        let (user_deposit, mut treasury, mut withdrawer) = withdraw_accounts(None, 0);
+        let mut system = MockAccountView::new(Address::default(), Address::default(), 0, &[]);
+
+        for impostor in [ID, pinocchio_security_common::SYSVAR_IDS[1]] {
+            // Owned by this program and holding genuine data: only the denylist stops it
+            let mut fake = MockAccountView::new(impostor, ID, 0, user_deposit.data()).writable();
+            let result = withdraw(
+                &ID,
+                &[fake.view(), treasury.view(), withdrawer.view(), system.view()],
+                &400u64.to_le_bytes(),
+            );
+            assert_eq!(result, Err(ProgramError::InvalidAccountData));
+
+            let result = deposit(
+                &ID,
+                &[fake.view(), treasury.view(), withdrawer.view(), system.view()],
+                &400u64.to_le_bytes(),
+            );
+            assert_eq!(result, Err(ProgramError::InvalidAccountData));
+
+            let result = heal_bump(&ID, &[fake.view()], &[HEAL_KIND_USER_DEPOSIT]);
+            assert_eq!(result, Err(ProgramError::InvalidAccountData));
+        }
+    }
+
+    /// Test Treasury deserialization with insufficient data.
+    #[test]
+    fn test_treasury_invalid_data_length() {
+        let short_buffer = [0u8; 10];
+        let result = Treasury::decode(&short_buffer);
+        assert!(result.is_err());
+    }
+
+    /// Test UserDeposit deserialization with insufficient data.
+    #[test]
+    fn test_user_deposit_invalid_data_length() {
+        let short_buffer = [0u8; 50];
+        let result = UserDeposit::decode(&short_buffer);
+        assert!(result.is_err());
+    }
+
+    /// Test that results and errors are identical with the `logs` feature disabled.
+    ///
+    /// Run with `cargo test --no-default-features` to exercise the no-op `log!` shim.
+    #[test]
+    fn test_results_unchanged_without_logs() {
+        let a = Address::new_from_array([4u8; 32]);
+        let b = Address::new_from_array([5u8; 32]);
+
+        // Paths that log before returning still return the same values
+        assert!(matches!(assert_distinct_accounts(&a, &a), Err(ProgramError::Custom(0x1006))));
+        assert!(assert_distinct_accounts(&a, &b).is_ok());
+
+        // Instruction routing is unaffected
+        let result = process_instruction(&ID, &[], &[]);
+        assert!(matches!(result, Err(ProgramError::InvalidInstructionData)));
+        let result = process_instruction(&ID, &[], &[0xFF]);
+        assert!(matches!(result, Err(ProgramError::InvalidInstructionData)));
+    }
+
+    /// Test a full init -> create -> deposit -> withdraw flow through
+    /// `process_instruction`, checking state after every step.
+    #[test]
+    fn test_program_test_treasury_flow() {
+        let authority_key = Address::new_from_array([1u8; 32]);
+        let owner_key = Address::new_from_array([2u8; 32]);
+        let (treasury_pda, _) = derive_treasury_pda(&authority_key, &ID).unwrap();
+        let (user_deposit_pda, _) =
+            derive_user_deposit_pda(&treasury_pda, &owner_key, &ID).unwrap();
+
+        let mut test = ProgramTest::new(ID, process_instruction);
+        // ProgramTest does not execute the deposit's transfer CPI, so the
+        // lamports backing the deposit start in the treasury and the owner
+        // keeps the 1_000 it funds the transfer with
+        let treasury =
+            test.add_account(treasury_pda, ID, treasury_rent() + 1_000, &[0u8; TREASURY_SIZE]);
+        let user_deposit = test.add_account(user_deposit_pda, ID, 0, &[0u8; USER_DEPOSIT_SIZE]);
+        let authority = test.add_account(authority_key, Address::default(), 0, &[]);
+        let owner = test.add_account(owner_key, Address::default(), 1_000, &[]);
+        let system = test.add_account(Address::default(), Address::default(), 0, &[]);
+
+        let balances = |test: &ProgramTest| {
+            let treasury = Treasury::decode(test.account(treasury).data()).unwrap();
+            let deposit = UserDeposit::decode(test.account(user_deposit).data()).unwrap();
+            (treasury.balance, deposit.amount, test.account(owner).lamports())
+        };
+
+        test.process(
+            &[AccountMeta::writable(treasury), AccountMeta::readonly(authority).signer()],
+            &[INITIALIZE_TREASURY_DISCRIMINATOR],
+        )
+        .unwrap();
+        assert_eq!(
+            Treasury::decode(test.account(treasury).data()).unwrap().authority,
+            authority_key
+        );
+
+        test.process(
+            &[
+                AccountMeta::writable(user_deposit),
+                AccountMeta::readonly(treasury),
+                AccountMeta::readonly(owner).signer(),
+            ],
+            &[CREATE_USER_DEPOSIT_DISCRIMINATOR],
+        )
+        .unwrap();
+        assert_eq!(balances(&test), (0, 0, 1_000));
+
+        let mut deposit_data = [DEPOSIT_DISCRIMINATOR; 9];
+        deposit_data[1..].copy_from_slice(&1_000u64.to_le_bytes());
+        let deposit_accounts = [
+            AccountMeta::writable(user_deposit),
+            AccountMeta::writable(treasury),
+            AccountMeta::writable(owner).signer(),
+            AccountMeta::readonly(system),
+        ];
+        test.process(&deposit_accounts, &deposit_data).unwrap();
+        assert_eq!(balances(&test), (1_000, 1_000, 1_000));
+
+        let mut withdraw_data = [WITHDRAW_DISCRIMINATOR; 9];
+        withdraw_data[1..].copy_from_slice(&400u64.to_le_bytes());
+        let withdraw_accounts = [
+            AccountMeta::writable(user_deposit),
+            AccountMeta::writable(treasury),
+            AccountMeta::writable(owner).signer(),
+            AccountMeta::readonly(system),
+        ];
+        test.process(&withdraw_accounts, &withdraw_data).unwrap();
+        assert_eq!(balances(&test), (600, 600, 1_400));
+        assert_eq!(test.account(treasury).lamports(), treasury_rent() + 600);
+
+        // Overdrawing fails and leaves every account untouched
+        withdraw_data[1..].copy_from_slice(&601u64.to_le_bytes());
+        assert_eq!(
+            test.process(&withdraw_accounts, &withdraw_data),
+            Err(CommonError::InsufficientBalance.into())
+        );
+        assert_eq!(balances(&test), (600, 600, 1_400));
+        assert_eq!(test.account(treasury).lamports(), treasury_rent() + 600);
+    }
+
+    /// Accounts of a funded deposit created with `allowlist` as instruction
+    /// data, in the order user_deposit, treasury, owner, system, cold wallet.
+    fn allowlisted_deposit(allowlist: &[u8]) -> (ProgramTest, [usize; 5]) {
+        let authority_key = Address::new_from_array([1u8; 32]);
+        let owner_key = Address::new_from_array([2u8; 32]);
+        let (treasury_pda, _) = derive_treasury_pda(&authority_key, &ID).unwrap();
+        let (user_deposit_pda, _) =
+            derive_user_deposit_pda(&treasury_pda, &owner_key, &ID).unwrap();
+
+        let mut test = ProgramTest::new(ID, process_instruction);
+        let treasury =
+            test.add_account(treasury_pda, ID, treasury_rent() + 1_000, &[0u8; TREASURY_SIZE]);
+        let user_deposit = test.add_account(user_deposit_pda, ID, 0, &[0u8; USER_DEPOSIT_SIZE]);
+        let authority = test.add_account(authority_key, Address::default(), 0, &[]);
+        let owner = test.add_account(owner_key, Address::default(), 1_000, &[]);
+        let system = test.add_account(Address::default(), Address::default(), 0, &[]);
+        let cold = test.add_account(COLD_WALLET, Address::default(), 0, &[]);
+
+        test.process(
+            &[AccountMeta::writable(treasury), AccountMeta::readonly(authority).signer()],
+            &[INITIALIZE_TREASURY_DISCRIMINATOR],
+        )
+        .unwrap();
+        let mut create_data = vec![CREATE_USER_DEPOSIT_DISCRIMINATOR];
+        create_data.extend_from_slice(allowlist);
+        test.process(
+            &[
+                AccountMeta::writable(user_deposit),
+                AccountMeta::readonly(treasury),
+                AccountMeta::readonly(owner).signer(),
+            ],
+            &create_data,
+        )
+        .unwrap();
+        let mut deposit_data = [DEPOSIT_DISCRIMINATOR; 9];
+        deposit_data[1..].copy_from_slice(&1_000u64.to_le_bytes());
+        test.process(
+            &[
+                AccountMeta::writable(user_deposit),
+                AccountMeta::writable(treasury),
+                AccountMeta::writable(owner).signer(),
+                AccountMeta::readonly(system),
+            ],
+            &deposit_data,
+        )
+        .unwrap();
+
+        (test, [user_deposit, treasury, owner, system, cold])
+    }
+
+    /// Withdraws 400 from an `allowlisted_deposit`, paid to `destination`
+    /// (co-signed when it is not the owner).
+    fn withdraw_allowlisted(
+        test: &mut ProgramTest,
+        accounts: [usize; 5],
+        destination: usize,
+    ) -> ProgramResult {
+        let [user_deposit, treasury, owner, system, _] = accounts;
+        let mut withdraw_data = [WITHDRAW_DISCRIMINATOR; 9];
+        withdraw_data[1..].copy_from_slice(&400u64.to_le_bytes());
+        test.process(
+            &[
+                AccountMeta::writable(user_deposit),
+                AccountMeta::writable(treasury),
+                AccountMeta::writable(owner).signer(),
+                AccountMeta::readonly(system),
+                AccountMeta::writable(destination).signer(),
+            ],
+            &withdraw_data,
+        )
+    }
+
+    /// Test that a deposit created with an allowlisted destination pays out
+    /// to it.
+    #[test]
+    fn test_withdraw_to_allowlisted_destination() {
+        let (mut test, accounts) = allowlisted_deposit(COLD_WALLET.as_ref());
+        let [user_deposit, treasury, _, _, cold] = accounts;
+        let record = UserDeposit::decode(test.account(user_deposit).data()).unwrap();
+        assert_eq!(record.allowed_destination, COLD_WALLET);
+
+        withdraw_allowlisted(&mut test, accounts, cold).unwrap();
+        assert_eq!(test.account(cold).lamports(), 400);
+        assert_eq!(test.account(treasury).lamports(), treasury_rent() + 600);
+        assert_eq!(UserDeposit::decode(test.account(user_deposit).data()).unwrap().amount, 600);
+    }
+
+    /// Test that a deposit created without an allowlist pays out to any
+    /// co-signed destination or to the owner.
+    #[test]
+    fn test_withdraw_without_allowlist_unrestricted() {
+        let (mut test, accounts) = allowlisted_deposit(&[]);
+        let [user_deposit, _, owner, _, cold] = accounts;
+        let record = UserDeposit::decode(test.account(user_deposit).data()).unwrap();
+        assert_eq!(record.allowed_destination, Address::default());
+
+        withdraw_allowlisted(&mut test, accounts, cold).unwrap();
+        withdraw_allowlisted(&mut test, accounts, owner).unwrap();
+        assert_eq!((test.account(cold).lamports(), test.account(owner).lamports()), (400, 1_400));
+        assert_eq!(UserDeposit::decode(test.account(user_deposit).data()).unwrap().amount, 200);
+    }
+
+    /// Test that an allowlisted deposit rejects any other destination, the
+    /// owner included, and leaves every account untouched.
+    #[test]
+    fn test_withdraw_to_wrong_destination_rejected() {
+        let (mut test, accounts) = allowlisted_deposit(COLD_WALLET.as_ref());
+        let [user_deposit, treasury, owner, _, _] = accounts;
+        let other =
+            test.add_account(Address::new_from_array([9u8; 32]), Address::default(), 0, &[]);
+        let snapshot = |test: &ProgramTest| {
+            (
+                account_hash(test.account(user_deposit).data()),
+                account_hash(test.account(treasury).data()),
+                test.account(treasury).lamports(),
+                test.account(owner).lamports(),
+                test.account(other).lamports(),
+            )
+        };
+        let before = snapshot(&test);
+
+        for destination in [other, owner] {
+            assert_eq!(
+                withdraw_allowlisted(&mut test, accounts, destination),
+                Err(SecureError::Unauthorized.into())
+            );
+            assert_eq!(snapshot(&test), before);
+        }
+    }
+
+    /// Test that create_user_deposit takes no data or a 32-byte allowlist.
+    #[test]
+    fn test_create_user_deposit_rejects_malformed_allowlist() {
+        let owner_key = Address::new_from_array([2u8; 32]);
+        let (treasury_pda, treasury_bump) =
+            derive_treasury_pda(&Address::new_from_array([1u8; 32]), &ID).unwrap();
+        let (user_deposit_pda, _) =
+            derive_user_deposit_pda(&treasury_pda, &owner_key, &ID).unwrap();
+        let mut treasury_data = [0u8; TREASURY_SIZE];
+        Treasury {
+            authority: Address::new_from_array([1u8; 32]),
+            balance: 0,
+            bump: treasury_bump,
+            co_authorities: NO_CO_AUTHORITIES,
+        }
+        .serialize(&mut treasury_data)
+        .unwrap();
+
+        let mut user_deposit =
+            MockAccountView::new(user_deposit_pda, ID, 0, &[0u8; USER_DEPOSIT_SIZE]).writable();
+        let mut treasury = MockAccountView::new(treasury_pda, ID, 0, &treasury_data);
+        let mut owner = MockAccountView::new(owner_key, Address::default(), 0, &[]).signer();
+        let result = create_user_deposit(
+            &ID,
+            &[user_deposit.view(), treasury.view(), owner.view()],
+            &[6u8; 31],
+        );
+        assert_eq!(result, Err(ProgramError::InvalidInstructionData));
+        assert_eq!(user_deposit.data(), &[0u8; USER_DEPOSIT_SIZE][..]);
+    }
+
+    /// Test that each deposit and withdrawal is counted, and a failed one is not.
+    #[test]
+    fn test_deposit_and_withdraw_counters() {
+        let authority_key = Address::new_from_array([1u8; 32]);
+        let owner_key = Address::new_from_array([2u8; 32]);
+        let (treasury_pda, _) = derive_treasury_pda(&authority_key, &ID).unwrap();
+        let (user_deposit_pda, _) =
+            derive_user_deposit_pda(&treasury_pda, &owner_key, &ID).unwrap();
+
+        let mut test = ProgramTest::new(ID, process_instruction);
+        let treasury =
+            test.add_account(treasury_pda, ID, treasury_rent() + 1_000, &[0u8; TREASURY_SIZE]);
+        let user_deposit = test.add_account(user_deposit_pda, ID, 0, &[0u8; USER_DEPOSIT_SIZE]);
+        let authority = test.add_account(authority_key, Address::default(), 0, &[]);
+        let owner = test.add_account(owner_key, Address::default(), 1_000, &[]);
+        let system = test.add_account(Address::default(), Address::default(), 0, &[]);
+
+        let counters = |test: &ProgramTest| {
+            let deposit = UserDeposit::decode(test.account(user_deposit).data()).unwrap();
+            (deposit.deposit_count, deposit.withdraw_count)
+        };
+        let instruction = |discriminator: u8, amount: u64| {
+            let mut data = [discriminator; 9];
+            data[1..].copy_from_slice(&amount.to_le_bytes());
+            data
+        };
+
+        test.process(
+            &[AccountMeta::writable(treasury), AccountMeta::readonly(authority).signer()],
+            &[INITIALIZE_TREASURY_DISCRIMINATOR],
+        )
+        .unwrap();
+        test.process(
+            &[
+                AccountMeta::writable(user_deposit),
+                AccountMeta::readonly(treasury),
+                AccountMeta::readonly(owner).signer(),
+            ],
+            &[CREATE_USER_DEPOSIT_DISCRIMINATOR],
+        )
+        .unwrap();
+        assert_eq!(counters(&test), (0, 0));
+
+        let deposit_accounts = [
+            AccountMeta::writable(user_deposit),
+            AccountMeta::writable(treasury),
+            AccountMeta::writable(owner).signer(),
+            AccountMeta::readonly(system),
+        ];
+        let withdraw_accounts = [
+            AccountMeta::writable(user_deposit),
+            AccountMeta::writable(treasury),
+            AccountMeta::writable(owner).signer(),
+            AccountMeta::readonly(system),
+        ];
+
+        for amount in [300, 300, 400] {
+            test.process(&deposit_accounts, &instruction(DEPOSIT_DISCRIMINATOR, amount)).unwrap();
+        }
+        assert_eq!(counters(&test), (3, 0));
+
+        test.process(&withdraw_accounts, &instruction(WITHDRAW_DISCRIMINATOR, 100)).unwrap();
+        test.process(&withdraw_accounts, &instruction(WITHDRAW_DISCRIMINATOR, 200)).unwrap();
+        assert_eq!(counters(&test), (3, 2));
+
+        // A rejected withdrawal is not counted
+        let result = test.process(&withdraw_accounts, &instruction(WITHDRAW_DISCRIMINATOR, 10_000));
+        assert_eq!(result, Err(CommonError::InsufficientBalance.into()));
+        assert_eq!(counters(&test), (3, 2));
+    }
+
+    /// Test that a saturated withdraw counter fails the withdrawal without debiting.
+    #[test]
+    fn test_withdraw_counter_overflow_rejected() {
+        let mut treasury = Treasury {
+            authority: Address::new_from_array([1u8; 32]),
+            balance: 1_000,
+            bump: 255,
+            co_authorities: NO_CO_AUTHORITIES,
+        };
+        let mut user_deposit = UserDeposit {
+            owner: Address::new_from_array([2u8; 32]),
+            treasury: Address::new_from_array([3u8; 32]),
+            amount: 400,
+            bump: 254,
+            deposit_count: 1,
+            withdraw_count: u32::MAX,
+            allowed_destination: Address::default(),
+        };
+
+        let result = apply_withdraw(&mut user_deposit, &mut treasury, 100);
+        assert_eq!(result, Err(ProgramError::ArithmeticOverflow));
+        assert_eq!((user_deposit.amount, treasury.balance), (400, 1_000));
+        assert_eq!(user_deposit.withdraw_count, u32::MAX);
+    }
+
+    // =========================================================================
+    // RE-INITIALIZATION
+    // =========================================================================
+
+    #[test]
+    fn test_initialize_treasury_and_user_deposit_reject_reinitialization() {
+        let authority_key = Address::new_from_array([1u8; 32]);
+        let owner_key = Address::new_from_array([2u8; 32]);
+        let (treasury_pda, _) = derive_treasury_pda(&authority_key, &ID).unwrap();
+        let (deposit_pda, _) = derive_user_deposit_pda(&treasury_pda, &owner_key, &ID).unwrap();
+        let mut authority =
+            MockAccountView::new(authority_key, Address::default(), 0, &[]).signer();
+        let mut owner = MockAccountView::new(owner_key, Address::default(), 0, &[]).signer();
+
+        let mut treasury =
+            MockAccountView::new(treasury_pda, ID, 0, &[0u8; TREASURY_SIZE]).writable();
+        initialize_treasury(&ID, &[treasury.view(), authority.view()], &[]).unwrap();
+        let mut deposit =
+            MockAccountView::new(deposit_pda, ID, 0, &[0u8; USER_DEPOSIT_SIZE]).writable();
+        create_user_deposit(&ID, &[deposit.view(), treasury.view(), owner.view()], &[]).unwrap();
+
+        assert_reinit_rejected(&mut treasury, |treasury| {
+            initialize_treasury(&ID, &[treasury.view(), authority.view()], &[])
+        });
+        assert_reinit_rejected(&mut deposit, |deposit| {
+            create_user_deposit(&ID, &[deposit.view(), treasury.view(), owner.view()], &[])
+        });
     }
 }
@@ -27,9 +27,20 @@
 
 #![allow(unexpected_cfgs)]
 
-use pinocchio::{entrypoint, error::ProgramError, AccountView, Address, ProgramResult};
+use pinocchio::{error::ProgramError, AccountView, Address, ProgramResult};
+
+// Logging shim: `log!` forwards to `solana_program_log` with the default `logs`
+// feature and compiles to nothing under `--no-default-features`.
+#[cfg(feature = "logs")]
 use solana_program_log::log;
 
+#[cfg(not(feature = "logs"))]
+macro_rules! log {
+    ($($arg:tt)*) => {{
+        let _ = core::format_args!($($arg)*);
+    }};
+}
+
 // =============================================================================
 // PROGRAM ID
 // =============================================================================
@@ -91,6 +102,7 @@ pub const WITHDRAW_DISCRIMINATOR: u8 = 3;
 /// This struct stores the treasury authority, balance tracking, and bump seed.
 /// In the vulnerable version, the bump is accepted from instruction data
 /// without verifying it's the canonical (highest valid) bump.
+#[derive(Debug, Clone, PartialEq)]
 pub struct Treasury {
     /// Treasury admin who can manage funds.
     /// Should be a seed component for PDA derivation.
@@ -155,6 +167,7 @@ impl Treasury {
 /// This struct creates a hierarchical relationship: each deposit is tied to
 /// both a specific treasury and a specific user. In the vulnerable version,
 /// this relationship is stored but never validated.
+#[derive(Debug, Clone, PartialEq)]
 pub struct UserDeposit {
     /// Depositor's pubkey - should be a signer for withdrawals.
     pub owner: Address,
@@ -227,7 +240,8 @@ impl UserDeposit {
 // ENTRYPOINT
 // =============================================================================
 
-entrypoint!(process_instruction);
+#[cfg(not(feature = "no-entrypoint"))]
+pinocchio::entrypoint!(process_instruction);
 
 /// Main entrypoint for the Pinocchio program.
 ///
@@ -638,9 +652,7 @@ mod tests {
         treasury.serialize(&mut buffer).unwrap();
 
         let deserialized = Treasury::try_from_slice(&buffer).unwrap();
-        assert_eq!(deserialized.authority, treasury.authority);
-        assert_eq!(deserialized.balance, treasury.balance);
-        assert_eq!(deserialized.bump, treasury.bump);
+        assert_eq!(deserialized, treasury);
     }
 
     /// Test UserDeposit serialization and deserialization roundtrip.
@@ -657,10 +669,7 @@ mod tests {
         user_deposit.serialize(&mut buffer).unwrap();
 
         let deserialized = UserDeposit::try_from_slice(&buffer).unwrap();
-        assert_eq!(deserialized.owner, user_deposit.owner);
-        assert_eq!(deserialized.treasury, user_deposit.treasury);
-        assert_eq!(deserialized.amount, user_deposit.amount);
-        assert_eq!(deserialized.bump, user_deposit.bump);
+        assert_eq!(deserialized, user_deposit);
     }
 
     /// Test Treasury deserialization with insufficient data.
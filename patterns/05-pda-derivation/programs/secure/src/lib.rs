@@ -69,6 +69,9 @@ pub struct UserDeposit {
     /// PDA bump seed - always canonical
     /// SECURITY: Validated on every access via bump constraint
     pub bump: u8,
+    /// Only address withdrawals may be sent to; `Pubkey::default()` = unrestricted
+    /// SECURITY: Set once at creation, enforced by the Withdraw constraint
+    pub allowed_destination: Pubkey,
 }
 
 // ============================================================================
@@ -109,7 +112,11 @@ pub mod secure_pda_derivation {
     /// - Treasury must be a valid Treasury account (type checked)
     /// - User deposit PDA includes treasury in seeds (hierarchical)
     /// - Canonical bump enforced by Anchor
-    pub fn create_user_deposit(ctx: Context<CreateUserDeposit>) -> Result<()> {
+    /// - Optional withdrawal destination allowlist (`Pubkey::default()` = any)
+    pub fn create_user_deposit(
+        ctx: Context<CreateUserDeposit>,
+        allowed_destination: Pubkey,
+    ) -> Result<()> {
         let user_deposit = &mut ctx.accounts.user_deposit;
 
         user_deposit.owner = ctx.accounts.owner.key();
@@ -118,12 +125,14 @@ pub mod secure_pda_derivation {
         user_deposit.amount = 0;
         // SECURITY: Store canonical bump from Anchor's derivation
         user_deposit.bump = ctx.bumps.user_deposit;
+        user_deposit.allowed_destination = allowed_destination;
 
         msg!(
-            "User deposit created: owner={}, treasury={}, bump={}",
+            "User deposit created: owner={}, treasury={}, bump={}, allowed_destination={}",
             user_deposit.owner,
             user_deposit.treasury,
-            user_deposit.bump
+            user_deposit.bump,
+            user_deposit.allowed_destination
         );
 
         Ok(())
@@ -178,6 +187,7 @@ pub mod secure_pda_derivation {
     /// - bump = stored_bump ensures canonical bump is used
     /// - has_one validates all account relationships
     /// - Only the deposit owner can withdraw
+    /// - Funds only go to the allowlisted destination, if one was set
     pub fn withdraw(ctx: Context<Withdraw>, amount: u64) -> Result<()> {
         let user_deposit = &mut ctx.accounts.user_deposit;
         let treasury = &mut ctx.accounts.treasury;
@@ -189,6 +199,7 @@ pub mod secure_pda_derivation {
         // 4. user_deposit.treasury == treasury.key() (has_one)
         // 5. treasury.authority matches (has_one on treasury seeds)
         // 6. Canonical bumps are used (bump = account.bump)
+        // 7. destination matches user_deposit.allowed_destination, if set
 
         require!(user_deposit.amount >= amount, PdaError::InsufficientBalance);
 
@@ -202,12 +213,12 @@ pub mod secure_pda_derivation {
         // Note: PDAs with data cannot use system_instruction::transfer
         // Instead, we directly manipulate lamports
         let treasury_info = treasury.to_account_info();
-        let withdrawer_info = ctx.accounts.withdrawer.to_account_info();
+        let destination_info = ctx.accounts.destination.to_account_info();
 
         **treasury_info.try_borrow_mut_lamports()? =
             treasury_info.lamports().checked_sub(amount).ok_or(PdaError::InsufficientBalance)?;
-        **withdrawer_info.try_borrow_mut_lamports()? =
-            withdrawer_info.lamports().checked_add(amount).ok_or(PdaError::ArithmeticOverflow)?;
+        **destination_info.try_borrow_mut_lamports()? =
+            destination_info.lamports().checked_add(amount).ok_or(PdaError::ArithmeticOverflow)?;
 
         msg!("Withdrew {} lamports securely", amount);
 
@@ -307,6 +318,7 @@ pub struct Deposit<'info> {
 /// - All PDAs re-validated via seeds constraints
 /// - All relationships validated via has_one
 /// - Canonical bumps enforced via bump = stored_bump
+/// - Destination checked against the deposit's allowlist
 #[derive(Accounts)]
 pub struct Withdraw<'info> {
     #[account(
@@ -338,6 +350,17 @@ pub struct Withdraw<'info> {
     )]
     pub withdrawer: Signer<'info>,
 
+    /// Receives the withdrawn lamports
+    /// CHECK: Only credited with lamports; restricted by the allowlist below
+    #[account(
+        mut,
+        // SECURITY: An allowlisted deposit can only pay out to its destination
+        constraint = user_deposit.allowed_destination == Pubkey::default()
+            || destination.key() == user_deposit.allowed_destination
+            @ PdaError::UnauthorizedAccess
+    )]
+    pub destination: AccountInfo<'info>,
+
     /// CHECK: Validated through has_one constraint
     pub owner: AccountInfo<'info>,
 
@@ -21,6 +21,9 @@
 //! | Mint validation | Ensure correct token type | Parse token account data, compare mint |
 //! | Owner validation | Prevent fund redirection | Parse token account data, compare owner |
 //! | Authority check | Restrict privileged ops | Compare against stored authority + signer check |
+//! | Pause (asymmetric) | Halt new exposure without trapping funds | `paused` blocks deposit/mint_reward; withdraw always allowed |
+//! | Deposit fee | Charge on entry without inflating claims | `apply_fee` splits amount; fee account mint/owner checked, only net credited |
+//! | Referral bonus | Reward referrers without unbacked credit | `REFERRAL_BPS` of the net deposit moves to a same-vault referrer record; self-referral rejected |
 //!
 //! **This program demonstrates proper security patterns for production use.**
 
@@ -33,8 +36,28 @@ use pinocchio::{
     instruction::{InstructionAccount, InstructionView},
     AccountView, Address, ProgramResult,
 };
+use pinocchio_security_common::{
+    address_hex, allocate_signed, assign_signed, check_batch_len, check_schema_version,
+    check_uninitialized, create_account_signed, find_program_address, rent_exempt_minimum,
+    transfer, CommonError, SafeMath,
+};
+
+// Re-exported so clients keep using this crate's rent helpers and System
+// Program ID.
+pub use pinocchio_security_common::{minimum_balance, rent_shortfall, SYSTEM_PROGRAM_ID};
+
+// Logging shim: `log!` forwards to `solana_program_log` with the default `logs`
+// feature and compiles to nothing under `--no-default-features`.
+#[cfg(feature = "logs")]
 use solana_program_log::log;
 
+#[cfg(not(feature = "logs"))]
+macro_rules! log {
+    ($($arg:tt)*) => {{
+        let _ = core::format_args!($($arg)*);
+    }};
+}
+
 // =============================================================================
 // PROGRAM ID
 // =============================================================================
@@ -55,11 +78,45 @@ pub const TOKEN_PROGRAM_ID: Address = Address::new_from_array([
 // CONSTANTS
 // =============================================================================
 
-/// Vault account size (no Anchor discriminator): 105 bytes
-pub const VAULT_SIZE: usize = 32 + 32 + 32 + 8 + 1;
+/// Vault account size (no Anchor discriminator): 196 bytes
+pub const VAULT_SIZE: usize = VAULT_RESERVED_OFFSET + VAULT_RESERVED_LEN;
+
+/// Vault layout version this program reads and writes.
+///
+/// Stored in the byte at [`VAULT_VERSION_OFFSET`]; vaults written by a newer
+/// version are rejected with `CommonError::UnsupportedVersion`.
+pub const SCHEMA_VERSION: u8 = 0;
+
+/// Offset of the layout version byte in Vault data
+pub const VAULT_VERSION_OFFSET: usize = 32 + 32 + 32 + 8 + 1 + 32 + 1 + 8 + 2 + 32;
+
+/// Offset of the reserved trailing region in Vault data
+pub const VAULT_RESERVED_OFFSET: usize = VAULT_VERSION_OFFSET + 1;
+
+/// Length of the reserved trailing region in Vault data.
+///
+/// Room for future fields without changing the account size. `serialize` and
+/// `try_from_slice` leave these bytes alone; `initialize_vault` zeroes them,
+/// and a later version may only assign meaning to a zero value that matches
+/// today's behavior.
+pub const VAULT_RESERVED_LEN: usize = 15;
+
+/// Basis points in 100%; the upper bound for `Vault::deposit_fee_bps`
+pub const MAX_FEE_BPS: u16 = 10_000;
+
+/// Largest amount `mint_reward_batch` may mint to a single destination
+///
+/// Bounds the damage of one malformed amount in an otherwise valid batch.
+pub const MAX_REWARD_PER_MINT: u64 = 1_000_000_000_000;
+
+/// Share of a referred deposit credited to the referrer, in basis points (1%)
+///
+/// The bonus is carved out of the deposit itself, so every credited unit is
+/// backed by tokens in the vault.
+pub const REFERRAL_BPS: u16 = 100;
 
-/// UserDeposit account size (no Anchor discriminator): 73 bytes
-pub const USER_DEPOSIT_SIZE: usize = 32 + 32 + 8 + 1;
+/// UserDeposit account size (no Anchor discriminator): 81 bytes
+pub const USER_DEPOSIT_SIZE: usize = 32 + 32 + 8 + 1 + 8;
 
 /// Seed prefix for vault PDA derivation
 pub const VAULT_SEED: &[u8] = b"vault";
@@ -72,6 +129,36 @@ pub const INITIALIZE_VAULT_DISCRIMINATOR: u8 = 0;
 pub const DEPOSIT_DISCRIMINATOR: u8 = 1;
 pub const WITHDRAW_DISCRIMINATOR: u8 = 2;
 pub const MINT_REWARD_DISCRIMINATOR: u8 = 3;
+pub const VERIFY_DEPOSIT_INTEGRITY_DISCRIMINATOR: u8 = 4;
+pub const ROTATE_VAULT_TOKEN_ACCOUNT_DISCRIMINATOR: u8 = 5;
+pub const CREATE_USER_DEPOSIT_DISCRIMINATOR: u8 = 6;
+pub const DEPOSIT_DELEGATED_DISCRIMINATOR: u8 = 7;
+pub const BURN_DEPOSIT_DISCRIMINATOR: u8 = 8;
+pub const FREEZE_USER_ACCOUNT_DISCRIMINATOR: u8 = 9;
+pub const THAW_USER_ACCOUNT_DISCRIMINATOR: u8 = 10;
+pub const SET_PAUSED_DISCRIMINATOR: u8 = 11;
+pub const MIGRATE_MINT_DISCRIMINATOR: u8 = 12;
+pub const DEPOSIT_WITH_REFERRAL_DISCRIMINATOR: u8 = 13;
+pub const DERIVE_ADDRESS_DISCRIMINATOR: u8 = 14;
+pub const MINT_REWARD_BATCH_DISCRIMINATOR: u8 = 15;
+pub const TRANSFER_BETWEEN_VAULTS_DISCRIMINATOR: u8 = 16;
+pub const RECONCILE_DISCRIMINATOR: u8 = 17;
+
+/// derive_address kind: Vault PDA, seeded by a mint
+pub const PDA_KIND_VAULT: u8 = 0;
+/// derive_address kind: UserDeposit PDA, seeded by a vault and a user
+pub const PDA_KIND_USER_DEPOSIT: u8 = 1;
+
+/// Instruction-format versions, carried in the byte after the discriminator
+/// of versioned instructions (currently `deposit`).
+///
+/// A handler dispatches on the version and rejects any it does not know, so
+/// a later format can add fields without misreading data from v0 clients.
+pub const IX_FORMAT_V0: u8 = 0;
+
+/// Deposit format that appends a replay-protection nonce (u64 LE) to the v0
+/// payload. See [`check_deposit_nonce`].
+pub const IX_FORMAT_V1: u8 = 1;
 
 // =============================================================================
 // CUSTOM ERROR CODES
@@ -86,10 +173,46 @@ pub enum TokenSecureError {
     OwnerMismatch = 0x1771, // 6001
     /// Caller is not authorized for this operation
     Unauthorized = 0x1772, // 6002
-    /// User doesn't have enough deposited tokens to withdraw
-    InsufficientBalance = 0x1773, // 6003
+    // 0x1773 (6003) reserved: insufficient balance is reported as
+    // CommonError::InsufficientBalance (0x2001), shared by every pattern.
     /// Arithmetic operation would overflow or underflow
     ArithmeticOverflow = 0x1774, // 6004
+    /// Vault address does not match PDA derived from ["vault", mint]
+    InvalidVaultPda = 0x1775, // 6005
+    /// UserDeposit address does not match PDA derived from ["user_deposit", vault, user]
+    InvalidUserDepositPda = 0x1776, // 6006
+    /// Stored bump is not the canonical bump for the account's seeds
+    NonCanonicalBump = 0x1777, // 6007
+    /// UserDeposit belongs to a different vault
+    VaultMismatch = 0x1778, // 6008
+    /// Token account is not the vault's recorded vault_token_account
+    VaultTokenAccountMismatch = 0x1779, // 6009
+    /// A single user's deposit exceeds the vault's total deposits
+    DepositExceedsVaultTotal = 0x177a, // 6010
+    /// Vault token account holds fewer tokens than total_deposits
+    VaultUndercollateralized = 0x177b, // 6011
+    /// Current vault token account still holds tokens
+    VaultTokenAccountNotEmpty = 0x177c, // 6012
+    /// Signer is not the approved delegate of the source token account
+    DelegateMismatch = 0x177d, // 6013
+    /// Deposit exceeds the delegate's approved allowance
+    DelegatedAmountExceeded = 0x177e, // 6014
+    /// Vault is paused; only withdrawals are allowed
+    VaultPaused = 0x177f, // 6015
+    /// UserDeposit has never been initialized (no user recorded)
+    NotInitialized = 0x1780, // 6016
+    /// Deposit fee is above MAX_FEE_BPS
+    InvalidFeeBps = 0x1781, // 6017
+    /// Vault still has outstanding deposits
+    VaultNotEmpty = 0x1782, // 6018
+    /// Referrer deposit record belongs to the depositor
+    SelfReferral = 0x1783, // 6019
+    /// A single reward mint is above MAX_REWARD_PER_MINT
+    PerMintCapExceeded = 0x1784, // 6020
+    /// Vault total_deposits differs from the sum supplied for reconciliation
+    ReconciliationMismatch = 0x1785, // 6021
+    /// Deposit nonce is not above the record's last_nonce (replayed or out of order)
+    StaleNonce = 0x1786, // 6022
 }
 
 impl From<TokenSecureError> for ProgramError {
@@ -98,12 +221,140 @@ impl From<TokenSecureError> for ProgramError {
     }
 }
 
+// =============================================================================
+// PDA DERIVATION
+// =============================================================================
+
+/// Derive the expected Vault PDA and canonical bump. Seeds: `["vault", mint]`
+#[inline]
+fn derive_vault_pda(mint: &Address, program_id: &Address) -> Result<(Address, u8), ProgramError> {
+    find_program_address(&[VAULT_SEED, mint.as_ref()], program_id)
+}
+
+/// Derive the expected UserDeposit PDA and canonical bump.
+/// Seeds: `["user_deposit", vault, user]`
+#[inline]
+fn derive_user_deposit_pda(
+    vault: &Address,
+    user: &Address,
+    program_id: &Address,
+) -> Result<(Address, u8), ProgramError> {
+    find_program_address(&[USER_DEPOSIT_SEED, vault.as_ref(), user.as_ref()], program_id)
+}
+
+/// Verify that `vault_address` is the PDA of `["vault", seed_mint]` and return
+/// its canonical bump.
+///
+/// // SECURITY: Program ownership alone does not make an account a vault;
+/// // any program-owned account of the right size parses as one. Anchor
+/// // equivalent: `seeds = [VAULT_SEED, vault.seed_mint.as_ref()], bump`.
+pub fn verify_vault_pda(
+    program_id: &Address,
+    vault_address: &Address,
+    vault_state: &Vault,
+) -> Result<u8, ProgramError> {
+    let (expected_vault, canonical_bump) = derive_vault_pda(&vault_state.seed_mint, program_id)?;
+    if vault_address != &expected_vault {
+        log!("SECURITY REJECTION: Vault PDA mismatch");
+        return Err(TokenSecureError::InvalidVaultPda.into());
+    }
+    Ok(canonical_bump)
+}
+
+/// Verify that `vault_state.bump` re-derives `vault_address` before it is used
+/// in signer seeds.
+///
+/// // SECURITY: Signing with a stored non-canonical bump produces a signer
+/// // that is not the vault PDA, so the CPI would fail opaquely inside the
+/// // token program. Rejecting it here gives a specific error instead.
+pub fn verify_vault_signer_bump(
+    program_id: &Address,
+    vault_address: &Address,
+    vault_state: &Vault,
+) -> ProgramResult {
+    let canonical_bump = verify_vault_pda(program_id, vault_address, vault_state)?;
+    if vault_state.bump != canonical_bump {
+        log!("SECURITY REJECTION: Vault bump {} is not canonical", vault_state.bump);
+        return Err(CommonError::InvalidBump.into());
+    }
+    Ok(())
+}
+
+/// Bump the vault PDA signs with in every instruction it signs for.
+///
+/// By default this is the stored `vault_state.bump`. With the
+/// `derive_bump_on_demand` feature the canonical bump is re-derived from
+/// `["vault", seed_mint]` on every call and the stored byte is never read.
+///
+/// // SECURITY: A derived bump cannot be non-canonical or tampered with, at
+/// // the cost of one `find_program_address` per call, which hashes one
+/// // candidate per bump tried, counting down from 255.
+#[cfg(not(feature = "derive_bump_on_demand"))]
+pub fn vault_signer_bump(
+    _program_id: &Address,
+    _vault_address: &Address,
+    vault_state: &Vault,
+) -> Result<u8, ProgramError> {
+    Ok(vault_state.bump)
+}
+
+/// Bump the vault PDA signs with in every instruction it signs for.
+///
+/// Re-derived from `["vault", seed_mint]` on every call; the stored
+/// `vault_state.bump` is never read. Fails with `InvalidVaultPda` when
+/// `vault_address` is not that PDA.
+#[cfg(feature = "derive_bump_on_demand")]
+pub fn vault_signer_bump(
+    program_id: &Address,
+    vault_address: &Address,
+    vault_state: &Vault,
+) -> Result<u8, ProgramError> {
+    verify_vault_pda(program_id, vault_address, vault_state)
+}
+
 // =============================================================================
 // SPL TOKEN CPI HELPERS
 // =============================================================================
 
 const SPL_TRANSFER_DISCRIMINATOR: u8 = 3;
 const SPL_MINT_TO_DISCRIMINATOR: u8 = 7;
+const SPL_BURN_DISCRIMINATOR: u8 = 8;
+const SPL_FREEZE_ACCOUNT_DISCRIMINATOR: u8 = 10;
+const SPL_THAW_ACCOUNT_DISCRIMINATOR: u8 = 11;
+
+/// Size of an SPL Token mint account
+pub const SPL_MINT_LEN: usize = 82;
+
+/// Rejects an account that is not an SPL mint owned by `token_program`.
+///
+/// // SECURITY: A vault bound to a non-mint address would accept deposits for
+/// // a mint no token account can hold. Anchor equivalent: `Account<'info, Mint>`
+/// // (owner check plus mint layout).
+pub fn assert_is_mint(account: &AccountView, token_program: &Address) -> ProgramResult {
+    if !account.owned_by(token_program) {
+        log!("SECURITY REJECTION: Mint not owned by the token program");
+        return Err(ProgramError::IllegalOwner);
+    }
+    if account.data_len() < SPL_MINT_LEN {
+        log!("SECURITY REJECTION: Account is too small to be a mint");
+        return Err(TokenSecureError::MintMismatch.into());
+    }
+    Ok(())
+}
+
+/// Rejects a token program account that is not SPL Token.
+///
+/// // SECURITY: A CPI goes to whatever program this account names. A fake
+/// // token program handed the vault PDA's signature, or a user's, can report
+/// // success without moving a token while the handler still updates its
+/// // records. Anchor equivalent: `Program<'info, Token>`.
+pub fn check_token_program(token_program: &AccountView) -> ProgramResult {
+    if token_program.address() != &TOKEN_PROGRAM_ID {
+        log!("SECURITY REJECTION: Token program is not SPL Token");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+    Ok(())
+}
 
 /// Parses the mint address from a token account's data.
 /// // SECURITY: This function extracts the mint from raw token account data,
@@ -133,6 +384,60 @@ pub fn parse_token_account_owner(token_account_data: &[u8]) -> Result<Address, P
     Ok(Address::new_from_array(owner_bytes))
 }
 
+/// Parses the token amount from a token account's data.
+/// // SECURITY: Lets the program read the real on-chain balance instead of
+/// // trusting internally tracked totals.
+pub fn parse_token_account_amount(token_account_data: &[u8]) -> Result<u64, ProgramError> {
+    if token_account_data.len() < 72 {
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let amount_bytes: [u8; 8] =
+        token_account_data[64..72].try_into().map_err(|_| ProgramError::InvalidAccountData)?;
+
+    Ok(u64::from_le_bytes(amount_bytes))
+}
+
+/// Parses the optional delegate from a token account's data.
+/// // SECURITY: Layout is `COption<Pubkey>`: a 4-byte tag at 72..76 (0 = None,
+/// // 1 = Some) followed by the delegate at 76..108.
+pub fn parse_token_account_delegate(
+    token_account_data: &[u8],
+) -> Result<Option<Address>, ProgramError> {
+    if token_account_data.len() < 108 {
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let tag = u32::from_le_bytes(
+        token_account_data[72..76].try_into().map_err(|_| ProgramError::InvalidAccountData)?,
+    );
+
+    match tag {
+        0 => Ok(None),
+        1 => {
+            let delegate_bytes: [u8; 32] = token_account_data[76..108]
+                .try_into()
+                .map_err(|_| ProgramError::InvalidAccountData)?;
+            Ok(Some(Address::new_from_array(delegate_bytes)))
+        }
+        _ => Err(ProgramError::InvalidAccountData),
+    }
+}
+
+/// Parses the amount the delegate is still allowed to transfer.
+/// // SECURITY: Read from bytes 121..129 so the program enforces the owner's
+/// // approval instead of relying on the token program alone.
+pub fn parse_delegated_amount(token_account_data: &[u8]) -> Result<u64, ProgramError> {
+    if token_account_data.len() < 129 {
+        return Err(ProgramError::InvalidAccountData);
+    }
+
+    let amount_bytes: [u8; 8] =
+        token_account_data[121..129].try_into().map_err(|_| ProgramError::InvalidAccountData)?;
+
+    Ok(u64::from_le_bytes(amount_bytes))
+}
+
 /// Invokes SPL Token Transfer instruction.
 pub fn spl_token_transfer(
     from: &AccountView,
@@ -223,11 +528,124 @@ pub fn spl_token_mint_to_signed<const N: usize>(
     invoke_signed::<3>(&instruction, &[mint, destination, authority], &[signer])
 }
 
+/// Builds SPL Token Burn instruction data: `[8, amount (u64 LE)]`.
+pub fn spl_token_burn_data(amount: u64) -> [u8; 9] {
+    let mut instruction_data = [0u8; 9];
+    instruction_data[0] = SPL_BURN_DISCRIMINATOR;
+    instruction_data[1..9].copy_from_slice(&amount.to_le_bytes());
+    instruction_data
+}
+
+/// Invokes SPL Token Burn instruction with PDA signer.
+pub fn spl_token_burn_signed<const N: usize>(
+    account: &AccountView,
+    mint: &AccountView,
+    authority: &AccountView,
+    token_program: &AccountView,
+    amount: u64,
+    signer_seeds: &[Seed; N],
+) -> ProgramResult {
+    let instruction_data = spl_token_burn_data(amount);
+
+    let accounts = [
+        InstructionAccount::writable(account.address()),
+        InstructionAccount::writable(mint.address()),
+        InstructionAccount::readonly_signer(authority.address()),
+    ];
+
+    let instruction = InstructionView {
+        program_id: token_program.address(),
+        accounts: &accounts,
+        data: &instruction_data,
+    };
+
+    // Build signer from seeds
+    let signer = Signer::from(signer_seeds);
+
+    // Invoke with signer seeds for PDA
+    invoke_signed::<3>(&instruction, &[account, mint, authority], &[signer])
+}
+
+/// Builds SPL Token FreezeAccount instruction data: `[10]`.
+pub fn spl_token_freeze_account_data() -> [u8; 1] {
+    [SPL_FREEZE_ACCOUNT_DISCRIMINATOR]
+}
+
+/// Builds SPL Token ThawAccount instruction data: `[11]`.
+pub fn spl_token_thaw_account_data() -> [u8; 1] {
+    [SPL_THAW_ACCOUNT_DISCRIMINATOR]
+}
+
+/// Invokes SPL Token FreezeAccount instruction with PDA signer (freeze authority).
+pub fn spl_token_freeze_account_signed<const N: usize>(
+    account: &AccountView,
+    mint: &AccountView,
+    authority: &AccountView,
+    token_program: &AccountView,
+    signer_seeds: &[Seed; N],
+) -> ProgramResult {
+    invoke_freeze_authority_signed(
+        &spl_token_freeze_account_data(),
+        account,
+        mint,
+        authority,
+        token_program,
+        signer_seeds,
+    )
+}
+
+/// Invokes SPL Token ThawAccount instruction with PDA signer (freeze authority).
+pub fn spl_token_thaw_account_signed<const N: usize>(
+    account: &AccountView,
+    mint: &AccountView,
+    authority: &AccountView,
+    token_program: &AccountView,
+    signer_seeds: &[Seed; N],
+) -> ProgramResult {
+    invoke_freeze_authority_signed(
+        &spl_token_thaw_account_data(),
+        account,
+        mint,
+        authority,
+        token_program,
+        signer_seeds,
+    )
+}
+
+/// FreezeAccount and ThawAccount share the same account list.
+fn invoke_freeze_authority_signed<const N: usize>(
+    instruction_data: &[u8],
+    account: &AccountView,
+    mint: &AccountView,
+    authority: &AccountView,
+    token_program: &AccountView,
+    signer_seeds: &[Seed; N],
+) -> ProgramResult {
+    let accounts = [
+        InstructionAccount::writable(account.address()),
+        InstructionAccount::readonly(mint.address()),
+        InstructionAccount::readonly_signer(authority.address()),
+    ];
+
+    let instruction = InstructionView {
+        program_id: token_program.address(),
+        accounts: &accounts,
+        data: instruction_data,
+    };
+
+    // Build signer from seeds
+    let signer = Signer::from(signer_seeds);
+
+    // Invoke with signer seeds for PDA
+    invoke_signed::<3>(&instruction, &[account, mint, authority], &[signer])
+}
+
 // =============================================================================
 // DATA STRUCTURES
 // =============================================================================
 
 /// Vault account storing token vault configuration.
+#[derive(Debug, Clone, PartialEq)]
 pub struct Vault {
     /// Authority who can manage the vault and mint rewards (32 bytes)
     /// // SECURITY: Used in authority validation for mint_reward
@@ -241,6 +659,24 @@ pub struct Vault {
     pub total_deposits: u64,
     /// PDA bump seed for signing (1 byte)
     pub bump: u8,
+    /// The SPL Token mint paid out by mint_reward (32 bytes)
+    /// // SECURITY: May differ from `mint`; mint_reward validates against this
+    pub reward_mint: Address,
+    /// Whether the authority has paused the vault (1 byte)
+    /// // SECURITY: Blocks deposits and reward minting only; withdrawals stay
+    /// // open so users can always exit
+    pub paused: bool,
+    /// Total reward tokens minted by mint_reward over the vault's lifetime (8 bytes)
+    /// // SECURITY: Lets monitoring compare reward supply against what this
+    /// // vault is known to have issued
+    pub total_minted: u64,
+    /// Share of each deposit taken as a fee, in basis points (2 bytes)
+    /// // SECURITY: Bounded by MAX_FEE_BPS at initialization
+    pub deposit_fee_bps: u16,
+    /// Mint the vault PDA was derived from (32 bytes)
+    /// // SECURITY: Fixed at initialization. `mint` can change via migrate_mint,
+    /// // but the vault address and its signer seeds always use this one.
+    pub seed_mint: Address,
 }
 
 impl Vault {
@@ -249,6 +685,9 @@ impl Vault {
             return Err(ProgramError::InvalidAccountData);
         }
 
+        // SECURITY: A newer layout may give meaning to bytes this version ignores
+        check_schema_version(data[VAULT_VERSION_OFFSET], SCHEMA_VERSION)?;
+
         let authority = Address::new_from_array(
             data[0..32].try_into().map_err(|_| ProgramError::InvalidAccountData)?,
         );
@@ -267,7 +706,36 @@ impl Vault {
 
         let bump = data[104];
 
-        Ok(Self { authority, mint, vault_token_account, total_deposits, bump })
+        let reward_mint = Address::new_from_array(
+            data[105..137].try_into().map_err(|_| ProgramError::InvalidAccountData)?,
+        );
+
+        let paused = data[137] != 0;
+
+        let total_minted = u64::from_le_bytes(
+            data[138..146].try_into().map_err(|_| ProgramError::InvalidAccountData)?,
+        );
+
+        let deposit_fee_bps = u16::from_le_bytes(
+            data[146..148].try_into().map_err(|_| ProgramError::InvalidAccountData)?,
+        );
+
+        let seed_mint = Address::new_from_array(
+            data[148..180].try_into().map_err(|_| ProgramError::InvalidAccountData)?,
+        );
+
+        Ok(Self {
+            authority,
+            mint,
+            vault_token_account,
+            total_deposits,
+            bump,
+            reward_mint,
+            paused,
+            total_minted,
+            deposit_fee_bps,
+            seed_mint,
+        })
     }
 
     pub fn serialize(&self, data: &mut [u8]) -> Result<(), ProgramError> {
@@ -280,12 +748,41 @@ impl Vault {
         data[64..96].copy_from_slice(self.vault_token_account.as_ref());
         data[96..104].copy_from_slice(&self.total_deposits.to_le_bytes());
         data[104] = self.bump;
+        data[105..137].copy_from_slice(self.reward_mint.as_ref());
+        data[137] = self.paused as u8;
+        data[138..146].copy_from_slice(&self.total_minted.to_le_bytes());
+        data[146..148].copy_from_slice(&self.deposit_fee_bps.to_le_bytes());
+        data[148..180].copy_from_slice(self.seed_mint.as_ref());
+        data[VAULT_VERSION_OFFSET] = SCHEMA_VERSION;
+
+        Ok(())
+    }
+
+    /// Adds `amount` to `total_deposits`.
+    ///
+    /// // SECURITY: Checked, so the vault total can never wrap around.
+    pub fn credit(&mut self, amount: u64) -> Result<(), TokenSecureError> {
+        self.total_deposits =
+            self.total_deposits.add_or(amount, TokenSecureError::ArithmeticOverflow)?;
+        Ok(())
+    }
 
+    /// Removes `amount` from `total_deposits`.
+    ///
+    /// // SECURITY: The vault can never pay out more than it owes depositors;
+    /// // a debit above `total_deposits` fails and leaves the total unchanged.
+    pub fn debit(&mut self, amount: u64) -> Result<(), TokenSecureError> {
+        if amount > self.total_deposits {
+            return Err(TokenSecureError::DepositExceedsVaultTotal);
+        }
+        self.total_deposits =
+            self.total_deposits.sub_or(amount, TokenSecureError::ArithmeticOverflow)?;
         Ok(())
     }
 }
 
 /// User deposit record tracking individual user deposits.
+#[derive(Debug, Clone, PartialEq)]
 pub struct UserDeposit {
     /// User who made the deposit (32 bytes)
     pub user: Address,
@@ -295,6 +792,8 @@ pub struct UserDeposit {
     pub amount: u64,
     /// PDA bump seed (1 byte)
     pub bump: u8,
+    /// Highest nonce of a v1 deposit credited to this record (8 bytes)
+    pub last_nonce: u64,
 }
 
 impl UserDeposit {
@@ -317,7 +816,11 @@ impl UserDeposit {
 
         let bump = data[72];
 
-        Ok(Self { user, vault, amount, bump })
+        let last_nonce = u64::from_le_bytes(
+            data[73..81].try_into().map_err(|_| ProgramError::InvalidAccountData)?,
+        );
+
+        Ok(Self { user, vault, amount, bump, last_nonce })
     }
 
     pub fn serialize(&self, data: &mut [u8]) -> Result<(), ProgramError> {
@@ -329,11 +832,144 @@ impl UserDeposit {
         data[32..64].copy_from_slice(self.vault.as_ref());
         data[64..72].copy_from_slice(&self.amount.to_le_bytes());
         data[72] = self.bump;
+        data[73..81].copy_from_slice(&self.last_nonce.to_le_bytes());
 
         Ok(())
     }
 }
 
+// =============================================================================
+// ACCOUNTING
+// =============================================================================
+
+/// Credits a deposit to both the user's record and the vault total.
+///
+/// // SECURITY: Both sides use checked arithmetic so they always move together.
+pub fn apply_deposit(
+    vault_state: &mut Vault,
+    user_deposit_state: &mut UserDeposit,
+    amount: u64,
+) -> Result<(), TokenSecureError> {
+    let user_amount =
+        user_deposit_state.amount.add_or(amount, TokenSecureError::ArithmeticOverflow)?;
+    vault_state.credit(amount)?;
+    user_deposit_state.amount = user_amount;
+
+    Ok(())
+}
+
+/// Splits a deposit `amount` into `(fee, net)` at `fee_bps` basis points.
+///
+/// The fee rounds down, so the depositor keeps any remainder.
+///
+/// // SECURITY: The product is taken in u128 so large amounts cannot overflow,
+/// // and `fee + net == amount` always holds.
+pub fn apply_fee(amount: u64, fee_bps: u16) -> Result<(u64, u64), TokenSecureError> {
+    if fee_bps > MAX_FEE_BPS {
+        return Err(TokenSecureError::InvalidFeeBps);
+    }
+
+    let fee = (amount as u128)
+        .checked_mul(fee_bps as u128)
+        .and_then(|scaled| scaled.checked_div(MAX_FEE_BPS as u128))
+        .and_then(|fee| u64::try_from(fee).ok())
+        .ok_or(TokenSecureError::ArithmeticOverflow)?;
+    let net = amount.sub_or(fee, TokenSecureError::ArithmeticOverflow)?;
+
+    Ok((fee, net))
+}
+
+/// Debits a withdrawal from both the user's record and the vault total.
+///
+/// // SECURITY: Rejects withdrawals above the user's deposit before touching either side.
+pub fn apply_withdraw(
+    vault_state: &mut Vault,
+    user_deposit_state: &mut UserDeposit,
+    amount: u64,
+) -> ProgramResult {
+    if user_deposit_state.amount < amount {
+        return Err(CommonError::InsufficientBalance.into());
+    }
+
+    let user_amount =
+        user_deposit_state.amount.sub_or(amount, TokenSecureError::ArithmeticOverflow)?;
+    vault_state.debit(amount)?;
+    user_deposit_state.amount = user_amount;
+
+    Ok(())
+}
+
+// =============================================================================
+// INSTRUCTION DATA LENGTH
+// =============================================================================
+
+/// Whether instruction data must be exactly the expected length.
+///
+/// Off by default: handlers read a fixed prefix and ignore trailing bytes.
+/// Enable the `strict_ix_len` feature to reject payloads with extra bytes a
+/// client may not have meant to send.
+pub const STRICT_IX_LEN: bool = cfg!(feature = "strict_ix_len");
+
+/// Expected payload length (after the discriminator) of each instruction.
+///
+/// `None` for an unknown discriminator and for `mint_reward_batch`, whose
+/// payload grows with the batch and is length-checked by its handler.
+pub fn expected_data_len(discriminator: u8) -> Option<usize> {
+    match discriminator {
+        // bump + deposit_fee_bps (u16)
+        INITIALIZE_VAULT_DISCRIMINATOR => Some(3),
+        // v0 layout; process_instruction checks other versions with
+        // expected_deposit_data_len
+        DEPOSIT_DISCRIMINATOR | DEPOSIT_WITH_REFERRAL_DISCRIMINATOR => {
+            expected_deposit_data_len(IX_FORMAT_V0)
+        }
+        // amount (u64)
+        WITHDRAW_DISCRIMINATOR
+        | MINT_REWARD_DISCRIMINATOR
+        | DEPOSIT_DELEGATED_DISCRIMINATOR
+        | BURN_DEPOSIT_DISCRIMINATOR
+        | TRANSFER_BETWEEN_VAULTS_DISCRIMINATOR => Some(8),
+        // expected_sum (u64)
+        RECONCILE_DISCRIMINATOR => Some(8),
+        VERIFY_DEPOSIT_INTEGRITY_DISCRIMINATOR
+        | ROTATE_VAULT_TOKEN_ACCOUNT_DISCRIMINATOR
+        | CREATE_USER_DEPOSIT_DISCRIMINATOR
+        | FREEZE_USER_ACCOUNT_DISCRIMINATOR
+        | THAW_USER_ACCOUNT_DISCRIMINATOR
+        | MIGRATE_MINT_DISCRIMINATOR => Some(0),
+        // paused flag
+        SET_PAUSED_DISCRIMINATOR => Some(1),
+        // kind + two 32-byte seeds (the second is ignored for PDA_KIND_VAULT)
+        DERIVE_ADDRESS_DISCRIMINATOR => Some(65),
+        _ => None,
+    }
+}
+
+/// Expected payload length of a versioned deposit, by format version.
+///
+/// `None` for an unknown version.
+pub fn expected_deposit_data_len(version: u8) -> Option<usize> {
+    match version {
+        // format version + amount (u64) + user_deposit bump
+        IX_FORMAT_V0 => Some(10),
+        // v0 + nonce (u64)
+        IX_FORMAT_V1 => Some(18),
+        _ => None,
+    }
+}
+
+/// Rejects a payload that is not exactly `expected` bytes when `strict`.
+///
+/// In lenient mode this accepts anything; short payloads are still rejected
+/// by the handlers' own prefix checks.
+pub fn check_data_len(data: &[u8], expected: usize, strict: bool) -> ProgramResult {
+    if strict && data.len() != expected {
+        log!("Rejected instruction data: expected {} bytes, got {}", expected, data.len());
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    Ok(())
+}
+
 // =============================================================================
 // ENTRYPOINT
 // =============================================================================
@@ -349,11 +985,43 @@ pub fn process_instruction(
     let (discriminator, data) =
         instruction_data.split_first().ok_or(ProgramError::InvalidInstructionData)?;
 
+    // mint_reward_batch requires exactly one amount per destination, in both modes
+    if *discriminator != MINT_REWARD_BATCH_DISCRIMINATOR {
+        let expected = match (*discriminator, data.first()) {
+            (DEPOSIT_DISCRIMINATOR | DEPOSIT_WITH_REFERRAL_DISCRIMINATOR, Some(version)) => {
+                expected_deposit_data_len(*version)
+            }
+            _ => expected_data_len(*discriminator),
+        }
+        .ok_or(ProgramError::InvalidInstructionData)?;
+        check_data_len(data, expected, STRICT_IX_LEN)?;
+    }
+
     match *discriminator {
         INITIALIZE_VAULT_DISCRIMINATOR => initialize_vault(program_id, accounts, data),
         DEPOSIT_DISCRIMINATOR => deposit(program_id, accounts, data),
         WITHDRAW_DISCRIMINATOR => withdraw(program_id, accounts, data),
         MINT_REWARD_DISCRIMINATOR => mint_reward(program_id, accounts, data),
+        VERIFY_DEPOSIT_INTEGRITY_DISCRIMINATOR => {
+            verify_deposit_integrity(program_id, accounts, data)
+        }
+        ROTATE_VAULT_TOKEN_ACCOUNT_DISCRIMINATOR => {
+            rotate_vault_token_account(program_id, accounts, data)
+        }
+        CREATE_USER_DEPOSIT_DISCRIMINATOR => create_user_deposit(program_id, accounts, data),
+        DEPOSIT_DELEGATED_DISCRIMINATOR => deposit_delegated(program_id, accounts, data),
+        BURN_DEPOSIT_DISCRIMINATOR => burn_deposit(program_id, accounts, data),
+        FREEZE_USER_ACCOUNT_DISCRIMINATOR => freeze_user_account(program_id, accounts, data),
+        THAW_USER_ACCOUNT_DISCRIMINATOR => thaw_user_account(program_id, accounts, data),
+        SET_PAUSED_DISCRIMINATOR => set_paused(program_id, accounts, data),
+        MIGRATE_MINT_DISCRIMINATOR => migrate_mint(program_id, accounts, data),
+        DEPOSIT_WITH_REFERRAL_DISCRIMINATOR => deposit_with_referral(program_id, accounts, data),
+        DERIVE_ADDRESS_DISCRIMINATOR => derive_address(program_id, accounts, data),
+        MINT_REWARD_BATCH_DISCRIMINATOR => mint_reward_batch(program_id, accounts, data),
+        TRANSFER_BETWEEN_VAULTS_DISCRIMINATOR => {
+            transfer_between_vaults(program_id, accounts, data)
+        }
+        RECONCILE_DISCRIMINATOR => reconcile(program_id, accounts, data),
         _ => Err(ProgramError::InvalidInstructionData),
     }
 }
@@ -365,8 +1033,23 @@ pub fn process_instruction(
 /// Initializes a new token vault with proper security configuration.
 /// // SECURITY: Vault PDA ensures deterministic, unforgeable address
 /// // SECURITY: Authority stored for future privileged operation validation
+///
+/// # Accounts
+/// 0. `[writable]` vault - The vault PDA
+/// 1. `[]` mint - Mint accepted for deposits (must be an SPL mint)
+/// 2. `[]` vault_token_account - The vault's SPL token account
+/// 3. `[signer]` authority - Vault authority
+/// 4. `[]` system_program - System program
+/// 5. `[]` token_program - SPL Token program
+/// 6. `[]` reward_mint - Mint paid out by mint_reward (may equal `mint`; must be an SPL mint)
+///
+/// # Instruction Data
+/// - bump (u8): Vault PDA bump
+/// - deposit_fee_bps (u16, optional): Fee on each deposit, at most
+///   `MAX_FEE_BPS`; 0 when omitted
 fn initialize_vault(program_id: &Address, accounts: &[AccountView], data: &[u8]) -> ProgramResult {
-    let [vault, mint, vault_token_account, authority, _system_program, _token_program] = accounts
+    let [vault, mint, vault_token_account, authority, _system_program, _token_program, reward_mint] =
+        accounts
     else {
         return Err(ProgramError::NotEnoughAccountKeys);
     };
@@ -381,7 +1064,20 @@ fn initialize_vault(program_id: &Address, accounts: &[AccountView], data: &[u8])
         return Err(ProgramError::IllegalOwner);
     }
 
+    // SECURITY: Refuse to overwrite a live vault's authority and mints
+    check_uninitialized(&vault.try_borrow()?)?;
+
+    // SECURITY: Both stored mints must be real SPL mints
+    assert_is_mint(mint, &TOKEN_PROGRAM_ID)?;
+    assert_is_mint(reward_mint, &TOKEN_PROGRAM_ID)?;
+
     let bump = if data.is_empty() { 0 } else { data[0] };
+    let deposit_fee_bps = data.get(1..3).map_or(0, |bps| u16::from_le_bytes([bps[0], bps[1]]));
+
+    // SECURITY: A fee above 100% would make every deposit fail in apply_fee
+    if deposit_fee_bps > MAX_FEE_BPS {
+        return Err(TokenSecureError::InvalidFeeBps.into());
+    }
 
     let vault_data = Vault {
         authority: Address::new_from_array(*authority.address().as_array()),
@@ -389,10 +1085,17 @@ fn initialize_vault(program_id: &Address, accounts: &[AccountView], data: &[u8])
         vault_token_account: Address::new_from_array(*vault_token_account.address().as_array()),
         total_deposits: 0,
         bump,
+        reward_mint: Address::new_from_array(*reward_mint.address().as_array()),
+        paused: false,
+        total_minted: 0,
+        deposit_fee_bps,
+        seed_mint: Address::new_from_array(*mint.address().as_array()),
     };
 
     let mut account_data = vault.try_borrow_mut()?;
     vault_data.serialize(&mut account_data)?;
+    // Reserved bytes must start out zero for future layout versions
+    account_data[VAULT_RESERVED_OFFSET..VAULT_SIZE].fill(0);
 
     log!("SECURE: Vault initialized for mint");
     log!("SECURE: Vault authority set");
@@ -400,6 +1103,122 @@ fn initialize_vault(program_id: &Address, accounts: &[AccountView], data: &[u8])
     Ok(())
 }
 
+/// Accounts of `deposit`, by role.
+///
+/// `from_slice` is the single place that knows the account order. It also
+/// checks each role that can be told apart without reading account data, so a
+/// client that swaps two accounts fails here rather than deeper in the handler.
+///
+/// ## Accounts
+/// 0. `[writable]` vault - The vault PDA
+/// 1. `[writable]` user_deposit - The user's UserDeposit record
+/// 2. `[writable]` user_token_account - Source token account (vault mint)
+/// 3. `[writable]` vault_token_account - The vault's token account
+/// 4. `[signer]` user - Depositor
+/// 5. `[]` system_program - System program
+/// 6. `[]` token_program - SPL Token program
+/// 7. `[writable]` fee_token_account - Receives the deposit fee
+pub struct DepositAccounts<'a> {
+    pub vault: &'a AccountView,
+    pub user_deposit: &'a AccountView,
+    pub user_token_account: &'a AccountView,
+    pub vault_token_account: &'a AccountView,
+    pub user: &'a AccountView,
+    pub system_program: &'a AccountView,
+    pub token_program: &'a AccountView,
+    pub fee_token_account: &'a AccountView,
+}
+
+impl<'a> DepositAccounts<'a> {
+    /// Names `accounts` by position and checks the data-free roles.
+    ///
+    /// // SECURITY: The user must sign, the programs must be the real System
+    /// // and Token programs (the Token program runs the transfers), and every
+    /// // token account must belong to the Token program.
+    pub fn from_slice(accounts: &'a [AccountView]) -> Result<Self, ProgramError> {
+        let [vault, user_deposit, user_token_account, vault_token_account, user, system_program, token_program, fee_token_account] =
+            accounts
+        else {
+            return Err(ProgramError::NotEnoughAccountKeys);
+        };
+
+        if !user.is_signer() {
+            return Err(ProgramError::MissingRequiredSignature);
+        }
+        if system_program.address() != &SYSTEM_PROGRAM_ID
+            || token_program.address() != &TOKEN_PROGRAM_ID
+        {
+            log!("SECURITY REJECTION: Deposit program accounts out of place");
+            return Err(ProgramError::IncorrectProgramId);
+        }
+        for token_account in [user_token_account, vault_token_account, fee_token_account] {
+            if !token_account.owned_by(&TOKEN_PROGRAM_ID) {
+                log!("SECURITY REJECTION: Deposit token account not owned by the token program");
+                return Err(ProgramError::IllegalOwner);
+            }
+        }
+
+        Ok(Self {
+            vault,
+            user_deposit,
+            user_token_account,
+            vault_token_account,
+            user,
+            system_program,
+            token_program,
+            fee_token_account,
+        })
+    }
+}
+
+/// Decoded `deposit` instruction data.
+#[derive(Debug, PartialEq, Eq)]
+pub struct DepositArgs {
+    /// Tokens to deposit
+    pub amount: u64,
+    /// Bump for a UserDeposit record created by this deposit
+    pub user_deposit_bump: u8,
+    /// Replay-protection nonce; only v1 data carries one
+    pub nonce: Option<u64>,
+}
+
+impl DepositArgs {
+    /// Parse `[version][payload]`, dispatching on the format version.
+    ///
+    /// v0 payload: amount (u64 LE) + user_deposit bump (u8). v1 appends a
+    /// nonce (u64 LE). Unknown versions are rejected with
+    /// `InvalidInstructionData`.
+    pub fn parse(data: &[u8]) -> Result<Self, ProgramError> {
+        let (version, payload) = data.split_first().ok_or(ProgramError::InvalidInstructionData)?;
+
+        match *version {
+            IX_FORMAT_V0 | IX_FORMAT_V1 => {
+                let payload_len = if *version == IX_FORMAT_V1 { 17 } else { 9 };
+                if payload.len() < payload_len {
+                    return Err(ProgramError::InvalidInstructionData);
+                }
+                let amount = u64::from_le_bytes(
+                    payload[0..8].try_into().map_err(|_| ProgramError::InvalidInstructionData)?,
+                );
+                let nonce = if *version == IX_FORMAT_V1 {
+                    Some(u64::from_le_bytes(
+                        payload[9..17]
+                            .try_into()
+                            .map_err(|_| ProgramError::InvalidInstructionData)?,
+                    ))
+                } else {
+                    None
+                };
+                Ok(Self { amount, user_deposit_bump: payload[8], nonce })
+            }
+            _ => {
+                log!("Rejected deposit: unknown instruction format version {}", *version);
+                Err(ProgramError::InvalidInstructionData)
+            }
+        }
+    }
+}
+
 /// Deposits tokens into the vault with full mint validation.
 ///
 /// // SECURITY: Mint Validation - The user_token_account's mint is checked against
@@ -409,31 +1228,109 @@ fn initialize_vault(program_id: &Address, accounts: &[AccountView], data: &[u8])
 /// ```rust,ignore
 /// #[account(constraint = user_token_account.mint == vault.mint @ TokenSecureError::MintMismatch)]
 /// ```
-fn deposit(_program_id: &Address, accounts: &[AccountView], data: &[u8]) -> ProgramResult {
-    let [vault, user_deposit, user_token_account, vault_token_account, user, _system_program, token_program] =
-        accounts
-    else {
+///
+/// Accounts are listed on [`DepositAccounts`]. Instruction data is versioned:
+/// `[IX_FORMAT_V0][amount: u64][user_deposit_bump: u8]`, or
+/// `[IX_FORMAT_V1][amount: u64][user_deposit_bump: u8][nonce: u64]` (see
+/// [`DepositArgs::parse`]).
+///
+/// // SECURITY: A v1 deposit is credited only if its nonce is above the
+/// // record's `last_nonce`, so a retried or replayed transaction cannot be
+/// // counted twice. See [`check_deposit_nonce`].
+///
+/// Rejected with `VaultPaused` while the vault is paused.
+///
+/// `vault.deposit_fee_bps` of the amount goes to `fee_token_account` (the
+/// trailing account); only the remainder is credited to the user.
+///
+/// // SECURITY: The fee account must hold the vault mint and belong to the
+/// // vault authority, so a depositor cannot route the fee back to themselves.
+fn deposit(program_id: &Address, accounts: &[AccountView], data: &[u8]) -> ProgramResult {
+    process_deposit(program_id, accounts, data, None)
+}
+
+/// Deposits tokens like [`deposit`] and credits a referrer.
+///
+/// `REFERRAL_BPS` of the net (post-fee) amount is credited to
+/// `referrer_deposit` instead of the depositor. The vault total still rises
+/// by exactly the net amount transferred in.
+///
+/// ## Accounts
+/// 0-7. Same as `deposit` (see [`DepositAccounts`])
+/// 8. `[writable]` referrer_deposit - Referrer's UserDeposit in the same vault
+///
+/// ## Anchor Equivalent
+/// ```rust,ignore
+/// #[account(
+///     mut,
+///     has_one = vault @ TokenSecureError::Unauthorized,
+///     constraint = referrer_deposit.user != user.key() @ TokenSecureError::SelfReferral
+/// )]
+/// pub referrer_deposit: Account<'info, UserDeposit>,
+/// ```
+fn deposit_with_referral(
+    program_id: &Address,
+    accounts: &[AccountView],
+    data: &[u8],
+) -> ProgramResult {
+    let [deposit_accounts @ .., referrer_deposit] = accounts else {
         return Err(ProgramError::NotEnoughAccountKeys);
     };
 
-    // SECURITY: Verify user is signer
-    if !user.is_signer() {
-        return Err(ProgramError::MissingRequiredSignature);
-    }
+    process_deposit(program_id, deposit_accounts, data, Some(referrer_deposit))
+}
 
-    if data.len() < 9 {
-        return Err(ProgramError::InvalidInstructionData);
+/// Rejects a referrer record that is the depositor's own, uninitialized, or
+/// from another vault.
+///
+/// // SECURITY: Self-referral is checked on both the record address and its
+/// // stored user, so a depositor cannot claim the bonus through either their
+/// // own record or a second alias of it.
+pub fn check_referrer(
+    referrer_state: &UserDeposit,
+    referrer_address: &Address,
+    user_deposit_address: &Address,
+    user: &Address,
+    vault_address: &Address,
+) -> Result<(), TokenSecureError> {
+    if referrer_address == user_deposit_address || &referrer_state.user == user {
+        log!("SECURITY REJECTION: Depositor cannot refer themselves");
+        return Err(TokenSecureError::SelfReferral);
     }
-    let amount = u64::from_le_bytes(
-        data[0..8].try_into().map_err(|_| ProgramError::InvalidInstructionData)?,
-    );
-    let user_deposit_bump = data[8];
+    assert_initialized(referrer_state)?;
+    check_deposit_record_vault(referrer_state, vault_address)
+}
+
+/// Shared body of [`deposit`] and [`deposit_with_referral`].
+fn process_deposit(
+    program_id: &Address,
+    accounts: &[AccountView],
+    data: &[u8],
+    referrer_deposit: Option<&AccountView>,
+) -> ProgramResult {
+    // SECURITY: Account order and the signer are checked in from_slice
+    let DepositAccounts {
+        vault,
+        user_deposit,
+        user_token_account,
+        vault_token_account,
+        user,
+        system_program: _,
+        token_program,
+        fee_token_account,
+    } = DepositAccounts::from_slice(accounts)?;
+
+    let DepositArgs { amount, user_deposit_bump, nonce } = DepositArgs::parse(data)?;
 
     // Read vault state to get expected mint
     let vault_data = vault.try_borrow()?;
     let mut vault_state = Vault::try_from_slice(&vault_data)?;
     drop(vault_data);
 
+    // SECURITY: No new funds enter a paused vault. This is the first state
+    // read after the vault, so a paused deposit reads no user or token account.
+    assert_not_paused(&vault_state)?;
+
     // ==========================================================================
     // SECURITY CHECK: Mint Validation
     // ==========================================================================
@@ -449,33 +1346,101 @@ fn deposit(_program_id: &Address, accounts: &[AccountView], data: &[u8]) -> Prog
         return Err(TokenSecureError::MintMismatch.into());
     }
 
+    // ==========================================================================
+    // SECURITY CHECK: Fee account mint and owner
+    // ==========================================================================
+    let fee_token_data = fee_token_account.try_borrow()?;
+    let fee_token_mint = parse_token_account_mint(&fee_token_data)?;
+    let fee_token_owner = parse_token_account_owner(&fee_token_data)?;
+    drop(fee_token_data);
+
+    if fee_token_mint != vault_state.mint {
+        log!("SECURITY REJECTION: Fee account mint does not match vault mint");
+        return Err(TokenSecureError::MintMismatch.into());
+    }
+    if fee_token_owner != vault_state.authority {
+        log!("SECURITY REJECTION: Fee account is not owned by the vault authority");
+        return Err(TokenSecureError::OwnerMismatch.into());
+    }
+
+    let (fee, net) = apply_fee(amount, vault_state.deposit_fee_bps)?;
+
+    // ==========================================================================
+    // SECURITY CHECK: Referrer record
+    // ==========================================================================
+    let mut referrer_state = match referrer_deposit {
+        Some(referrer_deposit) => {
+            if !referrer_deposit.owned_by(program_id) {
+                log!("SECURITY REJECTION: Referrer deposit not owned by this program");
+                return Err(ProgramError::IllegalOwner);
+            }
+            let referrer_data = referrer_deposit.try_borrow()?;
+            let referrer_state = UserDeposit::try_from_slice(&referrer_data)?;
+            drop(referrer_data);
+
+            check_referrer(
+                &referrer_state,
+                referrer_deposit.address(),
+                user_deposit.address(),
+                user.address(),
+                vault.address(),
+            )?;
+            Some(referrer_state)
+        }
+        None => None,
+    };
+    let (bonus, user_net) = match referrer_state {
+        Some(_) => apply_fee(net, REFERRAL_BPS)?,
+        None => (0, net),
+    };
+
     // Initialize or read user deposit
     let user_deposit_data = user_deposit.try_borrow()?;
+    // SECURITY: A short record would panic the lazy-init check below
+    if user_deposit_data.len() < USER_DEPOSIT_SIZE {
+        log!("SECURITY REJECTION: User deposit account too small");
+        return Err(ProgramError::InvalidAccountData);
+    }
     let mut user_deposit_state = if user_deposit_data[0..32] == [0u8; 32] {
         UserDeposit {
             user: Address::new_from_array(*user.address().as_array()),
             vault: Address::new_from_array(*vault.address().as_array()),
             amount: 0,
             bump: user_deposit_bump,
+            last_nonce: 0,
         }
     } else {
         UserDeposit::try_from_slice(&user_deposit_data)?
     };
     drop(user_deposit_data);
 
-    // SECURITY: Transfer with validated mint
-    spl_token_transfer(user_token_account, vault_token_account, user, token_program, amount)?;
+    // ==========================================================================
+    // SECURITY CHECK: Deposit record belongs to this vault
+    // ==========================================================================
+    check_deposit_record_vault(&user_deposit_state, vault.address())?;
 
-    // SECURITY: Safe arithmetic with checked operations
-    user_deposit_state.amount = user_deposit_state
-        .amount
-        .checked_add(amount)
-        .ok_or(TokenSecureError::ArithmeticOverflow)?;
+    // ==========================================================================
+    // SECURITY CHECK: Replay protection
+    // ==========================================================================
+    if let Some(nonce) = nonce {
+        check_deposit_nonce(&user_deposit_state, nonce)?;
+        user_deposit_state.last_nonce = nonce;
+    }
 
-    vault_state.total_deposits = vault_state
-        .total_deposits
-        .checked_add(amount)
-        .ok_or(TokenSecureError::ArithmeticOverflow)?;
+    // SECURITY: Transfer with validated mint
+    if fee > 0 {
+        spl_token_transfer(user_token_account, fee_token_account, user, token_program, fee)?;
+    }
+    if net > 0 {
+        spl_token_transfer(user_token_account, vault_token_account, user, token_program, net)?;
+    }
+
+    // SECURITY: Safe arithmetic with checked operations; only the net amount
+    // backs the user's claim on the vault, split with the referrer if any
+    apply_deposit(&mut vault_state, &mut user_deposit_state, user_net)?;
+    if let Some(referrer_state) = referrer_state.as_mut() {
+        apply_deposit(&mut vault_state, referrer_state, bonus)?;
+    }
 
     // Write updated states
     let mut vault_data = vault.try_borrow_mut()?;
@@ -484,6 +1449,12 @@ fn deposit(_program_id: &Address, accounts: &[AccountView], data: &[u8]) -> Prog
 
     let mut user_deposit_data = user_deposit.try_borrow_mut()?;
     user_deposit_state.serialize(&mut user_deposit_data)?;
+    drop(user_deposit_data);
+
+    if let (Some(referrer_deposit), Some(referrer_state)) = (referrer_deposit, referrer_state) {
+        let mut referrer_data = referrer_deposit.try_borrow_mut()?;
+        referrer_state.serialize(&mut referrer_data)?;
+    }
 
     log!("SECURE: Deposited tokens (mint validated)");
 
@@ -499,7 +1470,11 @@ fn deposit(_program_id: &Address, accounts: &[AccountView], data: &[u8]) -> Prog
 /// ```rust,ignore
 /// #[account(constraint = destination_token_account.owner == user.key() @ TokenSecureError::OwnerMismatch)]
 /// ```
-fn withdraw(_program_id: &Address, accounts: &[AccountView], data: &[u8]) -> ProgramResult {
+///
+/// // SECURITY: Deliberately NOT gated on `vault.paused`. Pausing exists to
+/// // stop new exposure, and blocking exits would trap user funds behind the
+/// // authority's key. See [`assert_not_paused`].
+fn withdraw(program_id: &Address, accounts: &[AccountView], data: &[u8]) -> ProgramResult {
     let [vault, user_deposit, vault_token_account, destination_token_account, user, token_program] =
         accounts
     else {
@@ -528,10 +1503,14 @@ fn withdraw(_program_id: &Address, accounts: &[AccountView], data: &[u8]) -> Pro
     let mut user_deposit_state = UserDeposit::try_from_slice(&user_deposit_data)?;
     drop(user_deposit_data);
 
+    // SECURITY: A zeroed account parses as an empty deposit; report it as
+    // missing rather than as an insufficient balance
+    assert_initialized(&user_deposit_state)?;
+
     // SECURITY: Check user has sufficient deposit balance
     if user_deposit_state.amount < amount {
         log!("SECURITY REJECTION: Insufficient balance for withdrawal");
-        return Err(TokenSecureError::InsufficientBalance.into());
+        return Err(CommonError::InsufficientBalance.into());
     }
 
     // ==========================================================================
@@ -556,11 +1535,17 @@ fn withdraw(_program_id: &Address, accounts: &[AccountView], data: &[u8]) -> Pro
         return Err(TokenSecureError::MintMismatch.into());
     }
 
+    // SECURITY: The vault PDA signs only for the real token program
+    check_token_program(token_program)?;
+
     // Build PDA signer seeds for vault authority
-    let vault_bump = vault_state.bump;
+    let vault_bump = vault_signer_bump(program_id, vault.address(), &vault_state)?;
     let bump_bytes = [vault_bump];
-    let seeds =
-        [Seed::from(VAULT_SEED), Seed::from(vault_state.mint.as_ref()), Seed::from(&bump_bytes)];
+    let seeds = [
+        Seed::from(VAULT_SEED),
+        Seed::from(vault_state.seed_mint.as_ref()),
+        Seed::from(&bump_bytes),
+    ];
 
     // SECURITY: Transfer to validated destination only
     spl_token_transfer_signed(
@@ -573,15 +1558,7 @@ fn withdraw(_program_id: &Address, accounts: &[AccountView], data: &[u8]) -> Pro
     )?;
 
     // SECURITY: Safe arithmetic with checked operations
-    user_deposit_state.amount = user_deposit_state
-        .amount
-        .checked_sub(amount)
-        .ok_or(TokenSecureError::ArithmeticOverflow)?;
-
-    vault_state.total_deposits = vault_state
-        .total_deposits
-        .checked_sub(amount)
-        .ok_or(TokenSecureError::ArithmeticOverflow)?;
+    apply_withdraw(&mut vault_state, &mut user_deposit_state, amount)?;
 
     // Write updated states
     let mut vault_data = vault.try_borrow_mut()?;
@@ -600,14 +1577,20 @@ fn withdraw(_program_id: &Address, accounts: &[AccountView], data: &[u8]) -> Pro
 ///
 /// // SECURITY: Authority Validation - The caller must be the vault authority
 /// // AND must sign the transaction.
+/// // SECURITY: Rewards are paid in `vault.reward_mint`, which need not be the
+/// // deposit `mint`; both the mint account and the destination must match it.
+/// // SECURITY: Each mint is added to `vault.total_minted` with checked
+/// // arithmetic, so the vault keeps a running record of reward supply issued.
 ///
 /// ## Anchor Equivalent
 /// ```rust,ignore
-/// #[account(has_one = authority @ TokenSecureError::Unauthorized)]
+/// #[account(has_one = authority @ TokenSecureError::Unauthorized, has_one = reward_mint)]
 /// pub vault: Account<'info, Vault>,
 /// pub authority: Signer<'info>,
+/// #[account(constraint = destination.mint == vault.reward_mint @ TokenSecureError::MintMismatch)]
+/// pub destination: Account<'info, TokenAccount>,
 /// ```
-fn mint_reward(_program_id: &Address, accounts: &[AccountView], data: &[u8]) -> ProgramResult {
+fn mint_reward(program_id: &Address, accounts: &[AccountView], data: &[u8]) -> ProgramResult {
     let [vault, mint, destination_token_account, authority, token_program] = accounts else {
         return Err(ProgramError::NotEnoughAccountKeys);
     };
@@ -621,9 +1604,12 @@ fn mint_reward(_program_id: &Address, accounts: &[AccountView], data: &[u8]) ->
 
     // Read vault state to get stored authority
     let vault_data = vault.try_borrow()?;
-    let vault_state = Vault::try_from_slice(&vault_data)?;
+    let mut vault_state = Vault::try_from_slice(&vault_data)?;
     drop(vault_data);
 
+    // SECURITY: No rewards are minted while the vault is paused
+    assert_not_paused(&vault_state)?;
+
     // ==========================================================================
     // SECURITY CHECK 1: Authority must be a signer
     // ==========================================================================
@@ -643,22 +1629,39 @@ fn mint_reward(_program_id: &Address, accounts: &[AccountView], data: &[u8]) ->
     }
 
     // ==========================================================================
-    // SECURITY CHECK 3: Destination mint validation
+    // SECURITY CHECK 3: Reward mint and destination mint validation
     // ==========================================================================
     let dest_data = destination_token_account.try_borrow()?;
     let dest_mint = parse_token_account_mint(&dest_data)?;
     drop(dest_data);
 
-    if dest_mint != vault_state.mint {
-        log!("SECURITY REJECTION: Destination mint does not match vault mint");
-        return Err(TokenSecureError::MintMismatch.into());
-    }
+    check_reward_mint(&vault_state, mint.address(), &dest_mint)?;
+
+    // ==========================================================================
+    // SECURITY CHECK 4: Stored bump is canonical for ["vault", mint]
+    // ==========================================================================
+    // // SECURITY: Equivalent to Anchor's `seeds = [...], bump = vault.bump`.
+    // // With `derive_bump_on_demand` there is no stored bump to trust;
+    // // vault_signer_bump derives it and checks the vault address instead.
+    #[cfg(not(feature = "derive_bump_on_demand"))]
+    verify_vault_signer_bump(program_id, vault.address(), &vault_state)?;
 
-    // Build PDA signer seeds for mint authority
-    let vault_bump = vault_state.bump;
+    // SECURITY: The vault PDA signs only for the real token program
+    check_token_program(token_program)?;
+
+    // SECURITY: Checked before minting so an overflowing total never mints
+    let total_minted =
+        vault_state.total_minted.add_or(amount, TokenSecureError::ArithmeticOverflow)?;
+
+    // Build PDA signer seeds for mint authority (the vault PDA signs for
+    // reward_mint, so it must be that mint's mint authority)
+    let vault_bump = vault_signer_bump(program_id, vault.address(), &vault_state)?;
     let bump_bytes = [vault_bump];
-    let seeds =
-        [Seed::from(VAULT_SEED), Seed::from(vault_state.mint.as_ref()), Seed::from(&bump_bytes)];
+    let seeds = [
+        Seed::from(VAULT_SEED),
+        Seed::from(vault_state.seed_mint.as_ref()),
+        Seed::from(&bump_bytes),
+    ];
 
     // SECURITY: Mint with verified authority
     spl_token_mint_to_signed(
@@ -670,68 +1673,3786 @@ fn mint_reward(_program_id: &Address, accounts: &[AccountView], data: &[u8]) ->
         &seeds,
     )?;
 
+    vault_state.total_minted = total_minted;
+    let mut vault_data = vault.try_borrow_mut()?;
+    vault_state.serialize(&mut vault_data)?;
+
     log!("SECURE: Minted reward tokens (authority verified)");
 
     Ok(())
 }
 
-// =============================================================================
-// TESTS
-// =============================================================================
+/// Mints reward tokens to several destinations in one instruction.
+///
+/// Same authority, reward mint, bump and token program validation as
+/// `mint_reward`, applied to every destination.
+///
+/// // SECURITY: Every amount is checked against MAX_REWARD_PER_MINT, every
+/// // destination against the reward mint, and the new `total_minted` is
+/// // computed before the first CPI, so one bad element rejects the whole
+/// // batch and nothing is minted.
+///
+/// # Accounts
+/// 0. `[writable]` vault - The vault PDA (mint authority of reward_mint)
+/// 1. `[writable]` mint - The vault's reward_mint
+/// 2. `[signer]` authority - Vault authority
+/// 3. `[]` token_program - SPL Token program
+/// 4. `[writable]` destination, ... - 1 to MAX_BATCH reward token accounts
+///
+/// # Instruction Data
+/// - amounts (u64 each): One per destination, in the same order
+fn mint_reward_batch(program_id: &Address, accounts: &[AccountView], data: &[u8]) -> ProgramResult {
+    let [vault, mint, authority, token_program, destinations @ ..] = accounts else {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    if destinations.is_empty() {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    }
 
-    #[test]
-    fn test_vault_serialization() {
-        let vault = Vault {
-            authority: Address::new_from_array([1u8; 32]),
-            mint: Address::new_from_array([2u8; 32]),
-            vault_token_account: Address::new_from_array([3u8; 32]),
-            total_deposits: 1_000_000,
-            bump: 255,
-        };
+    // SECURITY: Reject an oversized batch before any account is read
+    check_batch_len(destinations.len())?;
 
-        let mut buffer = [0u8; VAULT_SIZE];
-        vault.serialize(&mut buffer).unwrap();
+    if data.len() != destinations.len() * 8 {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    let amounts = data.chunks_exact(8).map(|chunk| {
+        let mut bytes = [0u8; 8];
+        bytes.copy_from_slice(chunk);
+        u64::from_le_bytes(bytes)
+    });
 
-        let deserialized = Vault::try_from_slice(&buffer).unwrap();
-        assert_eq!(deserialized.authority, vault.authority);
-        assert_eq!(deserialized.mint, vault.mint);
-        assert_eq!(deserialized.vault_token_account, vault.vault_token_account);
-        assert_eq!(deserialized.total_deposits, vault.total_deposits);
-        assert_eq!(deserialized.bump, vault.bump);
+    let vault_data = vault.try_borrow()?;
+    let mut vault_state = Vault::try_from_slice(&vault_data)?;
+    drop(vault_data);
+
+    assert_not_paused(&vault_state)?;
+
+    if !authority.is_signer() {
+        log!("SECURITY REJECTION: Authority must be a signer");
+        return Err(ProgramError::MissingRequiredSignature);
     }
 
-    #[test]
-    fn test_user_deposit_serialization() {
-        let user_deposit = UserDeposit {
-            user: Address::new_from_array([1u8; 32]),
-            vault: Address::new_from_array([2u8; 32]),
-            amount: 500_000,
-            bump: 254,
-        };
+    if vault_state.authority.as_ref() != authority.address().as_ref() {
+        log!("SECURITY REJECTION: Signer does not match vault authority");
+        return Err(TokenSecureError::Unauthorized.into());
+    }
 
-        let mut buffer = [0u8; USER_DEPOSIT_SIZE];
-        user_deposit.serialize(&mut buffer).unwrap();
+    // SECURITY: Equivalent to Anchor's `seeds = [...], bump = vault.bump`;
+    // with `derive_bump_on_demand` the stored byte is not trusted at all.
+    #[cfg(not(feature = "derive_bump_on_demand"))]
+    verify_vault_signer_bump(program_id, vault.address(), &vault_state)?;
+    let vault_bump = vault_signer_bump(program_id, vault.address(), &vault_state)?;
 
-        let deserialized = UserDeposit::try_from_slice(&buffer).unwrap();
-        assert_eq!(deserialized.user, user_deposit.user);
-        assert_eq!(deserialized.vault, user_deposit.vault);
-        assert_eq!(deserialized.amount, user_deposit.amount);
-        assert_eq!(deserialized.bump, user_deposit.bump);
+    // SECURITY: The vault PDA signs only for the real token program
+    check_token_program(token_program)?;
+
+    // Validate every element before the first CPI
+    let mut total_minted = vault_state.total_minted;
+    for (destination, amount) in destinations.iter().zip(amounts.clone()) {
+        check_mint_cap(amount)?;
+
+        let dest_data = destination.try_borrow()?;
+        let dest_mint = parse_token_account_mint(&dest_data)?;
+        drop(dest_data);
+        check_reward_mint(&vault_state, mint.address(), &dest_mint)?;
+
+        total_minted = total_minted.add_or(amount, TokenSecureError::ArithmeticOverflow)?;
     }
 
-    #[test]
-    fn test_error_conversion() {
-        let err: ProgramError = TokenSecureError::MintMismatch.into();
-        assert!(matches!(err, ProgramError::Custom(0x1770)));
+    let bump_bytes = [vault_bump];
+    let seeds = [
+        Seed::from(VAULT_SEED),
+        Seed::from(vault_state.seed_mint.as_ref()),
+        Seed::from(&bump_bytes),
+    ];
 
-        let err: ProgramError = TokenSecureError::OwnerMismatch.into();
-        assert!(matches!(err, ProgramError::Custom(0x1771)));
+    for (destination, amount) in destinations.iter().zip(amounts) {
+        spl_token_mint_to_signed(mint, destination, vault, token_program, amount, &seeds)?;
+    }
 
-        let err: ProgramError = TokenSecureError::Unauthorized.into();
-        assert!(matches!(err, ProgramError::Custom(0x1772)));
+    vault_state.total_minted = total_minted;
+    let mut vault_data = vault.try_borrow_mut()?;
+    vault_state.serialize(&mut vault_data)?;
+
+    log!("SECURE: Minted reward batch to {} destinations", destinations.len());
+
+    Ok(())
+}
+
+/// Rejects a single reward mint above [`MAX_REWARD_PER_MINT`].
+pub fn check_mint_cap(amount: u64) -> Result<(), TokenSecureError> {
+    if amount > MAX_REWARD_PER_MINT {
+        log!("SECURITY REJECTION: Reward amount above per-mint cap");
+        return Err(TokenSecureError::PerMintCapExceeded);
+    }
+    Ok(())
+}
+
+/// Requires both the mint being minted from and the destination token
+/// account's mint to be the vault's `reward_mint`.
+///
+/// // SECURITY: Validating against the deposit `mint` instead would let the
+/// // authority mint deposit tokens out of thin air (or fail for a vault with
+/// // a distinct reward token).
+pub fn check_reward_mint(
+    vault_state: &Vault,
+    mint: &Address,
+    destination_mint: &Address,
+) -> Result<(), TokenSecureError> {
+    if mint != &vault_state.reward_mint {
+        log!("SECURITY REJECTION: Mint is not the vault reward mint");
+        return Err(TokenSecureError::MintMismatch);
+    }
+    if destination_mint != &vault_state.reward_mint {
+        log!("SECURITY REJECTION: Destination mint does not match vault reward mint");
+        return Err(TokenSecureError::MintMismatch);
+    }
+    Ok(())
+}
+
+/// Rejects a deposit record that was allocated but never initialized.
+///
+/// // SECURITY: `UserDeposit::try_from_slice` accepts any buffer of the right
+/// // length, so a freshly zeroed account decodes as a zero-amount deposit.
+/// // An initialized record always names its user, which is never the zero key.
+pub fn assert_initialized(user_deposit_state: &UserDeposit) -> Result<(), TokenSecureError> {
+    if user_deposit_state.user == Address::default() {
+        log!("SECURITY REJECTION: UserDeposit is not initialized");
+        return Err(TokenSecureError::NotInitialized);
+    }
+    Ok(())
+}
+
+/// Rejects a stored deposit record that was created for a different vault.
+///
+/// // SECURITY: Without this, a user could pass their UserDeposit from vault A
+/// // to a deposit into vault B and have B credit a record A also trusts.
+/// // Anchor equivalent: `has_one = vault @ TokenSecureError::Unauthorized`
+pub fn check_deposit_record_vault(
+    user_deposit_state: &UserDeposit,
+    vault_address: &Address,
+) -> Result<(), TokenSecureError> {
+    if &user_deposit_state.vault != vault_address {
+        log!("SECURITY REJECTION: UserDeposit belongs to a different vault");
+        return Err(TokenSecureError::Unauthorized);
+    }
+    Ok(())
+}
+
+/// Rejects a v1 deposit whose nonce the record has already seen.
+///
+/// // SECURITY: Nonces must strictly increase per record. A client that
+/// // retries a transaction reuses its nonce, so a duplicate that lands after
+/// // the original fails here instead of crediting the deposit twice. A record
+/// // starts at `last_nonce == 0`, so the first nonce must be at least 1.
+pub fn check_deposit_nonce(
+    user_deposit_state: &UserDeposit,
+    nonce: u64,
+) -> Result<(), TokenSecureError> {
+    if nonce <= user_deposit_state.last_nonce {
+        log!("SECURITY REJECTION: Deposit nonce already used");
+        return Err(TokenSecureError::StaleNonce);
+    }
+    Ok(())
+}
+
+/// Rejects an instruction that would add funds or mint rewards while the
+/// vault is paused.
+///
+/// // SECURITY: The pause policy is asymmetric. `deposit`, `deposit_delegated`
+/// // and `mint_reward` call this; `withdraw` and `burn_deposit` do not, so a
+/// // paused (or abandoned) vault can never hold user funds hostage.
+pub fn assert_not_paused(vault_state: &Vault) -> Result<(), TokenSecureError> {
+    if vault_state.paused {
+        log!("SECURITY REJECTION: Vault is paused");
+        return Err(TokenSecureError::VaultPaused);
+    }
+    Ok(())
+}
+
+/// Pauses or unpauses the vault.
+///
+/// Instruction data is a single byte: `1` pauses, `0` unpauses.
+///
+/// ## Accounts
+/// 0. `[writable]` vault - The vault PDA
+/// 1. `[signer]` authority - Vault authority
+///
+/// ## Anchor Equivalent
+/// ```rust,ignore
+/// #[account(mut, has_one = authority @ TokenSecureError::Unauthorized)]
+/// pub vault: Account<'info, Vault>,
+/// pub authority: Signer<'info>,
+/// ```
+fn set_paused(program_id: &Address, accounts: &[AccountView], data: &[u8]) -> ProgramResult {
+    let [vault, authority] = accounts else {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    // ==========================================================================
+    // SECURITY CHECK 1: Authority must be a signer
+    // ==========================================================================
+    if !authority.is_signer() {
+        log!("SECURITY REJECTION: Authority must be a signer");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    let paused = match data.first() {
+        Some(0) => false,
+        Some(1) => true,
+        _ => return Err(ProgramError::InvalidInstructionData),
+    };
+
+    if !vault.owned_by(program_id) {
+        log!("SECURITY REJECTION: Vault not owned by this program");
+        return Err(ProgramError::IllegalOwner);
+    }
+
+    let vault_data = vault.try_borrow()?;
+    let mut vault_state = Vault::try_from_slice(&vault_data)?;
+    drop(vault_data);
+
+    // ==========================================================================
+    // SECURITY CHECK 2: Signer must match stored authority
+    // ==========================================================================
+    if vault_state.authority.as_ref() != authority.address().as_ref() {
+        log!("SECURITY REJECTION: Signer does not match vault authority");
+        return Err(TokenSecureError::Unauthorized.into());
+    }
+
+    vault_state.paused = paused;
+    let mut vault_data = vault.try_borrow_mut()?;
+    vault_state.serialize(&mut vault_data)?;
+
+    if paused {
+        log!("SECURE: Vault paused (withdrawals remain open)");
+    } else {
+        log!("SECURE: Vault unpaused");
+    }
+
+    Ok(())
+}
+
+/// Validates moving the vault to `new_mint` with `new_token_account` as its
+/// token account.
+///
+/// // SECURITY: Deposits are claims on tokens of the current mint, so the
+/// // mint may only change once every user has exited.
+pub fn check_mint_migration(
+    vault_address: &Address,
+    vault_state: &Vault,
+    new_mint: &Address,
+    new_token_mint: &Address,
+    new_token_owner: &Address,
+) -> Result<(), TokenSecureError> {
+    if vault_state.total_deposits != 0 {
+        log!("SECURITY REJECTION: Vault still holds {} in deposits", vault_state.total_deposits);
+        return Err(TokenSecureError::VaultNotEmpty);
+    }
+
+    if new_token_mint != new_mint {
+        log!("SECURITY REJECTION: New token account mint does not match new mint");
+        return Err(TokenSecureError::MintMismatch);
+    }
+
+    if new_token_owner != vault_address {
+        log!("SECURITY REJECTION: New token account not owned by vault PDA");
+        return Err(TokenSecureError::OwnerMismatch);
+    }
+
+    Ok(())
+}
+
+/// Moves an empty vault to a new deposit mint.
+///
+/// Replaces `vault.mint` and `vault.vault_token_account`. The vault address
+/// does not change: PDA checks and signer seeds use `vault.seed_mint`.
+///
+/// ## Accounts
+/// 0. `[writable]` vault - The vault PDA
+/// 1. `[signer]` authority - Vault authority
+/// 2. `[]` new_mint - Mint accepted for deposits from now on
+/// 3. `[]` new_vault_token_account - Vault PDA's token account for `new_mint`
+///
+/// ## Anchor Equivalent
+/// ```rust,ignore
+/// #[account(
+///     mut,
+///     has_one = authority @ TokenSecureError::Unauthorized,
+///     constraint = vault.total_deposits == 0 @ TokenSecureError::VaultNotEmpty
+/// )]
+/// pub vault: Account<'info, Vault>,
+/// pub authority: Signer<'info>,
+/// pub new_mint: Account<'info, Mint>,
+/// #[account(token::mint = new_mint, token::authority = vault)]
+/// pub new_vault_token_account: Account<'info, TokenAccount>,
+/// ```
+fn migrate_mint(program_id: &Address, accounts: &[AccountView], _data: &[u8]) -> ProgramResult {
+    let [vault, authority, new_mint, new_vault_token_account] = accounts else {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    // ==========================================================================
+    // SECURITY CHECK 1: Authority must be a signer
+    // ==========================================================================
+    if !authority.is_signer() {
+        log!("SECURITY REJECTION: Authority must be a signer");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    // ==========================================================================
+    // SECURITY CHECK 2: Account ownership
+    // ==========================================================================
+    if !vault.owned_by(program_id) {
+        log!("SECURITY REJECTION: Vault not owned by this program");
+        return Err(ProgramError::IllegalOwner);
+    }
+
+    if !new_vault_token_account.owned_by(&TOKEN_PROGRAM_ID) {
+        log!("SECURITY REJECTION: Token account must be owned by the token program");
+        return Err(ProgramError::IllegalOwner);
+    }
+
+    let vault_data = vault.try_borrow()?;
+    let mut vault_state = Vault::try_from_slice(&vault_data)?;
+    drop(vault_data);
+
+    // ==========================================================================
+    // SECURITY CHECK 3: Signer must match stored authority
+    // ==========================================================================
+    if vault_state.authority.as_ref() != authority.address().as_ref() {
+        log!("SECURITY REJECTION: Signer does not match vault authority");
+        return Err(TokenSecureError::Unauthorized.into());
+    }
+
+    // ==========================================================================
+    // SECURITY CHECK 4: Vault drained, new token account valid
+    // ==========================================================================
+    let new_data = new_vault_token_account.try_borrow()?;
+    let new_token_mint = parse_token_account_mint(&new_data)?;
+    let new_token_owner = parse_token_account_owner(&new_data)?;
+    drop(new_data);
+
+    check_mint_migration(
+        vault.address(),
+        &vault_state,
+        new_mint.address(),
+        &new_token_mint,
+        &new_token_owner,
+    )?;
+
+    vault_state.mint = Address::new_from_array(*new_mint.address().as_array());
+    vault_state.vault_token_account =
+        Address::new_from_array(*new_vault_token_account.address().as_array());
+
+    let mut vault_data = vault.try_borrow_mut()?;
+    vault_state.serialize(&mut vault_data)?;
+
+    log!("SECURE: Migrated vault mint (vault empty, authority verified)");
+
+    Ok(())
+}
+
+/// Checks every vault/deposit invariant, returning the first one that fails.
+///
+/// Runs in order: vault PDA and bump, deposit-to-vault relationship, deposit
+/// PDA and bump, vault token account relationship, and finally the accounting
+/// invariants (deposit <= total_deposits <= on-chain token balance). The token
+/// balance may exceed `total_deposits` (e.g. direct transfers), but never fall
+/// short of it.
+pub fn check_deposit_integrity(
+    program_id: &Address,
+    vault_address: &Address,
+    vault_state: &Vault,
+    user_deposit_address: &Address,
+    user_deposit_state: &UserDeposit,
+    vault_token_account_address: &Address,
+    vault_token_balance: u64,
+) -> Result<(), TokenSecureError> {
+    // Invariant 1: vault is the canonical PDA for its mint
+    let (expected_vault, vault_bump) = derive_vault_pda(&vault_state.seed_mint, program_id)
+        .map_err(|_| TokenSecureError::InvalidVaultPda)?;
+    if vault_address != &expected_vault {
+        log!("INTEGRITY FAIL: Vault PDA mismatch");
+        return Err(TokenSecureError::InvalidVaultPda);
+    }
+    if vault_state.bump != vault_bump {
+        log!("INTEGRITY FAIL: Vault bump {} is not canonical {}", vault_state.bump, vault_bump);
+        return Err(TokenSecureError::NonCanonicalBump);
+    }
+
+    // Invariant 2: user_deposit.vault points at this vault
+    if &user_deposit_state.vault != vault_address {
+        log!("INTEGRITY FAIL: UserDeposit belongs to a different vault");
+        return Err(TokenSecureError::VaultMismatch);
+    }
+
+    // Invariant 3: user_deposit is the canonical PDA for (vault, user)
+    let (expected_user_deposit, user_deposit_bump) =
+        derive_user_deposit_pda(vault_address, &user_deposit_state.user, program_id)
+            .map_err(|_| TokenSecureError::InvalidUserDepositPda)?;
+    if user_deposit_address != &expected_user_deposit {
+        log!("INTEGRITY FAIL: UserDeposit PDA mismatch");
+        return Err(TokenSecureError::InvalidUserDepositPda);
+    }
+    if user_deposit_state.bump != user_deposit_bump {
+        log!(
+            "INTEGRITY FAIL: UserDeposit bump {} is not canonical {}",
+            user_deposit_state.bump,
+            user_deposit_bump
+        );
+        return Err(TokenSecureError::NonCanonicalBump);
+    }
+
+    // Invariant 4: the supplied token account is the vault's token account
+    if &vault_state.vault_token_account != vault_token_account_address {
+        log!("INTEGRITY FAIL: Token account is not the vault token account");
+        return Err(TokenSecureError::VaultTokenAccountMismatch);
+    }
+
+    // Invariant 5: no single deposit exceeds the vault total
+    if user_deposit_state.amount > vault_state.total_deposits {
+        log!(
+            "INTEGRITY FAIL: Deposit {} exceeds vault total {}",
+            user_deposit_state.amount,
+            vault_state.total_deposits
+        );
+        return Err(TokenSecureError::DepositExceedsVaultTotal);
+    }
+
+    // Invariant 6: the vault actually holds the tokens it has recorded
+    if vault_token_balance < vault_state.total_deposits {
+        log!(
+            "INTEGRITY FAIL: Token balance {} below total_deposits {}",
+            vault_token_balance,
+            vault_state.total_deposits
+        );
+        return Err(TokenSecureError::VaultUndercollateralized);
+    }
+
+    Ok(())
+}
+
+/// Audits a user deposit against its vault without mutating any state.
+///
+/// Consolidates the checks scattered across deposit/withdraw/mint_reward into
+/// one read-only instruction for monitoring. Returns `Ok(())` only when every
+/// invariant holds; otherwise returns the error for the first failed invariant.
+///
+/// # Accounts
+/// 0. `[]` vault - The vault PDA
+/// 1. `[]` user_deposit - The user deposit PDA
+/// 2. `[]` vault_token_account - The vault's SPL token account
+///
+/// # Instruction Data
+/// - (empty)
+fn verify_deposit_integrity(
+    program_id: &Address,
+    accounts: &[AccountView],
+    _data: &[u8],
+) -> ProgramResult {
+    let [vault, user_deposit, vault_token_account] = accounts else {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    // SECURITY: Only program-owned state and token-program-owned balances are trusted
+    if !vault.owned_by(program_id) || !user_deposit.owned_by(program_id) {
+        log!("INTEGRITY FAIL: Vault or UserDeposit not owned by this program");
+        return Err(ProgramError::IllegalOwner);
+    }
+
+    if !vault_token_account.owned_by(&TOKEN_PROGRAM_ID) {
+        log!("INTEGRITY FAIL: Vault token account not owned by the token program");
+        return Err(ProgramError::IllegalOwner);
+    }
+
+    let vault_data = vault.try_borrow()?;
+    let vault_state = Vault::try_from_slice(&vault_data)?;
+    drop(vault_data);
+
+    let user_deposit_data = user_deposit.try_borrow()?;
+    let user_deposit_state = UserDeposit::try_from_slice(&user_deposit_data)?;
+    drop(user_deposit_data);
+
+    let token_data = vault_token_account.try_borrow()?;
+    let vault_token_balance = parse_token_account_amount(&token_data)?;
+    drop(token_data);
+
+    check_deposit_integrity(
+        program_id,
+        vault.address(),
+        &vault_state,
+        user_deposit.address(),
+        &user_deposit_state,
+        vault_token_account.address(),
+        vault_token_balance,
+    )?;
+
+    log!("INTEGRITY PASS: All deposit invariants hold");
+    log!("  Deposit: {}, Vault total: {}", user_deposit_state.amount, vault_state.total_deposits);
+    log!("  Token balance: {}", vault_token_balance);
+
+    Ok(())
+}
+
+/// Compares the vault's `total_deposits` with `expected_sum`, the sum of
+/// every `UserDeposit.amount` as computed off-chain.
+///
+/// A program cannot iterate its own accounts, so the authority totals the
+/// deposit records off-chain and this check pins the vault to that figure.
+pub fn check_reconciliation(
+    vault_state: &Vault,
+    expected_sum: u64,
+) -> Result<(), TokenSecureError> {
+    if vault_state.total_deposits != expected_sum {
+        log!(
+            "RECONCILE FAIL: total_deposits {} differs from deposit sum {}",
+            vault_state.total_deposits,
+            expected_sum
+        );
+        return Err(TokenSecureError::ReconciliationMismatch);
+    }
+
+    Ok(())
+}
+
+/// Audits the vault's `total_deposits` against the authority's off-chain sum
+/// of all user deposits, without mutating any state.
+///
+/// When the vault token account is supplied its on-chain balance is logged
+/// alongside, so one transaction shows the ledger, the deposit sum and the
+/// tokens backing them.
+///
+/// # Accounts
+/// 0. `[]` vault - The vault PDA
+/// 1. `[signer]` authority - Vault authority
+/// 2. `[]` vault_token_account (optional) - The vault's SPL token account
+///
+/// # Instruction Data
+/// - expected_sum (u64): Sum of every `UserDeposit.amount` for this vault
+fn reconcile(program_id: &Address, accounts: &[AccountView], data: &[u8]) -> ProgramResult {
+    let (vault, authority, vault_token_account) = match accounts {
+        [vault, authority] => (vault, authority, None),
+        [vault, authority, vault_token_account] => (vault, authority, Some(vault_token_account)),
+        _ => return Err(ProgramError::NotEnoughAccountKeys),
+    };
+
+    // SECURITY: Verify authority is signer
+    if !authority.is_signer() {
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    if data.len() < 8 {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    let expected_sum = u64::from_le_bytes(
+        data[0..8].try_into().map_err(|_| ProgramError::InvalidInstructionData)?,
+    );
+
+    // SECURITY: Only program-owned vault state is trusted
+    if !vault.owned_by(program_id) {
+        return Err(ProgramError::IllegalOwner);
+    }
+
+    let vault_data = vault.try_borrow()?;
+    let vault_state = Vault::try_from_slice(&vault_data)?;
+    drop(vault_data);
+
+    // SECURITY: Only the vault authority may run the audit it is accountable for
+    if vault_state.authority.as_ref() != authority.address().as_ref() {
+        return Err(TokenSecureError::Unauthorized.into());
+    }
+
+    if let Some(vault_token_account) = vault_token_account {
+        // SECURITY: The balance logged must be the vault's own token account
+        if !vault_token_account.owned_by(&TOKEN_PROGRAM_ID) {
+            return Err(ProgramError::IllegalOwner);
+        }
+        if &vault_state.vault_token_account != vault_token_account.address() {
+            return Err(TokenSecureError::VaultTokenAccountMismatch.into());
+        }
+
+        let token_data = vault_token_account.try_borrow()?;
+        let vault_token_balance = parse_token_account_amount(&token_data)?;
+        log!("  Token balance: {}", vault_token_balance);
+    }
+
+    check_reconciliation(&vault_state, expected_sum)?;
+
+    log!("RECONCILE PASS: total_deposits {} matches deposit sum", vault_state.total_deposits);
+
+    Ok(())
+}
+
+/// Checks that the vault token account may be replaced by a new one.
+///
+/// The old account must be the vault's recorded token account and hold zero
+/// tokens; the new account must hold the vault mint and be owned by the vault
+/// PDA so the vault can still sign transfers out of it.
+pub fn check_vault_token_account_rotation(
+    vault_address: &Address,
+    vault_state: &Vault,
+    old_token_account_address: &Address,
+    old_token_balance: u64,
+    new_token_mint: &Address,
+    new_token_owner: &Address,
+) -> Result<(), TokenSecureError> {
+    if &vault_state.vault_token_account != old_token_account_address {
+        log!("SECURITY REJECTION: Old token account is not the vault token account");
+        return Err(TokenSecureError::VaultTokenAccountMismatch);
+    }
+
+    // SECURITY: Rotating a funded account would strand deposits the vault no longer tracks
+    if old_token_balance != 0 {
+        log!("SECURITY REJECTION: Old token account still holds {} tokens", old_token_balance);
+        return Err(TokenSecureError::VaultTokenAccountNotEmpty);
+    }
+
+    if new_token_mint != &vault_state.mint {
+        log!("SECURITY REJECTION: New token account mint does not match vault mint");
+        return Err(TokenSecureError::MintMismatch);
+    }
+
+    if new_token_owner != vault_address {
+        log!("SECURITY REJECTION: New token account not owned by vault PDA");
+        return Err(TokenSecureError::OwnerMismatch);
+    }
+
+    Ok(())
+}
+
+/// Replaces the vault's recorded token account.
+///
+/// # Accounts
+/// 0. `[writable]` vault - The vault PDA
+/// 1. `[signer]` authority - The vault authority
+/// 2. `[]` vault_token_account - The current (drained) vault token account
+/// 3. `[]` new_vault_token_account - The replacement token account
+///
+/// # Instruction Data
+/// - (empty)
+///
+/// ## Anchor Equivalent
+/// ```rust,ignore
+/// #[account(
+///     mut,
+///     seeds = [VAULT_SEED, vault.seed_mint.as_ref()],
+///     bump,
+///     has_one = authority @ TokenSecureError::Unauthorized,
+///     has_one = vault_token_account
+/// )]
+/// pub vault: Account<'info, Vault>,
+/// #[account(
+///     constraint = vault_token_account.amount == 0 @ TokenSecureError::VaultTokenAccountNotEmpty
+/// )]
+/// pub vault_token_account: Account<'info, TokenAccount>,
+/// #[account(
+///     constraint = new_vault_token_account.mint == vault.mint @ TokenSecureError::MintMismatch,
+///     constraint = new_vault_token_account.owner == vault.key() @ TokenSecureError::OwnerMismatch
+/// )]
+/// pub new_vault_token_account: Account<'info, TokenAccount>,
+/// pub authority: Signer<'info>,
+/// ```
+fn rotate_vault_token_account(
+    program_id: &Address,
+    accounts: &[AccountView],
+    _data: &[u8],
+) -> ProgramResult {
+    let [vault, authority, vault_token_account, new_vault_token_account] = accounts else {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    // ==========================================================================
+    // SECURITY CHECK 1: Authority must be a signer
+    // ==========================================================================
+    if !authority.is_signer() {
+        log!("SECURITY REJECTION: Authority must be a signer");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    // ==========================================================================
+    // SECURITY CHECK 2: Account ownership
+    // ==========================================================================
+    // // SECURITY: Equivalent to Anchor's Account<'info, Vault> / Account<'info, TokenAccount>.
+    if !vault.owned_by(program_id) {
+        log!("SECURITY REJECTION: Vault not owned by this program");
+        return Err(ProgramError::IllegalOwner);
+    }
+
+    if !vault_token_account.owned_by(&TOKEN_PROGRAM_ID)
+        || !new_vault_token_account.owned_by(&TOKEN_PROGRAM_ID)
+    {
+        log!("SECURITY REJECTION: Token accounts must be owned by the token program");
+        return Err(ProgramError::IllegalOwner);
+    }
+
+    let vault_data = vault.try_borrow()?;
+    let mut vault_state = Vault::try_from_slice(&vault_data)?;
+    drop(vault_data);
+
+    // ==========================================================================
+    // SECURITY CHECK 3: Vault is the PDA of its seed mint
+    // ==========================================================================
+    // // SECURITY: The new token account must be owned by this address, so it
+    // // has to be the PDA that signs for the vault's transfers.
+    verify_vault_pda(program_id, vault.address(), &vault_state)?;
+
+    // ==========================================================================
+    // SECURITY CHECK 4: Signer must match stored authority
+    // ==========================================================================
+    // // SECURITY: This is equivalent to Anchor's has_one = authority constraint.
+    if vault_state.authority.as_ref() != authority.address().as_ref() {
+        log!("SECURITY REJECTION: Signer does not match vault authority");
+        return Err(TokenSecureError::Unauthorized.into());
+    }
+
+    // ==========================================================================
+    // SECURITY CHECK 5: Old account drained, new account valid
+    // ==========================================================================
+    let old_data = vault_token_account.try_borrow()?;
+    let old_token_balance = parse_token_account_amount(&old_data)?;
+    drop(old_data);
+
+    let new_data = new_vault_token_account.try_borrow()?;
+    let new_token_mint = parse_token_account_mint(&new_data)?;
+    let new_token_owner = parse_token_account_owner(&new_data)?;
+    drop(new_data);
+
+    check_vault_token_account_rotation(
+        vault.address(),
+        &vault_state,
+        vault_token_account.address(),
+        old_token_balance,
+        &new_token_mint,
+        &new_token_owner,
+    )?;
+
+    vault_state.vault_token_account =
+        Address::new_from_array(*new_vault_token_account.address().as_array());
+
+    let mut vault_data = vault.try_borrow_mut()?;
+    vault_state.serialize(&mut vault_data)?;
+
+    log!("SECURE: Rotated vault token account (authority verified)");
+
+    Ok(())
+}
+
+/// Checks that `signer` is the approved delegate and the allowance covers `amount`.
+pub fn check_delegated_deposit(
+    delegate: Option<&Address>,
+    delegated_amount: u64,
+    signer: &Address,
+    amount: u64,
+) -> Result<(), TokenSecureError> {
+    if delegate != Some(signer) {
+        log!("SECURITY REJECTION: Signer is not the approved delegate");
+        return Err(TokenSecureError::DelegateMismatch);
+    }
+
+    if delegated_amount < amount {
+        log!("SECURITY REJECTION: Delegated amount {} below deposit {}", delegated_amount, amount);
+        return Err(TokenSecureError::DelegatedAmountExceeded);
+    }
+
+    Ok(())
+}
+
+/// Moves tokens between two vaults of the same mint run by the same authority.
+///
+/// The source vault PDA signs the transfer between the two vault token
+/// accounts, and `total_deposits` moves with the tokens: the source is
+/// debited and the destination credited by `amount`.
+///
+/// // SECURITY: Both vaults must be distinct, owned by this program, hold the
+/// // same mint (`MintMismatch`) and name the signer as authority
+/// // (`Unauthorized`); tokens only move between the vaults' recorded token
+/// // accounts, and only through SPL Token. No new funds enter a paused
+/// // destination.
+///
+/// # Accounts
+/// 0. `[writable]` source_vault - Vault PDA the tokens leave (transfer authority)
+/// 1. `[writable]` destination_vault - Vault PDA the tokens join
+/// 2. `[writable]` source_vault_token_account - The source vault's token account
+/// 3. `[writable]` destination_vault_token_account - The destination vault's token account
+/// 4. `[signer]` authority - Authority of both vaults
+/// 5. `[]` token_program - SPL Token program
+///
+/// # Instruction Data
+/// - amount (u64): Amount to move (8 bytes, little-endian)
+///
+/// ## Anchor Equivalent
+/// ```rust,ignore
+/// #[account(mut, has_one = authority @ TokenSecureError::Unauthorized)]
+/// pub source_vault: Account<'info, Vault>,
+/// #[account(
+///     mut,
+///     has_one = authority @ TokenSecureError::Unauthorized,
+///     constraint = destination_vault.mint == source_vault.mint @ TokenSecureError::MintMismatch
+/// )]
+/// pub destination_vault: Account<'info, Vault>,
+/// pub authority: Signer<'info>,
+/// pub token_program: Program<'info, Token>,
+/// ```
+fn transfer_between_vaults(
+    program_id: &Address,
+    accounts: &[AccountView],
+    data: &[u8],
+) -> ProgramResult {
+    let [source_vault, destination_vault, source_token_account, destination_token_account, authority, token_program] =
+        accounts
+    else {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    // ==========================================================================
+    // SECURITY CHECK 1: Authority must be a signer
+    // ==========================================================================
+    if !authority.is_signer() {
+        log!("SECURITY REJECTION: Authority must be a signer");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    if data.len() < 8 {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    let amount = u64::from_le_bytes(
+        data[0..8].try_into().map_err(|_| ProgramError::InvalidInstructionData)?,
+    );
+
+    // ==========================================================================
+    // SECURITY CHECK 2: Two distinct vaults owned by this program
+    // ==========================================================================
+    // // SECURITY: The same vault twice would write a stale credit over the debit.
+    if source_vault.address() == destination_vault.address() {
+        log!("SECURITY REJECTION: Source and destination vault are the same account");
+        return Err(ProgramError::InvalidArgument);
+    }
+
+    if !source_vault.owned_by(program_id) || !destination_vault.owned_by(program_id) {
+        log!("SECURITY REJECTION: Vault not owned by this program");
+        return Err(ProgramError::IllegalOwner);
+    }
+
+    let source_data = source_vault.try_borrow()?;
+    let mut source_state = Vault::try_from_slice(&source_data)?;
+    drop(source_data);
+
+    let destination_data = destination_vault.try_borrow()?;
+    let mut destination_state = Vault::try_from_slice(&destination_data)?;
+    drop(destination_data);
+
+    // SECURITY: No new funds enter a paused vault
+    assert_not_paused(&destination_state)?;
+
+    // ==========================================================================
+    // SECURITY CHECK 3: Signer is the authority of both vaults
+    // ==========================================================================
+    if source_state.authority != *authority.address()
+        || destination_state.authority != *authority.address()
+    {
+        log!("SECURITY REJECTION: Signer is not the authority of both vaults");
+        return Err(TokenSecureError::Unauthorized.into());
+    }
+
+    // ==========================================================================
+    // SECURITY CHECK 4: Same mint, recorded token accounts
+    // ==========================================================================
+    if source_state.mint != destination_state.mint {
+        log!("SECURITY REJECTION: Vaults hold different mints");
+        return Err(TokenSecureError::MintMismatch.into());
+    }
+
+    if source_token_account.address() != &source_state.vault_token_account
+        || destination_token_account.address() != &destination_state.vault_token_account
+    {
+        log!("SECURITY REJECTION: Token account is not the vault token account");
+        return Err(TokenSecureError::VaultTokenAccountMismatch.into());
+    }
+
+    // SECURITY: Equivalent to Anchor's `seeds = [...], bump = source_vault.bump`;
+    // with `derive_bump_on_demand` the stored byte is not trusted at all.
+    #[cfg(not(feature = "derive_bump_on_demand"))]
+    verify_vault_signer_bump(program_id, source_vault.address(), &source_state)?;
+    let source_bump = vault_signer_bump(program_id, source_vault.address(), &source_state)?;
+
+    // SECURITY: The source vault PDA signs only for the real token program
+    check_token_program(token_program)?;
+
+    // SECURITY: Both totals are computed before the transfer
+    source_state.debit(amount)?;
+    destination_state.credit(amount)?;
+
+    let bump_bytes = [source_bump];
+    let seeds = [
+        Seed::from(VAULT_SEED),
+        Seed::from(source_state.seed_mint.as_ref()),
+        Seed::from(&bump_bytes),
+    ];
+    spl_token_transfer_signed(
+        source_token_account,
+        destination_token_account,
+        source_vault,
+        token_program,
+        amount,
+        &seeds,
+    )?;
+
+    let mut source_data = source_vault.try_borrow_mut()?;
+    source_state.serialize(&mut source_data)?;
+    drop(source_data);
+
+    let mut destination_data = destination_vault.try_borrow_mut()?;
+    destination_state.serialize(&mut destination_data)?;
+
+    log!("SECURE: Moved tokens between vaults (authority and mint validated)");
+
+    Ok(())
+}
+
+/// Deposits tokens on behalf of the source account owner via an approved delegate.
+///
+/// The delegate signs the transfer; the deposit is credited to the token
+/// account owner's UserDeposit PDA, which must already exist
+/// (see `create_user_deposit`).
+///
+/// // SECURITY: Crediting the delegate instead would let it deposit someone
+/// // else's tokens and then withdraw them as its own.
+///
+/// # Accounts
+/// 0. `[writable]` vault - The vault PDA
+/// 1. `[writable]` user_deposit - The token owner's UserDeposit PDA
+/// 2. `[writable]` source_token_account - Owner's token account, delegated to the signer
+/// 3. `[writable]` vault_token_account - The vault's token account
+/// 4. `[signer]` delegate - Approved delegate of the source token account
+/// 5. `[]` token_program - SPL Token program
+///
+/// # Instruction Data
+/// - amount (u64): Amount to deposit (8 bytes, little-endian)
+///
+/// ## Anchor Equivalent
+/// ```rust,ignore
+/// #[account(
+///     mut,
+///     constraint = source_token_account.mint == vault.mint @ TokenSecureError::MintMismatch,
+///     constraint = source_token_account.delegate == COption::Some(delegate.key())
+///         @ TokenSecureError::DelegateMismatch,
+///     constraint = source_token_account.delegated_amount >= amount
+///         @ TokenSecureError::DelegatedAmountExceeded
+/// )]
+/// pub source_token_account: Account<'info, TokenAccount>,
+/// ```
+fn deposit_delegated(program_id: &Address, accounts: &[AccountView], data: &[u8]) -> ProgramResult {
+    let [vault, user_deposit, source_token_account, vault_token_account, delegate, token_program] =
+        accounts
+    else {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    // ==========================================================================
+    // SECURITY CHECK 1: Delegate must be a signer
+    // ==========================================================================
+    if !delegate.is_signer() {
+        log!("SECURITY REJECTION: Delegate must be a signer");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    if data.len() < 8 {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    let amount = u64::from_le_bytes(
+        data[0..8].try_into().map_err(|_| ProgramError::InvalidInstructionData)?,
+    );
+
+    // ==========================================================================
+    // SECURITY CHECK 2: Account ownership
+    // ==========================================================================
+    if !vault.owned_by(program_id) || !user_deposit.owned_by(program_id) {
+        log!("SECURITY REJECTION: Vault or UserDeposit not owned by this program");
+        return Err(ProgramError::IllegalOwner);
+    }
+
+    if !source_token_account.owned_by(&TOKEN_PROGRAM_ID) {
+        log!("SECURITY REJECTION: Source token account not owned by the token program");
+        return Err(ProgramError::IllegalOwner);
+    }
+
+    // SECURITY: A fake token program could skip the transfer it is trusted
+    // to make, and the deposit would still be credited
+    check_token_program(token_program)?;
+
+    let vault_data = vault.try_borrow()?;
+    let mut vault_state = Vault::try_from_slice(&vault_data)?;
+    drop(vault_data);
+
+    // SECURITY: No new funds enter a paused vault
+    assert_not_paused(&vault_state)?;
+
+    // ==========================================================================
+    // SECURITY CHECK 3: Genuine vault and its token account
+    // ==========================================================================
+    // // SECURITY: The UserDeposit PDA below is derived from the vault address,
+    // // so the vault itself must be the PDA of its seed mint.
+    verify_vault_pda(program_id, vault.address(), &vault_state)?;
+
+    if vault_token_account.address() != &vault_state.vault_token_account {
+        log!("SECURITY REJECTION: Token account is not the vault token account");
+        return Err(TokenSecureError::VaultTokenAccountMismatch.into());
+    }
+
+    // ==========================================================================
+    // SECURITY CHECK 4: Mint, delegate and allowance
+    // ==========================================================================
+    let source_data = source_token_account.try_borrow()?;
+    let source_mint = parse_token_account_mint(&source_data)?;
+    let source_owner = parse_token_account_owner(&source_data)?;
+    let source_delegate = parse_token_account_delegate(&source_data)?;
+    let delegated_amount = parse_delegated_amount(&source_data)?;
+    drop(source_data);
+
+    if source_mint != vault_state.mint {
+        log!("SECURITY REJECTION: Token account mint does not match vault mint");
+        return Err(TokenSecureError::MintMismatch.into());
+    }
+
+    check_delegated_deposit(
+        source_delegate.as_ref(),
+        delegated_amount,
+        delegate.address(),
+        amount,
+    )?;
+
+    // ==========================================================================
+    // SECURITY CHECK 5: Credit the token owner, not the delegate
+    // ==========================================================================
+    let (expected_user_deposit, _) =
+        derive_user_deposit_pda(vault.address(), &source_owner, program_id)?;
+    if user_deposit.address() != &expected_user_deposit {
+        log!("SECURITY REJECTION: UserDeposit is not the token owner's PDA");
+        return Err(TokenSecureError::InvalidUserDepositPda.into());
+    }
+
+    let user_deposit_data = user_deposit.try_borrow()?;
+    let mut user_deposit_state = UserDeposit::try_from_slice(&user_deposit_data)?;
+    drop(user_deposit_data);
+
+    // SECURITY: Delegate signs as the transfer authority
+    spl_token_transfer(source_token_account, vault_token_account, delegate, token_program, amount)?;
+
+    // SECURITY: Safe arithmetic with checked operations
+    apply_deposit(&mut vault_state, &mut user_deposit_state, amount)?;
+
+    let mut vault_data = vault.try_borrow_mut()?;
+    vault_state.serialize(&mut vault_data)?;
+    drop(vault_data);
+
+    let mut user_deposit_data = user_deposit.try_borrow_mut()?;
+    user_deposit_state.serialize(&mut user_deposit_data)?;
+
+    log!("SECURE: Delegate deposited tokens (allowance validated)");
+
+    Ok(())
+}
+
+/// Burns a user's deposited tokens out of the vault instead of returning them.
+///
+/// The vault PDA signs the Burn as token account authority. Accounting is the
+/// same as a withdrawal: the user's deposit and `total_deposits` both drop by
+/// `amount`, so the vault stays fully backed.
+///
+/// # Accounts
+/// 0. `[writable]` vault - The vault PDA (burn authority)
+/// 1. `[writable]` user_deposit - The user's deposit PDA
+/// 2. `[writable]` vault_token_account - Token account to burn from
+/// 3. `[writable]` mint - The vault's mint
+/// 4. `[signer]` user - Owner of the deposit
+/// 5. `[]` token_program - SPL Token program
+///
+/// # Instruction Data
+/// - `amount` (u64) - Amount to burn
+///
+/// ## Anchor Equivalent
+/// ```rust,ignore
+/// #[account(mut, seeds = [VAULT_SEED, vault.seed_mint.as_ref()], bump = vault.bump)]
+/// pub vault: Account<'info, Vault>,
+/// #[account(address = vault.mint @ TokenSecureError::MintMismatch)]
+/// pub mint: Account<'info, Mint>,
+/// pub token_program: Program<'info, Token>,
+/// token::burn(ctx.accounts.burn_ctx().with_signer(signer), amount)?;
+/// ```
+fn burn_deposit(program_id: &Address, accounts: &[AccountView], data: &[u8]) -> ProgramResult {
+    let [vault, user_deposit, vault_token_account, mint, user, token_program] = accounts else {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    // ==========================================================================
+    // SECURITY CHECK 1: User must be a signer
+    // ==========================================================================
+    if !user.is_signer() {
+        log!("SECURITY REJECTION: User must be a signer");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    if data.len() < 8 {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    let amount = u64::from_le_bytes(
+        data[0..8].try_into().map_err(|_| ProgramError::InvalidInstructionData)?,
+    );
+
+    // ==========================================================================
+    // SECURITY CHECK 2: Account ownership
+    // ==========================================================================
+    if !vault.owned_by(program_id) || !user_deposit.owned_by(program_id) {
+        log!("SECURITY REJECTION: Vault or UserDeposit not owned by this program");
+        return Err(ProgramError::IllegalOwner);
+    }
+
+    let vault_data = vault.try_borrow()?;
+    let mut vault_state = Vault::try_from_slice(&vault_data)?;
+    drop(vault_data);
+
+    // ==========================================================================
+    // SECURITY CHECK 3: Genuine vault and SPL Token program
+    // ==========================================================================
+    // // SECURITY: The vault PDA signs the burn, and the UserDeposit PDA below
+    // // is derived from the vault address, so both must be the real vault.
+    #[cfg(not(feature = "derive_bump_on_demand"))]
+    verify_vault_signer_bump(program_id, vault.address(), &vault_state)?;
+    let vault_bump = vault_signer_bump(program_id, vault.address(), &vault_state)?;
+
+    check_token_program(token_program)?;
+
+    // ==========================================================================
+    // SECURITY CHECK 4: Vault token account and mint
+    // ==========================================================================
+    if vault_token_account.address() != &vault_state.vault_token_account {
+        log!("SECURITY REJECTION: Token account is not the vault token account");
+        return Err(TokenSecureError::VaultTokenAccountMismatch.into());
+    }
+
+    if mint.address() != &vault_state.mint {
+        log!("SECURITY REJECTION: Mint does not match vault mint");
+        return Err(TokenSecureError::MintMismatch.into());
+    }
+
+    // ==========================================================================
+    // SECURITY CHECK 5: Only the user's own deposit can be burned
+    // ==========================================================================
+    let (expected_user_deposit, _) =
+        derive_user_deposit_pda(vault.address(), user.address(), program_id)?;
+    if user_deposit.address() != &expected_user_deposit {
+        log!("SECURITY REJECTION: UserDeposit is not the signer's PDA");
+        return Err(TokenSecureError::InvalidUserDepositPda.into());
+    }
+
+    let user_deposit_data = user_deposit.try_borrow()?;
+    let mut user_deposit_state = UserDeposit::try_from_slice(&user_deposit_data)?;
+    drop(user_deposit_data);
+
+    // SECURITY: Reject before the CPI if the user hasn't deposited enough
+    apply_withdraw(&mut vault_state, &mut user_deposit_state, amount)?;
+
+    // Build PDA signer seeds for vault authority
+    let bump_bytes = [vault_bump];
+    let seeds = [
+        Seed::from(VAULT_SEED),
+        Seed::from(vault_state.seed_mint.as_ref()),
+        Seed::from(&bump_bytes),
+    ];
+
+    // SECURITY: Burn from the validated vault token account only
+    spl_token_burn_signed(vault_token_account, mint, vault, token_program, amount, &seeds)?;
+
+    let mut vault_data = vault.try_borrow_mut()?;
+    vault_state.serialize(&mut vault_data)?;
+    drop(vault_data);
+
+    let mut user_deposit_data = user_deposit.try_borrow_mut()?;
+    user_deposit_state.serialize(&mut user_deposit_data)?;
+
+    log!("SECURE: Burned deposited tokens (mint validated)");
+
+    Ok(())
+}
+
+/// Freezes a token account of the vault's mint, using the vault PDA as freeze authority.
+///
+/// Only works for mints whose freeze authority is the vault PDA; the token
+/// program rejects the CPI otherwise.
+///
+/// # Accounts
+/// 0. `[]` vault - The vault PDA (freeze authority)
+/// 1. `[]` mint - The vault's mint
+/// 2. `[writable]` target_token_account - Token account to freeze
+/// 3. `[signer]` authority - The vault authority
+/// 4. `[]` token_program - SPL Token program
+///
+/// ## Anchor Equivalent
+/// ```rust,ignore
+/// #[account(
+///     has_one = authority @ TokenSecureError::Unauthorized,
+///     seeds = [VAULT_SEED, vault.seed_mint.as_ref()],
+///     bump = vault.bump,
+/// )]
+/// pub vault: Account<'info, Vault>,
+/// #[account(mut, constraint = target.mint == vault.mint @ TokenSecureError::MintMismatch)]
+/// pub target: Account<'info, TokenAccount>,
+/// pub token_program: Program<'info, Token>,
+/// token::freeze_account(ctx.accounts.freeze_ctx().with_signer(signer))?;
+/// ```
+fn freeze_user_account(
+    program_id: &Address,
+    accounts: &[AccountView],
+    _data: &[u8],
+) -> ProgramResult {
+    set_user_account_frozen(program_id, accounts, true)
+}
+
+/// Thaws a token account previously frozen by `freeze_user_account`.
+///
+/// Same accounts and checks as `freeze_user_account`.
+fn thaw_user_account(
+    program_id: &Address,
+    accounts: &[AccountView],
+    _data: &[u8],
+) -> ProgramResult {
+    set_user_account_frozen(program_id, accounts, false)
+}
+
+fn set_user_account_frozen(
+    program_id: &Address,
+    accounts: &[AccountView],
+    freeze: bool,
+) -> ProgramResult {
+    let [vault, mint, target_token_account, authority, token_program] = accounts else {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    // ==========================================================================
+    // SECURITY CHECK 1: Authority must be a signer
+    // ==========================================================================
+    if !authority.is_signer() {
+        log!("SECURITY REJECTION: Authority must be a signer");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    if !vault.owned_by(program_id) {
+        log!("SECURITY REJECTION: Vault not owned by this program");
+        return Err(ProgramError::IllegalOwner);
+    }
+
+    let vault_data = vault.try_borrow()?;
+    let vault_state = Vault::try_from_slice(&vault_data)?;
+    drop(vault_data);
+
+    // ==========================================================================
+    // SECURITY CHECK 2: Signer must match stored authority
+    // ==========================================================================
+    if vault_state.authority.as_ref() != authority.address().as_ref() {
+        log!("SECURITY REJECTION: Signer does not match vault authority");
+        return Err(TokenSecureError::Unauthorized.into());
+    }
+
+    // ==========================================================================
+    // SECURITY CHECK 3: Target is a token account of the vault mint
+    // ==========================================================================
+    if mint.address() != &vault_state.mint {
+        log!("SECURITY REJECTION: Mint does not match vault mint");
+        return Err(TokenSecureError::MintMismatch.into());
+    }
+
+    if !target_token_account.owned_by(&TOKEN_PROGRAM_ID) {
+        log!("SECURITY REJECTION: Target not owned by the token program");
+        return Err(ProgramError::IllegalOwner);
+    }
+
+    let target_data = target_token_account.try_borrow()?;
+    let target_mint = parse_token_account_mint(&target_data)?;
+    drop(target_data);
+
+    if target_mint != vault_state.mint {
+        log!("SECURITY REJECTION: Target token account mint does not match vault mint");
+        return Err(TokenSecureError::MintMismatch.into());
+    }
+
+    // ==========================================================================
+    // SECURITY CHECK 4: Genuine vault signs, for the real token program only
+    // ==========================================================================
+    #[cfg(not(feature = "derive_bump_on_demand"))]
+    verify_vault_signer_bump(program_id, vault.address(), &vault_state)?;
+    let vault_bump = vault_signer_bump(program_id, vault.address(), &vault_state)?;
+
+    check_token_program(token_program)?;
+
+    // Build PDA signer seeds for freeze authority
+    let bump_bytes = [vault_bump];
+    let seeds = [
+        Seed::from(VAULT_SEED),
+        Seed::from(vault_state.seed_mint.as_ref()),
+        Seed::from(&bump_bytes),
+    ];
+
+    if freeze {
+        spl_token_freeze_account_signed(target_token_account, mint, vault, token_program, &seeds)?;
+        log!("SECURE: Froze user token account (authority verified)");
+    } else {
+        spl_token_thaw_account_signed(target_token_account, mint, vault, token_program, &seeds)?;
+        log!("SECURE: Thawed user token account (authority verified)");
+    }
+
+    Ok(())
+}
+
+/// Creates the caller's UserDeposit PDA, funding its rent from the user.
+///
+/// An empty address is created with `CreateAccount`, funded to the
+/// rent-exempt minimum. `CreateAccount` rejects an address that already holds
+/// lamports, so a pre-funded PDA is instead topped up by the shortfall only
+/// (nothing if it already covers the minimum), then allocated and assigned to
+/// this program with its own signer seeds.
+///
+/// // SECURITY: Without the pre-funded path, anyone could block a user's
+/// // record by sending a lamport to its address first.
+///
+/// # Accounts
+/// 0. `[writable]` user_deposit - The user deposit PDA (not yet created)
+/// 1. `[]` vault - The vault PDA
+/// 2. `[signer, writable]` user - The depositor, pays rent
+/// 3. `[]` system_program - System program
+///
+/// # Instruction Data
+/// - (empty) - bump is derived, not accepted from user
+///
+/// ## Anchor Equivalent
+/// ```rust,ignore
+/// #[account(
+///     init,
+///     payer = user,
+///     space = USER_DEPOSIT_SPACE,
+///     seeds = [USER_DEPOSIT_SEED, vault.key().as_ref(), user.key().as_ref()],
+///     bump
+/// )]
+/// pub user_deposit: Account<'info, UserDeposit>,
+/// ```
+fn create_user_deposit(
+    program_id: &Address,
+    accounts: &[AccountView],
+    _data: &[u8],
+) -> ProgramResult {
+    let [user_deposit, vault, user, system_program] = accounts else {
+        return Err(ProgramError::NotEnoughAccountKeys);
+    };
+
+    // ==========================================================================
+    // SECURITY CHECK 1: User must be a signer
+    // ==========================================================================
+    if !user.is_signer() {
+        log!("SECURITY REJECTION: User must be a signer");
+        return Err(ProgramError::MissingRequiredSignature);
+    }
+
+    // ==========================================================================
+    // SECURITY CHECK 2: Genuine vault, genuine system program
+    // ==========================================================================
+    if !vault.owned_by(program_id) {
+        log!("SECURITY REJECTION: Vault not owned by this program");
+        return Err(ProgramError::IllegalOwner);
+    }
+
+    if system_program.address() != &SYSTEM_PROGRAM_ID {
+        log!("SECURITY REJECTION: Invalid system program");
+        return Err(ProgramError::IncorrectProgramId);
+    }
+
+    // ==========================================================================
+    // SECURITY CHECK 3: UserDeposit is the canonical PDA and not yet created
+    // ==========================================================================
+    let (expected_user_deposit, bump) =
+        derive_user_deposit_pda(vault.address(), user.address(), program_id)?;
+
+    if user_deposit.address() != &expected_user_deposit {
+        log!("SECURITY REJECTION: UserDeposit PDA mismatch");
+        return Err(TokenSecureError::InvalidUserDepositPda.into());
+    }
+
+    if user_deposit.owned_by(program_id) {
+        log!("SECURITY REJECTION: UserDeposit already created");
+        return Err(ProgramError::AccountAlreadyInitialized);
+    }
+
+    let bump_bytes = [bump];
+    let seeds = [
+        Seed::from(USER_DEPOSIT_SEED),
+        Seed::from(vault.address().as_ref()),
+        Seed::from(user.address().as_ref()),
+        Seed::from(&bump_bytes),
+    ];
+
+    // Fund up to the rent-exempt minimum and hand the PDA to this program
+    let minimum = rent_exempt_minimum(USER_DEPOSIT_SIZE)?;
+    let shortfall = rent_shortfall(user_deposit.lamports(), minimum);
+    if user_deposit.lamports() == 0 {
+        create_account_signed(user, user_deposit, program_id, USER_DEPOSIT_SIZE, &seeds)?;
+    } else {
+        if shortfall > 0 {
+            transfer(user, user_deposit, shortfall)?;
+        }
+        allocate_signed(user_deposit, USER_DEPOSIT_SIZE, &seeds)?;
+        assign_signed(user_deposit, program_id, &seeds)?;
+    }
+
+    let user_deposit_state = UserDeposit {
+        user: Address::new_from_array(*user.address().as_array()),
+        vault: Address::new_from_array(*vault.address().as_array()),
+        amount: 0,
+        bump,
+        last_nonce: 0,
+    };
+
+    let mut user_deposit_data = user_deposit.try_borrow_mut()?;
+    user_deposit_state.serialize(&mut user_deposit_data)?;
+
+    log!("SECURE: UserDeposit created, rent top-up {} lamports", shortfall);
+
+    Ok(())
+}
+
+/// Re-derive a Vault or UserDeposit PDA from `[kind][seed][seed]`.
+///
+/// `PDA_KIND_VAULT` uses the first seed (mint) and ignores the second;
+/// `PDA_KIND_USER_DEPOSIT` uses both (vault, user). Fails with
+/// `InvalidInstructionData` for an unknown kind or a short payload.
+pub fn derive_pda_from_seeds(
+    program_id: &Address,
+    data: &[u8],
+) -> Result<(Address, u8), ProgramError> {
+    if data.len() < 65 {
+        return Err(ProgramError::InvalidInstructionData);
+    }
+    let first = Address::new_from_array(
+        data[1..33].try_into().map_err(|_| ProgramError::InvalidInstructionData)?,
+    );
+    let second = Address::new_from_array(
+        data[33..65].try_into().map_err(|_| ProgramError::InvalidInstructionData)?,
+    );
+
+    match data[0] {
+        PDA_KIND_VAULT => derive_vault_pda(&first, program_id),
+        PDA_KIND_USER_DEPOSIT => derive_user_deposit_pda(&first, &second, program_id),
+        _ => Err(ProgramError::InvalidInstructionData),
+    }
+}
+
+/// Logs the PDA this program derives for the given seeds.
+///
+/// Clients simulate it and compare the `derive_address: <hex address> <bump>`
+/// log line with their own derivation. Touches no accounts.
+///
+/// ## Accounts
+/// (none)
+///
+/// ## Instruction Data
+/// - kind (u8): `PDA_KIND_VAULT` or `PDA_KIND_USER_DEPOSIT`
+/// - seeds: two 32-byte seeds, see [`derive_pda_from_seeds`]
+fn derive_address(program_id: &Address, _accounts: &[AccountView], data: &[u8]) -> ProgramResult {
+    let (address, bump) = derive_pda_from_seeds(program_id, data)?;
+
+    let hex = address_hex(&address);
+    let hex = core::str::from_utf8(&hex).map_err(|_| ProgramError::InvalidArgument)?;
+    log!("derive_address: {} {}", hex, bump);
+
+    Ok(())
+}
+
+// =============================================================================
+// TESTS
+// =============================================================================
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pinocchio_security_common::{
+        assert_reinit_rejected, decode_address, MockAccountView, XorShift64,
+    };
+
+    #[test]
+    fn test_program_id_matches_documented_address() {
+        // Must match the doc comment on `ID` and Anchor.toml
+        assert_eq!(decode_address("CF7pz8p5P9iz2cqr4Lfd1eS2PBPtKij63YAgcSRPaGEt"), Some(ID));
+    }
+
+    #[test]
+    fn test_vault_serialization() {
+        let vault = Vault {
+            authority: Address::new_from_array([1u8; 32]),
+            mint: Address::new_from_array([2u8; 32]),
+            vault_token_account: Address::new_from_array([3u8; 32]),
+            total_deposits: 1_000_000,
+            bump: 255,
+            reward_mint: Address::new_from_array([4u8; 32]),
+            paused: true,
+            total_minted: 42_000,
+            deposit_fee_bps: 250,
+            seed_mint: Address::new_from_array([2u8; 32]),
+        };
+
+        let mut buffer = [0u8; VAULT_SIZE];
+        vault.serialize(&mut buffer).unwrap();
+
+        let deserialized = Vault::try_from_slice(&buffer).unwrap();
+        assert_eq!(deserialized, vault);
+    }
+
+    #[test]
+    fn test_vault_future_version_rejected() {
+        let (_, vault, ..) = integrity_fixture();
+        let mut buffer = [0u8; VAULT_SIZE];
+        vault.serialize(&mut buffer).unwrap();
+        assert_eq!(buffer[VAULT_VERSION_OFFSET], SCHEMA_VERSION);
+
+        buffer[VAULT_VERSION_OFFSET] = SCHEMA_VERSION + 1;
+        assert!(matches!(
+            Vault::try_from_slice(&buffer),
+            Err(e) if e == CommonError::UnsupportedVersion.into()
+        ));
+    }
+
+    #[test]
+    fn test_vault_reserved_padding_preserved() {
+        let (_, vault, _, _, _) = integrity_fixture();
+        let reserved = VAULT_RESERVED_OFFSET..VAULT_SIZE;
+
+        let mut buffer = [0u8; VAULT_SIZE];
+        vault.serialize(&mut buffer).unwrap();
+        Vault::try_from_slice(&buffer).unwrap().serialize(&mut buffer).unwrap();
+        assert_eq!(buffer[reserved.clone()], [0u8; VAULT_RESERVED_LEN]);
+
+        // Bytes a future version wrote survive an update by this version
+        buffer[reserved.clone()].copy_from_slice(&[0xab; VAULT_RESERVED_LEN]);
+        let mut updated = Vault::try_from_slice(&buffer).unwrap();
+        assert_eq!(updated.total_deposits, vault.total_deposits);
+        updated.total_deposits += 1;
+        updated.serialize(&mut buffer).unwrap();
+        assert_eq!(buffer[reserved], [0xab; VAULT_RESERVED_LEN]);
+        assert_eq!(Vault::try_from_slice(&buffer).unwrap().total_deposits, 1_001);
+    }
+
+    /// Runs initialize_vault with `mint` owned by `mint_owner` and `mint_len` bytes long.
+    fn run_initialize_vault(mint_owner: Address, mint_len: usize) -> ProgramResult {
+        let mut vault =
+            MockAccountView::new(Address::new_from_array([1u8; 32]), ID, 0, &[0u8; VAULT_SIZE])
+                .writable();
+        run_initialize_vault_on(&mut vault, mint_owner, mint_len)
+    }
+
+    fn run_initialize_vault_on(
+        vault: &mut MockAccountView,
+        mint_owner: Address,
+        mint_len: usize,
+    ) -> ProgramResult {
+        let mut mint = MockAccountView::new(
+            Address::new_from_array([2u8; 32]),
+            mint_owner,
+            0,
+            &vec![0u8; mint_len],
+        );
+        let mut vault_token =
+            MockAccountView::new(Address::new_from_array([3u8; 32]), TOKEN_PROGRAM_ID, 0, &[]);
+        let mut authority =
+            MockAccountView::new(Address::new_from_array([4u8; 32]), Address::default(), 0, &[])
+                .signer();
+        let mut system = MockAccountView::new(SYSTEM_PROGRAM_ID, Address::default(), 0, &[]);
+        let mut token = MockAccountView::new(TOKEN_PROGRAM_ID, Address::default(), 0, &[]);
+        let mut reward_mint = MockAccountView::new(
+            Address::new_from_array([5u8; 32]),
+            TOKEN_PROGRAM_ID,
+            0,
+            &[0u8; SPL_MINT_LEN],
+        );
+
+        initialize_vault(
+            &ID,
+            &[
+                vault.view(),
+                mint.view(),
+                vault_token.view(),
+                authority.view(),
+                system.view(),
+                token.view(),
+                reward_mint.view(),
+            ],
+            &[255],
+        )
+    }
+
+    #[test]
+    fn test_initialize_vault_rejects_non_mint() {
+        assert_eq!(run_initialize_vault(TOKEN_PROGRAM_ID, SPL_MINT_LEN), Ok(()));
+
+        // Not owned by the token program (e.g. a wallet or another program's account)
+        assert_eq!(
+            run_initialize_vault(Address::default(), SPL_MINT_LEN),
+            Err(ProgramError::IllegalOwner)
+        );
+
+        // Token-program owned but too small for the mint layout
+        assert_eq!(
+            run_initialize_vault(TOKEN_PROGRAM_ID, SPL_MINT_LEN - 1),
+            Err(TokenSecureError::MintMismatch.into())
+        );
+    }
+
+    #[test]
+    fn test_vault_credit_and_debit() {
+        let (_, mut v, ..) = integrity_fixture();
+        assert_eq!(v.total_deposits, 1_000);
+
+        assert!(v.credit(500).is_ok());
+        assert_eq!(v.total_deposits, 1_500);
+        assert!(v.debit(1_500).is_ok());
+        assert_eq!(v.total_deposits, 0);
+    }
+
+    #[test]
+    fn test_vault_credit_and_debit_reject_without_change() {
+        let (_, mut v, ..) = integrity_fixture();
+
+        assert!(matches!(v.debit(1_001), Err(TokenSecureError::DepositExceedsVaultTotal)));
+        assert_eq!(v.total_deposits, 1_000);
+
+        assert!(matches!(v.credit(u64::MAX), Err(TokenSecureError::ArithmeticOverflow)));
+        assert_eq!(v.total_deposits, 1_000);
+    }
+
+    #[test]
+    fn test_user_deposit_serialization() {
+        let user_deposit = UserDeposit {
+            user: Address::new_from_array([1u8; 32]),
+            vault: Address::new_from_array([2u8; 32]),
+            amount: 500_000,
+            bump: 254,
+            last_nonce: 42,
+        };
+
+        let mut buffer = [0u8; USER_DEPOSIT_SIZE];
+        user_deposit.serialize(&mut buffer).unwrap();
+
+        let deserialized = UserDeposit::try_from_slice(&buffer).unwrap();
+        assert_eq!(deserialized, user_deposit);
+    }
+
+    #[test]
+    fn test_error_conversion() {
+        let err: ProgramError = TokenSecureError::MintMismatch.into();
+        assert!(matches!(err, ProgramError::Custom(0x1770)));
+
+        let err: ProgramError = TokenSecureError::OwnerMismatch.into();
+        assert!(matches!(err, ProgramError::Custom(0x1771)));
+
+        let err: ProgramError = TokenSecureError::Unauthorized.into();
+        assert!(matches!(err, ProgramError::Custom(0x1772)));
+
+        let err: ProgramError = TokenSecureError::NotInitialized.into();
+        assert!(matches!(err, ProgramError::Custom(0x1780)));
+    }
+
+    /// Builds a fully consistent vault/deposit pair for integrity tests.
+    fn integrity_fixture() -> (Address, Vault, Address, UserDeposit, Address) {
+        let mint = Address::new_from_array([2u8; 32]);
+        let user = Address::new_from_array([4u8; 32]);
+        let vault_token_account = Address::new_from_array([3u8; 32]);
+
+        let (vault_address, vault_bump) = derive_vault_pda(&mint, &ID).unwrap();
+        let (user_deposit_address, user_deposit_bump) =
+            derive_user_deposit_pda(&vault_address, &user, &ID).unwrap();
+
+        let vault = Vault {
+            authority: Address::new_from_array([1u8; 32]),
+            mint,
+            vault_token_account,
+            total_deposits: 1_000,
+            bump: vault_bump,
+            reward_mint: Address::new_from_array([6u8; 32]),
+            paused: false,
+            total_minted: 0,
+            deposit_fee_bps: 0,
+            seed_mint: mint,
+        };
+        let user_deposit = UserDeposit {
+            user,
+            vault: vault_address,
+            amount: 400,
+            bump: user_deposit_bump,
+            last_nonce: 0,
+        };
+
+        (vault_address, vault, user_deposit_address, user_deposit, vault_token_account)
+    }
+
+    fn run_integrity(
+        vault_address: &Address,
+        vault: &Vault,
+        user_deposit_address: &Address,
+        user_deposit: &UserDeposit,
+        vault_token_account: &Address,
+        balance: u64,
+    ) -> Result<(), TokenSecureError> {
+        check_deposit_integrity(
+            &ID,
+            vault_address,
+            vault,
+            user_deposit_address,
+            user_deposit,
+            vault_token_account,
+            balance,
+        )
+    }
+
+    #[test]
+    fn test_vault_signer_bump_must_be_canonical() {
+        let (vault_address, mut vault, ..) = integrity_fixture();
+        assert!(verify_vault_signer_bump(&ID, &vault_address, &vault).is_ok());
+
+        vault.bump = vault.bump.wrapping_sub(1);
+        let result = verify_vault_signer_bump(&ID, &vault_address, &vault);
+        assert_eq!(result, Err(CommonError::InvalidBump.into()));
+
+        let other = Address::new_from_array([9u8; 32]);
+        let result = verify_vault_signer_bump(&ID, &other, &vault);
+        assert_eq!(result, Err(TokenSecureError::InvalidVaultPda.into()));
+    }
+
+    #[test]
+    fn test_vault_signer_bump_source() {
+        let (vault_address, mut vault, ..) = integrity_fixture();
+        let canonical = vault.bump;
+        vault.bump = 0;
+
+        let result = vault_signer_bump(&ID, &vault_address, &vault);
+        if cfg!(feature = "derive_bump_on_demand") {
+            // The stored byte is ignored and the canonical bump re-derived
+            assert_eq!(result, Ok(canonical));
+            let other = Address::new_from_array([9u8; 32]);
+            let result = vault_signer_bump(&ID, &other, &vault);
+            assert_eq!(result, Err(TokenSecureError::InvalidVaultPda.into()));
+        } else {
+            assert_eq!(result, Ok(0));
+        }
+    }
+
+    #[test]
+    fn test_mint_reward_with_zeroed_stored_bump() {
+        let (vault_address, mut vault_state, _, _, _) = integrity_fixture();
+        vault_state.bump = 0;
+        let mut vault_data = [0u8; VAULT_SIZE];
+        vault_state.serialize(&mut vault_data).unwrap();
+
+        let mut vault = MockAccountView::new(vault_address, ID, 0, &vault_data).writable();
+        let mut mint =
+            MockAccountView::new(vault_state.reward_mint, TOKEN_PROGRAM_ID, 0, &[]).writable();
+        let mut destination = MockAccountView::new(
+            Address::new_from_array([7u8; 32]),
+            TOKEN_PROGRAM_ID,
+            0,
+            &token_account(&vault_state.reward_mint, &Address::new_from_array([4u8; 32])),
+        )
+        .writable();
+        let mut authority =
+            MockAccountView::new(vault_state.authority, Address::default(), 0, &[]).signer();
+        let mut token = MockAccountView::new(TOKEN_PROGRAM_ID, Address::default(), 0, &[]);
+
+        let accounts =
+            [vault.view(), mint.view(), destination.view(), authority.view(), token.view()];
+        let result = mint_reward(&ID, &accounts, &100u64.to_le_bytes());
+        if cfg!(feature = "derive_bump_on_demand") {
+            // Signs with the derived bump; the stored byte is left as it was
+            assert_eq!(result, Ok(()));
+            let after = Vault::try_from_slice(vault.data()).unwrap();
+            assert_eq!((after.total_minted, after.bump), (100, 0));
+        } else {
+            assert_eq!(result, Err(CommonError::InvalidBump.into()));
+        }
+    }
+
+    /// Test that the batch mint and the vault-to-vault transfer sign with
+    /// the derived bump, ignoring a corrupted stored one.
+    #[cfg(feature = "derive_bump_on_demand")]
+    #[test]
+    fn test_batch_and_transfer_sign_without_stored_bump() {
+        let (vault_address, mut vault_state, _, _, _) = integrity_fixture();
+        vault_state.bump = vault_state.bump.wrapping_sub(1);
+        let mut vault_data = [0u8; VAULT_SIZE];
+        vault_state.serialize(&mut vault_data).unwrap();
+
+        let mut vault = MockAccountView::new(vault_address, ID, 0, &vault_data).writable();
+        let mut mint =
+            MockAccountView::new(vault_state.reward_mint, TOKEN_PROGRAM_ID, 0, &[]).writable();
+        let mut authority =
+            MockAccountView::new(vault_state.authority, Address::default(), 0, &[]).signer();
+        let mut token = MockAccountView::new(TOKEN_PROGRAM_ID, Address::default(), 0, &[]);
+        let mut destination = MockAccountView::new(
+            Address::new_from_array([7u8; 32]),
+            TOKEN_PROGRAM_ID,
+            0,
+            &token_account(&vault_state.reward_mint, &Address::new_from_array([4u8; 32])),
+        )
+        .writable();
+        let accounts =
+            [vault.view(), mint.view(), authority.view(), token.view(), destination.view()];
+        assert_eq!(process_instruction(&ID, &accounts, &mint_batch_data(&[100])), Ok(()));
+        let after = Vault::try_from_slice(vault.data()).unwrap();
+        assert_eq!((after.total_minted, after.bump), (100, vault_state.bump));
+
+        let (sibling_address, sibling) = sibling_vault(&vault_state);
+        let result = run_transfer_between_vaults(
+            (vault_address, &vault_state),
+            (sibling_address, &sibling),
+            vault_state.authority,
+            300,
+        );
+        assert_eq!(result, (Ok(()), 700, 350));
+    }
+
+    #[test]
+    fn test_reward_mint_distinct_from_deposit_mint() {
+        let (_, vault, ..) = integrity_fixture();
+        assert_ne!(vault.reward_mint, vault.mint);
+
+        // Minting reward_mint into a reward_mint token account
+        assert!(check_reward_mint(&vault, &vault.reward_mint, &vault.reward_mint).is_ok());
+
+        // Destination holds the deposit mint, not the reward mint
+        let result = check_reward_mint(&vault, &vault.reward_mint, &vault.mint);
+        assert!(matches!(result, Err(TokenSecureError::MintMismatch)));
+
+        // Minting the deposit mint itself, even into a matching destination
+        let result = check_reward_mint(&vault, &vault.mint, &vault.mint);
+        assert!(matches!(result, Err(TokenSecureError::MintMismatch)));
+        let result = check_reward_mint(&vault, &vault.mint, &vault.reward_mint);
+        assert!(matches!(result, Err(TokenSecureError::MintMismatch)));
+    }
+
+    #[test]
+    fn test_cross_vault_deposit_record_rejected() {
+        let (vault_address, _, _, user_deposit, _) = integrity_fixture();
+        assert!(check_deposit_record_vault(&user_deposit, &vault_address).is_ok());
+
+        // Record from the vault of another mint, spliced into this vault's deposit
+        let (other_vault, _) = derive_vault_pda(&Address::new_from_array([8u8; 32]), &ID).unwrap();
+        let result = check_deposit_record_vault(&user_deposit, &other_vault);
+        assert!(matches!(result, Err(TokenSecureError::Unauthorized)));
+    }
+
+    #[test]
+    fn test_integrity_all_pass() {
+        let (va, v, uda, ud, vta) = integrity_fixture();
+        assert!(run_integrity(&va, &v, &uda, &ud, &vta, 1_000).is_ok());
+        // Surplus tokens (e.g. direct transfers) do not break solvency
+        assert!(run_integrity(&va, &v, &uda, &ud, &vta, 5_000).is_ok());
+    }
+
+    #[test]
+    fn test_integrity_invalid_vault_pda() {
+        let (_, v, uda, ud, vta) = integrity_fixture();
+        let fake_vault = Address::new_from_array([9u8; 32]);
+        let result = run_integrity(&fake_vault, &v, &uda, &ud, &vta, 1_000);
+        assert!(matches!(result, Err(TokenSecureError::InvalidVaultPda)));
+    }
+
+    #[test]
+    fn test_integrity_non_canonical_vault_bump() {
+        let (va, mut v, uda, ud, vta) = integrity_fixture();
+        v.bump = v.bump.wrapping_sub(1);
+        let result = run_integrity(&va, &v, &uda, &ud, &vta, 1_000);
+        assert!(matches!(result, Err(TokenSecureError::NonCanonicalBump)));
+    }
+
+    #[test]
+    fn test_integrity_vault_mismatch() {
+        let (va, v, uda, mut ud, vta) = integrity_fixture();
+        ud.vault = Address::new_from_array([9u8; 32]);
+        let result = run_integrity(&va, &v, &uda, &ud, &vta, 1_000);
+        assert!(matches!(result, Err(TokenSecureError::VaultMismatch)));
+    }
+
+    #[test]
+    fn test_integrity_invalid_user_deposit_pda() {
+        let (va, v, _, ud, vta) = integrity_fixture();
+        let fake_deposit = Address::new_from_array([9u8; 32]);
+        let result = run_integrity(&va, &v, &fake_deposit, &ud, &vta, 1_000);
+        assert!(matches!(result, Err(TokenSecureError::InvalidUserDepositPda)));
+    }
+
+    #[test]
+    fn test_integrity_non_canonical_user_deposit_bump() {
+        let (va, v, uda, mut ud, vta) = integrity_fixture();
+        ud.bump = ud.bump.wrapping_sub(1);
+        let result = run_integrity(&va, &v, &uda, &ud, &vta, 1_000);
+        assert!(matches!(result, Err(TokenSecureError::NonCanonicalBump)));
+    }
+
+    #[test]
+    fn test_integrity_vault_token_account_mismatch() {
+        let (va, v, uda, ud, _) = integrity_fixture();
+        let other_token_account = Address::new_from_array([9u8; 32]);
+        let result = run_integrity(&va, &v, &uda, &ud, &other_token_account, 1_000);
+        assert!(matches!(result, Err(TokenSecureError::VaultTokenAccountMismatch)));
+    }
+
+    #[test]
+    fn test_integrity_deposit_exceeds_vault_total() {
+        let (va, v, uda, mut ud, vta) = integrity_fixture();
+        ud.amount = v.total_deposits + 1;
+        let result = run_integrity(&va, &v, &uda, &ud, &vta, 1_000);
+        assert!(matches!(result, Err(TokenSecureError::DepositExceedsVaultTotal)));
+    }
+
+    #[test]
+    fn test_integrity_vault_undercollateralized() {
+        let (va, v, uda, ud, vta) = integrity_fixture();
+        let result = run_integrity(&va, &v, &uda, &ud, &vta, 999);
+        assert!(matches!(result, Err(TokenSecureError::VaultUndercollateralized)));
+    }
+
+    #[test]
+    fn test_parse_token_account_amount() {
+        let mut data = [0u8; 165];
+        data[64..72].copy_from_slice(&42u64.to_le_bytes());
+        assert_eq!(parse_token_account_amount(&data).unwrap(), 42);
+        assert!(parse_token_account_amount(&data[..71]).is_err());
+    }
+
+    /// Builds token account data with the given delegate and delegated amount.
+    fn delegated_token_account(delegate: Option<&Address>, delegated_amount: u64) -> [u8; 165] {
+        let mut data = [0u8; 165];
+        if let Some(delegate) = delegate {
+            data[72..76].copy_from_slice(&1u32.to_le_bytes());
+            data[76..108].copy_from_slice(delegate.as_ref());
+        }
+        data[121..129].copy_from_slice(&delegated_amount.to_le_bytes());
+        data
+    }
+
+    #[test]
+    fn test_parse_token_account_delegate() {
+        let delegate = Address::new_from_array([7u8; 32]);
+
+        let data = delegated_token_account(Some(&delegate), 500);
+        assert_eq!(parse_token_account_delegate(&data).unwrap(), Some(delegate));
+        assert_eq!(parse_delegated_amount(&data).unwrap(), 500);
+
+        let data = delegated_token_account(None, 0);
+        assert_eq!(parse_token_account_delegate(&data).unwrap(), None);
+
+        let mut data = delegated_token_account(None, 0);
+        data[72] = 2;
+        assert!(parse_token_account_delegate(&data).is_err());
+        assert!(parse_token_account_delegate(&data[..107]).is_err());
+        assert!(parse_delegated_amount(&data[..128]).is_err());
+    }
+
+    #[test]
+    fn test_delegated_deposit_sufficient_allowance() {
+        let delegate = Address::new_from_array([7u8; 32]);
+        let data = delegated_token_account(Some(&delegate), 500);
+        let approved = parse_token_account_delegate(&data).unwrap();
+        let allowance = parse_delegated_amount(&data).unwrap();
+
+        assert!(check_delegated_deposit(approved.as_ref(), allowance, &delegate, 500).is_ok());
+        assert!(check_delegated_deposit(approved.as_ref(), allowance, &delegate, 1).is_ok());
+    }
+
+    #[test]
+    fn test_delegated_deposit_insufficient_allowance() {
+        let delegate = Address::new_from_array([7u8; 32]);
+        let data = delegated_token_account(Some(&delegate), 500);
+        let approved = parse_token_account_delegate(&data).unwrap();
+        let allowance = parse_delegated_amount(&data).unwrap();
+
+        let result = check_delegated_deposit(approved.as_ref(), allowance, &delegate, 501);
+        assert!(matches!(result, Err(TokenSecureError::DelegatedAmountExceeded)));
+    }
+
+    #[test]
+    fn test_delegated_deposit_rejects_non_delegate() {
+        let delegate = Address::new_from_array([7u8; 32]);
+        let stranger = Address::new_from_array([8u8; 32]);
+
+        let result = check_delegated_deposit(Some(&delegate), 500, &stranger, 1);
+        assert!(matches!(result, Err(TokenSecureError::DelegateMismatch)));
+
+        let result = check_delegated_deposit(None, 500, &stranger, 1);
+        assert!(matches!(result, Err(TokenSecureError::DelegateMismatch)));
+    }
+
+    /// Runs `deposit_delegated` of `amount` into the integrity fixture's vault
+    /// held at `vault_address`, signed by `signer`, from the user's token
+    /// account holding `source_mint` and delegated to `[7; 32]` for 500.
+    /// Returns the result, the user's deposit and the vault total afterwards.
+    fn run_deposit_delegated(
+        vault_address: Address,
+        source_mint: &Address,
+        signer: Address,
+        amount: u64,
+    ) -> (ProgramResult, u64, u64) {
+        let (_, vault_state, _, user_deposit, _) = integrity_fixture();
+        let (user_deposit_address, _) =
+            derive_user_deposit_pda(&vault_address, &user_deposit.user, &ID).unwrap();
+        let mut vault_data = [0u8; VAULT_SIZE];
+        vault_state.serialize(&mut vault_data).unwrap();
+        let mut record_data = [0u8; USER_DEPOSIT_SIZE];
+        UserDeposit { vault: vault_address, ..user_deposit.clone() }
+            .serialize(&mut record_data)
+            .unwrap();
+        let mut source_data =
+            delegated_token_account(Some(&Address::new_from_array([7u8; 32])), 500);
+        source_data[0..32].copy_from_slice(source_mint.as_ref());
+        source_data[32..64].copy_from_slice(user_deposit.user.as_ref());
+
+        let mut vault = MockAccountView::new(vault_address, ID, 0, &vault_data).writable();
+        let mut record = MockAccountView::new(user_deposit_address, ID, 0, &record_data).writable();
+        let mut source = MockAccountView::new(
+            Address::new_from_array([5u8; 32]),
+            TOKEN_PROGRAM_ID,
+            0,
+            &source_data,
+        )
+        .writable();
+        let mut vault_token = MockAccountView::new(
+            vault_state.vault_token_account,
+            TOKEN_PROGRAM_ID,
+            0,
+            &token_account(&vault_state.mint, &vault_address),
+        )
+        .writable();
+        let mut delegate = MockAccountView::new(signer, Address::default(), 0, &[]).signer();
+        let mut token = MockAccountView::new(TOKEN_PROGRAM_ID, Address::default(), 0, &[]);
+
+        let result = deposit_delegated(
+            &ID,
+            &[
+                vault.view(),
+                record.view(),
+                source.view(),
+                vault_token.view(),
+                delegate.view(),
+                token.view(),
+            ],
+            &amount.to_le_bytes(),
+        );
+        (
+            result,
+            UserDeposit::try_from_slice(record.data()).unwrap().amount,
+            Vault::try_from_slice(vault.data()).unwrap().total_deposits,
+        )
+    }
+
+    #[test]
+    fn test_deposit_delegated_handler() {
+        let (vault_address, vault, _, _, _) = integrity_fixture();
+        let delegate = Address::new_from_array([7u8; 32]);
+        let unchanged = |error: ProgramError| (Err(error), 400, 1_000);
+
+        // Credited to the token owner's record, up to the allowance
+        let result = run_deposit_delegated(vault_address, &vault.mint, delegate, 500);
+        assert_eq!(result, (Ok(()), 900, 1_500));
+
+        // A signer the owner never approved
+        let stranger = Address::new_from_array([8u8; 32]);
+        let result = run_deposit_delegated(vault_address, &vault.mint, stranger, 100);
+        assert_eq!(result, unchanged(TokenSecureError::DelegateMismatch.into()));
+
+        // More than the owner approved
+        let result = run_deposit_delegated(vault_address, &vault.mint, delegate, 501);
+        assert_eq!(result, unchanged(TokenSecureError::DelegatedAmountExceeded.into()));
+
+        // A source account of another mint
+        let other_mint = Address::new_from_array([9u8; 32]);
+        let result = run_deposit_delegated(vault_address, &other_mint, delegate, 100);
+        assert_eq!(result, unchanged(TokenSecureError::MintMismatch.into()));
+
+        // Vault data copied to a program-owned account that is not the PDA
+        let result = run_deposit_delegated(stranger, &vault.mint, delegate, 100);
+        assert_eq!(result, unchanged(TokenSecureError::InvalidVaultPda.into()));
+    }
+
+    /// Runs `withdraw` of `amount` by the integrity fixture's user, returning
+    /// the result, the user's deposit and the vault total afterwards.
+    fn run_withdraw(amount: u64) -> (ProgramResult, u64, u64) {
+        run_withdraw_via(TOKEN_PROGRAM_ID, amount)
+    }
+
+    /// `run_withdraw` with the CPI sent to `token_program`.
+    fn run_withdraw_via(token_program: Address, amount: u64) -> (ProgramResult, u64, u64) {
+        let (vault_address, vault_state, user_deposit_address, user_deposit, _) =
+            integrity_fixture();
+        let mut vault_data = [0u8; VAULT_SIZE];
+        vault_state.serialize(&mut vault_data).unwrap();
+        let mut record_data = [0u8; USER_DEPOSIT_SIZE];
+        user_deposit.serialize(&mut record_data).unwrap();
+
+        let mut vault = MockAccountView::new(vault_address, ID, 0, &vault_data).writable();
+        let mut record = MockAccountView::new(user_deposit_address, ID, 0, &record_data).writable();
+        let mut vault_token = MockAccountView::new(
+            vault_state.vault_token_account,
+            TOKEN_PROGRAM_ID,
+            0,
+            &token_account(&vault_state.mint, &vault_address),
+        )
+        .writable();
+        let mut destination = MockAccountView::new(
+            Address::new_from_array([7u8; 32]),
+            TOKEN_PROGRAM_ID,
+            0,
+            &token_account(&vault_state.mint, &user_deposit.user),
+        )
+        .writable();
+        let mut user = MockAccountView::new(user_deposit.user, Address::default(), 0, &[]).signer();
+        let mut token = MockAccountView::new(token_program, Address::default(), 0, &[]);
+
+        let result = withdraw(
+            &ID,
+            &[
+                vault.view(),
+                record.view(),
+                vault_token.view(),
+                destination.view(),
+                user.view(),
+                token.view(),
+            ],
+            &amount.to_le_bytes(),
+        );
+        (
+            result,
+            UserDeposit::try_from_slice(record.data()).unwrap().amount,
+            Vault::try_from_slice(vault.data()).unwrap().total_deposits,
+        )
+    }
+
+    #[test]
+    fn test_withdraw_insufficient_balance_uses_common_code() {
+        let (result, amount, total) = run_withdraw(401);
+        assert_eq!(result, Err(ProgramError::Custom(0x2001)));
+        assert_eq!(result, Err(CommonError::InsufficientBalance.into()));
+
+        // Nothing is debited on rejection
+        assert_eq!((amount, total), (400, 1_000));
+        assert_eq!(run_withdraw(400), (Ok(()), 0, 600));
+    }
+
+    #[test]
+    fn test_withdraw_rejects_fake_token_program() {
+        let fake_program = Address::new_from_array([8u8; 32]);
+        let result = run_withdraw_via(fake_program, 400);
+        assert_eq!(result, (Err(ProgramError::IncorrectProgramId), 400, 1_000));
+    }
+
+    #[test]
+    fn test_burn_instruction_data_and_accounting() {
+        let data = spl_token_burn_data(300);
+        assert_eq!(data[0], 8);
+        assert_eq!(u64::from_le_bytes(data[1..9].try_into().unwrap()), 300);
+
+        // Burning debits the user and the vault total together
+        let (_, mut v, _, mut ud, _) = integrity_fixture();
+        apply_withdraw(&mut v, &mut ud, 300).unwrap();
+        assert_eq!((ud.amount, v.total_deposits), (100, 700));
+    }
+
+    /// Runs `burn_deposit` of `amount` against the integrity fixture, signed by
+    /// `user`, burning from `vault_token_address` through `token_program`.
+    /// Returns the result, the fixture user's deposit and the vault total.
+    fn run_burn_deposit(
+        user: Address,
+        vault_token_address: Address,
+        token_program: Address,
+        amount: u64,
+    ) -> (ProgramResult, u64, u64) {
+        let (vault_address, vault_state, user_deposit_address, user_deposit, _) =
+            integrity_fixture();
+        let mut vault_data = [0u8; VAULT_SIZE];
+        vault_state.serialize(&mut vault_data).unwrap();
+        let mut record_data = [0u8; USER_DEPOSIT_SIZE];
+        user_deposit.serialize(&mut record_data).unwrap();
+
+        let mut vault = MockAccountView::new(vault_address, ID, 0, &vault_data).writable();
+        let mut record = MockAccountView::new(user_deposit_address, ID, 0, &record_data).writable();
+        let mut vault_token = MockAccountView::new(
+            vault_token_address,
+            TOKEN_PROGRAM_ID,
+            0,
+            &token_account(&vault_state.mint, &vault_address),
+        )
+        .writable();
+        let mut mint =
+            MockAccountView::new(vault_state.mint, TOKEN_PROGRAM_ID, 0, &[0u8; SPL_MINT_LEN])
+                .writable();
+        let mut signer = MockAccountView::new(user, Address::default(), 0, &[]).signer();
+        let mut token = MockAccountView::new(token_program, Address::default(), 0, &[]);
+
+        let result = burn_deposit(
+            &ID,
+            &[
+                vault.view(),
+                record.view(),
+                vault_token.view(),
+                mint.view(),
+                signer.view(),
+                token.view(),
+            ],
+            &amount.to_le_bytes(),
+        );
+        (
+            result,
+            UserDeposit::try_from_slice(record.data()).unwrap().amount,
+            Vault::try_from_slice(vault.data()).unwrap().total_deposits,
+        )
+    }
+
+    #[test]
+    fn test_burn_deposit_handler() {
+        let (_, vault, _, user_deposit, vault_token) = integrity_fixture();
+        let user = user_deposit.user;
+        let unchanged = |error: ProgramError| (Err(error), 400, 1_000);
+
+        let result = run_burn_deposit(user, vault_token, TOKEN_PROGRAM_ID, 300);
+        assert_eq!(result, (Ok(()), 100, 700));
+
+        // A program that would accept the vault's signature and burn nothing
+        let fake_program = Address::new_from_array([8u8; 32]);
+        let result = run_burn_deposit(user, vault_token, fake_program, 300);
+        assert_eq!(result, unchanged(ProgramError::IncorrectProgramId));
+
+        // Another token account of the vault's mint
+        let other_token = Address::new_from_array([9u8; 32]);
+        let result = run_burn_deposit(user, other_token, TOKEN_PROGRAM_ID, 300);
+        assert_eq!(result, unchanged(TokenSecureError::VaultTokenAccountMismatch.into()));
+
+        // Someone else signing for the user's deposit
+        let stranger = vault.authority;
+        let result = run_burn_deposit(stranger, vault_token, TOKEN_PROGRAM_ID, 300);
+        assert_eq!(result, unchanged(TokenSecureError::InvalidUserDepositPda.into()));
+    }
+
+    /// Runs `freeze_user_account` (or thaw) on a token account of the fixture
+    /// vault's mint, signed by `authority`, through `token_program`.
+    fn run_set_frozen(freeze: bool, authority: Address, token_program: Address) -> ProgramResult {
+        let (vault_address, vault_state, _, user_deposit, _) = integrity_fixture();
+        let mut vault_data = [0u8; VAULT_SIZE];
+        vault_state.serialize(&mut vault_data).unwrap();
+
+        let mut vault = MockAccountView::new(vault_address, ID, 0, &vault_data);
+        let mut mint =
+            MockAccountView::new(vault_state.mint, TOKEN_PROGRAM_ID, 0, &[0u8; SPL_MINT_LEN]);
+        let mut target = MockAccountView::new(
+            Address::new_from_array([7u8; 32]),
+            TOKEN_PROGRAM_ID,
+            0,
+            &token_account(&vault_state.mint, &user_deposit.user),
+        )
+        .writable();
+        let mut signer = MockAccountView::new(authority, Address::default(), 0, &[]).signer();
+        let mut token = MockAccountView::new(token_program, Address::default(), 0, &[]);
+
+        let accounts = [vault.view(), mint.view(), target.view(), signer.view(), token.view()];
+        if freeze {
+            freeze_user_account(&ID, &accounts, &[])
+        } else {
+            thaw_user_account(&ID, &accounts, &[])
+        }
+    }
+
+    #[test]
+    fn test_freeze_and_thaw_handlers() {
+        let (_, vault, _, user_deposit, _) = integrity_fixture();
+        let fake_program = Address::new_from_array([8u8; 32]);
+
+        for freeze in [true, false] {
+            assert_eq!(run_set_frozen(freeze, vault.authority, TOKEN_PROGRAM_ID), Ok(()));
+
+            // Only the vault authority may freeze or thaw
+            assert_eq!(
+                run_set_frozen(freeze, user_deposit.user, TOKEN_PROGRAM_ID),
+                Err(TokenSecureError::Unauthorized.into())
+            );
+
+            // The vault PDA never signs for another program
+            assert_eq!(
+                run_set_frozen(freeze, vault.authority, fake_program),
+                Err(ProgramError::IncorrectProgramId)
+            );
+        }
+    }
+
+    #[test]
+    fn test_freeze_and_thaw_instruction_data() {
+        assert_eq!(spl_token_freeze_account_data(), [10]);
+        assert_eq!(spl_token_thaw_account_data(), [11]);
+
+        // Neither program-level instruction carries a payload
+        assert_eq!(expected_data_len(FREEZE_USER_ACCOUNT_DISCRIMINATOR), Some(0));
+        assert_eq!(expected_data_len(THAW_USER_ACCOUNT_DISCRIMINATOR), Some(0));
+    }
+
+    #[test]
+    fn test_strict_ix_len_exact_short_and_overlong() {
+        let expected = expected_data_len(DEPOSIT_DISCRIMINATOR).unwrap();
+        assert_eq!(expected, 10);
+
+        assert!(check_data_len(&[0u8; 10], expected, true).is_ok());
+        assert_eq!(
+            check_data_len(&[0u8; 9], expected, true),
+            Err(ProgramError::InvalidInstructionData)
+        );
+        assert_eq!(
+            check_data_len(&[0u8; 11], expected, true),
+            Err(ProgramError::InvalidInstructionData)
+        );
+        assert_eq!(
+            check_data_len(&[0u8; 1024], expected, true),
+            Err(ProgramError::InvalidInstructionData)
+        );
+    }
+
+    #[test]
+    fn test_lenient_ix_len_ignores_trailing_bytes() {
+        let expected = expected_data_len(DEPOSIT_DISCRIMINATOR).unwrap();
+        assert!(check_data_len(&[0u8; 10], expected, false).is_ok());
+        assert!(check_data_len(&[0u8; 1024], expected, false).is_ok());
+
+        // Unknown discriminators have no expected length
+        assert_eq!(expected_data_len(0xff), None);
+    }
+
+    #[test]
+    fn test_deposit_args_v0() {
+        let mut data = [IX_FORMAT_V0; 10];
+        data[1..9].copy_from_slice(&1_234u64.to_le_bytes());
+        data[9] = 253;
+        assert_eq!(
+            DepositArgs::parse(&data),
+            Ok(DepositArgs { amount: 1_234, user_deposit_bump: 253, nonce: None })
+        );
+
+        // v0 still needs its full payload
+        assert_eq!(DepositArgs::parse(&data[..9]), Err(ProgramError::InvalidInstructionData));
+        assert_eq!(DepositArgs::parse(&[]), Err(ProgramError::InvalidInstructionData));
+    }
+
+    #[test]
+    fn test_deposit_args_v1_carries_nonce() {
+        let data = deposit_v1_data(1_234, 7);
+        assert_eq!(
+            DepositArgs::parse(&data),
+            Ok(DepositArgs { amount: 1_234, user_deposit_bump: 0, nonce: Some(7) })
+        );
+
+        // A v1 payload without its nonce is rejected, not read as v0
+        assert_eq!(DepositArgs::parse(&data[..10]), Err(ProgramError::InvalidInstructionData));
+
+        assert_eq!(expected_deposit_data_len(IX_FORMAT_V0), Some(10));
+        assert_eq!(expected_deposit_data_len(IX_FORMAT_V1), Some(data.len()));
+        assert_eq!(expected_deposit_data_len(IX_FORMAT_V1 + 1), None);
+    }
+
+    #[test]
+    fn test_deposit_args_unknown_version_rejected() {
+        let mut data = [0u8; 18];
+        data[1..9].copy_from_slice(&1_234u64.to_le_bytes());
+        for version in IX_FORMAT_V1 + 1..=u8::MAX {
+            data[0] = version;
+            assert_eq!(DepositArgs::parse(&data), Err(ProgramError::InvalidInstructionData));
+        }
+    }
+
+    #[test]
+    fn test_rent_exempt_minimum_matches_cluster_default() {
+        // Same value `getMinimumBalanceForRentExemption(81)` returns on a default cluster
+        assert_eq!(rent_exempt_minimum(USER_DEPOSIT_SIZE).unwrap(), 1_454_640);
+        assert_eq!(minimum_balance(3_480, 2.0, 0), 890_880);
+    }
+
+    /// Runs `create_user_deposit` for user `[2; 32]` in vault `[1; 32]`, with
+    /// the PDA holding `lamports` and `system_program` as the program account.
+    /// Returns the result and the PDA afterwards.
+    fn run_create_user_deposit(
+        lamports: u64,
+        system_program: Address,
+    ) -> (ProgramResult, MockAccountView) {
+        let vault_key = Address::new_from_array([1u8; 32]);
+        let user_key = Address::new_from_array([2u8; 32]);
+        let (deposit_pda, _) = derive_user_deposit_pda(&vault_key, &user_key, &ID).unwrap();
+
+        // Allocate does not run off-chain, so the mock starts at full size
+        let mut user_deposit = MockAccountView::new(
+            deposit_pda,
+            SYSTEM_PROGRAM_ID,
+            lamports,
+            &[0u8; USER_DEPOSIT_SIZE],
+        )
+        .writable();
+        let mut vault = MockAccountView::new(vault_key, ID, 0, &[0u8; VAULT_SIZE]);
+        let mut user =
+            MockAccountView::new(user_key, SYSTEM_PROGRAM_ID, 10_000_000, &[]).signer().writable();
+        let mut system = MockAccountView::new(system_program, Address::default(), 0, &[]);
+
+        let result = create_user_deposit(
+            &ID,
+            &[user_deposit.view(), vault.view(), user.view(), system.view()],
+            &[],
+        );
+        (result, user_deposit)
+    }
+
+    #[test]
+    fn test_create_user_deposit_from_any_starting_balance() {
+        let minimum = rent_exempt_minimum(USER_DEPOSIT_SIZE).unwrap();
+        let vault_key = Address::new_from_array([1u8; 32]);
+        let user_key = Address::new_from_array([2u8; 32]);
+        let (_, bump) = derive_user_deposit_pda(&vault_key, &user_key, &ID).unwrap();
+
+        // Empty (CreateAccount), partially pre-funded (top-up) and fully
+        // pre-funded (no transfer) PDAs are all created
+        for lamports in [0, minimum / 2, minimum, minimum + 1] {
+            let (result, user_deposit) = run_create_user_deposit(lamports, SYSTEM_PROGRAM_ID);
+            assert_eq!(result, Ok(()), "rejected from {} lamports", lamports);
+
+            let record = UserDeposit::try_from_slice(user_deposit.data()).unwrap();
+            assert_eq!(
+                record,
+                UserDeposit { user: user_key, vault: vault_key, amount: 0, bump, last_nonce: 0 }
+            );
+        }
+
+        // The funding CPIs must reach the real System Program
+        let fake_system = Address::new_from_array([9u8; 32]);
+        let (result, user_deposit) = run_create_user_deposit(0, fake_system);
+        assert_eq!(result, Err(ProgramError::IncorrectProgramId));
+        assert_eq!(user_deposit.data(), &[0u8; USER_DEPOSIT_SIZE][..]);
+    }
+
+    #[test]
+    fn test_rotation_allowed_when_old_account_empty() {
+        let (va, v, _, _, vta) = integrity_fixture();
+        let result = check_vault_token_account_rotation(&va, &v, &vta, 0, &v.mint, &va);
+        assert!(result.is_ok());
+    }
+
+    #[test]
+    fn test_rotation_rejected_when_old_account_not_empty() {
+        let (va, v, _, _, vta) = integrity_fixture();
+        let result = check_vault_token_account_rotation(&va, &v, &vta, 1, &v.mint, &va);
+        assert!(matches!(result, Err(TokenSecureError::VaultTokenAccountNotEmpty)));
+    }
+
+    #[test]
+    fn test_rotation_rejects_invalid_accounts() {
+        let (va, v, _, _, vta) = integrity_fixture();
+        let other = Address::new_from_array([9u8; 32]);
+
+        let result = check_vault_token_account_rotation(&va, &v, &other, 0, &v.mint, &va);
+        assert!(matches!(result, Err(TokenSecureError::VaultTokenAccountMismatch)));
+
+        let result = check_vault_token_account_rotation(&va, &v, &vta, 0, &other, &va);
+        assert!(matches!(result, Err(TokenSecureError::MintMismatch)));
+
+        let result = check_vault_token_account_rotation(&va, &v, &vta, 0, &v.mint, &other);
+        assert!(matches!(result, Err(TokenSecureError::OwnerMismatch)));
+    }
+
+    /// Runs `rotate_vault_token_account` on the integrity fixture's vault held
+    /// at `vault_address`, signed by `signer`, with a replacement token
+    /// account holding `new_mint` and owned by `new_owner`. Returns the result
+    /// and the recorded vault token account afterwards.
+    fn run_rotate(
+        vault_address: Address,
+        signer: Address,
+        new_mint: &Address,
+        new_owner: &Address,
+    ) -> (ProgramResult, Address) {
+        let (_, vault_state, _, _, vault_token_address) = integrity_fixture();
+        let mut vault_data = [0u8; VAULT_SIZE];
+        vault_state.serialize(&mut vault_data).unwrap();
+
+        let mut vault = MockAccountView::new(vault_address, ID, 0, &vault_data).writable();
+        let mut authority = MockAccountView::new(signer, Address::default(), 0, &[]).signer();
+        let mut old_token = MockAccountView::new(
+            vault_token_address,
+            TOKEN_PROGRAM_ID,
+            0,
+            &token_account(&vault_state.mint, &vault_address),
+        );
+        let mut new_token = MockAccountView::new(
+            Address::new_from_array([9u8; 32]),
+            TOKEN_PROGRAM_ID,
+            0,
+            &token_account(new_mint, new_owner),
+        );
+
+        let result = rotate_vault_token_account(
+            &ID,
+            &[vault.view(), authority.view(), old_token.view(), new_token.view()],
+            &[],
+        );
+        (result, Vault::try_from_slice(vault.data()).unwrap().vault_token_account)
+    }
+
+    #[test]
+    fn test_rotate_vault_token_account_handler() {
+        let (vault_address, vault, _, _, vault_token_address) = integrity_fixture();
+        let unchanged = |error: ProgramError| (Err(error), vault_token_address);
+
+        let result = run_rotate(vault_address, vault.authority, &vault.mint, &vault_address);
+        assert_eq!(result, (Ok(()), Address::new_from_array([9u8; 32])));
+
+        // Replacement owned by someone other than the vault PDA
+        let attacker = Address::new_from_array([8u8; 32]);
+        let result = run_rotate(vault_address, vault.authority, &vault.mint, &attacker);
+        assert_eq!(result, unchanged(TokenSecureError::OwnerMismatch.into()));
+
+        // Replacement holding another mint
+        let other_mint = Address::new_from_array([7u8; 32]);
+        let result = run_rotate(vault_address, vault.authority, &other_mint, &vault_address);
+        assert_eq!(result, unchanged(TokenSecureError::MintMismatch.into()));
+
+        // Signed by someone other than the vault authority
+        let result = run_rotate(vault_address, attacker, &vault.mint, &vault_address);
+        assert_eq!(result, unchanged(TokenSecureError::Unauthorized.into()));
+
+        // Vault data copied to a program-owned account that is not the PDA
+        let result = run_rotate(attacker, vault.authority, &vault.mint, &attacker);
+        assert_eq!(result, unchanged(TokenSecureError::InvalidVaultPda.into()));
+    }
+
+    #[test]
+    fn test_mint_reward_tracks_total_minted() {
+        let (vault_address, vault_state, _, _, _) = integrity_fixture();
+        let mut vault_data = [0u8; VAULT_SIZE];
+        vault_state.serialize(&mut vault_data).unwrap();
+
+        let mut vault = MockAccountView::new(vault_address, ID, 0, &vault_data).writable();
+        let mut mint =
+            MockAccountView::new(vault_state.reward_mint, TOKEN_PROGRAM_ID, 0, &[]).writable();
+        let mut destination = MockAccountView::new(
+            Address::new_from_array([7u8; 32]),
+            TOKEN_PROGRAM_ID,
+            0,
+            &token_account(&vault_state.reward_mint, &Address::new_from_array([4u8; 32])),
+        )
+        .writable();
+        let mut authority =
+            MockAccountView::new(vault_state.authority, Address::default(), 0, &[]).signer();
+        let mut token = MockAccountView::new(TOKEN_PROGRAM_ID, Address::default(), 0, &[]);
+
+        for (amount, expected_total) in [(100u64, 100u64), (250, 350), (50, 400)] {
+            let accounts =
+                [vault.view(), mint.view(), destination.view(), authority.view(), token.view()];
+            assert!(mint_reward(&ID, &accounts, &amount.to_le_bytes()).is_ok());
+            let total_minted = Vault::try_from_slice(vault.data()).unwrap().total_minted;
+            assert_eq!(total_minted, expected_total);
+        }
+
+        // An overflowing total is rejected and leaves the running total intact
+        let accounts =
+            [vault.view(), mint.view(), destination.view(), authority.view(), token.view()];
+        let result = mint_reward(&ID, &accounts, &u64::MAX.to_le_bytes());
+        assert_eq!(result, Err(TokenSecureError::ArithmeticOverflow.into()));
+        assert_eq!(Vault::try_from_slice(vault.data()).unwrap().total_minted, 400);
+    }
+
+    fn mint_batch_data(amounts: &[u64]) -> Vec<u8> {
+        let mut data = vec![MINT_REWARD_BATCH_DISCRIMINATOR];
+        for amount in amounts {
+            data.extend_from_slice(&amount.to_le_bytes());
+        }
+        data
+    }
+
+    #[test]
+    fn test_mint_reward_batch_rejects_over_cap_element() {
+        let (vault_address, vault_state, _, _, _) = integrity_fixture();
+        let mut vault_data = [0u8; VAULT_SIZE];
+        vault_state.serialize(&mut vault_data).unwrap();
+
+        let mut vault = MockAccountView::new(vault_address, ID, 0, &vault_data).writable();
+        let mut mint =
+            MockAccountView::new(vault_state.reward_mint, TOKEN_PROGRAM_ID, 0, &[]).writable();
+        let mut authority =
+            MockAccountView::new(vault_state.authority, Address::default(), 0, &[]).signer();
+        let mut token = MockAccountView::new(TOKEN_PROGRAM_ID, Address::default(), 0, &[]);
+        let mut fake_token =
+            MockAccountView::new(Address::new_from_array([66u8; 32]), Address::default(), 0, &[]);
+        let mut destinations: Vec<MockAccountView> = (0..4u8)
+            .map(|i| {
+                MockAccountView::new(
+                    Address::new_from_array([20 + i; 32]),
+                    TOKEN_PROGRAM_ID,
+                    0,
+                    &token_account(
+                        &vault_state.reward_mint,
+                        &Address::new_from_array([30 + i; 32]),
+                    ),
+                )
+                .writable()
+            })
+            .collect();
+
+        let mut run = |token: &mut MockAccountView, amounts: &[u64]| {
+            let mut accounts = vec![vault.view(), mint.view(), authority.view(), token.view()];
+            accounts.extend(destinations.iter_mut().map(|d| d.view()));
+            let result = process_instruction(&ID, &accounts, &mint_batch_data(amounts));
+            (result, Vault::try_from_slice(vault.data()).unwrap().total_minted)
+        };
+
+        // The vault PDA never signs for a program other than SPL Token
+        let (result, total) = run(&mut fake_token, &[100, 200, 300, 400]);
+        assert_eq!(result, Err(ProgramError::IncorrectProgramId));
+        assert_eq!(total, 0);
+
+        // One over-cap element among valid ones: nothing is minted
+        let (result, total) = run(&mut token, &[100, 200, MAX_REWARD_PER_MINT + 1, 300]);
+        assert_eq!(result, Err(TokenSecureError::PerMintCapExceeded.into()));
+        assert_eq!(total, 0);
+
+        // Exactly at the cap is allowed
+        let (result, total) = run(&mut token, &[100, 200, MAX_REWARD_PER_MINT, 300]);
+        assert_eq!(result, Ok(()));
+        assert_eq!(total, MAX_REWARD_PER_MINT + 600);
+
+        // One amount per destination, no more and no less
+        let (result, _) = run(&mut token, &[100, 200, 300]);
+        assert_eq!(result, Err(ProgramError::InvalidInstructionData));
+    }
+
+    /// A second vault of the fixture's mint and authority, derived from its
+    /// own seed mint.
+    fn sibling_vault(vault: &Vault) -> (Address, Vault) {
+        let seed_mint = Address::new_from_array([12u8; 32]);
+        let (address, bump) = derive_vault_pda(&seed_mint, &ID).unwrap();
+        let sibling = Vault {
+            vault_token_account: Address::new_from_array([13u8; 32]),
+            total_deposits: 50,
+            bump,
+            seed_mint,
+            ..vault.clone()
+        };
+        (address, sibling)
+    }
+
+    /// Runs `transfer_between_vaults` signed by `signer`, returning the
+    /// result and both vaults' `total_deposits` afterwards.
+    fn run_transfer_between_vaults(
+        source: (Address, &Vault),
+        destination: (Address, &Vault),
+        signer: Address,
+        amount: u64,
+    ) -> (ProgramResult, u64, u64) {
+        run_transfer_between_vaults_via(TOKEN_PROGRAM_ID, source, destination, signer, amount)
+    }
+
+    /// `run_transfer_between_vaults` with the CPI sent to `token_program`.
+    fn run_transfer_between_vaults_via(
+        token_program: Address,
+        (source_address, source): (Address, &Vault),
+        (destination_address, destination): (Address, &Vault),
+        signer: Address,
+        amount: u64,
+    ) -> (ProgramResult, u64, u64) {
+        let mut source_data = [0u8; VAULT_SIZE];
+        source.serialize(&mut source_data).unwrap();
+        let mut destination_data = [0u8; VAULT_SIZE];
+        destination.serialize(&mut destination_data).unwrap();
+
+        let mut source_vault = MockAccountView::new(source_address, ID, 0, &source_data).writable();
+        let mut destination_vault =
+            MockAccountView::new(destination_address, ID, 0, &destination_data).writable();
+        let mut source_token = MockAccountView::new(
+            source.vault_token_account,
+            TOKEN_PROGRAM_ID,
+            0,
+            &token_account(&source.mint, &source_address),
+        )
+        .writable();
+        let mut destination_token = MockAccountView::new(
+            destination.vault_token_account,
+            TOKEN_PROGRAM_ID,
+            0,
+            &token_account(&destination.mint, &destination_address),
+        )
+        .writable();
+        let mut authority = MockAccountView::new(signer, Address::default(), 0, &[]).signer();
+        let mut token = MockAccountView::new(token_program, Address::default(), 0, &[]);
+
+        let mut data = [TRANSFER_BETWEEN_VAULTS_DISCRIMINATOR; 9];
+        data[1..9].copy_from_slice(&amount.to_le_bytes());
+        let result = process_instruction(
+            &ID,
+            &[
+                source_vault.view(),
+                destination_vault.view(),
+                source_token.view(),
+                destination_token.view(),
+                authority.view(),
+                token.view(),
+            ],
+            &data,
+        );
+        (
+            result,
+            Vault::try_from_slice(source_vault.data()).unwrap().total_deposits,
+            Vault::try_from_slice(destination_vault.data()).unwrap().total_deposits,
+        )
+    }
+
+    #[test]
+    fn test_transfer_between_vaults_moves_deposits() {
+        let (vault_address, vault, _, _, _) = integrity_fixture();
+        let (sibling_address, sibling) = sibling_vault(&vault);
+
+        let result = run_transfer_between_vaults(
+            (vault_address, &vault),
+            (sibling_address, &sibling),
+            vault.authority,
+            300,
+        );
+        assert_eq!(result, (Ok(()), 700, 350));
+
+        // Not more than the source vault owes its depositors
+        let result = run_transfer_between_vaults(
+            (vault_address, &vault),
+            (sibling_address, &sibling),
+            vault.authority,
+            1_001,
+        );
+        assert_eq!(result, (Err(TokenSecureError::DepositExceedsVaultTotal.into()), 1_000, 50));
+    }
+
+    #[test]
+    fn test_transfer_between_vaults_rejections() {
+        let (vault_address, vault, _, _, _) = integrity_fixture();
+        let (sibling_address, sibling) = sibling_vault(&vault);
+        let unchanged = |error: ProgramError| (Err(error), 1_000, 50);
+
+        let other_mint = Vault { mint: Address::new_from_array([14u8; 32]), ..sibling.clone() };
+        let result = run_transfer_between_vaults(
+            (vault_address, &vault),
+            (sibling_address, &other_mint),
+            vault.authority,
+            300,
+        );
+        assert_eq!(result, unchanged(TokenSecureError::MintMismatch.into()));
+
+        // A signer that runs only one of the vaults
+        let other_authority = Address::new_from_array([15u8; 32]);
+        let foreign = Vault { authority: other_authority, ..sibling.clone() };
+        let result = run_transfer_between_vaults(
+            (vault_address, &vault),
+            (sibling_address, &foreign),
+            other_authority,
+            300,
+        );
+        assert_eq!(result, unchanged(TokenSecureError::Unauthorized.into()));
+        let result = run_transfer_between_vaults(
+            (vault_address, &vault),
+            (sibling_address, &foreign),
+            vault.authority,
+            300,
+        );
+        assert_eq!(result, unchanged(TokenSecureError::Unauthorized.into()));
+
+        // The source vault PDA never signs for a program other than SPL Token
+        let result = run_transfer_between_vaults_via(
+            Address::new_from_array([8u8; 32]),
+            (vault_address, &vault),
+            (sibling_address, &sibling),
+            vault.authority,
+            300,
+        );
+        assert_eq!(result, unchanged(ProgramError::IncorrectProgramId));
+
+        // The same vault on both sides
+        let (result, total, _) = run_transfer_between_vaults(
+            (vault_address, &vault),
+            (vault_address, &vault),
+            vault.authority,
+            300,
+        );
+        assert_eq!((result, total), (Err(ProgramError::InvalidArgument), 1_000));
+    }
+
+    /// Runs `reconcile` on the integrity fixture's vault, signed by `signer`,
+    /// with the vault token account holding `token_balance` when given.
+    fn run_reconcile(
+        signer: Address,
+        expected_sum: u64,
+        token_balance: Option<u64>,
+    ) -> ProgramResult {
+        let (vault_address, vault_state, _, _, vault_token_address) = integrity_fixture();
+        let mut vault_data = [0u8; VAULT_SIZE];
+        vault_state.serialize(&mut vault_data).unwrap();
+
+        let mut vault = MockAccountView::new(vault_address, ID, 0, &vault_data);
+        let mut authority = MockAccountView::new(signer, Address::default(), 0, &[]).signer();
+        let mut token_data = token_account(&vault_state.mint, &vault_address);
+        token_data[64..72].copy_from_slice(&token_balance.unwrap_or(0).to_le_bytes());
+        let mut vault_token =
+            MockAccountView::new(vault_token_address, TOKEN_PROGRAM_ID, 0, &token_data);
+
+        let mut data = [RECONCILE_DISCRIMINATOR; 9];
+        data[1..9].copy_from_slice(&expected_sum.to_le_bytes());
+        let result = match token_balance {
+            Some(_) => process_instruction(
+                &ID,
+                &[vault.view(), authority.view(), vault_token.view()],
+                &data,
+            ),
+            None => process_instruction(&ID, &[vault.view(), authority.view()], &data),
+        };
+
+        // Reconciliation is read-only
+        assert_eq!(vault.data(), &vault_data[..]);
+        result
+    }
+
+    #[test]
+    fn test_reconcile_matching_sum() {
+        let (_, vault, _, _, _) = integrity_fixture();
+
+        assert_eq!(run_reconcile(vault.authority, 1_000, None), Ok(()));
+        assert_eq!(run_reconcile(vault.authority, 1_000, Some(1_200)), Ok(()));
+    }
+
+    #[test]
+    fn test_reconcile_mismatching_sum() {
+        let (_, vault, _, _, _) = integrity_fixture();
+        let mismatch = Err(TokenSecureError::ReconciliationMismatch.into());
+
+        assert_eq!(run_reconcile(vault.authority, 999, None), mismatch);
+        assert_eq!(run_reconcile(vault.authority, 1_001, Some(1_200)), mismatch);
+
+        // Only the vault authority may reconcile
+        let intruder = Address::new_from_array([8u8; 32]);
+        assert_eq!(
+            run_reconcile(intruder, 1_000, None),
+            Err(TokenSecureError::Unauthorized.into())
+        );
+    }
+
+    // =========================================================================
+    // PAUSE POLICY
+    // =========================================================================
+
+    /// Token account data with the given mint and owner.
+    fn token_account(mint: &Address, owner: &Address) -> [u8; 165] {
+        let mut data = [0u8; 165];
+        data[0..32].copy_from_slice(mint.as_ref());
+        data[32..64].copy_from_slice(owner.as_ref());
+        data
+    }
+
+    fn paused_fixture() -> (Address, [u8; VAULT_SIZE], UserDeposit) {
+        let (vault_address, mut vault, _, user_deposit, _) = integrity_fixture();
+        vault.paused = true;
+        let mut vault_data = [0u8; VAULT_SIZE];
+        vault.serialize(&mut vault_data).unwrap();
+        (vault_address, vault_data, user_deposit)
+    }
+
+    #[test]
+    fn test_paused_vault_blocks_deposits() {
+        let (vault_address, vault_data, _) = paused_fixture();
+        let vault_state = Vault::try_from_slice(&vault_data).unwrap();
+        assert!(matches!(assert_not_paused(&vault_state), Err(TokenSecureError::VaultPaused)));
+
+        let fee_data = token_account(&vault_state.mint, &vault_state.authority);
+        let mut accounts =
+            deposit_accounts(vault_address, &vault_state, &[0u8; USER_DEPOSIT_SIZE], &fee_data);
+
+        let mut data = [IX_FORMAT_V0; 10];
+        data[1..9].copy_from_slice(&100u64.to_le_bytes());
+        let result = run_deposit_accounts(&mut accounts, &data);
+        assert_eq!(result, Err(TokenSecureError::VaultPaused.into()));
+        assert_eq!(accounts[0].data(), &vault_data[..]);
+        assert_eq!(accounts[1].data(), &[0u8; USER_DEPOSIT_SIZE][..]);
+    }
+
+    #[test]
+    fn test_paused_vault_rejects_deposit_before_reading_user_accounts() {
+        let (vault_address, vault_data, _) = paused_fixture();
+        let vault_state = Vault::try_from_slice(&vault_data).unwrap();
+        let fee_data = token_account(&vault_state.mint, &vault_state.authority);
+        let mut accounts =
+            deposit_accounts(vault_address, &vault_state, &[0u8; USER_DEPOSIT_SIZE], &fee_data);
+
+        // Any borrow of the record, source, vault token or fee account fails
+        // with AccountBorrowFailed instead of VaultPaused
+        for index in [1, 2, 3, 7] {
+            accounts[index] = accounts[index].clone().untouchable();
+        }
+
+        let mut data = [IX_FORMAT_V0; 10];
+        data[1..9].copy_from_slice(&100u64.to_le_bytes());
+        let result = run_deposit_accounts(&mut accounts, &data);
+        assert_eq!(result, Err(TokenSecureError::VaultPaused.into()));
+        assert_eq!(accounts[0].data(), &vault_data[..]);
+    }
+
+    #[test]
+    fn test_paused_vault_still_allows_withdraw() {
+        let (vault_address, vault_data, user_deposit) = paused_fixture();
+        let vault_state = Vault::try_from_slice(&vault_data).unwrap();
+        let mut record_data = [0u8; USER_DEPOSIT_SIZE];
+        user_deposit.serialize(&mut record_data).unwrap();
+
+        let mut vault = MockAccountView::new(vault_address, ID, 0, &vault_data).writable();
+        let mut record =
+            MockAccountView::new(Address::new_from_array([5u8; 32]), ID, 0, &record_data)
+                .writable();
+        let mut vault_token = MockAccountView::new(
+            vault_state.vault_token_account,
+            TOKEN_PROGRAM_ID,
+            0,
+            &token_account(&vault_state.mint, &vault_address),
+        )
+        .writable();
+        let mut destination = MockAccountView::new(
+            Address::new_from_array([7u8; 32]),
+            TOKEN_PROGRAM_ID,
+            0,
+            &token_account(&vault_state.mint, &user_deposit.user),
+        )
+        .writable();
+        let mut user = MockAccountView::new(user_deposit.user, Address::default(), 0, &[]).signer();
+        let mut token = MockAccountView::new(TOKEN_PROGRAM_ID, Address::default(), 0, &[]);
+
+        let result = withdraw(
+            &ID,
+            &[
+                vault.view(),
+                record.view(),
+                vault_token.view(),
+                destination.view(),
+                user.view(),
+                token.view(),
+            ],
+            &150u64.to_le_bytes(),
+        );
+        assert!(result.is_ok());
+
+        let vault_after = Vault::try_from_slice(vault.data()).unwrap();
+        let record_after = UserDeposit::try_from_slice(record.data()).unwrap();
+        assert_eq!((record_after.amount, vault_after.total_deposits), (250, 850));
+        assert!(vault_after.paused);
+    }
+
+    #[cfg(feature = "derive_bump_on_demand")]
+    #[test]
+    fn test_withdraw_signs_without_stored_bump() {
+        let (vault_address, mut vault_state, _, user_deposit, _) = integrity_fixture();
+        vault_state.bump = 0;
+        let mut vault_data = [0u8; VAULT_SIZE];
+        vault_state.serialize(&mut vault_data).unwrap();
+        let mut record_data = [0u8; USER_DEPOSIT_SIZE];
+        user_deposit.serialize(&mut record_data).unwrap();
+
+        let mut vault = MockAccountView::new(vault_address, ID, 0, &vault_data).writable();
+        let mut record =
+            MockAccountView::new(Address::new_from_array([5u8; 32]), ID, 0, &record_data)
+                .writable();
+        let mut vault_token = MockAccountView::new(
+            vault_state.vault_token_account,
+            TOKEN_PROGRAM_ID,
+            0,
+            &token_account(&vault_state.mint, &vault_address),
+        )
+        .writable();
+        let mut destination = MockAccountView::new(
+            Address::new_from_array([7u8; 32]),
+            TOKEN_PROGRAM_ID,
+            0,
+            &token_account(&vault_state.mint, &user_deposit.user),
+        )
+        .writable();
+        let mut user = MockAccountView::new(user_deposit.user, Address::default(), 0, &[]).signer();
+        let mut token = MockAccountView::new(TOKEN_PROGRAM_ID, Address::default(), 0, &[]);
+        let accounts = [
+            vault.view(),
+            record.view(),
+            vault_token.view(),
+            destination.view(),
+            user.view(),
+            token.view(),
+        ];
+
+        assert_eq!(withdraw(&ID, &accounts, &150u64.to_le_bytes()), Ok(()));
+        let vault_after = Vault::try_from_slice(vault.data()).unwrap();
+        assert_eq!((vault_after.total_deposits, vault_after.bump), (850, 0));
+    }
+
+    #[test]
+    fn test_withdraw_from_uninitialized_deposit_rejected() {
+        let (vault_address, vault_state, _, _, _) = integrity_fixture();
+        let mut vault_data = [0u8; VAULT_SIZE];
+        vault_state.serialize(&mut vault_data).unwrap();
+        let user = Address::new_from_array([4u8; 32]);
+
+        // Allocated for the program but never written
+        let mut vault = MockAccountView::new(vault_address, ID, 0, &vault_data).writable();
+        let mut record = MockAccountView::new(
+            Address::new_from_array([5u8; 32]),
+            ID,
+            0,
+            &[0u8; USER_DEPOSIT_SIZE],
+        )
+        .writable();
+        let mut vault_token = MockAccountView::new(
+            vault_state.vault_token_account,
+            TOKEN_PROGRAM_ID,
+            0,
+            &token_account(&vault_state.mint, &vault_address),
+        )
+        .writable();
+        let mut destination = MockAccountView::new(
+            Address::new_from_array([7u8; 32]),
+            TOKEN_PROGRAM_ID,
+            0,
+            &token_account(&vault_state.mint, &user),
+        )
+        .writable();
+        let mut signer = MockAccountView::new(user, Address::default(), 0, &[]).signer();
+        let mut token = MockAccountView::new(TOKEN_PROGRAM_ID, Address::default(), 0, &[]);
+
+        let accounts = [
+            vault.view(),
+            record.view(),
+            vault_token.view(),
+            destination.view(),
+            signer.view(),
+            token.view(),
+        ];
+        let result = withdraw(&ID, &accounts, &0u64.to_le_bytes());
+        assert_eq!(result, Err(TokenSecureError::NotInitialized.into()));
+        let result = withdraw(&ID, &accounts, &1u64.to_le_bytes());
+        assert_eq!(result, Err(TokenSecureError::NotInitialized.into()));
+    }
+
+    /// Runs `deposit` of `amount` against the integrity fixture's vault with
+    /// the given fee and fee account, returning `(user credit, vault total)`.
+    fn run_deposit_with_fee(
+        fee_bps: u16,
+        fee_account_data: &[u8],
+        amount: u64,
+    ) -> Result<(u64, u64), ProgramError> {
+        run_deposit_into(&[0u8; USER_DEPOSIT_SIZE], fee_bps, fee_account_data, amount)
+    }
+
+    /// [`run_deposit_with_fee`] against a user deposit record holding
+    /// `record_data`.
+    fn run_deposit_into(
+        record_data: &[u8],
+        fee_bps: u16,
+        fee_account_data: &[u8],
+        amount: u64,
+    ) -> Result<(u64, u64), ProgramError> {
+        let mut data = [IX_FORMAT_V0; 10];
+        data[1..9].copy_from_slice(&amount.to_le_bytes());
+        let (record_after, vault_after) =
+            run_deposit_ix(record_data, fee_bps, fee_account_data, &data)?;
+        Ok((record_after.amount, vault_after.total_deposits))
+    }
+
+    /// v1 `deposit` data for `amount` with `nonce`.
+    fn deposit_v1_data(amount: u64, nonce: u64) -> [u8; 18] {
+        let mut data = [0u8; 18];
+        data[0] = IX_FORMAT_V1;
+        data[1..9].copy_from_slice(&amount.to_le_bytes());
+        data[10..18].copy_from_slice(&nonce.to_le_bytes());
+        data
+    }
+
+    /// The eight `deposit` accounts for the integrity fixture's user, with
+    /// `vault_state` stored at `vault_address` and a record holding
+    /// `record_data`.
+    fn deposit_accounts(
+        vault_address: Address,
+        vault_state: &Vault,
+        record_data: &[u8],
+        fee_account_data: &[u8],
+    ) -> [MockAccountView; 8] {
+        let (_, _, _, user_deposit, _) = integrity_fixture();
+        let mut vault_data = [0u8; VAULT_SIZE];
+        vault_state.serialize(&mut vault_data).unwrap();
+
+        let vault = MockAccountView::new(vault_address, ID, 0, &vault_data).writable();
+        let record =
+            MockAccountView::new(Address::new_from_array([5u8; 32]), ID, 0, record_data).writable();
+        let source = MockAccountView::new(
+            Address::new_from_array([7u8; 32]),
+            TOKEN_PROGRAM_ID,
+            0,
+            &token_account(&vault_state.mint, &user_deposit.user),
+        )
+        .writable();
+        let vault_token = MockAccountView::new(
+            vault_state.vault_token_account,
+            TOKEN_PROGRAM_ID,
+            0,
+            &token_account(&vault_state.mint, &vault_address),
+        )
+        .writable();
+        let user = MockAccountView::new(user_deposit.user, Address::default(), 0, &[]).signer();
+        let system = MockAccountView::new(SYSTEM_PROGRAM_ID, Address::default(), 0, &[]);
+        let token = MockAccountView::new(TOKEN_PROGRAM_ID, Address::default(), 0, &[]);
+        let fee = MockAccountView::new(
+            Address::new_from_array([8u8; 32]),
+            TOKEN_PROGRAM_ID,
+            0,
+            fee_account_data,
+        )
+        .writable();
+
+        [vault, record, source, vault_token, user, system, token, fee]
+    }
+
+    /// Runs `deposit` over `accounts` with raw instruction `data`.
+    fn run_deposit_accounts(accounts: &mut [MockAccountView; 8], data: &[u8]) -> ProgramResult {
+        let views: Vec<AccountView> = accounts.iter_mut().map(|account| account.view()).collect();
+        deposit(&ID, &views, data)
+    }
+
+    /// Runs `deposit` with raw instruction `data` against the integrity
+    /// fixture's vault and a record holding `record_data`, returning the
+    /// record and vault afterwards.
+    fn run_deposit_ix(
+        record_data: &[u8],
+        fee_bps: u16,
+        fee_account_data: &[u8],
+        data: &[u8],
+    ) -> Result<(UserDeposit, Vault), ProgramError> {
+        let (vault_address, mut vault_state, _, _, _) = integrity_fixture();
+        vault_state.deposit_fee_bps = fee_bps;
+        let mut accounts =
+            deposit_accounts(vault_address, &vault_state, record_data, fee_account_data);
+        run_deposit_accounts(&mut accounts, data)?;
+
+        let record_after = UserDeposit::try_from_slice(accounts[1].data()).unwrap();
+        let vault_after = Vault::try_from_slice(accounts[0].data()).unwrap();
+        Ok((record_after, vault_after))
+    }
+
+    /// The integrity fixture's record (400 deposited) with `last_nonce`.
+    fn record_with_nonce(last_nonce: u64) -> [u8; USER_DEPOSIT_SIZE] {
+        let (_, _, _, user_deposit, _) = integrity_fixture();
+        let mut data = [0u8; USER_DEPOSIT_SIZE];
+        UserDeposit { last_nonce, ..user_deposit }.serialize(&mut data).unwrap();
+        data
+    }
+
+    #[test]
+    fn test_deposit_fresh_nonce_credited() {
+        let (_, vault_state, _, _, _) = integrity_fixture();
+        let fee_account = token_account(&vault_state.mint, &vault_state.authority);
+
+        let (record, vault) =
+            run_deposit_ix(&record_with_nonce(5), 0, &fee_account, &deposit_v1_data(200, 6))
+                .unwrap();
+        assert_eq!((record.amount, record.last_nonce, vault.total_deposits), (600, 6, 1_200));
+
+        // A fresh record accepts any nonce from 1
+        let (record, _) =
+            run_deposit_ix(&record_with_nonce(0), 0, &fee_account, &deposit_v1_data(200, 1))
+                .unwrap();
+        assert_eq!(record.last_nonce, 1);
+    }
+
+    #[test]
+    fn test_deposit_replayed_nonce_rejected() {
+        let (_, vault_state, _, _, _) = integrity_fixture();
+        let fee_account = token_account(&vault_state.mint, &vault_state.authority);
+
+        let (record, _) =
+            run_deposit_ix(&record_with_nonce(5), 0, &fee_account, &deposit_v1_data(200, 6))
+                .unwrap();
+        let mut record_data = [0u8; USER_DEPOSIT_SIZE];
+        record.serialize(&mut record_data).unwrap();
+
+        // The same transaction retried after it landed
+        let result = run_deposit_ix(&record_data, 0, &fee_account, &deposit_v1_data(200, 6));
+        assert_eq!(result.unwrap_err(), TokenSecureError::StaleNonce.into());
+
+        // Nonce 0 never passes, even on a fresh record
+        let result = run_deposit_ix(&record_with_nonce(0), 0, &fee_account, &deposit_v1_data(1, 0));
+        assert_eq!(result.unwrap_err(), TokenSecureError::StaleNonce.into());
+    }
+
+    #[test]
+    fn test_deposit_out_of_order_nonce_rejected() {
+        let (_, vault_state, _, _, _) = integrity_fixture();
+        let fee_account = token_account(&vault_state.mint, &vault_state.authority);
+
+        // Nonce 9 landed first; an older nonce 7 arriving later is rejected
+        let result =
+            run_deposit_ix(&record_with_nonce(9), 0, &fee_account, &deposit_v1_data(200, 7));
+        assert_eq!(result.unwrap_err(), TokenSecureError::StaleNonce.into());
+
+        // v0 deposits carry no nonce and leave last_nonce alone
+        let mut data = [IX_FORMAT_V0; 10];
+        data[1..9].copy_from_slice(&200u64.to_le_bytes());
+        let (record, _) = run_deposit_ix(&record_with_nonce(9), 0, &fee_account, &data).unwrap();
+        assert_eq!((record.amount, record.last_nonce), (600, 9));
+    }
+
+    #[test]
+    fn test_derive_address_matches_handler_derivation() {
+        let (vault_address, vault_state, user_deposit_address, user_deposit, _) =
+            integrity_fixture();
+
+        let mut data = [0u8; 66];
+        data[0] = DERIVE_ADDRESS_DISCRIMINATOR;
+        data[1] = PDA_KIND_VAULT;
+        data[2..34].copy_from_slice(vault_state.mint.as_ref());
+        let derived = derive_pda_from_seeds(&ID, &data[1..]).unwrap();
+        assert_eq!(derived, (vault_address, vault_state.bump));
+        assert!(process_instruction(&ID, &[], &data).is_ok());
+
+        data[1] = PDA_KIND_USER_DEPOSIT;
+        data[2..34].copy_from_slice(vault_address.as_ref());
+        data[34..66].copy_from_slice(user_deposit.user.as_ref());
+        let derived = derive_pda_from_seeds(&ID, &data[1..]).unwrap();
+        assert_eq!(derived, (user_deposit_address, user_deposit.bump));
+        assert!(process_instruction(&ID, &[], &data).is_ok());
+
+        data[1] = 2;
+        assert!(derive_pda_from_seeds(&ID, &data[1..]).is_err());
+        assert!(derive_pda_from_seeds(&ID, &data[1..65]).is_err());
+    }
+
+    #[test]
+    fn test_apply_fee_splits_amount() {
+        assert!(matches!(apply_fee(1_000, 0), Ok((0, 1_000))));
+        assert!(matches!(apply_fee(1_000, 250), Ok((25, 975))));
+        assert!(matches!(apply_fee(1_000, MAX_FEE_BPS), Ok((1_000, 0))));
+        // Rounds the fee down
+        assert!(matches!(apply_fee(39, 250), Ok((0, 39))));
+        assert!(matches!(apply_fee(u64::MAX, MAX_FEE_BPS), Ok((u64::MAX, 0))));
+        assert!(matches!(apply_fee(1_000, MAX_FEE_BPS + 1), Err(TokenSecureError::InvalidFeeBps)));
+    }
+
+    #[test]
+    fn test_deposit_with_zero_fee_credits_full_amount() {
+        let (_, vault_state, _, _, _) = integrity_fixture();
+        let fee_account = token_account(&vault_state.mint, &vault_state.authority);
+        assert_eq!(run_deposit_with_fee(0, &fee_account, 200), Ok((200, 1_200)));
+    }
+
+    #[test]
+    fn test_deposit_fee_credits_net_amount() {
+        let (_, vault_state, _, _, _) = integrity_fixture();
+        let fee_account = token_account(&vault_state.mint, &vault_state.authority);
+        assert_eq!(run_deposit_with_fee(250, &fee_account, 200), Ok((195, 1_195)));
+
+        // The fee account must hold the vault mint and belong to the authority
+        let wrong_mint = token_account(&Address::new_from_array([9u8; 32]), &vault_state.authority);
+        assert_eq!(
+            run_deposit_with_fee(250, &wrong_mint, 200),
+            Err(TokenSecureError::MintMismatch.into())
+        );
+        let wrong_owner = token_account(&vault_state.mint, &Address::new_from_array([4u8; 32]));
+        assert_eq!(
+            run_deposit_with_fee(250, &wrong_owner, 200),
+            Err(TokenSecureError::OwnerMismatch.into())
+        );
+    }
+
+    #[test]
+    fn test_deposit_rejects_short_user_deposit() {
+        let (_, vault_state, _, _, _) = integrity_fixture();
+        let fee_account = token_account(&vault_state.mint, &vault_state.authority);
+        assert_eq!(
+            run_deposit_into(&[0u8; 10], 0, &fee_account, 200),
+            Err(ProgramError::InvalidAccountData)
+        );
+    }
+
+    #[test]
+    fn test_deposit_full_fee_credits_nothing() {
+        let (_, vault_state, _, _, _) = integrity_fixture();
+        let fee_account = token_account(&vault_state.mint, &vault_state.authority);
+        assert_eq!(run_deposit_with_fee(MAX_FEE_BPS, &fee_account, 200), Ok((0, 1_000)));
+    }
+
+    /// Runs `deposit_with_referral` of `amount` (no deposit fee) into a fresh
+    /// user record, returning `(user credit, referrer credit, vault total)`.
+    fn run_deposit_with_referral(
+        referrer_address: Address,
+        referrer: &UserDeposit,
+        amount: u64,
+    ) -> Result<(u64, u64, u64), ProgramError> {
+        let (vault_address, vault_state, _, user_deposit, _) = integrity_fixture();
+        let mut vault_data = [0u8; VAULT_SIZE];
+        vault_state.serialize(&mut vault_data).unwrap();
+        let mut referrer_data = [0u8; USER_DEPOSIT_SIZE];
+        referrer.serialize(&mut referrer_data).unwrap();
+
+        let mut vault = MockAccountView::new(vault_address, ID, 0, &vault_data).writable();
+        let mut record = MockAccountView::new(
+            Address::new_from_array([5u8; 32]),
+            ID,
+            0,
+            &[0u8; USER_DEPOSIT_SIZE],
+        )
+        .writable();
+        let mut source = MockAccountView::new(
+            Address::new_from_array([7u8; 32]),
+            TOKEN_PROGRAM_ID,
+            0,
+            &token_account(&vault_state.mint, &user_deposit.user),
+        )
+        .writable();
+        let mut vault_token = MockAccountView::new(
+            vault_state.vault_token_account,
+            TOKEN_PROGRAM_ID,
+            0,
+            &token_account(&vault_state.mint, &vault_address),
+        )
+        .writable();
+        let mut user = MockAccountView::new(user_deposit.user, Address::default(), 0, &[]).signer();
+        let mut system = MockAccountView::new(SYSTEM_PROGRAM_ID, Address::default(), 0, &[]);
+        let mut token = MockAccountView::new(TOKEN_PROGRAM_ID, Address::default(), 0, &[]);
+        let mut fee = MockAccountView::new(
+            Address::new_from_array([8u8; 32]),
+            TOKEN_PROGRAM_ID,
+            0,
+            &token_account(&vault_state.mint, &vault_state.authority),
+        )
+        .writable();
+        let mut referrer_deposit =
+            MockAccountView::new(referrer_address, ID, 0, &referrer_data).writable();
+
+        let mut data = [IX_FORMAT_V0; 10];
+        data[1..9].copy_from_slice(&amount.to_le_bytes());
+        deposit_with_referral(
+            &ID,
+            &[
+                vault.view(),
+                record.view(),
+                source.view(),
+                vault_token.view(),
+                user.view(),
+                system.view(),
+                token.view(),
+                fee.view(),
+                referrer_deposit.view(),
+            ],
+            &data,
+        )?;
+
+        let record_after = UserDeposit::try_from_slice(record.data()).unwrap();
+        let referrer_after = UserDeposit::try_from_slice(referrer_deposit.data()).unwrap();
+        let vault_after = Vault::try_from_slice(vault.data()).unwrap();
+        Ok((record_after.amount, referrer_after.amount, vault_after.total_deposits))
+    }
+
+    /// Test that DepositAccounts names the accounts in order and that swapped
+    /// accounts fail a role check.
+    #[test]
+    fn test_deposit_accounts_reordered_rejected() {
+        let token_owned = |seed: u8| {
+            MockAccountView::new(Address::new_from_array([seed; 32]), TOKEN_PROGRAM_ID, 0, &[])
+                .writable()
+        };
+        let mut mocks = [
+            MockAccountView::new(Address::new_from_array([1u8; 32]), ID, 0, &[]).writable(),
+            MockAccountView::new(Address::new_from_array([2u8; 32]), ID, 0, &[]).writable(),
+            token_owned(3),
+            token_owned(4),
+            MockAccountView::new(Address::new_from_array([5u8; 32]), Address::default(), 0, &[])
+                .signer(),
+            MockAccountView::new(SYSTEM_PROGRAM_ID, Address::default(), 0, &[]),
+            MockAccountView::new(TOKEN_PROGRAM_ID, Address::default(), 0, &[]),
+            token_owned(8),
+        ];
+        let accounts: Vec<AccountView> = mocks.iter_mut().map(|mock| mock.view()).collect();
+
+        let named = DepositAccounts::from_slice(&accounts).unwrap();
+        assert_eq!(named.vault.address(), &Address::new_from_array([1u8; 32]));
+        assert_eq!(named.user.address(), &Address::new_from_array([5u8; 32]));
+        assert_eq!(named.fee_token_account.address(), &Address::new_from_array([8u8; 32]));
+
+        let swapped = |i: usize, j: usize| {
+            let mut reordered = accounts.clone();
+            reordered.swap(i, j);
+            DepositAccounts::from_slice(&reordered).err()
+        };
+        // vault <-> user: the account in the user slot did not sign
+        assert_eq!(swapped(0, 4), Some(ProgramError::MissingRequiredSignature));
+        // system_program <-> token_program
+        assert_eq!(swapped(5, 6), Some(ProgramError::IncorrectProgramId));
+        // user_deposit <-> user_token_account: a program account in a token slot
+        assert_eq!(swapped(1, 2), Some(ProgramError::IllegalOwner));
+        // Too few accounts
+        assert_eq!(
+            DepositAccounts::from_slice(&accounts[..7]).err(),
+            Some(ProgramError::NotEnoughAccountKeys)
+        );
+    }
+
+    fn referrer_record(user: Address, vault: Address) -> UserDeposit {
+        UserDeposit { user, vault, amount: 50, bump: 254, last_nonce: 0 }
+    }
+
+    #[test]
+    fn test_deposit_with_referral_credits_referrer() {
+        let (vault_address, _, _, _, _) = integrity_fixture();
+        let referrer = referrer_record(Address::new_from_array([9u8; 32]), vault_address);
+
+        // 1% of 1_000 moves to the referrer; the vault total rises by the full 1_000
+        let result =
+            run_deposit_with_referral(Address::new_from_array([10u8; 32]), &referrer, 1_000);
+        assert_eq!(result, Ok((990, 60, 2_000)));
+    }
+
+    #[test]
+    fn test_deposit_with_referral_rejects_self_referral() {
+        let (vault_address, _, _, user_deposit, _) = integrity_fixture();
+        let self_referral = Err(TokenSecureError::SelfReferral.into());
+
+        // Another record holding the depositor's own key
+        let referrer = referrer_record(user_deposit.user, vault_address);
+        let result =
+            run_deposit_with_referral(Address::new_from_array([10u8; 32]), &referrer, 1_000);
+        assert_eq!(result, self_referral);
+
+        // The depositor's own record passed again as the referrer
+        let referrer = referrer_record(Address::new_from_array([9u8; 32]), vault_address);
+        let result =
+            run_deposit_with_referral(Address::new_from_array([5u8; 32]), &referrer, 1_000);
+        assert_eq!(result, self_referral);
+    }
+
+    #[test]
+    fn test_deposit_with_referral_rejects_cross_vault_referrer() {
+        let other_vault = Address::new_from_array([11u8; 32]);
+        let referrer = referrer_record(Address::new_from_array([9u8; 32]), other_vault);
+
+        let result =
+            run_deposit_with_referral(Address::new_from_array([10u8; 32]), &referrer, 1_000);
+        assert_eq!(result, Err(TokenSecureError::Unauthorized.into()));
+    }
+
+    #[test]
+    fn test_set_paused_requires_authority() {
+        let (vault_address, vault_data, _) = paused_fixture();
+        let authority = Vault::try_from_slice(&vault_data).unwrap().authority;
+        let mut vault = MockAccountView::new(vault_address, ID, 0, &vault_data).writable();
+
+        let mut intruder =
+            MockAccountView::new(Address::new_from_array([8u8; 32]), Address::default(), 0, &[])
+                .signer();
+        let result = set_paused(&ID, &[vault.view(), intruder.view()], &[0]);
+        assert_eq!(result, Err(TokenSecureError::Unauthorized.into()));
+
+        let mut authority = MockAccountView::new(authority, Address::default(), 0, &[]).signer();
+        assert!(set_paused(&ID, &[vault.view(), authority.view()], &[0]).is_ok());
+        assert!(!Vault::try_from_slice(vault.data()).unwrap().paused);
+    }
+
+    /// Runs `migrate_mint` from the integrity fixture's vault with
+    /// `total_deposits` outstanding, returning the stored vault afterwards.
+    fn run_migrate_mint(total_deposits: u64) -> (Result<(), ProgramError>, Vault) {
+        let (vault_address, mut vault_state, _, _, _) = integrity_fixture();
+        vault_state.total_deposits = total_deposits;
+        let mut vault_data = [0u8; VAULT_SIZE];
+        vault_state.serialize(&mut vault_data).unwrap();
+
+        let new_mint_address = Address::new_from_array([12u8; 32]);
+        let mut vault = MockAccountView::new(vault_address, ID, 0, &vault_data).writable();
+        let mut authority =
+            MockAccountView::new(vault_state.authority, Address::default(), 0, &[]).signer();
+        let mut new_mint = MockAccountView::new(new_mint_address, TOKEN_PROGRAM_ID, 0, &[]);
+        let mut new_token = MockAccountView::new(
+            Address::new_from_array([13u8; 32]),
+            TOKEN_PROGRAM_ID,
+            0,
+            &token_account(&new_mint_address, &vault_address),
+        );
+
+        let result = migrate_mint(
+            &ID,
+            &[vault.view(), authority.view(), new_mint.view(), new_token.view()],
+            &[],
+        );
+        (result, Vault::try_from_slice(vault.data()).unwrap())
+    }
+
+    #[test]
+    fn test_migrate_mint_on_empty_vault() {
+        let (vault_address, vault_before, ..) = integrity_fixture();
+        let (result, vault_after) = run_migrate_mint(0);
+        assert!(result.is_ok());
+
+        assert_eq!(vault_after.mint, Address::new_from_array([12u8; 32]));
+        assert_eq!(vault_after.vault_token_account, Address::new_from_array([13u8; 32]));
+        // The vault keeps its address and can still sign
+        assert_eq!(vault_after.seed_mint, vault_before.mint);
+        assert!(verify_vault_signer_bump(&ID, &vault_address, &vault_after).is_ok());
+    }
+
+    #[test]
+    fn test_migrate_mint_rejected_with_outstanding_deposits() {
+        let (result, vault_after) = run_migrate_mint(1);
+        assert_eq!(result, Err(TokenSecureError::VaultNotEmpty.into()));
+        assert_eq!(vault_after.mint, Address::new_from_array([2u8; 32]));
+
+        // The new token account must hold the new mint and belong to the vault
+        let (vault_address, vault_state, ..) = integrity_fixture();
+        let mut empty = vault_state;
+        empty.total_deposits = 0;
+        let new_mint = Address::new_from_array([12u8; 32]);
+        assert!(matches!(
+            check_mint_migration(&vault_address, &empty, &new_mint, &empty.mint, &vault_address),
+            Err(TokenSecureError::MintMismatch)
+        ));
+        assert!(matches!(
+            check_mint_migration(&vault_address, &empty, &new_mint, &new_mint, &empty.authority),
+            Err(TokenSecureError::OwnerMismatch)
+        ));
+    }
+
+    // =========================================================================
+    // DEPOSIT/WITHDRAW SYMMETRY MODEL TEST
+    // =========================================================================
+
+    /// Number of randomized deposit/withdraw steps in the model test.
+    const MODEL_STEPS: usize = 1_000;
+
+    /// Number of users sharing the vault in the model test.
+    const MODEL_USERS: usize = 4;
+
+    /// Runs `data` through `process_instruction`, the program's real entry
+    /// point, behind `discriminator`.
+    fn run_ix(accounts: &[AccountView], discriminator: u8, data: &[u8]) -> ProgramResult {
+        let mut instruction_data = vec![discriminator];
+        instruction_data.extend_from_slice(data);
+        process_instruction(&ID, accounts, &instruction_data)
+    }
+
+    #[test]
+    fn test_deposit_withdraw_symmetry_randomized() {
+        let mut rng = XorShift64::new(0x5EED_0006_D00D_F00D);
+
+        let authority = Address::new_from_array([1u8; 32]);
+        let mint = Address::new_from_array([2u8; 32]);
+        let vault_token_address = Address::new_from_array([3u8; 32]);
+        let (vault_address, vault_bump) = derive_vault_pda(&mint, &ID).unwrap();
+
+        let mut vault_data = [0u8; VAULT_SIZE];
+        Vault {
+            authority,
+            mint,
+            vault_token_account: vault_token_address,
+            total_deposits: 0,
+            bump: vault_bump,
+            reward_mint: mint,
+            paused: false,
+            total_minted: 0,
+            deposit_fee_bps: 0,
+            seed_mint: mint,
+        }
+        .serialize(&mut vault_data)
+        .unwrap();
+
+        let mut vault = MockAccountView::new(vault_address, ID, 0, &vault_data).writable();
+        let mut vault_token = MockAccountView::new(
+            vault_token_address,
+            TOKEN_PROGRAM_ID,
+            0,
+            &token_account(&mint, &vault_address),
+        )
+        .writable();
+        let mut fee = MockAccountView::new(
+            Address::new_from_array([8u8; 32]),
+            TOKEN_PROGRAM_ID,
+            0,
+            &token_account(&mint, &authority),
+        )
+        .writable();
+        let mut system = MockAccountView::new(SYSTEM_PROGRAM_ID, Address::default(), 0, &[]);
+        let mut token = MockAccountView::new(TOKEN_PROGRAM_ID, Address::default(), 0, &[]);
+
+        let user_key = |i: usize| Address::new_from_array([i as u8 + 10; 32]);
+        let mut signers: Vec<MockAccountView> = (0..MODEL_USERS)
+            .map(|i| MockAccountView::new(user_key(i), Address::default(), 0, &[]).signer())
+            .collect();
+        let mut user_token_accounts: Vec<MockAccountView> = (0..MODEL_USERS)
+            .map(|i| {
+                MockAccountView::new(
+                    Address::new_from_array([i as u8 + 20; 32]),
+                    TOKEN_PROGRAM_ID,
+                    0,
+                    &token_account(&mint, &user_key(i)),
+                )
+                .writable()
+            })
+            .collect();
+        let mut user_addresses = Vec::new();
+        let mut records: Vec<MockAccountView> = (0..MODEL_USERS)
+            .map(|i| {
+                let user = user_key(i);
+                let (user_deposit_address, bump) =
+                    derive_user_deposit_pda(&vault_address, &user, &ID).unwrap();
+                let mut data = [0u8; USER_DEPOSIT_SIZE];
+                UserDeposit { user, vault: vault_address, amount: 0, bump, last_nonce: 0 }
+                    .serialize(&mut data)
+                    .unwrap();
+                user_addresses.push(user_deposit_address);
+                MockAccountView::new(user_deposit_address, ID, 0, &data).writable()
+            })
+            .collect();
+
+        // Tokens the transfer CPIs would have moved; CPIs do not run off-chain
+        let mut vault_token_balance: u64 = 0;
+
+        let (mut accepted, mut rejected) = (0usize, 0usize);
+        for step in 0..MODEL_STEPS {
+            let user_index = rng.below(MODEL_USERS as u64) as usize;
+            let vault_before = vault.data().to_vec();
+            let record_before = records[user_index].data().to_vec();
+            let record = &mut records[user_index];
+            let user_token = &mut user_token_accounts[user_index];
+            let signer = &mut signers[user_index];
+
+            let is_deposit = rng.below(2) == 0;
+            let result = if is_deposit {
+                let amount = rng.below(1_000_000);
+                let mut data = [IX_FORMAT_V0; 10];
+                data[1..9].copy_from_slice(&amount.to_le_bytes());
+                let accounts = [
+                    vault.view(),
+                    record.view(),
+                    user_token.view(),
+                    vault_token.view(),
+                    signer.view(),
+                    system.view(),
+                    token.view(),
+                    fee.view(),
+                ];
+                run_ix(&accounts, DEPOSIT_DISCRIMINATOR, &data).map(|()| amount)
+            } else {
+                // Occasionally exceed the deposit to exercise the rejection path
+                let deposited = UserDeposit::try_from_slice(record.data()).unwrap().amount;
+                let amount = rng.below(deposited + deposited / 4 + 2);
+                let accounts = [
+                    vault.view(),
+                    record.view(),
+                    vault_token.view(),
+                    user_token.view(),
+                    signer.view(),
+                    token.view(),
+                ];
+                run_ix(&accounts, WITHDRAW_DISCRIMINATOR, &amount.to_le_bytes()).map(|()| amount)
+            };
+
+            match result {
+                Ok(amount) if is_deposit => {
+                    vault_token_balance += amount;
+                    accepted += 1;
+                }
+                Ok(amount) => {
+                    vault_token_balance -= amount;
+                    accepted += 1;
+                }
+                Err(_) => {
+                    // Rejected operations must leave state untouched
+                    assert_eq!(
+                        vault.data(),
+                        &vault_before[..],
+                        "step {}: rejected op mutated vault",
+                        step
+                    );
+                    assert_eq!(
+                        records[user_index].data(),
+                        &record_before[..],
+                        "step {}: rejected op mutated user",
+                        step
+                    );
+                    rejected += 1;
+                }
+            }
+
+            // Invariant: sum(user deposits) == vault total_deposits == tokens held
+            let vault_state = Vault::try_from_slice(vault.data()).unwrap();
+            let mut sum_deposits: u64 = 0;
+            for (user_deposit_address, record) in user_addresses.iter().zip(records.iter()) {
+                let user_deposit_state = UserDeposit::try_from_slice(record.data()).unwrap();
+                sum_deposits += user_deposit_state.amount;
+
+                let integrity = check_deposit_integrity(
+                    &ID,
+                    &vault_address,
+                    &vault_state,
+                    user_deposit_address,
+                    &user_deposit_state,
+                    &vault_token_address,
+                    vault_token_balance,
+                );
+                assert!(integrity.is_ok(), "step {}: integrity check failed", step);
+            }
+            assert_eq!(
+                sum_deposits, vault_state.total_deposits,
+                "step {}: sum(user deposits) != vault total_deposits",
+                step
+            );
+            assert_eq!(
+                vault_token_balance, vault_state.total_deposits,
+                "step {}: vault token balance != total_deposits",
+                step
+            );
+        }
+
+        // Both paths must actually have been exercised
+        assert!(accepted > 0 && rejected > 0);
+    }
+
+    // =========================================================================
+    // RE-INITIALIZATION
+    // =========================================================================
+
+    #[test]
+    fn test_initialize_vault_rejects_reinitialization() {
+        let mut vault =
+            MockAccountView::new(Address::new_from_array([1u8; 32]), ID, 0, &[0u8; VAULT_SIZE])
+                .writable();
+        assert!(run_initialize_vault_on(&mut vault, TOKEN_PROGRAM_ID, SPL_MINT_LEN).is_ok());
+
+        assert_reinit_rejected(&mut vault, |vault| {
+            run_initialize_vault_on(vault, TOKEN_PROGRAM_ID, SPL_MINT_LEN)
+        });
+    }
+
+    #[test]
+    fn test_create_user_deposit_rejects_reinitialization() {
+        let vault_key = Address::new_from_array([1u8; 32]);
+        let user_key = Address::new_from_array([2u8; 32]);
+        let (deposit_pda, bump) = derive_user_deposit_pda(&vault_key, &user_key, &ID).unwrap();
+
+        let mut data = [0u8; USER_DEPOSIT_SIZE];
+        UserDeposit { user: user_key, vault: vault_key, amount: 500, bump, last_nonce: 0 }
+            .serialize(&mut data)
+            .unwrap();
+        let mut user_deposit = MockAccountView::new(deposit_pda, ID, 0, &data).writable();
+        let mut vault = MockAccountView::new(vault_key, ID, 0, &[0u8; VAULT_SIZE]);
+        let mut user = MockAccountView::new(user_key, Address::default(), 0, &[]).signer();
+        let mut system = MockAccountView::new(SYSTEM_PROGRAM_ID, Address::default(), 0, &[]);
+
+        assert_reinit_rejected(&mut user_deposit, |user_deposit| {
+            create_user_deposit(
+                &ID,
+                &[user_deposit.view(), vault.view(), user.view(), system.view()],
+                &[],
+            )
+        });
     }
 }
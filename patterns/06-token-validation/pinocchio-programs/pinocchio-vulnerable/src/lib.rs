@@ -37,8 +37,19 @@ use pinocchio::{
     instruction::{InstructionAccount, InstructionView},
     AccountView, Address, ProgramResult,
 };
+
+// Logging shim: `log!` forwards to `solana_program_log` with the default `logs`
+// feature and compiles to nothing under `--no-default-features`.
+#[cfg(feature = "logs")]
 use solana_program_log::log;
 
+#[cfg(not(feature = "logs"))]
+macro_rules! log {
+    ($($arg:tt)*) => {{
+        let _ = core::format_args!($($arg)*);
+    }};
+}
+
 // =============================================================================
 // PROGRAM ID
 // =============================================================================
@@ -247,6 +258,7 @@ pub fn spl_token_mint_to_signed<const N: usize>(
 // =============================================================================
 
 /// Vault account storing token vault configuration.
+#[derive(Debug, Clone, PartialEq)]
 pub struct Vault {
     /// Authority who can manage the vault (32 bytes)
     pub authority: Address,
@@ -305,6 +317,7 @@ impl Vault {
 }
 
 /// User deposit record tracking individual user deposits.
+#[derive(Debug, Clone, PartialEq)]
 pub struct UserDeposit {
     /// User who made the deposit (32 bytes)
     pub user: Address,
@@ -645,11 +658,7 @@ mod tests {
         vault.serialize(&mut buffer).unwrap();
 
         let deserialized = Vault::try_from_slice(&buffer).unwrap();
-        assert_eq!(deserialized.authority, vault.authority);
-        assert_eq!(deserialized.mint, vault.mint);
-        assert_eq!(deserialized.vault_token_account, vault.vault_token_account);
-        assert_eq!(deserialized.total_deposits, vault.total_deposits);
-        assert_eq!(deserialized.bump, vault.bump);
+        assert_eq!(deserialized, vault);
     }
 
     #[test]
@@ -665,10 +674,7 @@ mod tests {
         user_deposit.serialize(&mut buffer).unwrap();
 
         let deserialized = UserDeposit::try_from_slice(&buffer).unwrap();
-        assert_eq!(deserialized.user, user_deposit.user);
-        assert_eq!(deserialized.vault, user_deposit.vault);
-        assert_eq!(deserialized.amount, user_deposit.amount);
-        assert_eq!(deserialized.bump, user_deposit.bump);
+        assert_eq!(deserialized, user_deposit);
     }
 
     #[test]
@@ -28,6 +28,7 @@
 //! in the vulnerable implementation.
 
 use anchor_lang::prelude::*;
+use anchor_lang::solana_program::program_option::COption;
 use anchor_spl::token::{self, Mint, MintTo, Token, TokenAccount, Transfer};
 
 // Program ID generated from keypair
@@ -146,6 +147,60 @@ pub mod secure_token_validation {
         Ok(())
     }
 
+    /// Deposits tokens on behalf of the source account owner via an approved delegate.
+    ///
+    /// # Security Features
+    ///
+    /// 1. **Delegate Validation**: `source_token_account.delegate` must be the
+    ///    signing delegate, so only an approved party can move the tokens.
+    ///
+    /// 2. **Allowance Check**: `delegated_amount >= amount` keeps the delegate
+    ///    within what the owner approved.
+    ///
+    /// 3. **Beneficiary Binding**: The deposit is credited to the token account
+    ///    owner's UserDeposit PDA, never the delegate's. Otherwise a delegate
+    ///    could deposit someone else's tokens and withdraw them as its own.
+    ///
+    /// 4. **Mint Validation**: Same `source_token_account.mint == vault.mint`
+    ///    check as `deposit`.
+    ///
+    /// # Arguments
+    /// * `ctx` - Context containing validated delegated deposit accounts
+    /// * `amount` - Amount of tokens to deposit
+    pub fn deposit_delegated(ctx: Context<DepositDelegated>, amount: u64) -> Result<()> {
+        // SECURITY: Record belongs to the token owner, not the delegate
+        let owner = ctx.accounts.source_token_account.owner;
+        let user_deposit = &mut ctx.accounts.user_deposit;
+        if user_deposit.user == Pubkey::default() {
+            // First deposit - initialize the record
+            user_deposit.user = owner;
+            user_deposit.vault = ctx.accounts.vault.key();
+            user_deposit.amount = 0;
+            user_deposit.bump = ctx.bumps.user_deposit;
+        }
+
+        // SECURITY: Delegate and allowance enforced by account constraints
+        let cpi_accounts = Transfer {
+            from: ctx.accounts.source_token_account.to_account_info(),
+            to: ctx.accounts.vault_token_account.to_account_info(),
+            authority: ctx.accounts.delegate.to_account_info(),
+        };
+        let cpi_ctx = CpiContext::new(ctx.accounts.token_program.to_account_info(), cpi_accounts);
+        token::transfer(cpi_ctx, amount)?;
+
+        // SECURITY: Safe arithmetic with checked operations
+        user_deposit.amount =
+            user_deposit.amount.checked_add(amount).ok_or(TokenSecureError::ArithmeticOverflow)?;
+
+        let vault = &mut ctx.accounts.vault;
+        vault.total_deposits =
+            vault.total_deposits.checked_add(amount).ok_or(TokenSecureError::ArithmeticOverflow)?;
+
+        msg!("SECURE: Delegate deposited {} tokens for {} (allowance validated)", amount, owner);
+
+        Ok(())
+    }
+
     /// Withdraws tokens from the vault with full owner validation.
     ///
     /// # Security Features
@@ -262,6 +317,44 @@ pub mod secure_token_validation {
 
         Ok(())
     }
+
+    /// Replaces the vault's token account with a new one.
+    ///
+    /// `vault_token_account` is recorded at initialization and trusted by every
+    /// later instruction, so rotating it is a privileged, tightly checked operation.
+    ///
+    /// # Security Features
+    ///
+    /// 1. **Authority Validation**: `has_one = authority` + `Signer` restrict
+    ///    rotation to the vault authority.
+    ///
+    /// 2. **Drained Old Account**: The current token account must hold zero
+    ///    tokens, so no deposits are stranded in an account the vault forgets.
+    ///
+    /// 3. **New Account Validation**: The new token account must hold the vault
+    ///    mint and be owned by the vault PDA, so the vault can still sign for it.
+    ///
+    /// This instruction has no vulnerable counterpart - it exists only to show
+    /// how a trusted address can be rotated safely.
+    ///
+    /// # Arguments
+    /// * `ctx` - Context containing the vault and both token accounts
+    pub fn rotate_vault_token_account(ctx: Context<RotateVaultTokenAccount>) -> Result<()> {
+        // SECURITY: Authority, drained balance, mint and owner enforced by constraints
+        let old_token_account = ctx.accounts.vault_token_account.key();
+        let new_token_account = ctx.accounts.new_vault_token_account.key();
+
+        let vault = &mut ctx.accounts.vault;
+        vault.vault_token_account = new_token_account;
+
+        msg!(
+            "SECURE: Rotated vault token account {} -> {} (authority verified)",
+            old_token_account,
+            new_token_account
+        );
+
+        Ok(())
+    }
 }
 
 // ============================================================================
@@ -410,6 +503,70 @@ pub struct Deposit<'info> {
     pub token_program: Program<'info, Token>,
 }
 
+/// Accounts required for a deposit made by an approved delegate.
+///
+/// # Security Features
+///
+/// 1. **Delegate Validation**: `source_token_account.delegate == Some(delegate)`.
+///
+/// 2. **Allowance Check**: `source_token_account.delegated_amount >= amount`.
+///
+/// 3. **Beneficiary Binding**: `user_deposit` is derived from the source
+///    account owner, so the credit always goes to the owner of the tokens.
+///
+/// 4. **Mint Validation**: `source_token_account.mint == vault.mint`.
+#[derive(Accounts)]
+#[instruction(amount: u64)]
+pub struct DepositDelegated<'info> {
+    /// Vault receiving the deposit
+    #[account(
+        mut,
+        seeds = [VAULT_SEED, vault.mint.as_ref()],
+        bump = vault.bump
+    )]
+    pub vault: Account<'info, Vault>,
+
+    // SECURITY: Signer must be the approved delegate, within its allowance
+    /// Owner's token account to transfer from (SECURE: mint, delegate, allowance validated)
+    #[account(
+        mut,
+        constraint = source_token_account.mint == vault.mint @ TokenSecureError::MintMismatch,
+        constraint = source_token_account.delegate == COption::Some(delegate.key())
+            @ TokenSecureError::DelegateMismatch,
+        constraint = source_token_account.delegated_amount >= amount
+            @ TokenSecureError::DelegatedAmountExceeded
+    )]
+    pub source_token_account: Account<'info, TokenAccount>,
+
+    // SECURITY: Credit goes to the token owner's record - seeds: ["user_deposit", vault, owner]
+    /// Token owner's deposit record PDA
+    #[account(
+        init_if_needed,
+        payer = delegate,
+        space = USER_DEPOSIT_SPACE,
+        seeds = [USER_DEPOSIT_SEED, vault.key().as_ref(), source_token_account.owner.as_ref()],
+        bump
+    )]
+    pub user_deposit: Account<'info, UserDeposit>,
+
+    /// Vault's token account to receive tokens
+    #[account(
+        mut,
+        constraint = vault_token_account.key() == vault.vault_token_account
+    )]
+    pub vault_token_account: Account<'info, TokenAccount>,
+
+    /// Approved delegate of the source token account (pays for PDA creation)
+    #[account(mut)]
+    pub delegate: Signer<'info>,
+
+    /// System program for PDA creation
+    pub system_program: Program<'info, System>,
+
+    /// Token program for transfer
+    pub token_program: Program<'info, Token>,
+}
+
 /// Accounts required for withdrawal operation.
 ///
 /// # Security Features
@@ -519,6 +676,52 @@ pub struct MintReward<'info> {
     pub token_program: Program<'info, Token>,
 }
 
+/// Accounts required for rotating the vault token account.
+///
+/// # Security Features
+///
+/// 1. **Authority Validation**: `has_one = authority` ensures only the vault
+///    authority can rotate, and the authority must sign.
+///
+/// 2. **Current Account Binding**: `has_one = vault_token_account` ensures the
+///    account checked for a zero balance is the one the vault actually uses.
+///
+/// 3. **Drained Balance**: `vault_token_account.amount == 0` rejects rotation
+///    while tokens remain in the old account.
+///
+/// 4. **New Account Validation**: mint must equal `vault.mint` and owner must
+///    be the vault PDA.
+#[derive(Accounts)]
+pub struct RotateVaultTokenAccount<'info> {
+    /// Vault whose token account is being rotated
+    #[account(
+        mut,
+        seeds = [VAULT_SEED, vault.mint.as_ref()],
+        bump = vault.bump,
+        has_one = authority @ TokenSecureError::Unauthorized,
+        has_one = vault_token_account
+    )]
+    pub vault: Account<'info, Vault>,
+
+    // SECURITY: Old account must be fully drained before it is forgotten
+    /// Current vault token account
+    #[account(
+        constraint = vault_token_account.amount == 0 @ TokenSecureError::VaultTokenAccountNotEmpty
+    )]
+    pub vault_token_account: Account<'info, TokenAccount>,
+
+    // SECURITY: New account must accept the vault mint and be controlled by the vault PDA
+    /// Replacement vault token account
+    #[account(
+        constraint = new_vault_token_account.mint == vault.mint @ TokenSecureError::MintMismatch,
+        constraint = new_vault_token_account.owner == vault.key() @ TokenSecureError::OwnerMismatch
+    )]
+    pub new_vault_token_account: Account<'info, TokenAccount>,
+
+    /// Vault authority (must be vault.authority and must sign)
+    pub authority: Signer<'info>,
+}
+
 // ============================================================================
 // Error Codes
 // ============================================================================
@@ -553,4 +756,19 @@ pub enum TokenSecureError {
     /// Triggered when: deposit/withdrawal causes numeric overflow
     #[msg("Arithmetic overflow")]
     ArithmeticOverflow,
+
+    /// Current vault token account still holds tokens
+    /// Triggered when: rotating the vault token account before draining it
+    #[msg("Vault token account must be empty before rotation")]
+    VaultTokenAccountNotEmpty,
+
+    /// Signer is not the approved delegate of the source token account
+    /// Triggered when: deposit_delegated signer != source_token_account.delegate
+    #[msg("Signer is not the approved delegate of the token account")]
+    DelegateMismatch,
+
+    /// Deposit exceeds the delegate's approved allowance
+    /// Triggered when: amount > source_token_account.delegated_amount
+    #[msg("Deposit amount exceeds delegated allowance")]
+    DelegatedAmountExceeded,
 }
@@ -33,6 +33,7 @@ import {
   createAccount,
   mintTo,
   getAccount,
+  approve,
   TOKEN_PROGRAM_ID,
   createInitializeAccountInstruction,
   getMinimumBalanceForRentExemptAccount,
@@ -52,6 +53,7 @@ const INITIAL_MINT_AMOUNT = 1_000_000;
 const DEPOSIT_AMOUNT = 100_000;
 const WITHDRAW_AMOUNT = 50_000;
 const MINT_REWARD_AMOUNT = 10_000;
+const DELEGATED_AMOUNT = 20_000;
 
 // PDA Seeds (must match program constants)
 const VAULT_SEED = Buffer.from("vault");
@@ -1029,5 +1031,220 @@ describe("Token Validation Pattern", () => {
 
       expect(Number(userBalance.amount)).to.equal(expectedBalance);
     });
+
+    it("✓ allows delegated deposit within the approved allowance", async () => {
+      console.log("\n=== SECURITY TEST: Delegated Deposit ===\n");
+
+      // Fund a delegate and approve it on the user's token account
+      const delegate = Keypair.generate();
+      const sig = await provider.connection.requestAirdrop(
+        delegate.publicKey,
+        anchor.web3.LAMPORTS_PER_SOL
+      );
+      await provider.connection.confirmTransaction(sig);
+
+      await approve(
+        provider.connection,
+        user,
+        userSecureTokenAccount,
+        delegate.publicKey,
+        user,
+        DELEGATED_AMOUNT
+      );
+
+      const depositBefore = await secureProgram.account.userDeposit.fetch(
+        secureUserDeposit
+      );
+
+      // Delegate deposits half of its allowance on the user's behalf
+      await secureProgram.methods
+        .depositDelegated(new anchor.BN(DELEGATED_AMOUNT / 2))
+        .accountsStrict({
+          vault: secureVault,
+          sourceTokenAccount: userSecureTokenAccount,
+          userDeposit: secureUserDeposit,
+          vaultTokenAccount: secureVaultTokenAccount,
+          delegate: delegate.publicKey,
+          systemProgram: SystemProgram.programId,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([delegate])
+        .rpc();
+
+      // Credited to the token owner's record, not the delegate's
+      const depositAfter = await secureProgram.account.userDeposit.fetch(
+        secureUserDeposit
+      );
+      console.log("SECURITY VERIFIED: Delegate deposit credited to token owner");
+      console.log("- Deposit before:", depositBefore.amount.toString());
+      console.log("- Deposit after:", depositAfter.amount.toString());
+
+      expect(depositAfter.user.toBase58()).to.equal(user.publicKey.toBase58());
+      expect(depositAfter.amount.toNumber()).to.equal(
+        depositBefore.amount.toNumber() + DELEGATED_AMOUNT / 2
+      );
+
+      // Remaining allowance is DELEGATED_AMOUNT / 2 - exceeding it must fail
+      try {
+        await secureProgram.methods
+          .depositDelegated(new anchor.BN(DELEGATED_AMOUNT))
+          .accountsStrict({
+            vault: secureVault,
+            sourceTokenAccount: userSecureTokenAccount,
+            userDeposit: secureUserDeposit,
+            vaultTokenAccount: secureVaultTokenAccount,
+            delegate: delegate.publicKey,
+            systemProgram: SystemProgram.programId,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          })
+          .signers([delegate])
+          .rpc();
+
+        // Should not reach here
+        expect.fail("Should have rejected deposit above delegated allowance");
+      } catch (err: unknown) {
+        const error = err as Error;
+        console.log("SECURITY VERIFIED: Deposit above allowance rejected!");
+        console.log("- Error:", error.message?.substring(0, 100));
+
+        expect(error.message).to.include("DelegatedAmountExceeded");
+      }
+    });
+
+    it("✓ rejects delegated deposit from a signer that is not the delegate", async () => {
+      console.log("\n=== SECURITY TEST: Delegate Validation ===\n");
+
+      // Attacker was never approved on the user's token account
+      try {
+        await secureProgram.methods
+          .depositDelegated(new anchor.BN(1))
+          .accountsStrict({
+            vault: secureVault,
+            sourceTokenAccount: userSecureTokenAccount,
+            userDeposit: secureUserDeposit,
+            vaultTokenAccount: secureVaultTokenAccount,
+            delegate: attacker.publicKey,
+            systemProgram: SystemProgram.programId,
+            tokenProgram: TOKEN_PROGRAM_ID,
+          })
+          .signers([attacker])
+          .rpc();
+
+        // Should not reach here
+        expect.fail("Should have rejected non-delegate signer");
+      } catch (err: unknown) {
+        const error = err as Error;
+        console.log("SECURITY VERIFIED: Non-delegate deposit rejected!");
+        console.log("- Error:", error.message?.substring(0, 100));
+
+        expect(error.message).to.include("DelegateMismatch");
+      }
+    });
+
+    it("✓ rejects vault token account rotation while old account holds tokens", async () => {
+      console.log("\n=== SECURITY TEST: Rotation Requires Empty Account ===\n");
+
+      // New account is valid (right mint, owned by the vault PDA)
+      const newVaultTokenAccount = await createTokenAccountForPDA(
+        provider.connection,
+        authority,
+        secureLegitMint,
+        secureVault
+      );
+
+      // secureVaultTokenAccount still holds the user's deposit
+      try {
+        await secureProgram.methods
+          .rotateVaultTokenAccount()
+          .accountsStrict({
+            vault: secureVault,
+            vaultTokenAccount: secureVaultTokenAccount,
+            newVaultTokenAccount: newVaultTokenAccount,
+            authority: authority.publicKey,
+          })
+          .signers([authority])
+          .rpc();
+
+        // Should not reach here
+        expect.fail("Should have rejected rotation of a non-empty account");
+      } catch (err: unknown) {
+        const error = err as Error;
+        console.log("SECURITY VERIFIED: Rotation of funded account rejected!");
+        console.log("- Error:", error.message?.substring(0, 100));
+
+        expect(error.message).to.include("VaultTokenAccountNotEmpty");
+      }
+
+      // Vault still points at the original token account
+      const vaultState = await secureProgram.account.vault.fetch(secureVault);
+      expect(vaultState.vaultTokenAccount.toBase58()).to.equal(
+        secureVaultTokenAccount.toBase58()
+      );
+    });
+
+    it("✓ allows vault token account rotation when old account is empty", async () => {
+      console.log("\n=== SECURITY TEST: Rotation of Empty Account ===\n");
+
+      // Fresh vault with no deposits
+      const rotateMint = await createMint(
+        provider.connection,
+        authority,
+        authority.publicKey,
+        null,
+        9
+      );
+
+      const [rotateVault] = PublicKey.findProgramAddressSync(
+        [VAULT_SEED, rotateMint.toBuffer()],
+        secureProgram.programId
+      );
+
+      const oldVaultTokenAccount = await createTokenAccountForPDA(
+        provider.connection,
+        authority,
+        rotateMint,
+        rotateVault
+      );
+
+      await secureProgram.methods
+        .initializeVault()
+        .accountsStrict({
+          vault: rotateVault,
+          mint: rotateMint,
+          vaultTokenAccount: oldVaultTokenAccount,
+          authority: authority.publicKey,
+          systemProgram: SystemProgram.programId,
+          tokenProgram: TOKEN_PROGRAM_ID,
+        })
+        .signers([authority])
+        .rpc();
+
+      const newVaultTokenAccount = await createTokenAccountForPDA(
+        provider.connection,
+        authority,
+        rotateMint,
+        rotateVault
+      );
+
+      await secureProgram.methods
+        .rotateVaultTokenAccount()
+        .accountsStrict({
+          vault: rotateVault,
+          vaultTokenAccount: oldVaultTokenAccount,
+          newVaultTokenAccount: newVaultTokenAccount,
+          authority: authority.publicKey,
+        })
+        .signers([authority])
+        .rpc();
+
+      const vaultState = await secureProgram.account.vault.fetch(rotateVault);
+
+      console.log("SECURITY VERIFIED: Empty vault token account rotated!");
+      console.log("- New vault token account:", newVaultTokenAccount.toBase58());
+
+      expect(vaultState.vaultTokenAccount.toBase58()).to.equal(
+        newVaultTokenAccount.toBase58()
+      );
+    });
   });
 });
@@ -151,6 +151,9 @@ async function createProgramAccount(
  * Instruction data format:
  * - discriminator (u8): 0
  * - bump (u8): vault PDA bump
+ * - deposit_fee_bps (u16 LE, secure program only): fee on each deposit
+ *
+ * The secure program also takes the reward mint as a trailing account.
  */
 function buildInitializeVaultInstruction(
   programId: PublicKey,
@@ -158,12 +161,17 @@ function buildInitializeVaultInstruction(
   mint: PublicKey,
   vaultTokenAccount: PublicKey,
   authority: PublicKey,
-  bump: number
+  bump: number,
+  rewardMint?: PublicKey,
+  depositFeeBps?: number
 ): TransactionInstruction {
-  // Instruction data: [discriminator, bump]
-  const data = Buffer.alloc(2);
+  // Instruction data: [discriminator, bump, deposit_fee_bps (secure only)]
+  const data = Buffer.alloc(depositFeeBps === undefined ? 2 : 4);
   data.writeUInt8(INITIALIZE_VAULT_DISCRIMINATOR, 0);
   data.writeUInt8(bump, 1);
+  if (depositFeeBps !== undefined) {
+    data.writeUInt16LE(depositFeeBps, 2);
+  }
 
   return new TransactionInstruction({
     programId,
@@ -174,6 +182,7 @@ function buildInitializeVaultInstruction(
       { pubkey: authority, isSigner: true, isWritable: false },
       { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
       { pubkey: TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },
+      ...(rewardMint ? [{ pubkey: rewardMint, isSigner: false, isWritable: false }] : []),
     ],
     data,
   });
@@ -184,8 +193,15 @@ function buildInitializeVaultInstruction(
  *
  * Instruction data format:
  * - discriminator (u8): 1
+ * - format_version (u8, secure program only): 0 or 1
  * - amount (u64 LE): tokens to deposit
  * - user_deposit_bump (u8): user deposit PDA bump
+ * - nonce (u64 LE, format 1 only): must exceed the record's last nonce
+ *
+ * Pass `formatVersion` for the secure program, which reads a version byte
+ * after the discriminator; omit it for the vulnerable program. The secure
+ * program also takes the fee token account as a trailing account, and
+ * format 1 requires `nonce`.
  */
 function buildDepositInstruction(
   programId: PublicKey,
@@ -195,13 +211,27 @@ function buildDepositInstruction(
   vaultTokenAccount: PublicKey,
   user: PublicKey,
   amount: bigint,
-  userDepositBump: number
+  userDepositBump: number,
+  formatVersion?: number,
+  feeTokenAccount?: PublicKey,
+  nonce?: bigint
 ): TransactionInstruction {
-  // Instruction data: [discriminator (1), amount (8), bump (1)]
-  const data = Buffer.alloc(10);
+  if (formatVersion === 1 && nonce === undefined) {
+    throw new Error("deposit format 1 requires a nonce");
+  }
+
+  // Instruction data: [discriminator (1), version (0 or 1), amount (8), bump (1), nonce (8, v1)]
+  const offset = formatVersion === undefined ? 1 : 2;
+  const data = Buffer.alloc(offset + 9 + (formatVersion === 1 ? 8 : 0));
   data.writeUInt8(DEPOSIT_DISCRIMINATOR, 0);
-  data.writeBigUInt64LE(amount, 1);
-  data.writeUInt8(userDepositBump, 9);
+  if (formatVersion !== undefined) {
+    data.writeUInt8(formatVersion, 1);
+  }
+  data.writeBigUInt64LE(amount, offset);
+  data.writeUInt8(userDepositBump, offset + 8);
+  if (formatVersion === 1) {
+    data.writeBigUInt64LE(nonce!, offset + 9);
+  }
 
   return new TransactionInstruction({
     programId,
@@ -213,6 +243,7 @@ function buildDepositInstruction(
       { pubkey: user, isSigner: true, isWritable: false },
       { pubkey: SystemProgram.programId, isSigner: false, isWritable: false },
       { pubkey: TOKEN_PROGRAM_ID, isSigner: false, isWritable: false },
+      ...(feeTokenAccount ? [{ pubkey: feeTokenAccount, isSigner: false, isWritable: true }] : []),
     ],
     data,
   });